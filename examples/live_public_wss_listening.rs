@@ -1,3 +1,4 @@
+#![allow(deprecated)]
 use kraken_async_rs::wss::kraken_wss_client::{KrakenMessageStream, KrakenWSSClient};
 use kraken_async_rs::wss::public::messages::PublicMessage;
 use kraken_async_rs::wss::subscribe_messages::{SubscribeMessage, Subscription};
@@ -1,3 +1,4 @@
+use futures_util::StreamExt;
 use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
 use kraken_async_rs::clients::kraken_client::KrakenClient;
 use kraken_async_rs::clients::rate_limited_kraken_client::RateLimitedKrakenClient;
@@ -5,7 +6,7 @@ use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProv
 use kraken_async_rs::request_types::RecentTradesRequest;
 use kraken_async_rs::response_types::RecentTrade;
 use kraken_async_rs::secrets::secrets_provider::{EnvSecretsProvider, SecretsProvider};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::sync::Arc;
 use time::{Duration, OffsetDateTime};
 use tokio::sync::Mutex;
@@ -15,8 +16,8 @@ const MAX_RECENT_TRADES: i64 = 1000;
 /// Retrieve all trades for a pair iteratively by using Kraken's pagination (since, count) parameters
 ///
 /// This creates a RateLimitedKrakenClient to proactively avoid getting rate-limited by the API.
-/// The first request is made, then subsequent ones update the `since` parameter until no new trades
-/// are seen.
+/// `recent_trades_stream` makes the first request, then updates the `since` parameter from each
+/// response's `last` cursor until the end time is reached.
 ///
 /// The result is verified by checking that number of records retrieved is the same as the number
 /// of unique ids retrieved.
@@ -32,47 +33,23 @@ async fn main() {
     let mut client: RateLimitedKrakenClient<CoreKrakenClient> =
         RateLimitedKrakenClient::new(secrets_provider, nonce_provider);
 
-    let since = OffsetDateTime::now_utc()
+    let now = OffsetDateTime::now_utc();
+    let since = now
         .checked_sub(Duration::hours(1))
         .unwrap()
         .unix_timestamp();
 
-    // get recent trades starting 1 hour ago in blocks of 1000 (max Kraken allows)
+    // get trades from 1 hour ago until now in blocks of 1000 (max Kraken allows)
     let request = RecentTradesRequest::builder("XXBTZUSD".to_string())
         .since(since)
         .count(MAX_RECENT_TRADES)
         .build();
 
-    let mut results: HashMap<String, Vec<RecentTrade>> = HashMap::new();
-    let mut last = request.since;
-
-    // keep retrieving and adding to results until no new data is seen
-    loop {
-        let mut request = request.clone();
-        request.since = last;
-
-        let result = client.get_recent_trades(&request).await.unwrap().result;
-
-        last = result.as_ref().map(|o| o.last);
-
-        if let Some(data) = result {
-            let mut no_new_data = false;
-            for (pair, mut trades) in data.trades {
-                if (trades.len() as i64) < request.count.unwrap_or(MAX_RECENT_TRADES) {
-                    no_new_data = true;
-                }
-                results.entry(pair).or_default().append(&mut trades);
-            }
-
-            if no_new_data {
-                break;
-            }
-        } else {
-            break;
-        }
-    }
-
-    let trades = results.get("XXBTZUSD").unwrap();
+    let trades: Vec<RecentTrade> = client
+        .recent_trades_stream(request, Some(now.unix_timestamp()))
+        .map(|trade| trade.unwrap())
+        .collect()
+        .await;
 
     println!("{}", trades.len());
 
@@ -1,4 +1,4 @@
-use kraken_async_rs::clients::kraken_client::KrakenClient;
+#![allow(deprecated)]
 use kraken_async_rs::wss::kraken_wss_client::{KrakenMessageStream, KrakenWSSClient};
 use kraken_async_rs::wss::public::messages::PublicMessage;
 use kraken_async_rs::wss::subscribe_messages::{SubscribeMessage, Subscription};
@@ -0,0 +1,272 @@
+//! A registry that resolves Kraken's many names for the same asset or pair
+//!
+//! Kraken refers to a pair by its REST key (`XXBTZUSD`), `altname` (`XBTUSD`), v1 websocket name
+//! (`XBT/USD`) and v2 websocket symbol (`BTC/USD`), while most other venues would call it
+//! `BTCUSD`. Assets similarly have a REST key (`XXBT`), `altname` (`XBT`) and v2 symbol (`BTC`).
+//!
+//! [AssetRegistry] indexes the results of `get_asset_info` and `get_tradable_asset_pairs` so any of
+//! these forms can be resolved to the same [RegisteredPair] or [RegisteredAsset], e.g. to join REST
+//! results with websocket messages.
+//!
+//! ```no_run
+//! # use kraken_async_rs::asset_registry::AssetRegistry;
+//! # use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+//! # use kraken_async_rs::clients::kraken_client::KrakenClient;
+//! # use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+//! # use kraken_async_rs::secrets::secrets_provider::{SecretsProvider, StaticSecretsProvider};
+//! # use std::sync::Arc;
+//! # use std::time::Duration;
+//! # use tokio::sync::Mutex;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+//! #     Box::new(Arc::new(Mutex::new(StaticSecretsProvider::new("", ""))));
+//! # let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+//! #     Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+//! let mut client = CoreKrakenClient::new(secrets_provider, nonce_provider);
+//! let mut registry = AssetRegistry::new(Duration::from_secs(3600));
+//! registry.refresh(&mut client).await.unwrap();
+//!
+//! let pair = registry.pair("BTCUSD").unwrap();
+//! assert_eq!("XXBTZUSD", pair.key);
+//! assert_eq!("BTC/USD", pair.symbol);
+//! assert_eq!("XBT", registry.base_asset("BTC/USD").unwrap().info.alt_name);
+//!
+//! // later, e.g. before each use, to pick up listings and status changes
+//! registry.refresh_if_stale(&mut client).await.unwrap();
+//! # }
+//! ```
+use crate::clients::errors::ClientError;
+use crate::clients::kraken_client::KrakenClient;
+use crate::request_types::{AssetInfoRequest, TradableAssetPairsRequest};
+use crate::response_types::{AssetInfo, TradableAssetPair};
+use crate::wss::v2::v2_symbol;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// An asset and all the names it's known by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredAsset {
+    /// The REST key, e.g. `XXBT`
+    pub key: String,
+    /// The v2 websocket symbol, e.g. `BTC`
+    pub symbol: String,
+    /// Asset details, including the `alt_name` (e.g. `XBT`), asset class and decimals
+    pub info: AssetInfo,
+}
+
+/// A trading pair and all the names it's known by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredPair {
+    /// The REST key, e.g. `XXBTZUSD`
+    pub key: String,
+    /// The v2 websocket symbol, e.g. `BTC/USD`
+    pub symbol: String,
+    /// The common ticker, e.g. `BTCUSD`
+    pub ticker: String,
+    /// Pair details, including the `alt_name` (e.g. `XBTUSD`), `ws_name` (e.g. `XBT/USD`), base
+    /// and quote asset keys, and decimals
+    pub details: TradableAssetPair,
+}
+
+/// Resolves any name of an asset or pair to its canonical details.
+///
+/// Names are matched case-insensitively. The registry is empty until refreshed from a client, or
+/// given assets and pairs with [AssetRegistry::update].
+///
+/// If two assets or pairs share a name, a REST key takes precedence over an `altname`, which takes
+/// precedence over the derived websocket names and ticker. Collisions between names of the same
+/// kind are logged, and resolve to the entry with the first REST key in lexicographic order.
+#[derive(Debug, Clone)]
+pub struct AssetRegistry {
+    assets: Vec<RegisteredAsset>,
+    pairs: Vec<RegisteredPair>,
+    asset_names: HashMap<String, usize>,
+    pair_names: HashMap<String, usize>,
+    refresh_interval: Duration,
+    refreshed_at: Option<Instant>,
+}
+
+impl AssetRegistry {
+    /// Create an empty registry that is considered stale once `refresh_interval` has passed since
+    /// it was last refreshed.
+    pub fn new(refresh_interval: Duration) -> AssetRegistry {
+        AssetRegistry {
+            assets: Vec::new(),
+            pairs: Vec::new(),
+            asset_names: HashMap::new(),
+            pair_names: HashMap::new(),
+            refresh_interval,
+            refreshed_at: None,
+        }
+    }
+
+    /// Request all assets and pairs, and replace the contents of the registry with them.
+    pub async fn refresh<C: KrakenClient>(&mut self, client: &mut C) -> Result<(), ClientError> {
+        let assets = client
+            .get_asset_info(&AssetInfoRequest::builder().build())
+            .await?
+            .result
+            .ok_or(ClientError::Parse(
+                "Asset info response contained no result",
+            ))?;
+
+        let pairs = client
+            .get_tradable_asset_pairs(&TradableAssetPairsRequest::builder().build())
+            .await?
+            .result
+            .ok_or(ClientError::Parse(
+                "Tradable asset pairs response contained no result",
+            ))?;
+
+        self.update(assets, pairs);
+        Ok(())
+    }
+
+    /// Refresh the registry if it has never been refreshed, or `refresh_interval` has passed.
+    ///
+    /// Returns whether a refresh was made.
+    pub async fn refresh_if_stale<C: KrakenClient>(
+        &mut self,
+        client: &mut C,
+    ) -> Result<bool, ClientError> {
+        if self.is_stale() {
+            self.refresh(client).await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Whether the registry has never been refreshed, or `refresh_interval` has passed since then.
+    pub fn is_stale(&self) -> bool {
+        self.refreshed_at
+            .is_none_or(|refreshed_at| refreshed_at.elapsed() >= self.refresh_interval)
+    }
+
+    /// Replace the contents of the registry with the results of `get_asset_info` and
+    /// `get_tradable_asset_pairs`.
+    pub fn update(
+        &mut self,
+        assets: HashMap<String, AssetInfo>,
+        pairs: HashMap<String, TradableAssetPair>,
+    ) {
+        self.assets = assets
+            .into_iter()
+            .map(|(key, info)| RegisteredAsset {
+                symbol: v2_symbol(&info.alt_name),
+                key,
+                info,
+            })
+            .collect();
+        self.assets.sort_by(|a, b| a.key.cmp(&b.key));
+        self.asset_names = index_names(&self.assets, "asset", |asset| {
+            [
+                vec![asset.key.as_str()],
+                vec![asset.info.alt_name.as_str()],
+                vec![asset.symbol.as_str()],
+            ]
+        });
+
+        self.pairs = pairs
+            .into_iter()
+            .map(|(key, details)| {
+                let symbol = v2_symbol(&details.ws_name);
+                RegisteredPair {
+                    ticker: symbol.replace('/', ""),
+                    symbol,
+                    key,
+                    details,
+                }
+            })
+            .collect();
+        self.pairs.sort_by(|a, b| a.key.cmp(&b.key));
+        self.pair_names = index_names(&self.pairs, "pair", |pair| {
+            [
+                vec![pair.key.as_str()],
+                vec![pair.details.alt_name.as_str()],
+                vec![
+                    pair.details.ws_name.as_str(),
+                    pair.symbol.as_str(),
+                    pair.ticker.as_str(),
+                ],
+            ]
+        });
+
+        self.refreshed_at = Some(Instant::now());
+    }
+
+    /// Resolve a pair from any of its names, e.g. `XXBTZUSD`, `XBTUSD`, `XBT/USD`, `BTC/USD` or
+    /// `BTCUSD`.
+    pub fn pair(&self, name: &str) -> Option<&RegisteredPair> {
+        self.pair_names
+            .get(&name.to_uppercase())
+            .map(|index| &self.pairs[*index])
+    }
+
+    /// Resolve an asset from any of its names, e.g. `XXBT`, `XBT` or `BTC`.
+    pub fn asset(&self, name: &str) -> Option<&RegisteredAsset> {
+        self.asset_names
+            .get(&name.to_uppercase())
+            .map(|index| &self.assets[*index])
+    }
+
+    /// The base asset of a pair, given any of the pair's names.
+    pub fn base_asset(&self, pair: &str) -> Option<&RegisteredAsset> {
+        self.pair(pair)
+            .and_then(|pair| self.asset(&pair.details.base))
+    }
+
+    /// The quote asset of a pair, given any of the pair's names.
+    pub fn quote_asset(&self, pair: &str) -> Option<&RegisteredAsset> {
+        self.pair(pair)
+            .and_then(|pair| self.asset(&pair.details.quote))
+    }
+
+    /// All registered pairs, sorted by REST key.
+    pub fn pairs(&self) -> impl Iterator<Item = &RegisteredPair> {
+        self.pairs.iter()
+    }
+
+    /// All registered assets, sorted by REST key.
+    pub fn assets(&self) -> impl Iterator<Item = &RegisteredAsset> {
+        self.assets.iter()
+    }
+}
+
+/// Index the upper-cased names of each entry, where `names` gives an entry's names grouped from
+/// most to least authoritative, e.g. REST key, then `altname`, then derived names.
+///
+/// Each group is indexed for all entries before the next, so a name already taken by a more
+/// authoritative group is never reassigned. Empty names are skipped.
+fn index_names<T>(
+    entries: &[T],
+    kind: &str,
+    names: impl Fn(&T) -> [Vec<&str>; 3],
+) -> HashMap<String, usize> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for group in 0..3 {
+        let mut claimed_in_group: HashMap<String, usize> = HashMap::new();
+
+        for (entry, entry_names) in entries.iter().map(&names).enumerate() {
+            for name in entry_names[group].iter().filter(|name| !name.is_empty()) {
+                let name = name.to_uppercase();
+
+                match claimed_in_group.get(&name) {
+                    Some(other) if *other != entry => {
+                        warn!("{} name {} is shared by more than one {}", kind, name, kind);
+                    }
+                    Some(_) => {}
+                    None => {
+                        index.entry(name.clone()).or_insert(entry);
+                        claimed_in_group.insert(name, entry);
+                    }
+                }
+            }
+        }
+    }
+
+    index
+}
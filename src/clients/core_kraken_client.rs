@@ -2,28 +2,26 @@
 use crate::clients::errors::ClientError;
 use crate::clients::errors::KrakenError;
 use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::http_transport::{CoreKrakenClientBuilder, HttpTransport};
 use crate::clients::kraken_client::endpoints::*;
 use crate::clients::kraken_client::KrakenClient;
 use crate::crypto::nonce_provider::NonceProvider;
 use crate::crypto::nonce_request::NonceRequest;
-use crate::crypto::signatures::{generate_signature, Signature};
+use crate::crypto::request_signer::{HmacRequestSigner, RequestSignature, RequestSigner};
 use crate::request_types::*;
 use crate::response_types::*;
-use crate::secrets::secrets_provider::SecretsProvider;
 #[allow(unused)]
 use crate::secrets::secrets_provider::StaticSecretsProvider;
-use http_body_util::BodyExt;
+use crate::secrets::secrets_provider::{
+    AsyncSecretsProvider, SecretsError, SecretsProvider, SecretsProviderAdapter,
+};
 use hyper::http::request::Builder;
 use hyper::{Method, Request, Uri};
-use hyper_tls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::client::legacy::Client;
-use hyper_util::rt::TokioExecutor;
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
 use to_query_params::{QueryParams, ToQueryParams};
 use tokio::sync::Mutex;
 use tracing::debug;
@@ -87,27 +85,31 @@ struct EmptyRequest {}
 #[derive(Debug, Clone)]
 pub struct CoreKrakenClient {
     pub api_url: String,
-    secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
-    nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
-    http_client: Client<HttpsConnector<HttpConnector>, String>,
+    credentials: Arc<RwLock<Credentials>>,
+    transport: Arc<dyn HttpTransport>,
     user_agent: Option<String>,
 }
 
+/// The signer and nonces of one API key, which are always replaced together since Kraken tracks
+/// nonces separately for each key.
+#[derive(Debug, Clone)]
+struct Credentials {
+    signer: Arc<dyn RequestSigner>,
+    nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+}
+
+/// An encoded request body and its signature headers.
+struct SignedRequest {
+    body_data: String,
+    signature: RequestSignature,
+}
+
 impl KrakenClient for CoreKrakenClient {
     fn new(
         secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
         nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
     ) -> Self {
-        let https = HttpsConnector::new();
-        let http_client: Client<HttpsConnector<HttpConnector>, String> =
-            Client::builder(TokioExecutor::new()).build(https);
-        CoreKrakenClient {
-            api_url: KRAKEN_BASE_URL.into(),
-            secrets_provider,
-            nonce_provider,
-            http_client,
-            user_agent: None,
-        }
+        CoreKrakenClient::builder(secrets_provider, nonce_provider).build_default()
     }
 
     fn new_with_url(
@@ -115,15 +117,9 @@ impl KrakenClient for CoreKrakenClient {
         nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
         url: String,
     ) -> Self {
-        let https = HttpsConnector::new();
-        let http_client = Client::builder(TokioExecutor::new()).build(https);
-        CoreKrakenClient {
-            api_url: url,
-            secrets_provider,
-            nonce_provider,
-            http_client,
-            user_agent: None,
-        }
+        CoreKrakenClient::builder(secrets_provider, nonce_provider)
+            .url(url)
+            .build_default()
     }
 
     async fn set_user_agent(&mut self, user_agent: String) {
@@ -562,12 +558,12 @@ impl KrakenClient for CoreKrakenClient {
         &mut self,
     ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
         let url = Url::from_str(&self.api_url(GET_WS_TOKEN_ENDPOINT))?;
-        let signature = self
+        let signed = self
             .get_form_signature(GET_WS_TOKEN_ENDPOINT, &EmptyRequest::default())
-            .await;
+            .await?;
 
         let response_body = self
-            .body_from_url_and_form_with_auth(Method::POST, &url, signature)
+            .body_from_url_and_form_with_auth(Method::POST, &url, signed)
             .await?;
 
         Ok(serde_json::from_str(&response_body)?)
@@ -575,6 +571,106 @@ impl KrakenClient for CoreKrakenClient {
 }
 
 impl CoreKrakenClient {
+    /// Create a [CoreKrakenClientBuilder] to configure timeouts, proxying, connection pooling or
+    /// a custom connector.
+    pub fn builder(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> CoreKrakenClientBuilder {
+        let secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>> = Box::new(Arc::new(
+            Mutex::new(SecretsProviderAdapter::new(secrets_provider)),
+        ));
+        Self::builder_with_async_secrets(secrets_provider, nonce_provider)
+    }
+
+    /// Create a [CoreKrakenClientBuilder] for a client that retrieves secrets from an
+    /// [AsyncSecretsProvider], e.g. one backed by a remote secret store.
+    pub fn builder_with_async_secrets(
+        secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> CoreKrakenClientBuilder {
+        CoreKrakenClientBuilder::new(hmac_signer(secrets_provider), nonce_provider)
+    }
+
+    /// Create a [CoreKrakenClientBuilder] for a client that signs private requests with the given
+    /// [RequestSigner], e.g. one that forwards requests to a signing daemon or hardware security
+    /// module so the API secret is never held by the client.
+    pub fn builder_with_signer(
+        signer: Arc<dyn RequestSigner>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> CoreKrakenClientBuilder {
+        CoreKrakenClientBuilder::new(signer, nonce_provider)
+    }
+
+    pub(crate) fn new_with_transport(
+        signer: Arc<dyn RequestSigner>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        url: String,
+        transport: Arc<dyn HttpTransport>,
+    ) -> Self {
+        CoreKrakenClient {
+            api_url: url,
+            credentials: Arc::new(RwLock::new(Credentials {
+                signer,
+                nonce_provider,
+            })),
+            transport,
+            user_agent: None,
+        }
+    }
+
+    /// Switch all following private requests to a new API key, given a provider for its secrets
+    /// and a nonce provider for the new key.
+    ///
+    /// Secrets are retrieved from the new provider before switching, so the current key stays in
+    /// use if they're unavailable. Requests that were already signed with the old key finish as
+    /// normal. The key is shared between clones of this client, so a clone kept before wrapping the
+    /// client in another [KrakenClient] can rotate the key of the wrapped client.
+    ///
+    /// Nonces are tracked per key by Kraken, so the new key's nonce provider starts fresh rather
+    /// than continuing from the old key's nonces. Persistent nonce providers, like a
+    /// [FileNonceProvider](crate::crypto::nonce_provider::FileNonceProvider), should use a separate
+    /// file for each key.
+    pub async fn rotate_key(
+        &self,
+        secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Result<(), SecretsError> {
+        secrets_provider.lock().await.get_secrets().await?;
+        self.rotate_signer(hmac_signer(secrets_provider), nonce_provider);
+        Ok(())
+    }
+
+    /// Switch all following private requests to a new [RequestSigner] and nonce provider, e.g.
+    /// after a new key has been loaded into a signing daemon.
+    ///
+    /// Like [CoreKrakenClient::rotate_key], requests already signed by the old signer finish as
+    /// normal, but the new signer is not checked before switching.
+    pub fn rotate_signer(
+        &self,
+        signer: Arc<dyn RequestSigner>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) {
+        *self
+            .credentials
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Credentials {
+            signer,
+            nonce_provider,
+        };
+
+        debug!("Rotated API key");
+    }
+
+    pub(crate) fn transport(&self) -> Arc<dyn HttpTransport> {
+        self.transport.clone()
+    }
+
+    pub(crate) fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     fn api_url(&self, endpoint: &str) -> String {
         format!("{}{}", self.api_url, endpoint)
     }
@@ -612,6 +708,8 @@ impl CoreKrakenClient {
         Self::parse_body_and_errors(&response_body)
     }
 
+    /// Requests rejected for an invalid nonce are retried once, since signing again takes a new
+    /// nonce that may succeed if the last was only out of order.
     async fn private_form_post<T, R>(
         &mut self,
         url: &str,
@@ -621,16 +719,35 @@ impl CoreKrakenClient {
         T: for<'a> Deserialize<'a>,
         R: ToQueryParams,
     {
-        let signature = self.get_form_signature(url, request).await;
+        match self.signed_form_post(url, request).await {
+            Err(ClientError::Kraken(KrakenError::InvalidNonce(_))) => {
+                debug!("Retrying request to {} after invalid nonce", url);
+                self.signed_form_post(url, request).await
+            }
+            result => result,
+        }
+    }
+
+    async fn signed_form_post<T, R>(
+        &mut self,
+        url: &str,
+        request: &R,
+    ) -> Result<ResultErrorResponse<T>, ClientError>
+    where
+        T: for<'a> Deserialize<'a>,
+        R: ToQueryParams,
+    {
+        let signed = self.get_form_signature(url, request).await?;
         let url = Url::from_str(&self.api_url(url))?;
 
         let response_body = self
-            .body_from_url_and_form_with_auth(Method::POST, &url, signature)
+            .body_from_url_and_form_with_auth(Method::POST, &url, signed)
             .await?;
 
         Self::parse_body_and_errors(&response_body)
     }
 
+    /// Requests rejected for an invalid nonce are retried once, like [Self::private_form_post].
     async fn private_json_post<T, R>(
         &mut self,
         url: &str,
@@ -640,16 +757,35 @@ impl CoreKrakenClient {
         T: for<'a> Deserialize<'a>,
         R: Serialize,
     {
-        let signature = self.get_json_signature(url, request).await?;
+        match self.signed_json_post(url, request).await {
+            Err(ClientError::Kraken(KrakenError::InvalidNonce(_))) => {
+                debug!("Retrying request to {} after invalid nonce", url);
+                self.signed_json_post(url, request).await
+            }
+            result => result,
+        }
+    }
+
+    async fn signed_json_post<T, R>(
+        &mut self,
+        url: &str,
+        request: &R,
+    ) -> Result<ResultErrorResponse<T>, ClientError>
+    where
+        T: for<'a> Deserialize<'a>,
+        R: Serialize,
+    {
+        let signed = self.get_json_signature(url, request).await?;
         let url = Url::from_str(&self.api_url(url))?;
 
         let response_body = self
-            .body_from_url_and_json_with_auth(Method::POST, &url, signature)
+            .body_from_url_and_json_with_auth(Method::POST, &url, signed)
             .await?;
 
         Self::parse_body_and_errors(&response_body)
     }
 
+    /// Requests rejected for an invalid nonce are retried once, like [Self::private_form_post].
     async fn private_post_binary<R>(
         &mut self,
         url: &str,
@@ -658,26 +794,61 @@ impl CoreKrakenClient {
     where
         R: ToQueryParams,
     {
-        let signature = self.get_form_signature(url, request).await;
+        match self.signed_post_binary(url, request).await {
+            Err(ClientError::Kraken(KrakenError::InvalidNonce(_))) => {
+                debug!("Retrying request to {} after invalid nonce", url);
+                self.signed_post_binary(url, request).await
+            }
+            result => result,
+        }
+    }
+
+    async fn signed_post_binary<R>(
+        &mut self,
+        url: &str,
+        request: &R,
+    ) -> Result<Vec<u8>, ClientError>
+    where
+        R: ToQueryParams,
+    {
+        let signed = self.get_form_signature(url, request).await?;
         let url = Url::from_str(&self.api_url(url))?;
 
-        self.body_from_url_as_data(Method::POST, &url, signature)
-            .await
+        let data = self
+            .body_from_url_as_data(Method::POST, &url, signed)
+            .await?;
+        Self::parse_binary_errors(data)
     }
 
-    fn parse_body_and_errors<T>(body: &str) -> Result<ResultErrorResponse<T>, ClientError>
+    /// Binary responses are returned as-is, unless they're a JSON body with an error from Kraken.
+    fn parse_binary_errors(data: Vec<u8>) -> Result<Vec<u8>, ClientError> {
+        match serde_json::from_slice::<ResultErrorResponse<serde_json::Value>>(&data) {
+            Ok(result) => match Self::first_error(&result.error) {
+                Some(err) => Err(ClientError::Kraken(err)),
+                None => Ok(data),
+            },
+            Err(_) => Ok(data),
+        }
+    }
+
+    /// The first error in a response, skipping any warnings (`W`-prefixed entries) before it.
+    fn first_error(errors: &[String]) -> Option<KrakenError> {
+        errors
+            .iter()
+            .find_map(|error| KrakenError::try_from(error).ok())
+    }
+
+    pub(crate) fn parse_body_and_errors<T>(
+        body: &str,
+    ) -> Result<ResultErrorResponse<T>, ClientError>
     where
         T: for<'a> Deserialize<'a>,
     {
         let result: ResultErrorResponse<T> = serde_json::from_str(body)?;
 
-        if let Some(error) = result.error.first() {
-            error
-                .try_into()
-                .map(|err: KrakenError| Err(ClientError::Kraken(err)))
-                .unwrap_or(Ok(result))
-        } else {
-            Ok(result)
+        match Self::first_error(&result.error) {
+            Some(err) => Err(ClientError::Kraken(err)),
+            None => Ok(result),
         }
     }
 
@@ -700,9 +871,9 @@ impl CoreKrakenClient {
         &mut self,
         method: Method,
         url: &Url,
-        signature: Signature,
+        signed: SignedRequest,
     ) -> Result<String, ClientError> {
-        let request = self.build_form_request(method, url, signature).await?;
+        let request = self.build_form_request(method, url, signed)?;
         self.body_from_request(request).await
     }
 
@@ -710,19 +881,15 @@ impl CoreKrakenClient {
         &mut self,
         method: Method,
         url: &Url,
-        signature: Signature,
+        signed: SignedRequest,
     ) -> Result<String, ClientError> {
-        let mut secrets_provider = self.secrets_provider.lock().await;
         let request = Self::request_builder_from_url(method, url)?
             .header("Accept", "application/json")
             .header("Content-Type", "application/json")
             .header("User-Agent", self.get_user_agent().as_str())
-            .header(
-                "API-Key",
-                secrets_provider.get_secrets().key.expose_secret(),
-            )
-            .header("API-Sign", signature.signature)
-            .body(signature.body_data)?;
+            .header("API-Key", signed.signature.api_key.expose_secret())
+            .header("API-Sign", signed.signature.api_sign)
+            .body(signed.body_data)?;
 
         self.body_from_request(request).await
     }
@@ -731,35 +898,29 @@ impl CoreKrakenClient {
         &mut self,
         method: Method,
         url: &Url,
-        signature: Signature,
+        signed: SignedRequest,
     ) -> Result<Vec<u8>, ClientError> {
-        let request = self.build_form_request(method, url, signature).await?;
-        let resp = self.http_client.request(request).await?;
-
-        let status = resp.status();
-        let bytes = resp.into_body().collect().await?.to_bytes();
+        let request = self.build_form_request(method, url, signed)?;
+        let (status, bytes) = self.transport.send(request).await?;
 
         if !status.is_success() {
-            Err(ClientError::HttpStatus(format!(
-                "HTTP Status: {}",
-                status.as_u16()
-            )))
+            Err(ClientError::HttpStatus(
+                status.as_u16(),
+                format!("HTTP Status: {}", status.as_u16()),
+            ))
         } else {
             Ok(bytes.to_vec())
         }
     }
 
     async fn body_from_request(&self, req: Request<String>) -> Result<String, ClientError> {
-        let resp = self.http_client.request(req).await?;
-
-        let status = resp.status();
-        let bytes = resp.into_body().collect().await?.to_bytes();
+        let (status, bytes) = self.transport.send(req).await?;
         let text = String::from_utf8(bytes.to_vec()).or(Err(ClientError::Parse(
             "Failed to parse bytes from response body.",
         )))?;
 
         if !status.is_success() {
-            Err(ClientError::HttpStatus(text))
+            Err(ClientError::HttpStatus(status.as_u16(), text))
         } else {
             if cfg!(feature = "debug-inbound") {
                 debug!("Received: {}", text);
@@ -769,60 +930,83 @@ impl CoreKrakenClient {
         }
     }
 
-    async fn build_form_request(
-        &mut self,
+    fn build_form_request(
+        &self,
         method: Method,
         url: &Url,
-        signature: Signature,
+        signed: SignedRequest,
     ) -> Result<Request<String>, ClientError> {
-        let mut secrets_provider = self.secrets_provider.lock().await;
         let request = Self::request_builder_from_url(method, url)?
             .header("Accept", "application/json")
             .header("Content-Type", "application/x-www-form-urlencoded")
             .header("User-Agent", self.get_user_agent().as_str())
-            .header(
-                "API-Key",
-                secrets_provider.get_secrets().key.expose_secret(),
-            )
-            .header("API-Sign", signature.signature)
-            .body(signature.body_data)?;
+            .header("API-Key", signed.signature.api_key.expose_secret())
+            .header("API-Sign", signed.signature.api_sign)
+            .body(signed.body_data)?;
         Ok(request)
     }
 
-    async fn get_form_signature<R>(&mut self, endpoint: &str, request: &R) -> Signature
+    /// The current key's credentials, which a request keeps using even if the key is rotated
+    /// before it completes.
+    fn credentials(&self) -> Credentials {
+        self.credentials
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    async fn get_form_signature<R>(
+        &mut self,
+        endpoint: &str,
+        request: &R,
+    ) -> Result<SignedRequest, ClientError>
     where
         R: ToQueryParams,
     {
-        let mut secrets_provider = self.secrets_provider.lock().await;
-        let mut provider = self.nonce_provider.lock().await;
-        let nonce = provider.get_nonce();
+        let credentials = self.credentials();
+        let nonce = credentials
+            .nonce_provider
+            .lock()
+            .await
+            .try_get_nonce()
+            .map_err(ClientError::Nonce)?;
         let encoded_data = self.encode_form_request(nonce, request);
-        generate_signature(
-            nonce,
-            secrets_provider.get_secrets().secret.expose_secret(),
-            endpoint,
-            encoded_data,
-        )
+
+        Self::sign(&credentials, endpoint, nonce, encoded_data).await
     }
 
     async fn get_json_signature<R>(
         &mut self,
         endpoint: &str,
         request: &R,
-    ) -> Result<Signature, ClientError>
+    ) -> Result<SignedRequest, ClientError>
     where
         R: Serialize,
     {
-        let mut secrets_provider = self.secrets_provider.lock().await;
-        let mut nonce_provider = self.nonce_provider.lock().await;
-        let nonce = nonce_provider.get_nonce();
+        let credentials = self.credentials();
+        let nonce = credentials
+            .nonce_provider
+            .lock()
+            .await
+            .try_get_nonce()
+            .map_err(ClientError::Nonce)?;
         let encoded_data = self.encode_json_request(nonce, request)?;
-        Ok(generate_signature(
-            nonce,
-            secrets_provider.get_secrets().secret.expose_secret(),
-            endpoint,
-            encoded_data,
-        ))
+
+        Self::sign(&credentials, endpoint, nonce, encoded_data).await
+    }
+
+    async fn sign(
+        credentials: &Credentials,
+        endpoint: &str,
+        nonce: u64,
+        body_data: String,
+    ) -> Result<SignedRequest, ClientError> {
+        let signature = credentials.signer.sign(endpoint, nonce, &body_data).await?;
+
+        Ok(SignedRequest {
+            body_data,
+            signature,
+        })
     }
 
     fn encode_json_request<R>(&self, nonce: u64, request: &R) -> Result<String, ClientError>
@@ -848,6 +1032,12 @@ impl CoreKrakenClient {
     }
 }
 
+fn hmac_signer(
+    secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>>,
+) -> Arc<dyn RequestSigner> {
+    Arc::new(HmacRequestSigner::new(secrets_provider))
+}
+
 #[cfg(test)]
 #[macro_export]
 macro_rules! test_parse_error_matches_pattern {
@@ -888,21 +1078,41 @@ mod tests {
     pub const ERROR_TRADE_LOCKED: &str = r#"{"error":["ETrade:Locked"]}"#;
     pub const ERROR_FEATURE_DISABLED: &str = r#"{"error":["EAPI:Feature disabled"]}"#;
 
+    pub const ERROR_INSUFFICIENT_FUNDS: &str = r#"{"error":["EOrder:Insufficient funds"]}"#;
+    pub const ERROR_ORDER_MINIMUM: &str = r#"{"error":["EOrder:Order minimum not met"]}"#;
+    pub const ERROR_TICK_SIZE: &str = r#"{"error":["EOrder:Tick size check failed"]}"#;
+    pub const ERROR_POST_ONLY: &str = r#"{"error":["EOrder:Post only order"]}"#;
+    pub const ERROR_POSITIONS_LIMIT: &str = r#"{"error":["EOrder:Positions limit exceeded"]}"#;
+    pub const ERROR_CANNOT_OPEN_POSITION: &str = r#"{"error":["EOrder:Cannot open position"]}"#;
+    pub const ERROR_CANNOT_OPEN_OPPOSING_POSITION: &str =
+        r#"{"error":["EOrder:Cannot open opposing position"]}"#;
+    pub const ERROR_UNKNOWN_WITHDRAW_KEY: &str = r#"{"error":["EFunding:Unknown withdraw key"]}"#;
+    pub const ERROR_FUNDING_INSUFFICIENT_FUNDS: &str =
+        r#"{"error":["EFunding:Insufficient funds"]}"#;
+    pub const ERROR_UNKNOWN_ASSET: &str = r#"{"error":["EQuery:Unknown asset"]}"#;
+    pub const ERROR_FUNDING_UNKNOWN_ASSET: &str = r#"{"error":["EFunding:Unknown asset"]}"#;
+    pub const ERROR_MARKET_CANCEL_ONLY: &str =
+        r#"{"error":["EService:Market in cancel_only mode"]}"#;
+    pub const ERROR_UNRECOGNIZED: &str = r#"{"error":["EGeneral:Something new"]}"#;
+    pub const WARNING: &str = r#"{"error":["WGeneral:Some warning"],"result":{"ZUSD":"1.0"}}"#;
+    pub const WARNING_THEN_ERROR: &str =
+        r#"{"error":["WGeneral:Some warning","EOrder:Insufficient funds"]}"#;
+
     #[test]
     fn test_parse_body_and_errors() {
         test_parse_error_matches_pattern!(
             ERROR_PERMISSION_DENIED,
-            Err(ClientError::Kraken(KrakenError::PermissionDenied))
+            Err(ClientError::Kraken(KrakenError::PermissionDenied(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_INVALID_KEY,
-            Err(ClientError::Kraken(KrakenError::InvalidKey))
+            Err(ClientError::Kraken(KrakenError::InvalidKey(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_UNKNOWN_ASSET_PAIR,
-            Err(ClientError::Kraken(KrakenError::UnknownAssetPair))
+            Err(ClientError::Kraken(KrakenError::UnknownAssetPair(_)))
         );
 
         test_parse_error_matches_pattern!(
@@ -912,67 +1122,173 @@ mod tests {
 
         test_parse_error_matches_pattern!(
             ERROR_INVALID_SIGNATURE,
-            Err(ClientError::Kraken(KrakenError::InvalidSignature))
+            Err(ClientError::Kraken(KrakenError::InvalidSignature(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_INVALID_NONCE,
-            Err(ClientError::Kraken(KrakenError::InvalidNonce))
+            Err(ClientError::Kraken(KrakenError::InvalidNonce(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_INVALID_SESSION,
-            Err(ClientError::Kraken(KrakenError::InvalidSession))
+            Err(ClientError::Kraken(KrakenError::InvalidSession(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_BAD_REQUEST,
-            Err(ClientError::Kraken(KrakenError::BadRequest))
+            Err(ClientError::Kraken(KrakenError::BadRequest(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_UNKNOWN_METHOD,
-            Err(ClientError::Kraken(KrakenError::UnknownMethod))
+            Err(ClientError::Kraken(KrakenError::UnknownMethod(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_API_RATE_LIMIT,
-            Err(ClientError::Kraken(KrakenError::RateLimitExceeded))
+            Err(ClientError::Kraken(KrakenError::RateLimitExceeded(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_ORDER_RATE_LIMIT,
-            Err(ClientError::Kraken(KrakenError::TradingRateLimitExceeded))
+            Err(ClientError::Kraken(KrakenError::TradingRateLimitExceeded(
+                _
+            )))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_RATE_LIMIT_LOCKOUT,
-            Err(ClientError::Kraken(KrakenError::TemporaryLockout))
+            Err(ClientError::Kraken(KrakenError::TemporaryLockout(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_SERVICE_UNAVAILABLE,
-            Err(ClientError::Kraken(KrakenError::ServiceUnavailable))
+            Err(ClientError::Kraken(KrakenError::ServiceUnavailable(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_SERVICE_BUSY,
-            Err(ClientError::Kraken(KrakenError::ServiceBusy))
+            Err(ClientError::Kraken(KrakenError::ServiceBusy(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_INTERNAL_ERROR,
-            Err(ClientError::Kraken(KrakenError::InternalError))
+            Err(ClientError::Kraken(KrakenError::InternalError(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_TRADE_LOCKED,
-            Err(ClientError::Kraken(KrakenError::TradeLocked))
+            Err(ClientError::Kraken(KrakenError::TradeLocked(_)))
         );
 
         test_parse_error_matches_pattern!(
             ERROR_FEATURE_DISABLED,
-            Err(ClientError::Kraken(KrakenError::FeatureDisabled))
+            Err(ClientError::Kraken(KrakenError::FeatureDisabled(_)))
+        );
+    }
+
+    #[test]
+    fn test_parse_body_and_detailed_errors() {
+        test_parse_error_matches_pattern!(
+            ERROR_INSUFFICIENT_FUNDS,
+            Err(ClientError::Kraken(KrakenError::InsufficientFunds(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_ORDER_MINIMUM,
+            Err(ClientError::Kraken(KrakenError::OrderMinimumNotMet(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_TICK_SIZE,
+            Err(ClientError::Kraken(KrakenError::TickSizeCheckFailed(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_POST_ONLY,
+            Err(ClientError::Kraken(KrakenError::PostOnlyRejected(..)))
         );
+
+        test_parse_error_matches_pattern!(
+            ERROR_POSITIONS_LIMIT,
+            Err(ClientError::Kraken(KrakenError::PositionsLimitExceeded(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_CANNOT_OPEN_POSITION,
+            Err(ClientError::Kraken(KrakenError::CannotOpenPosition(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_CANNOT_OPEN_OPPOSING_POSITION,
+            Err(ClientError::Kraken(
+                KrakenError::CannotOpenOpposingPosition(..)
+            ))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_UNKNOWN_WITHDRAW_KEY,
+            Err(ClientError::Kraken(KrakenError::UnknownWithdrawKey(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_FUNDING_INSUFFICIENT_FUNDS,
+            Err(ClientError::Kraken(KrakenError::FundingInsufficientFunds(
+                ..
+            )))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_UNKNOWN_ASSET,
+            Err(ClientError::Kraken(KrakenError::UnknownAsset(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_FUNDING_UNKNOWN_ASSET,
+            Err(ClientError::Kraken(KrakenError::FundingUnknownAsset(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_MARKET_CANCEL_ONLY,
+            Err(ClientError::Kraken(KrakenError::MarketCancelOnly(..)))
+        );
+
+        test_parse_error_matches_pattern!(
+            ERROR_UNRECOGNIZED,
+            Err(ClientError::Kraken(KrakenError::Unknown(..)))
+        );
+    }
+
+    #[test]
+    fn test_parse_body_keeps_raw_error() {
+        let err = CoreKrakenClient::parse_body_and_errors::<AccountBalances>(ERROR_TICK_SIZE);
+
+        assert!(matches!(
+            err,
+            Err(ClientError::Kraken(KrakenError::TickSizeCheckFailed(raw))) if raw == "EOrder:Tick size check failed"
+        ));
+    }
+
+    #[test]
+    fn test_parse_body_ignores_warnings() {
+        let result = CoreKrakenClient::parse_body_and_errors::<AccountBalances>(WARNING).unwrap();
+
+        assert_eq!(vec!["WGeneral:Some warning".to_string()], result.error);
+        assert!(result.result.is_some());
+    }
+
+    #[test]
+    fn test_parse_body_finds_error_after_warning() {
+        test_parse_error_matches_pattern!(
+            WARNING_THEN_ERROR,
+            Err(ClientError::Kraken(KrakenError::InsufficientFunds(_)))
+        );
+
+        let binary = CoreKrakenClient::parse_binary_errors(WARNING_THEN_ERROR.as_bytes().to_vec());
+        assert!(matches!(
+            binary,
+            Err(ClientError::Kraken(KrakenError::InsufficientFunds(_)))
+        ));
     }
 }
@@ -0,0 +1,433 @@
+//! A dry-run [KrakenClient] that never submits real orders or moves funds
+use crate::clients::errors::ClientError;
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::kraken_client::KrakenClient;
+use crate::crypto::nonce_provider::NonceProvider;
+use crate::request_types::*;
+use crate::response_types::*;
+use crate::secrets::secrets_provider::SecretsProvider;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// A [KrakenClient] decorator that guarantees no order is placed and no funds are moved, e.g. for
+/// staging deployments that use real API keys.
+///
+/// `add_order`, `add_order_batch` and `edit_order` are always sent with `validate=true`, so Kraken
+/// checks the request and returns a description of the order without placing it. Calls that move
+/// funds (`withdraw_funds`, `request_wallet_transfer`, `account_transfer`, `allocate_earn_funds`
+/// and `deallocate_earn_funds`) or create accounts (`create_sub_account`) fail with
+/// [ClientError::DryRun] without making a request.
+///
+/// All other calls are passed through unchanged, including cancelling orders, since they can't
+/// create new exposure.
+///
+/// This includes `get_websockets_token`, so that private websocket channels can still be read,
+/// but a token can also place real orders over the websocket API. Any [KrakenWSSClient] used with
+/// a token from this client must be put in validate-only mode with
+/// [KrakenWSSClient::set_validate_only] before connecting.
+///
+/// [KrakenWSSClient]: crate::wss::v2::kraken_wss_client::KrakenWSSClient
+/// [KrakenWSSClient::set_validate_only]: crate::wss::v2::kraken_wss_client::KrakenWSSClient::set_validate_only
+#[derive(Debug, Clone)]
+pub struct DryRunKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    core_client: C,
+}
+
+impl<C> KrakenClient for DryRunKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    fn new(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Self {
+        DryRunKrakenClient::new_with_client(C::new(secrets_provider, nonce_provider))
+    }
+
+    fn new_with_url(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        url: String,
+    ) -> Self {
+        DryRunKrakenClient::new_with_client(C::new_with_url(secrets_provider, nonce_provider, url))
+    }
+
+    async fn set_user_agent(&mut self, user_agent: String) {
+        self.core_client.set_user_agent(user_agent).await;
+    }
+
+    async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
+        self.core_client.get_server_time().await
+    }
+
+    async fn get_system_status(
+        &mut self,
+    ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
+        self.core_client.get_system_status().await
+    }
+
+    async fn get_asset_info(
+        &mut self,
+        request: &AssetInfoRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
+        self.core_client.get_asset_info(request).await
+    }
+
+    async fn get_tradable_asset_pairs(
+        &mut self,
+        request: &TradableAssetPairsRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
+        self.core_client.get_tradable_asset_pairs(request).await
+    }
+
+    async fn get_ticker_information(
+        &mut self,
+        request: &TickerRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
+        self.core_client.get_ticker_information(request).await
+    }
+
+    async fn get_ohlc(
+        &mut self,
+        request: &OHLCRequest,
+    ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
+        self.core_client.get_ohlc(request).await
+    }
+
+    async fn get_orderbook(
+        &mut self,
+        request: &OrderbookRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
+        self.core_client.get_orderbook(request).await
+    }
+
+    async fn get_recent_trades(
+        &mut self,
+        request: &RecentTradesRequest,
+    ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
+        self.core_client.get_recent_trades(request).await
+    }
+
+    async fn get_recent_spreads(
+        &mut self,
+        request: &RecentSpreadsRequest,
+    ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
+        self.core_client.get_recent_spreads(request).await
+    }
+
+    async fn get_account_balance(
+        &mut self,
+    ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
+        self.core_client.get_account_balance().await
+    }
+
+    async fn get_extended_balances(
+        &mut self,
+    ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
+        self.core_client.get_extended_balances().await
+    }
+
+    async fn get_trade_balances(
+        &mut self,
+        request: &TradeBalanceRequest,
+    ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
+        self.core_client.get_trade_balances(request).await
+    }
+
+    async fn get_open_orders(
+        &mut self,
+        request: &OpenOrdersRequest,
+    ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
+        self.core_client.get_open_orders(request).await
+    }
+
+    async fn get_closed_orders(
+        &mut self,
+        request: &ClosedOrdersRequest,
+    ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
+        self.core_client.get_closed_orders(request).await
+    }
+
+    async fn query_orders_info(
+        &mut self,
+        request: &OrderRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
+        self.core_client.query_orders_info(request).await
+    }
+
+    async fn get_trades_history(
+        &mut self,
+        request: &TradesHistoryRequest,
+    ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
+        self.core_client.get_trades_history(request).await
+    }
+
+    async fn query_trades_info(
+        &mut self,
+        request: &TradeInfoRequest,
+    ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
+        self.core_client.query_trades_info(request).await
+    }
+
+    async fn get_open_positions(
+        &mut self,
+        request: &OpenPositionsRequest,
+    ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
+        self.core_client.get_open_positions(request).await
+    }
+
+    async fn get_ledgers_info(
+        &mut self,
+        request: &LedgersInfoRequest,
+    ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
+        self.core_client.get_ledgers_info(request).await
+    }
+
+    async fn query_ledgers(
+        &mut self,
+        request: &QueryLedgerRequest,
+    ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
+        self.core_client.query_ledgers(request).await
+    }
+
+    async fn get_trade_volume(
+        &mut self,
+        request: &TradeVolumeRequest,
+    ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
+        self.core_client.get_trade_volume(request).await
+    }
+
+    async fn request_export_report(
+        &mut self,
+        request: &ExportReportRequest,
+    ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
+        self.core_client.request_export_report(request).await
+    }
+
+    async fn get_export_report_status(
+        &mut self,
+        request: &ExportReportStatusRequest,
+    ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
+        self.core_client.get_export_report_status(request).await
+    }
+
+    async fn retrieve_export_report(
+        &mut self,
+        request: &RetrieveExportReportRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        self.core_client.retrieve_export_report(request).await
+    }
+
+    async fn delete_export_report(
+        &mut self,
+        request: &DeleteExportRequest,
+    ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
+        self.core_client.delete_export_report(request).await
+    }
+
+    async fn add_order(
+        &mut self,
+        request: &AddOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
+        let mut request = request.clone();
+        request.validate = Some(true);
+        self.core_client.add_order(&request).await
+    }
+
+    async fn add_order_batch(
+        &mut self,
+        request: &AddBatchedOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
+        let mut request = request.clone();
+        request.validate = Some(true);
+        self.core_client.add_order_batch(&request).await
+    }
+
+    async fn edit_order(
+        &mut self,
+        request: &EditOrderRequest,
+    ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
+        let mut request = request.clone();
+        request.validate = Some(true);
+        self.core_client.edit_order(&request).await
+    }
+
+    async fn cancel_order(
+        &mut self,
+        request: &CancelOrderRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order(request).await
+    }
+
+    async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_all_orders().await
+    }
+
+    async fn cancel_all_orders_after(
+        &mut self,
+        request: &CancelAllOrdersAfterRequest,
+    ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
+        self.core_client.cancel_all_orders_after(request).await
+    }
+
+    async fn cancel_order_batch(
+        &mut self,
+        request: &CancelBatchOrdersRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order_batch(request).await
+    }
+
+    async fn get_deposit_methods(
+        &mut self,
+        request: &DepositMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
+        self.core_client.get_deposit_methods(request).await
+    }
+
+    async fn get_deposit_addresses(
+        &mut self,
+        request: &DepositAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
+        self.core_client.get_deposit_addresses(request).await
+    }
+
+    async fn get_status_of_recent_deposits(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
+        self.core_client
+            .get_status_of_recent_deposits(request)
+            .await
+    }
+
+    async fn get_withdrawal_methods(
+        &mut self,
+        request: &WithdrawalMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
+        self.core_client.get_withdrawal_methods(request).await
+    }
+
+    async fn get_withdrawal_addresses(
+        &mut self,
+        request: &WithdrawalAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
+        self.core_client.get_withdrawal_addresses(request).await
+    }
+
+    async fn get_withdrawal_info(
+        &mut self,
+        request: &WithdrawalInfoRequest,
+    ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
+        self.core_client.get_withdrawal_info(request).await
+    }
+
+    async fn withdraw_funds(
+        &mut self,
+        _request: &WithdrawFundsRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        Err(ClientError::DryRun("withdraw_funds"))
+    }
+
+    async fn get_status_of_recent_withdrawals(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
+        self.core_client
+            .get_status_of_recent_withdrawals(request)
+            .await
+    }
+
+    async fn request_withdrawal_cancellation(
+        &mut self,
+        request: &WithdrawCancelRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client
+            .request_withdrawal_cancellation(request)
+            .await
+    }
+
+    async fn request_wallet_transfer(
+        &mut self,
+        _request: &WalletTransferRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        Err(ClientError::DryRun("request_wallet_transfer"))
+    }
+
+    async fn create_sub_account(
+        &mut self,
+        _request: &CreateSubAccountRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::DryRun("create_sub_account"))
+    }
+
+    async fn account_transfer(
+        &mut self,
+        _request: &AccountTransferRequest,
+    ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
+        Err(ClientError::DryRun("account_transfer"))
+    }
+
+    async fn allocate_earn_funds(
+        &mut self,
+        _request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::DryRun("allocate_earn_funds"))
+    }
+
+    async fn deallocate_earn_funds(
+        &mut self,
+        _request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::DryRun("deallocate_earn_funds"))
+    }
+
+    async fn get_earn_allocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_allocation_status(request).await
+    }
+
+    async fn get_earn_deallocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_deallocation_status(request).await
+    }
+
+    async fn list_earn_strategies(
+        &mut self,
+        request: &ListEarnStrategiesRequest,
+    ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
+        self.core_client.list_earn_strategies(request).await
+    }
+
+    async fn list_earn_allocations(
+        &mut self,
+        request: &ListEarnAllocationsRequest,
+    ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
+        self.core_client.list_earn_allocations(request).await
+    }
+
+    async fn get_websockets_token(
+        &mut self,
+    ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
+        self.core_client.get_websockets_token().await
+    }
+}
+
+impl<C> DryRunKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    /// Create a new dry-run client that delegates calls to any type that implements [KrakenClient].
+    pub fn new_with_client(client: C) -> DryRunKrakenClient<C> {
+        DryRunKrakenClient {
+            core_client: client,
+        }
+    }
+}
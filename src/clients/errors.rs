@@ -1,4 +1,6 @@
 //! Client error type and sub-types
+use crate::crypto::request_signer::SigningError;
+use crate::secrets::secrets_provider::SecretsError;
 use hyper::http::uri::InvalidUri;
 use hyper::http::Error as HyperHttpError;
 use hyper::Error as HyperError;
@@ -10,8 +12,8 @@ use std::fmt::{Display, Formatter};
 use url::ParseError as UrlParseError;
 
 /// `ClientError::Kraken` contains all parsed error messages like `PermissionDenied` and
-/// `UnknownMethod`, but is not inclusive of all potential error messages that can be present in
-/// the `error` field of a response.
+/// `InsufficientFunds`. Errors that aren't specifically recognized are returned as
+/// `KrakenError::Unknown`, while warnings are left in the `error` field of a response.
 ///
 /// Various dependency errors are exposed, but may be of limited use aside from triggering retries.
 #[derive(Debug)]
@@ -21,9 +23,24 @@ pub enum ClientError {
     HyperClient(HyperClientError),
     HyperHttp(HyperHttpError),
     HyperUri(InvalidUri),
-    HttpStatus(String),
+    HttpStatus(u16, String),
     UrlParse(UrlParseError),
     Parse(&'static str),
+    Timeout(&'static str),
+    /// The proxy given to [CoreKrakenClientBuilder::proxy] can't be used
+    ///
+    /// [CoreKrakenClientBuilder::proxy]: crate::clients::http_transport::CoreKrakenClientBuilder::proxy
+    Proxy(&'static str),
+    Replay(String),
+    /// A paginated stream couldn't be completed, e.g. the id to stream results since wasn't found
+    Pagination(String),
+    PaperTrading(&'static str),
+    DryRun(&'static str),
+    Secrets(SecretsError),
+    /// A [RequestSigner](crate::crypto::request_signer::RequestSigner) failed to sign a request
+    Signing(SigningError),
+    /// A [NonceProvider](crate::crypto::nonce_provider::NonceProvider) failed to generate a nonce
+    Nonce(std::io::Error),
     Kraken(KrakenError),
 }
 
@@ -63,6 +80,22 @@ impl From<SerdeError> for ClientError {
     }
 }
 
+impl From<SecretsError> for ClientError {
+    fn from(value: SecretsError) -> Self {
+        Self::Secrets(value)
+    }
+}
+
+/// Secrets errors are kept as [ClientError::Secrets], whether or not they came from a signer.
+impl From<SigningError> for ClientError {
+    fn from(value: SigningError) -> Self {
+        match value {
+            SigningError::Secrets(err) => Self::Secrets(err),
+            err => Self::Signing(err),
+        }
+    }
+}
+
 impl Display for ClientError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -71,9 +104,22 @@ impl Display for ClientError {
             ClientError::HyperClient(err) => write!(f, "{}", err),
             ClientError::HyperHttp(err) => write!(f, "{}", err),
             ClientError::HyperUri(err) => write!(f, "{}", err),
-            ClientError::HttpStatus(body) => write!(f, "Non-successful status with body: {}", body),
+            ClientError::HttpStatus(_, body) => {
+                write!(f, "Non-successful status with body: {}", body)
+            }
             ClientError::UrlParse(err) => write!(f, "{}", err),
             ClientError::Parse(err) => write!(f, "{}", err),
+            ClientError::Timeout(kind) => write!(f, "Timed out waiting for {}", kind),
+            ClientError::Proxy(err) => write!(f, "Invalid proxy: {}", err),
+            ClientError::Replay(err) => write!(f, "{}", err),
+            ClientError::Pagination(err) => write!(f, "Pagination failed: {}", err),
+            ClientError::PaperTrading(method) => {
+                write!(f, "{} is not available when paper trading", method)
+            }
+            ClientError::DryRun(method) => write!(f, "{} is refused in dry-run mode", method),
+            ClientError::Secrets(err) => write!(f, "{}", err),
+            ClientError::Signing(err) => write!(f, "{}", err),
+            ClientError::Nonce(err) => write!(f, "Failed to generate nonce: {}", err),
             ClientError::Kraken(err) => write!(f, "{}", err),
         }
     }
@@ -87,9 +133,18 @@ impl Error for ClientError {
             ClientError::HyperClient(e) => Some(e),
             ClientError::HyperHttp(e) => Some(e),
             ClientError::HyperUri(e) => Some(e),
-            ClientError::HttpStatus(_) => None,
+            ClientError::HttpStatus(..) => None,
             ClientError::UrlParse(e) => Some(e),
             ClientError::Parse(_) => None,
+            ClientError::Timeout(_) => None,
+            ClientError::Proxy(_) => None,
+            ClientError::Replay(_) => None,
+            ClientError::Pagination(_) => None,
+            ClientError::PaperTrading(_) => None,
+            ClientError::DryRun(_) => None,
+            ClientError::Secrets(e) => Some(e),
+            ClientError::Signing(e) => Some(e),
+            ClientError::Nonce(e) => Some(e),
             ClientError::Kraken(e) => Some(e),
         }
     }
@@ -99,102 +154,243 @@ impl Error for ClientError {
 ///
 /// The user still has significant responsibilities in checking error fields!
 ///
-/// Parsing of error messages is done for the general and higher-level errors produced by the API,
-/// not including individual trading and request errors. For instance, `PermissionDenied` and
-/// `UnknownAssetPair` are parsed because they're broadly applicable to many endpoints, while specific
-/// trading errors like "InsufficientMargin" are not.
+/// Every variant keeps the raw error message, since these often contain additional detail from
+/// Kraken, e.g. `KrakenError::InsufficientFunds("EOrder:Insufficient funds")`. Errors are parsed
+/// per category, so the same message from a different category is a different variant, like
+/// `InsufficientFunds` (`EOrder`) and `FundingInsufficientFunds` (`EFunding`), or `UnknownAsset`
+/// (`EQuery`) and `FundingUnknownAsset` (`EFunding`).
+///
+/// Any other error (a message with the `E` severity prefix) is returned as `Unknown`, while warnings
+/// (`W` prefix) are not parsed and remain in the response's `error` field.
 ///
 /// More documentation can be found on Kraken's [API error support page].
 ///
 /// [API error support page]: https://support.kraken.com/hc/en-us/articles/360001491786-API-error-messages
 #[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
 pub enum KrakenError {
-    PermissionDenied,
-    InvalidKey,
-    UnknownAssetPair,
+    PermissionDenied(String),
+    InvalidKey(String),
+    UnknownAssetPair(String),
     InvalidArguments(String),
-    InvalidSignature,
-    InvalidNonce,
-    InvalidSession,
-    BadRequest,
-    UnknownMethod,
-    RateLimitExceeded,
-    TradingRateLimitExceeded,
-    TemporaryLockout,
-    ServiceUnavailable,
-    ServiceBusy,
-    InternalError,
-    TradeLocked,
-    FeatureDisabled,
+    InvalidSignature(String),
+    InvalidNonce(String),
+    InvalidSession(String),
+    BadRequest(String),
+    UnknownMethod(String),
+    RateLimitExceeded(String),
+    TradingRateLimitExceeded(String),
+    TemporaryLockout(String),
+    ServiceUnavailable(String),
+    ServiceBusy(String),
+    InternalError(String),
+    TradeLocked(String),
+    FeatureDisabled(String),
+    // EOrder
+    InsufficientFunds(String),
+    InsufficientMargin(String),
+    OrderMinimumNotMet(String),
+    CostMinimumNotMet(String),
+    TickSizeCheckFailed(String),
+    InvalidPrice(String),
+    PostOnlyRejected(String),
+    ReduceOnlyRejected(String),
+    OrdersLimitExceeded(String),
+    PositionsLimitExceeded(String),
+    DomainRateLimitExceeded(String),
+    MarginAllowanceExceeded(String),
+    MarginLevelTooLow(String),
+    MarginPositionSizeExceeded(String),
+    CannotOpenPosition(String),
+    CannotOpenOpposingPosition(String),
+    UnknownOrder(String),
+    UnknownPosition(String),
+    // EFunding
+    FundingInsufficientFunds(String),
+    UnknownWithdrawKey(String),
+    InvalidAmount(String),
+    TooManyAddresses(String),
+    UnknownReferenceId(String),
+    MaxFeeExceeded(String),
+    FundingUnknownAsset(String),
+    // EQuery
+    UnknownAsset(String),
+    // EService
+    MarketCancelOnly(String),
+    MarketPostOnly(String),
+    DeadlineElapsed(String),
+    Unknown(String),
 }
 
 impl Error for KrakenError {}
 
 impl Display for KrakenError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw())
+    }
+}
+
+impl KrakenError {
+    /// The raw error message returned by Kraken, e.g. `"EAPI:Invalid nonce"`.
+    pub fn raw(&self) -> &str {
         match self {
-            KrakenError::PermissionDenied => write!(f, "PermissionDenied"),
-            KrakenError::InvalidKey => write!(f, "InvalidKey"),
-            KrakenError::UnknownAssetPair => write!(f, "UnknownAssetPair"),
-            KrakenError::InvalidArguments(s) => write!(f, "{s}"),
-            KrakenError::InvalidSignature => write!(f, "InvalidSignature"),
-            KrakenError::InvalidNonce => write!(f, "InvalidNonce"),
-            KrakenError::InvalidSession => write!(f, "InvalidSession"),
-            KrakenError::BadRequest => write!(f, "BadRequest"),
-            KrakenError::UnknownMethod => write!(f, "UnknownMethod"),
-            KrakenError::RateLimitExceeded => write!(f, "RateLimitExceeded"),
-            KrakenError::TradingRateLimitExceeded => write!(f, "TradingRateLimitExceeded"),
-            KrakenError::TemporaryLockout => write!(f, "TemporaryLockout"),
-            KrakenError::ServiceUnavailable => write!(f, "ServiceUnavailable"),
-            KrakenError::ServiceBusy => write!(f, "ServiceBusy"),
-            KrakenError::InternalError => write!(f, "InternalError"),
-            KrakenError::TradeLocked => write!(f, "TradeLocked"),
-            KrakenError::FeatureDisabled => write!(f, "FeatureDisabled"),
+            KrakenError::PermissionDenied(s)
+            | KrakenError::InvalidKey(s)
+            | KrakenError::UnknownAssetPair(s)
+            | KrakenError::InvalidArguments(s)
+            | KrakenError::InvalidSignature(s)
+            | KrakenError::InvalidNonce(s)
+            | KrakenError::InvalidSession(s)
+            | KrakenError::BadRequest(s)
+            | KrakenError::UnknownMethod(s)
+            | KrakenError::RateLimitExceeded(s)
+            | KrakenError::TradingRateLimitExceeded(s)
+            | KrakenError::TemporaryLockout(s)
+            | KrakenError::ServiceUnavailable(s)
+            | KrakenError::ServiceBusy(s)
+            | KrakenError::InternalError(s)
+            | KrakenError::TradeLocked(s)
+            | KrakenError::FeatureDisabled(s)
+            | KrakenError::InsufficientFunds(s)
+            | KrakenError::InsufficientMargin(s)
+            | KrakenError::OrderMinimumNotMet(s)
+            | KrakenError::CostMinimumNotMet(s)
+            | KrakenError::TickSizeCheckFailed(s)
+            | KrakenError::InvalidPrice(s)
+            | KrakenError::PostOnlyRejected(s)
+            | KrakenError::ReduceOnlyRejected(s)
+            | KrakenError::OrdersLimitExceeded(s)
+            | KrakenError::PositionsLimitExceeded(s)
+            | KrakenError::DomainRateLimitExceeded(s)
+            | KrakenError::MarginAllowanceExceeded(s)
+            | KrakenError::MarginLevelTooLow(s)
+            | KrakenError::MarginPositionSizeExceeded(s)
+            | KrakenError::CannotOpenPosition(s)
+            | KrakenError::CannotOpenOpposingPosition(s)
+            | KrakenError::UnknownOrder(s)
+            | KrakenError::UnknownPosition(s)
+            | KrakenError::FundingInsufficientFunds(s)
+            | KrakenError::UnknownWithdrawKey(s)
+            | KrakenError::InvalidAmount(s)
+            | KrakenError::TooManyAddresses(s)
+            | KrakenError::UnknownReferenceId(s)
+            | KrakenError::MaxFeeExceeded(s)
+            | KrakenError::FundingUnknownAsset(s)
+            | KrakenError::UnknownAsset(s)
+            | KrakenError::MarketCancelOnly(s)
+            | KrakenError::MarketPostOnly(s)
+            | KrakenError::DeadlineElapsed(s)
+            | KrakenError::Unknown(s) => s,
         }
     }
 }
 
 /// Parsing for all supported error types from the raw messages in `ResultErrorResponse.error`.
+///
+/// Any message with the error (`E`) severity prefix is parsed, falling back to `Unknown` if not
+/// otherwise recognized. Warnings and other messages produce `Err(())`.
 impl TryFrom<&String> for KrakenError {
     type Error = ();
 
     fn try_from(value: &String) -> Result<Self, Self::Error> {
-        if value.starts_with("EGeneral:Permission denied") {
-            Ok(KrakenError::PermissionDenied)
+        let variant: fn(String) -> KrakenError = if value.starts_with("EGeneral:Permission denied")
+        {
+            KrakenError::PermissionDenied
         } else if value.starts_with("EAPI:Invalid key") {
-            Ok(KrakenError::InvalidKey)
+            KrakenError::InvalidKey
         } else if value.starts_with("EQuery:Unknown asset pair") {
-            Ok(KrakenError::UnknownAssetPair)
+            KrakenError::UnknownAssetPair
         } else if value.starts_with("EGeneral:Invalid arguments") {
-            Ok(KrakenError::InvalidArguments(value.clone()))
+            KrakenError::InvalidArguments
         } else if value.starts_with("EAPI:Invalid signature") {
-            Ok(KrakenError::InvalidSignature)
+            KrakenError::InvalidSignature
         } else if value.starts_with("EAPI:Invalid nonce") {
-            Ok(KrakenError::InvalidNonce)
+            KrakenError::InvalidNonce
         } else if value.starts_with("ESession:Invalid session") {
-            Ok(KrakenError::InvalidSession)
+            KrakenError::InvalidSession
         } else if value.starts_with("EAPI:Bad request") {
-            Ok(KrakenError::BadRequest)
+            KrakenError::BadRequest
         } else if value.starts_with("EGeneral:Unknown Method") {
-            Ok(KrakenError::UnknownMethod)
+            KrakenError::UnknownMethod
         } else if value.starts_with("EAPI:Rate limit exceeded") {
-            Ok(KrakenError::RateLimitExceeded)
+            KrakenError::RateLimitExceeded
         } else if value.starts_with("EOrder:Rate limit exceeded") {
-            Ok(KrakenError::TradingRateLimitExceeded)
+            KrakenError::TradingRateLimitExceeded
         } else if value.starts_with("EGeneral:Temporary lockout") {
-            Ok(KrakenError::TemporaryLockout)
+            KrakenError::TemporaryLockout
         } else if value.starts_with("EService:Unavailable") {
-            Ok(KrakenError::ServiceUnavailable)
+            KrakenError::ServiceUnavailable
         } else if value.starts_with("EService:Busy") {
-            Ok(KrakenError::ServiceBusy)
+            KrakenError::ServiceBusy
+        } else if value.starts_with("EService:Market in cancel_only mode") {
+            KrakenError::MarketCancelOnly
+        } else if value.starts_with("EService:Market in post_only mode") {
+            KrakenError::MarketPostOnly
+        } else if value.starts_with("EService:Deadline elapsed") {
+            KrakenError::DeadlineElapsed
         } else if value.starts_with("EGeneral:Internal error") {
-            Ok(KrakenError::InternalError)
+            KrakenError::InternalError
         } else if value.starts_with("ETrade:Locked") {
-            Ok(KrakenError::TradeLocked)
+            KrakenError::TradeLocked
         } else if value.starts_with("EAPI:Feature disabled") {
-            Ok(KrakenError::FeatureDisabled)
+            KrakenError::FeatureDisabled
+        } else if value.starts_with("EOrder:Insufficient funds") {
+            KrakenError::InsufficientFunds
+        } else if value.starts_with("EOrder:Insufficient margin") {
+            KrakenError::InsufficientMargin
+        } else if value.starts_with("EOrder:Order minimum not met") {
+            KrakenError::OrderMinimumNotMet
+        } else if value.starts_with("EOrder:Cost minimum not met") {
+            KrakenError::CostMinimumNotMet
+        } else if value.starts_with("EOrder:Tick size check failed") {
+            KrakenError::TickSizeCheckFailed
+        } else if value.starts_with("EOrder:Invalid price") {
+            KrakenError::InvalidPrice
+        } else if value.starts_with("EOrder:Post only order") {
+            KrakenError::PostOnlyRejected
+        } else if value.starts_with("EOrder:Reduce only") {
+            KrakenError::ReduceOnlyRejected
+        } else if value.starts_with("EOrder:Orders limit exceeded") {
+            KrakenError::OrdersLimitExceeded
+        } else if value.starts_with("EOrder:Positions limit exceeded") {
+            KrakenError::PositionsLimitExceeded
+        } else if value.starts_with("EOrder:Domain rate limit exceeded") {
+            KrakenError::DomainRateLimitExceeded
+        } else if value.starts_with("EOrder:Margin allowance exceeded") {
+            KrakenError::MarginAllowanceExceeded
+        } else if value.starts_with("EOrder:Margin level too low") {
+            KrakenError::MarginLevelTooLow
+        } else if value.starts_with("EOrder:Margin position size exceeded") {
+            KrakenError::MarginPositionSizeExceeded
+        } else if value.starts_with("EOrder:Cannot open position") {
+            KrakenError::CannotOpenPosition
+        } else if value.starts_with("EOrder:Cannot open opposing position") {
+            KrakenError::CannotOpenOpposingPosition
+        } else if value.starts_with("EOrder:Unknown order") {
+            KrakenError::UnknownOrder
+        } else if value.starts_with("EOrder:Unknown position") {
+            KrakenError::UnknownPosition
+        } else if value.starts_with("EFunding:Insufficient funds") {
+            KrakenError::FundingInsufficientFunds
+        } else if value.starts_with("EFunding:Unknown withdraw key") {
+            KrakenError::UnknownWithdrawKey
+        } else if value.starts_with("EFunding:Invalid amount") {
+            KrakenError::InvalidAmount
+        } else if value.starts_with("EFunding:Too many addresses") {
+            KrakenError::TooManyAddresses
+        } else if value.starts_with("EFunding:Unknown reference id") {
+            KrakenError::UnknownReferenceId
+        } else if value.starts_with("EFunding:Max fee exceeded") {
+            KrakenError::MaxFeeExceeded
+        } else if value.starts_with("EFunding:Unknown asset") {
+            KrakenError::FundingUnknownAsset
+        } else if value.starts_with("EQuery:Unknown asset") {
+            KrakenError::UnknownAsset
+        } else if value.starts_with('E') {
+            KrakenError::Unknown
         } else {
-            Err(())
-        }
+            return Err(());
+        };
+
+        Ok(variant(value.clone()))
     }
 }
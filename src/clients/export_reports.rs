@@ -0,0 +1,410 @@
+//! An end-to-end workflow for bulk trade and ledger history using export reports
+//!
+//! Exporting a report is done in several steps using the raw endpoints of a [KrakenClient]:
+//! 1. request the report with `request_export_report`
+//! 2. poll `get_export_report_status` until the report is `Processed`
+//! 3. download the report with `retrieve_export_report`, which is a zip archive of CSV or TSV files
+//! 4. optionally delete the report with `delete_export_report`
+//!
+//! [export_trades] and [export_ledgers] complete all steps, parsing the report into
+//! [ExportedTrade]s or [ExportedLedgerEntry]s. This is considerably faster than paginating
+//! through the full history of accounts with many years of trades.
+use crate::clients::errors::ClientError;
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::kraken_client::KrakenClient;
+use crate::request_types::{
+    DeleteExportRequest, DeleteExportType, ExportReportRequest, ExportReportStatusRequest,
+    ReportFormatType, ReportType, RetrieveExportReportRequest,
+};
+use crate::response_types::{
+    BuySell, ExportReportStatus, ExportReportStatusType, LedgerEntryType, TradeType,
+};
+use rust_decimal::Decimal;
+use serde::de::{DeserializeOwned, Error as DeError};
+use serde::{Deserialize, Deserializer};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read};
+use std::time::Duration;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+use tokio::time::{sleep, Instant};
+use tracing::debug;
+
+pub use csv::Error as CsvError;
+pub use zip::result::ZipError;
+
+/// Timestamps in reports are formatted like "2024-01-02 03:04:05.6789", in UTC.
+const EXPORT_TIME_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]]");
+
+/// Errors produced while exporting a report.
+#[derive(Debug)]
+pub enum ExportReportError {
+    Client(ClientError),
+    Zip(ZipError),
+    Csv(CsvError),
+    /// The report with the given id was not processed before the timeout elapsed.
+    Timeout(String),
+    /// The report with the given id ended in a status other than `Processed`, e.g. `Failed`, so
+    /// it will never be ready to download.
+    NotProcessed(String, ExportReportStatusType),
+}
+
+impl From<ClientError> for ExportReportError {
+    fn from(value: ClientError) -> Self {
+        Self::Client(value)
+    }
+}
+
+impl From<ZipError> for ExportReportError {
+    fn from(value: ZipError) -> Self {
+        Self::Zip(value)
+    }
+}
+
+impl From<CsvError> for ExportReportError {
+    fn from(value: CsvError) -> Self {
+        Self::Csv(value)
+    }
+}
+
+impl Display for ExportReportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportReportError::Client(err) => write!(f, "{}", err),
+            ExportReportError::Zip(err) => write!(f, "{}", err),
+            ExportReportError::Csv(err) => write!(f, "{}", err),
+            ExportReportError::Timeout(id) => {
+                write!(
+                    f,
+                    "Export report {} was not processed before timing out",
+                    id
+                )
+            }
+            ExportReportError::NotProcessed(id, status) => {
+                write!(
+                    f,
+                    "Export report {} is {} and won't be processed",
+                    id, status
+                )
+            }
+        }
+    }
+}
+
+impl Error for ExportReportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportReportError::Client(e) => Some(e),
+            ExportReportError::Zip(e) => Some(e),
+            ExportReportError::Csv(e) => Some(e),
+            ExportReportError::Timeout(_) => None,
+            ExportReportError::NotProcessed(..) => None,
+        }
+    }
+}
+
+/// Options for polling and cleaning up export reports.
+///
+/// The status of a report is first checked after `poll_interval`, which doubles after each check
+/// up to `max_poll_interval`. If the report isn't processed within `timeout`, the workflow fails
+/// with [ExportReportError::Timeout], leaving the report in place so it can be retrieved later.
+/// Reports are likewise only deleted once they've been downloaded and parsed successfully.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportReportOptions {
+    pub poll_interval: Duration,
+    pub max_poll_interval: Duration,
+    pub timeout: Duration,
+    /// Delete the report from Kraken after it's been downloaded.
+    pub delete_after: bool,
+}
+
+impl Default for ExportReportOptions {
+    fn default() -> Self {
+        ExportReportOptions {
+            poll_interval: Duration::from_secs(1),
+            max_poll_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(30 * 60),
+            delete_after: false,
+        }
+    }
+}
+
+/// A trade as provided in a trades export report, mirroring [Trade](crate::response_types::Trade).
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ExportedTrade {
+    #[serde(rename = "txid")]
+    pub tx_id: String,
+    #[serde(rename = "ordertxid")]
+    pub order_tx_id: String,
+    pub pair: String,
+    #[serde(deserialize_with = "deserialize_export_time")]
+    pub time: f64,
+    #[serde(rename = "type")]
+    pub side: BuySell,
+    #[serde(rename = "ordertype")]
+    pub order_type: TradeType,
+    pub price: Decimal,
+    pub cost: Decimal,
+    pub fee: Decimal,
+    #[serde(rename = "vol")]
+    pub volume: Decimal,
+    pub margin: Decimal,
+    #[serde(default)]
+    pub misc: String,
+    #[serde(default, deserialize_with = "deserialize_comma_separated")]
+    pub ledgers: Vec<String>,
+}
+
+/// A ledger entry as provided in a ledgers export report, mirroring
+/// [LedgerEntry](crate::response_types::LedgerEntry).
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ExportedLedgerEntry {
+    #[serde(rename = "txid")]
+    pub id: String,
+    #[serde(rename = "refid")]
+    pub ref_id: String,
+    #[serde(deserialize_with = "deserialize_export_time")]
+    pub time: f64,
+    #[serde(rename = "type")]
+    pub entry_type: LedgerEntryType,
+    #[serde(default)]
+    pub subtype: String,
+    #[serde(rename = "aclass")]
+    pub asset_class: String,
+    pub asset: String,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub balance: Decimal,
+}
+
+/// Request, wait for, download and parse a trades report.
+///
+/// The `report` of the request is set to [ReportType::Trades].
+pub async fn export_trades<C>(
+    client: &mut C,
+    mut request: ExportReportRequest,
+    options: ExportReportOptions,
+) -> Result<Vec<ExportedTrade>, ExportReportError>
+where
+    C: KrakenClient,
+{
+    request.report = ReportType::Trades;
+    export_report(client, &request, options).await
+}
+
+/// Request, wait for, download and parse a ledgers report.
+///
+/// The `report` of the request is set to [ReportType::Ledgers].
+pub async fn export_ledgers<C>(
+    client: &mut C,
+    mut request: ExportReportRequest,
+    options: ExportReportOptions,
+) -> Result<Vec<ExportedLedgerEntry>, ExportReportError>
+where
+    C: KrakenClient,
+{
+    request.report = ReportType::Ledgers;
+    export_report(client, &request, options).await
+}
+
+/// Request, wait for, download and parse a report into rows of type `T`.
+pub async fn export_report<C, T>(
+    client: &mut C,
+    request: &ExportReportRequest,
+    options: ExportReportOptions,
+) -> Result<Vec<T>, ExportReportError>
+where
+    C: KrakenClient,
+    T: DeserializeOwned,
+{
+    let id = match client.request_export_report(request).await? {
+        ResultErrorResponse {
+            result: Some(report),
+            ..
+        } => report.id,
+        _ => return Err(ClientError::Parse("Export report response contained no result").into()),
+    };
+
+    wait_for_export_report(client, request.report.clone(), &id, options).await?;
+
+    let retrieve_request = RetrieveExportReportRequest::builder(id.clone()).build();
+    let report = client.retrieve_export_report(&retrieve_request).await?;
+
+    let format = request.format.clone().unwrap_or(ReportFormatType::Csv);
+    let rows = parse_export_report(&report, &format)?;
+
+    if options.delete_after {
+        let delete_request = DeleteExportRequest::builder(id, DeleteExportType::Delete).build();
+        client.delete_export_report(&delete_request).await?;
+    }
+
+    Ok(rows)
+}
+
+/// Poll the status of the report with the given id until it's processed, backing off between
+/// requests according to `options`.
+///
+/// The status is always checked at least once, even if `timeout` is shorter than `poll_interval`.
+pub async fn wait_for_export_report<C>(
+    client: &mut C,
+    report: ReportType,
+    id: &str,
+    options: ExportReportOptions,
+) -> Result<ExportReportStatus, ExportReportError>
+where
+    C: KrakenClient,
+{
+    let deadline = Instant::now() + options.timeout;
+    let status_request = ExportReportStatusRequest::builder(report).build();
+    let mut poll_interval = options.poll_interval;
+
+    loop {
+        sleep(poll_interval).await;
+        poll_interval = (poll_interval * 2).min(options.max_poll_interval);
+
+        let statuses = client
+            .get_export_report_status(&status_request)
+            .await?
+            .result
+            .unwrap_or_default();
+
+        match statuses.into_iter().find(|status| status.id == id) {
+            Some(status) => match status.status {
+                ExportReportStatusType::Processed => return Ok(status),
+                ExportReportStatusType::Queued | ExportReportStatusType::Processing => {
+                    debug!("Export report {} is {}", id, status.status)
+                }
+                terminal => {
+                    return Err(ExportReportError::NotProcessed(id.to_string(), terminal));
+                }
+            },
+            None => debug!("Export report {} not found in statuses", id),
+        }
+
+        if Instant::now() + poll_interval > deadline {
+            return Err(ExportReportError::Timeout(id.to_string()));
+        }
+    }
+}
+
+/// Unzip a downloaded report and parse the rows of every file it contains.
+pub fn parse_export_report<T>(
+    report: &[u8],
+    format: &ReportFormatType,
+) -> Result<Vec<T>, ExportReportError>
+where
+    T: DeserializeOwned,
+{
+    let delimiter = match format {
+        ReportFormatType::Csv => b',',
+        ReportFormatType::Tsv => b'\t',
+    };
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(report))?;
+    let mut rows = Vec::new();
+
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+
+        rows.extend(parse_rows(file, delimiter)?);
+    }
+
+    Ok(rows)
+}
+
+fn parse_rows<R, T>(reader: R, delimiter: u8) -> Result<Vec<T>, CsvError>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader)
+        .deserialize()
+        .collect()
+}
+
+fn deserialize_export_time<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let time = String::deserialize(deserializer)?;
+
+    if let Ok(timestamp) = time.parse::<f64>() {
+        return Ok(timestamp);
+    }
+
+    let date_time = PrimitiveDateTime::parse(&time, EXPORT_TIME_FORMAT).map_err(DeError::custom)?;
+    let timestamp = date_time.assume_utc().unix_timestamp_nanos();
+
+    Ok(timestamp as f64 / 1e9)
+}
+
+fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = String::deserialize(deserializer)?;
+
+    Ok(values
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADES_CSV: &str = r#""txid","ordertxid","pair","time","type","ordertype","price","cost","fee","vol","margin","misc","ledgers"
+"TZX2WP-XSEOP-FP7WYR","OAAVL3-5NWXO-SLDNKV","XXBTZUSD","2024-01-02 03:04:05.5","buy","limit","42000.1","420.001","1.09","0.01","0","","LVTSFS-NHZVM-EXNZ5M,LJNFMJ-2GBXG-JSZHVS"
+"#;
+
+    const LEDGERS_TSV: &str = "txid\trefid\ttime\ttype\tsubtype\taclass\tasset\tamount\tfee\tbalance\n\
+        LVTSFS-NHZVM-EXNZ5M\tTZX2WP-XSEOP-FP7WYR\t2024-01-02 03:04:05\ttrade\t\tcurrency\tXXBT\t0.01\t0\t1.01\n";
+
+    #[test]
+    fn test_parse_trade_rows() {
+        let trades: Vec<ExportedTrade> = parse_rows(TRADES_CSV.as_bytes(), b',').unwrap();
+
+        assert_eq!(1, trades.len());
+        let trade = &trades[0];
+        assert_eq!("TZX2WP-XSEOP-FP7WYR", trade.tx_id);
+        assert_eq!(1704164645.5, trade.time);
+        assert_eq!(BuySell::Buy, trade.side);
+        assert_eq!(TradeType::Limit, trade.order_type);
+        assert_eq!(Decimal::new(420001, 1), trade.price);
+        assert_eq!(
+            vec!["LVTSFS-NHZVM-EXNZ5M", "LJNFMJ-2GBXG-JSZHVS"],
+            trade.ledgers
+        );
+    }
+
+    #[test]
+    fn test_parse_ledger_rows() {
+        let entries: Vec<ExportedLedgerEntry> = parse_rows(LEDGERS_TSV.as_bytes(), b'\t').unwrap();
+
+        assert_eq!(1, entries.len());
+        let entry = &entries[0];
+        assert_eq!("LVTSFS-NHZVM-EXNZ5M", entry.id);
+        assert_eq!(1704164645.0, entry.time);
+        assert_eq!(LedgerEntryType::Trade, entry.entry_type);
+        assert_eq!("", entry.subtype);
+        assert_eq!(Decimal::new(101, 2), entry.balance);
+    }
+
+    #[test]
+    fn test_parse_invalid_report() {
+        let result = parse_export_report::<ExportedTrade>(&[0, 1, 0, 1], &ReportFormatType::Csv);
+
+        assert!(matches!(result, Err(ExportReportError::Zip(_))));
+    }
+}
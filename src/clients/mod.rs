@@ -5,8 +5,34 @@
 //!
 //! Implementations:
 //! - [CoreKrakenClient]: Basic impl of REST calls with no rate limiting or additional behavior
+//! - [CoreKrakenClientBuilder]: Configures the HTTP transport of a [CoreKrakenClient], e.g. timeouts and proxying
 //! - [RateLimitedKrakenClient]: Rate-limited decorator of arbitrary [KrakenClient] implementations
+//! - [RetryingKrakenClient]: Decorator that retries transient failures of arbitrary [KrakenClient] implementations
+//! - [RecordingKrakenClient]/[ReplayKrakenClient]: Record responses to a cassette file, and serve them without network access
+//! - [PaperTradingKrakenClient]: Simulates trading against live market data, without risking funds
+//! - [DryRunKrakenClient]: Validates every order with Kraken without placing it, and refuses to move funds
 //!
+//! Pagination:
+//! - [closed_orders_stream]: Stream of all closed orders, walking every page of results
+//! - [trades_history_stream]/[ledgers_stream]: Streams of all trades or ledger entries, with `_since`
+//!   variants that fetch only results newer than a given id
+//! - [recent_trades_stream]: Stream of public trades for a pair between two timestamps
+//!
+//! Export reports:
+//! - [export_trades]/[export_ledgers]: Request, download and parse a trades or ledgers report
+//!
+//! [CoreKrakenClientBuilder]: crate::clients::http_transport::CoreKrakenClientBuilder
+//! [RetryingKrakenClient]: crate::clients::retrying_kraken_client::RetryingKrakenClient
+//! [RecordingKrakenClient]: crate::clients::recording_kraken_client::RecordingKrakenClient
+//! [ReplayKrakenClient]: crate::clients::recording_kraken_client::ReplayKrakenClient
+//! [PaperTradingKrakenClient]: crate::clients::paper_trading_kraken_client::PaperTradingKrakenClient
+//! [DryRunKrakenClient]: crate::clients::dry_run_kraken_client::DryRunKrakenClient
+//! [closed_orders_stream]: crate::clients::pagination::closed_orders_stream
+//! [trades_history_stream]: crate::clients::pagination::trades_history_stream
+//! [ledgers_stream]: crate::clients::pagination::ledgers_stream
+//! [recent_trades_stream]: crate::clients::pagination::recent_trades_stream
+//! [export_trades]: crate::clients::export_reports::export_trades
+//! [export_ledgers]: crate::clients::export_reports::export_ledgers
 #[allow(unused)]
 use crate::clients::core_kraken_client::CoreKrakenClient;
 #[allow(unused)]
@@ -15,7 +41,14 @@ use crate::clients::kraken_client::KrakenClient;
 use crate::clients::rate_limited_kraken_client::RateLimitedKrakenClient;
 
 pub mod core_kraken_client;
+pub mod dry_run_kraken_client;
 pub mod errors;
+pub mod export_reports;
 pub mod http_response_types;
+pub mod http_transport;
 pub mod kraken_client;
+pub mod pagination;
+pub mod paper_trading_kraken_client;
 pub mod rate_limited_kraken_client;
+pub mod recording_kraken_client;
+pub mod retrying_kraken_client;
@@ -0,0 +1,567 @@
+//! Streams that walk paginated REST endpoints to completion
+//!
+//! Kraken paginates several private history endpoints (closed orders, trades history and ledgers)
+//! using an offset (`ofs`) into results sorted from newest to oldest, returning the total `count`
+//! of results alongside each page. The streams here request successive pages until all results
+//! have been returned, de-duplicating results that shift between pages when new entries are added
+//! during pagination.
+//!
+//! Public trade history is instead paginated by the `last` cursor returned with each page of
+//! [RecentTrades], which is provided as `since` in the following request. See
+//! [recent_trades_stream] and [recent_trades_pages].
+//!
+//! Streams make requests through the provided client, so it's recommended to use a
+//! [RateLimitedKrakenClient] to avoid exceeding rate limits. Each page costs 2 tokens of the private
+//! endpoint rate limit.
+//!
+//! [RateLimitedKrakenClient]: crate::clients::rate_limited_kraken_client::RateLimitedKrakenClient
+use crate::clients::errors::ClientError;
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::kraken_client::KrakenClient;
+use crate::request_types::{
+    ClosedOrdersRequest, LedgersInfoRequest, RecentTradesRequest, TradesHistoryRequest,
+};
+use crate::response_types::{ClosedOrder, LedgerEntry, RecentTrade, RecentTrades, Trade};
+use futures_util::{stream, Stream, StreamExt};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+
+/// The number of recent pages whose ids are kept to de-duplicate results that shift between pages.
+const SEEN_PAGES: usize = 2;
+
+/// Tracks the offset, total count and recently seen ids of a stream over an offset-paginated
+/// endpoint.
+///
+/// Only the ids of the last [SEEN_PAGES] pages are kept, since results only shift onto the next
+/// page as new results are added, so memory stays bounded over long walks.
+#[derive(Debug)]
+pub(crate) struct OffsetPaginator<T> {
+    offset: i64,
+    count: Option<i64>,
+    seen: VecDeque<HashSet<String>>,
+    buffer: VecDeque<(String, T)>,
+    stop_at: Option<String>,
+    done: bool,
+}
+
+impl<T> OffsetPaginator<T> {
+    /// Create a paginator that requests its first page at `offset`.
+    pub(crate) fn new(offset: i64) -> Self {
+        OffsetPaginator {
+            offset,
+            count: None,
+            seen: VecDeque::new(),
+            buffer: VecDeque::new(),
+            stop_at: None,
+            done: false,
+        }
+    }
+
+    /// Stop paginating once the result with the given id is found, without returning it or any
+    /// results after it.
+    pub(crate) fn stop_at(mut self, id: String) -> Self {
+        self.stop_at = Some(id);
+        self
+    }
+
+    /// Get the next buffered result, if any.
+    pub(crate) fn next_buffered(&mut self) -> Option<(String, T)> {
+        self.buffer.pop_front()
+    }
+
+    /// Get the offset of the next page to request, or None if all pages have been requested.
+    pub(crate) fn next_offset(&self) -> Option<i64> {
+        if self.done {
+            None
+        } else {
+            Some(self.offset)
+        }
+    }
+
+    /// Add a page of results, advancing the offset and buffering any unseen results in the order
+    /// they're given.
+    ///
+    /// `count` is the total number of results reported with the page, which may grow as new
+    /// results are added during pagination.
+    pub(crate) fn push_page(&mut self, page: Vec<(String, T)>, count: i64) {
+        let page_size = page.len() as i64;
+        self.offset += page_size;
+        self.count = Some(count);
+
+        if page_size == 0 || self.offset >= count {
+            self.done = true;
+        }
+
+        let mut page_ids = HashSet::new();
+        for (id, item) in page {
+            if self.stop_at.as_ref() == Some(&id) {
+                self.stop_at = None;
+                self.done = true;
+                break;
+            }
+
+            let seen = self.seen.iter().any(|ids| ids.contains(&id));
+            if page_ids.insert(id.clone()) && !seen {
+                self.buffer.push_back((id, item));
+            }
+        }
+
+        self.seen.push_back(page_ids);
+        if self.seen.len() > SEEN_PAGES {
+            self.seen.pop_front();
+        }
+    }
+
+    /// Stop requesting pages, e.g. after an error.
+    pub(crate) fn finish(&mut self) {
+        self.stop_at = None;
+        self.done = true;
+    }
+
+    /// Get the id given to [OffsetPaginator::stop_at] if every page was requested without finding
+    /// it, returning it only once.
+    pub(crate) fn take_missing_stop_at(&mut self) -> Option<String> {
+        if self.done && self.buffer.is_empty() {
+            self.stop_at.take()
+        } else {
+            None
+        }
+    }
+}
+
+/// The maximum number of trades Kraken returns for a single recent trades request.
+const MAX_RECENT_TRADES: i64 = 1000;
+
+/// A page of results keyed by id, and the total count of results reported with it.
+type Page<T> = (Vec<(String, T)>, i64);
+
+/// A request for an endpoint that's paginated by offset, returning pages of results keyed by id.
+pub(crate) trait OffsetPaginatedRequest: Sized {
+    type Item;
+
+    fn offset(&self) -> Option<i64>;
+
+    fn set_offset(&mut self, offset: i64);
+
+    /// Request a page of results, returning them from newest to oldest with the total count.
+    fn fetch_page<C>(
+        &self,
+        client: &mut C,
+    ) -> impl Future<Output = Result<Page<Self::Item>, ClientError>>
+    where
+        C: KrakenClient;
+}
+
+impl OffsetPaginatedRequest for ClosedOrdersRequest {
+    type Item = ClosedOrder;
+
+    fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    fn set_offset(&mut self, offset: i64) {
+        self.offset = Some(offset);
+    }
+
+    async fn fetch_page<C>(&self, client: &mut C) -> Result<Page<ClosedOrder>, ClientError>
+    where
+        C: KrakenClient,
+    {
+        match client.get_closed_orders(self).await? {
+            ResultErrorResponse {
+                result: Some(result),
+                ..
+            } => Ok((
+                newest_first(result.closed, |order| order.close_time),
+                result.count,
+            )),
+            _ => Err(ClientError::Parse(
+                "Closed orders response contained no result",
+            )),
+        }
+    }
+}
+
+impl OffsetPaginatedRequest for TradesHistoryRequest {
+    type Item = Trade;
+
+    fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    fn set_offset(&mut self, offset: i64) {
+        self.offset = Some(offset);
+    }
+
+    async fn fetch_page<C>(&self, client: &mut C) -> Result<Page<Trade>, ClientError>
+    where
+        C: KrakenClient,
+    {
+        match client.get_trades_history(self).await? {
+            ResultErrorResponse {
+                result: Some(result),
+                ..
+            } => Ok((
+                newest_first(result.trades, |trade| Some(trade.time)),
+                result.count,
+            )),
+            _ => Err(ClientError::Parse(
+                "Trades history response contained no result",
+            )),
+        }
+    }
+}
+
+impl OffsetPaginatedRequest for LedgersInfoRequest {
+    type Item = LedgerEntry;
+
+    fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    fn set_offset(&mut self, offset: i64) {
+        self.offset = Some(offset);
+    }
+
+    async fn fetch_page<C>(&self, client: &mut C) -> Result<Page<LedgerEntry>, ClientError>
+    where
+        C: KrakenClient,
+    {
+        match client.get_ledgers_info(self).await? {
+            ResultErrorResponse {
+                result: Some(result),
+                ..
+            } => Ok((
+                newest_first(result.ledger, |entry| Some(entry.time)),
+                result.count,
+            )),
+            _ => Err(ClientError::Parse("Ledgers response contained no result")),
+        }
+    }
+}
+
+/// Sort a page of results from newest to oldest, as they're returned by Kraken, breaking ties by id.
+fn newest_first<T>(page: HashMap<String, T>, time: impl Fn(&T) -> Option<f64>) -> Vec<(String, T)> {
+    let mut page: Vec<(String, T)> = page.into_iter().collect();
+    page.sort_by(|(a_id, a), (b_id, b)| {
+        time(b)
+            .partial_cmp(&time(a))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a_id.cmp(b_id))
+    });
+    page
+}
+
+/// Stream all results of an offset-paginated request, stopping before `stop_at` if it's given.
+fn offset_paginated_stream<C, R>(
+    client: &mut C,
+    request: R,
+    stop_at: Option<String>,
+) -> impl Stream<Item = Result<(String, R::Item), ClientError>> + '_
+where
+    C: KrakenClient,
+    R: OffsetPaginatedRequest + 'static,
+{
+    let mut paginator = OffsetPaginator::new(request.offset().unwrap_or(0));
+    if let Some(id) = stop_at {
+        paginator = paginator.stop_at(id);
+    }
+
+    stream::unfold(
+        (client, request, paginator),
+        |(client, mut request, mut paginator)| async move {
+            loop {
+                if let Some(result) = paginator.next_buffered() {
+                    return Some((Ok(result), (client, request, paginator)));
+                }
+
+                if let Some(id) = paginator.take_missing_stop_at() {
+                    let err = ClientError::Pagination(format!("{} was not found", id));
+                    return Some((Err(err), (client, request, paginator)));
+                }
+
+                request.set_offset(paginator.next_offset()?);
+
+                match request.fetch_page(client).await {
+                    Ok((page, count)) => paginator.push_page(page, count),
+                    Err(err) => {
+                        paginator.finish();
+                        return Some((Err(err), (client, request, paginator)));
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// Stream all closed orders matching the request, from newest to oldest.
+///
+/// Pagination starts at `request.offset` (or 0), and respects the `start` and `end` bounds of the
+/// request. A long-running backfill can be resumed after a restart by either:
+/// - setting `offset` to the initial offset plus the number of orders received, or
+/// - setting `end` to the close time of the last order received, since older orders are returned
+///   in later pages (the order closed at `end` may be returned again)
+///
+/// Orders are de-duplicated by id if they shift between pages while new orders close. The stream
+/// ends after the first error, or when all orders have been returned.
+pub fn closed_orders_stream<C>(
+    client: &mut C,
+    request: ClosedOrdersRequest,
+) -> impl Stream<Item = Result<(String, ClosedOrder), ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    offset_paginated_stream(client, request, None)
+}
+
+/// Stream all trades matching the request, from newest to oldest.
+///
+/// This follows the same pagination, de-duplication and resuming behavior as
+/// [closed_orders_stream], using the `time` of the last trade received in place of a close time.
+pub fn trades_history_stream<C>(
+    client: &mut C,
+    request: TradesHistoryRequest,
+) -> impl Stream<Item = Result<(String, Trade), ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    offset_paginated_stream(client, request, None)
+}
+
+/// Stream all trades newer than the trade with id `trade_id`, from newest to oldest.
+///
+/// Pages are requested until `trade_id` is found, so previously downloaded history is not
+/// requested again. If `trade_id` isn't in the results (e.g. it's excluded by the request's
+/// filters), all trades matching the request are streamed, followed by a
+/// `ClientError::Pagination` error.
+pub fn trades_history_stream_since<C>(
+    client: &mut C,
+    request: TradesHistoryRequest,
+    trade_id: String,
+) -> impl Stream<Item = Result<(String, Trade), ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    offset_paginated_stream(client, request, Some(trade_id))
+}
+
+/// Stream all ledger entries matching the request, from newest to oldest.
+///
+/// This follows the same pagination, de-duplication and resuming behavior as
+/// [closed_orders_stream], using the `time` of the last entry received in place of a close time.
+/// `without_count` should not be set, since the count is required to paginate.
+pub fn ledgers_stream<C>(
+    client: &mut C,
+    request: LedgersInfoRequest,
+) -> impl Stream<Item = Result<(String, LedgerEntry), ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    offset_paginated_stream(client, request, None)
+}
+
+/// Stream all ledger entries newer than the entry with id `ledger_id`, from newest to oldest.
+///
+/// Pages are requested until `ledger_id` is found, so previously downloaded history is not
+/// requested again. If `ledger_id` isn't in the results (e.g. it's excluded by the request's
+/// filters), all ledger entries matching the request are streamed, followed by a
+/// `ClientError::Pagination` error.
+pub fn ledgers_stream_since<C>(
+    client: &mut C,
+    request: LedgersInfoRequest,
+    ledger_id: String,
+) -> impl Stream<Item = Result<(String, LedgerEntry), ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    offset_paginated_stream(client, request, Some(ledger_id))
+}
+
+/// Stream pages of public trades for the requested pair, starting at `request.since` and following
+/// the `last` cursor of each page, until all trades before `end` (a unix timestamp in seconds)
+/// have been returned. Without an `end`, the stream ends once it has caught up to the most recent
+/// trades.
+///
+/// Each page contains the `last` cursor that follows it, so a long-running backfill can save the
+/// cursor after processing a page, and later resume by setting `since` to the saved cursor.
+///
+/// Trades at or after `end` are removed from the final page. The stream ends after the first error.
+pub fn recent_trades_pages<C>(
+    client: &mut C,
+    request: RecentTradesRequest,
+    end: Option<i64>,
+) -> impl Stream<Item = Result<RecentTrades, ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    stream::unfold(
+        (client, request, false),
+        move |(client, mut request, done)| async move {
+            if done {
+                return None;
+            }
+
+            let mut page = match client.get_recent_trades(&request).await {
+                Ok(ResultErrorResponse {
+                    result: Some(page), ..
+                }) => page,
+                Ok(_) => {
+                    let err = ClientError::Parse("Recent trades response contained no result");
+                    return Some((Err(err), (client, request, true)));
+                }
+                Err(err) => return Some((Err(err), (client, request, true))),
+            };
+
+            let n_trades: usize = page.trades.values().map(Vec::len).sum();
+            let caught_up = (n_trades as i64) < request.count.unwrap_or(MAX_RECENT_TRADES);
+            let mut done = caught_up || request.since == Some(page.last);
+
+            if let Some(end) = end {
+                for trades in page.trades.values_mut() {
+                    let n_before = trades.len();
+                    trades.retain(|trade| trade.time < end as f64);
+                    done |= trades.len() < n_before;
+                }
+            }
+
+            request.since = Some(page.last);
+            Some((Ok(page), (client, request, done)))
+        },
+    )
+}
+
+/// Stream all public trades for the requested pair, from `request.since` until `end` (a unix
+/// timestamp in seconds), or until caught up to the most recent trades if no `end` is given.
+///
+/// Trades are returned from oldest to newest. To checkpoint a long-running backfill, use
+/// [recent_trades_pages], which provides the cursor for resuming after each page.
+pub fn recent_trades_stream<C>(
+    client: &mut C,
+    request: RecentTradesRequest,
+    end: Option<i64>,
+) -> impl Stream<Item = Result<RecentTrade, ClientError>> + '_
+where
+    C: KrakenClient,
+{
+    recent_trades_pages(client, request, end).flat_map(|page| {
+        let trades = match page {
+            Ok(page) => page.trades.into_values().flatten().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        };
+        stream::iter(trades)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(ids: &[&str]) -> Vec<(String, ())> {
+        ids.iter().map(|id| (id.to_string(), ())).collect()
+    }
+
+    fn drain(paginator: &mut OffsetPaginator<()>) -> Vec<String> {
+        std::iter::from_fn(|| paginator.next_buffered())
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn test_paginator_advances_until_count() {
+        let mut paginator = OffsetPaginator::new(0);
+        assert_eq!(Some(0), paginator.next_offset());
+
+        paginator.push_page(page(&["a", "b"]), 3);
+        assert_eq!(Some(2), paginator.next_offset());
+        assert_eq!(vec!["a", "b"], drain(&mut paginator));
+
+        paginator.push_page(page(&["c"]), 3);
+        assert_eq!(None, paginator.next_offset());
+        assert_eq!(vec!["c"], drain(&mut paginator));
+    }
+
+    #[test]
+    fn test_paginator_skips_shifted_duplicates() {
+        let mut paginator = OffsetPaginator::new(0);
+
+        paginator.push_page(page(&["a", "b"]), 4);
+        // a new result was added, shifting "b" onto the next page
+        paginator.push_page(page(&["b", "c"]), 5);
+        paginator.push_page(page(&["d"]), 5);
+
+        assert_eq!(None, paginator.next_offset());
+        assert_eq!(vec!["a", "b", "c", "d"], drain(&mut paginator));
+    }
+
+    #[test]
+    fn test_paginator_stops_on_empty_page() {
+        let mut paginator = OffsetPaginator::new(50);
+
+        paginator.push_page(page(&[]), 100);
+
+        assert_eq!(None, paginator.next_offset());
+    }
+
+    #[test]
+    fn test_paginator_stops_at_id() {
+        let mut paginator = OffsetPaginator::new(0).stop_at("c".to_string());
+
+        paginator.push_page(page(&["a", "b"]), 10);
+        assert_eq!(Some(2), paginator.next_offset());
+
+        paginator.push_page(page(&["c", "d"]), 10);
+        assert_eq!(None, paginator.next_offset());
+        assert_eq!(vec!["a", "b"], drain(&mut paginator));
+    }
+
+    #[test]
+    fn test_paginator_reports_missing_stop_at() {
+        let mut paginator = OffsetPaginator::new(0).stop_at("z".to_string());
+
+        paginator.push_page(page(&["a", "b"]), 2);
+        assert_eq!(None, paginator.next_offset());
+        assert_eq!(None, paginator.take_missing_stop_at());
+
+        assert_eq!(vec!["a", "b"], drain(&mut paginator));
+        assert_eq!(Some("z".to_string()), paginator.take_missing_stop_at());
+        assert_eq!(None, paginator.take_missing_stop_at());
+    }
+
+    #[test]
+    fn test_paginator_forgets_old_pages() {
+        let mut paginator = OffsetPaginator::new(0);
+
+        for n in 0..10 {
+            paginator.push_page(page(&[&n.to_string()]), 100);
+        }
+
+        assert_eq!(SEEN_PAGES, paginator.seen.len());
+        assert_eq!(10, drain(&mut paginator).len());
+    }
+
+    #[test]
+    fn test_newest_first() {
+        let page = HashMap::from([
+            ("b".to_string(), 1.0),
+            ("a".to_string(), 2.0),
+            ("c".to_string(), 2.0),
+        ]);
+
+        let sorted: Vec<String> = newest_first(page, |time| Some(*time))
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+
+        assert_eq!(vec!["a", "c", "b"], sorted);
+    }
+
+    #[test]
+    fn test_paginator_finish() {
+        let mut paginator: OffsetPaginator<()> = OffsetPaginator::new(0);
+
+        paginator.finish();
+
+        assert_eq!(None, paginator.next_offset());
+    }
+}
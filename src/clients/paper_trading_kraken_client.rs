@@ -0,0 +1,622 @@
+//! A paper-trading [KrakenClient] that simulates fills against live market data
+use crate::clients::core_kraken_client::CoreKrakenClient;
+use crate::clients::errors::ClientError;
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::kraken_client::KrakenClient;
+use crate::crypto::nonce_provider::NonceProvider;
+use crate::fees::FeeCurrency;
+use crate::request_types::*;
+use crate::response_types::*;
+use crate::secrets::secrets_provider::SecretsProvider;
+use crate::simulated_exchange::matching_engine::NewOrder;
+use crate::simulated_exchange::paper_account::PaperAccount;
+use crate::wss::errors::WSSError;
+use crate::wss::v2::base_messages::WssMessage;
+use futures_util::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// A [KrakenClient] that trades a simulated account instead of real funds, while getting prices
+/// from the live exchange.
+///
+/// Public endpoints are forwarded to the wrapped client. `add_order`, `edit_order`,
+/// `cancel_order`, `get_open_orders`, `get_closed_orders`, `get_account_balance` and
+/// `get_trades_history` are served from a local account, and all other private endpoints fail
+/// with [ClientError::PaperTrading] without making any request.
+///
+/// Fills are driven by a v2 [KrakenMessageStream] subscribed to the `trade` and `book` channels of
+/// the traded pairs, passed to [PaperTradingKrakenClient::follow_market_data]:
+/// - orders that cross the book when added fill immediately as a taker, walking the book's levels
+/// - resting limit orders fill as a maker at their limit price when a public trade prints at or
+///   through the limit, or when the book moves through it
+/// - market orders are rejected until a book has been received for the pair
+///
+/// Fees use the `fees` and `fees_maker` tiers of the pair's [TradableAssetPair] for the paper
+/// account's volume over the last 30 days, counted in the pair's `fee_volume_currency`. Like
+/// Kraken, they're charged in the base asset for sells and the quote asset for buys, unless the
+/// order has the `fcib` or `fciq` flag. Pair details are fetched
+/// with `get_tradable_asset_pairs` the first time a pair is traded, or can be provided up front with
+/// [PaperTradingKrakenClient::add_pair].
+///
+/// The account starts empty, and is funded with [PaperTradingKrakenClient::deposit]. Clones share
+/// the same account.
+///
+/// ```no_run
+/// # use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+/// # use kraken_async_rs::clients::kraken_client::KrakenClient;
+/// # use kraken_async_rs::clients::paper_trading_kraken_client::PaperTradingKrakenClient;
+/// # use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+/// # use kraken_async_rs::request_types::AddOrderRequest;
+/// # use kraken_async_rs::response_types::{BuySell, OrderType};
+/// # use kraken_async_rs::secrets::secrets_provider::{SecretsProvider, StaticSecretsProvider};
+/// # use kraken_async_rs::wss::v2::base_messages::{Message, WssMessage};
+/// # use kraken_async_rs::wss::v2::kraken_wss_client::KrakenWSSClient;
+/// # use kraken_async_rs::wss::v2::market_data_messages::{BookSubscription, TradesSubscription};
+/// # use rust_decimal_macros::dec;
+/// # use std::sync::Arc;
+/// # use tokio::sync::Mutex;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+/// #     Box::new(Arc::new(Mutex::new(StaticSecretsProvider::new("", ""))));
+/// # let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+/// #     Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+/// let mut client: PaperTradingKrakenClient<CoreKrakenClient> =
+///     PaperTradingKrakenClient::new(secrets_provider, nonce_provider);
+/// client.deposit("ZUSD", dec!(10000)).await;
+///
+/// let mut wss_client = KrakenWSSClient::new();
+/// let mut stream = wss_client.connect::<WssMessage>().await.unwrap();
+/// let symbols = vec!["BTC/USD".into()];
+/// stream
+///     .send(&Message::new_subscription(TradesSubscription::new(symbols.clone()), 0))
+///     .await
+///     .unwrap();
+/// stream
+///     .send(&Message::new_subscription(BookSubscription::new(symbols), 1))
+///     .await
+///     .unwrap();
+/// client.follow_market_data(stream);
+///
+/// let order = AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(0.01), "XBTUSD".into())
+///     .price(dec!(50000))
+///     .build();
+/// client.add_order(&order).await.unwrap();
+/// # }
+/// ```
+///
+/// [KrakenMessageStream]: crate::wss::v2::kraken_wss_client::KrakenMessageStream
+#[derive(Debug, Clone)]
+pub struct PaperTradingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    core_client: C,
+    account: Arc<Mutex<PaperAccount>>,
+}
+
+impl<C> KrakenClient for PaperTradingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    fn new(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Self {
+        PaperTradingKrakenClient::new_with_client(C::new(secrets_provider, nonce_provider))
+    }
+
+    fn new_with_url(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        url: String,
+    ) -> Self {
+        PaperTradingKrakenClient::new_with_client(C::new_with_url(
+            secrets_provider,
+            nonce_provider,
+            url,
+        ))
+    }
+
+    async fn set_user_agent(&mut self, user_agent: String) {
+        self.core_client.set_user_agent(user_agent).await;
+    }
+
+    async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
+        self.core_client.get_server_time().await
+    }
+
+    async fn get_system_status(
+        &mut self,
+    ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
+        self.core_client.get_system_status().await
+    }
+
+    async fn get_asset_info(
+        &mut self,
+        request: &AssetInfoRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
+        self.core_client.get_asset_info(request).await
+    }
+
+    async fn get_tradable_asset_pairs(
+        &mut self,
+        request: &TradableAssetPairsRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
+        self.core_client.get_tradable_asset_pairs(request).await
+    }
+
+    async fn get_ticker_information(
+        &mut self,
+        request: &TickerRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
+        self.core_client.get_ticker_information(request).await
+    }
+
+    async fn get_ohlc(
+        &mut self,
+        request: &OHLCRequest,
+    ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
+        self.core_client.get_ohlc(request).await
+    }
+
+    async fn get_orderbook(
+        &mut self,
+        request: &OrderbookRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
+        self.core_client.get_orderbook(request).await
+    }
+
+    async fn get_recent_trades(
+        &mut self,
+        request: &RecentTradesRequest,
+    ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
+        self.core_client.get_recent_trades(request).await
+    }
+
+    async fn get_recent_spreads(
+        &mut self,
+        request: &RecentSpreadsRequest,
+    ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
+        self.core_client.get_recent_spreads(request).await
+    }
+
+    async fn get_account_balance(
+        &mut self,
+    ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
+        let balances = self.account.lock().await.balances();
+        respond(Ok(balances))
+    }
+
+    async fn get_extended_balances(
+        &mut self,
+    ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
+        Err(ClientError::PaperTrading("get_extended_balances"))
+    }
+
+    async fn get_trade_balances(
+        &mut self,
+        _request: &TradeBalanceRequest,
+    ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
+        Err(ClientError::PaperTrading("get_trade_balances"))
+    }
+
+    async fn get_open_orders(
+        &mut self,
+        request: &OpenOrdersRequest,
+    ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
+        let trades = request.trades.unwrap_or(false);
+        let open = self
+            .account
+            .lock()
+            .await
+            .open_orders(request.userref, trades);
+        respond(Ok(open))
+    }
+
+    async fn get_closed_orders(
+        &mut self,
+        request: &ClosedOrdersRequest,
+    ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
+        let trades = request.trades.unwrap_or(false);
+        let offset = request.offset.unwrap_or(0).max(0) as usize;
+        let closed = self
+            .account
+            .lock()
+            .await
+            .closed_orders(request.userref, trades, offset);
+        respond(Ok(closed))
+    }
+
+    async fn query_orders_info(
+        &mut self,
+        _request: &OrderRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
+        Err(ClientError::PaperTrading("query_orders_info"))
+    }
+
+    async fn get_trades_history(
+        &mut self,
+        request: &TradesHistoryRequest,
+    ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
+        let offset = request.offset.unwrap_or(0).max(0) as usize;
+        let trades = self.account.lock().await.trades_history(offset);
+        respond(Ok(trades))
+    }
+
+    async fn query_trades_info(
+        &mut self,
+        _request: &TradeInfoRequest,
+    ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
+        Err(ClientError::PaperTrading("query_trades_info"))
+    }
+
+    async fn get_open_positions(
+        &mut self,
+        _request: &OpenPositionsRequest,
+    ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
+        Err(ClientError::PaperTrading("get_open_positions"))
+    }
+
+    async fn get_ledgers_info(
+        &mut self,
+        _request: &LedgersInfoRequest,
+    ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
+        Err(ClientError::PaperTrading("get_ledgers_info"))
+    }
+
+    async fn query_ledgers(
+        &mut self,
+        _request: &QueryLedgerRequest,
+    ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
+        Err(ClientError::PaperTrading("query_ledgers"))
+    }
+
+    async fn get_trade_volume(
+        &mut self,
+        _request: &TradeVolumeRequest,
+    ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
+        Err(ClientError::PaperTrading("get_trade_volume"))
+    }
+
+    async fn request_export_report(
+        &mut self,
+        _request: &ExportReportRequest,
+    ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
+        Err(ClientError::PaperTrading("request_export_report"))
+    }
+
+    async fn get_export_report_status(
+        &mut self,
+        _request: &ExportReportStatusRequest,
+    ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
+        Err(ClientError::PaperTrading("get_export_report_status"))
+    }
+
+    async fn retrieve_export_report(
+        &mut self,
+        _request: &RetrieveExportReportRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        Err(ClientError::PaperTrading("retrieve_export_report"))
+    }
+
+    async fn delete_export_report(
+        &mut self,
+        _request: &DeleteExportRequest,
+    ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
+        Err(ClientError::PaperTrading("delete_export_report"))
+    }
+
+    async fn add_order(
+        &mut self,
+        request: &AddOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
+        self.load_pair(&request.pair).await?;
+
+        let order = NewOrder {
+            pair: request.pair.clone(),
+            side: request.side,
+            order_type: request.order_type,
+            volume: request.volume,
+            price: request.price,
+            user_ref: request.user_ref,
+            post_only: request
+                .order_flags
+                .as_ref()
+                .is_some_and(|flags| flags.to_string().split(',').any(|flag| flag == "post")),
+            fee_currency: FeeCurrency::for_order(request.side, request.order_flags.as_ref()),
+        };
+
+        let validate = request.validate.unwrap_or(false);
+        let result = self.account.lock().await.add_order(order, validate);
+
+        respond(result.map(|(tx_id, description)| {
+            json!({
+                "txid": tx_id.into_iter().collect::<Vec<String>>(),
+                "descr": {"order": description},
+            })
+        }))
+    }
+
+    async fn add_order_batch(
+        &mut self,
+        _request: &AddBatchedOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
+        Err(ClientError::PaperTrading("add_order_batch"))
+    }
+
+    async fn edit_order(
+        &mut self,
+        request: &EditOrderRequest,
+    ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
+        let validate = request.validate.unwrap_or(false);
+        let result = self.account.lock().await.edit_order(
+            &request.tx_id,
+            request.volume,
+            request.price,
+            request.user_ref,
+            validate,
+        );
+        respond(result)
+    }
+
+    async fn cancel_order(
+        &mut self,
+        request: &CancelOrderRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        let id = match &request.tx_id {
+            IntOrString::Int(user_ref) => user_ref.to_string(),
+            IntOrString::String(tx_id) => tx_id.clone(),
+        };
+
+        let result = self.account.lock().await.cancel_order(&id);
+        respond(result.map(|count| json!({ "count": count })))
+    }
+
+    async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        Err(ClientError::PaperTrading("cancel_all_orders"))
+    }
+
+    async fn cancel_all_orders_after(
+        &mut self,
+        _request: &CancelAllOrdersAfterRequest,
+    ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
+        Err(ClientError::PaperTrading("cancel_all_orders_after"))
+    }
+
+    async fn cancel_order_batch(
+        &mut self,
+        _request: &CancelBatchOrdersRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        Err(ClientError::PaperTrading("cancel_order_batch"))
+    }
+
+    async fn get_deposit_methods(
+        &mut self,
+        _request: &DepositMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
+        Err(ClientError::PaperTrading("get_deposit_methods"))
+    }
+
+    async fn get_deposit_addresses(
+        &mut self,
+        _request: &DepositAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
+        Err(ClientError::PaperTrading("get_deposit_addresses"))
+    }
+
+    async fn get_status_of_recent_deposits(
+        &mut self,
+        _request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
+        Err(ClientError::PaperTrading("get_status_of_recent_deposits"))
+    }
+
+    async fn get_withdrawal_methods(
+        &mut self,
+        _request: &WithdrawalMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
+        Err(ClientError::PaperTrading("get_withdrawal_methods"))
+    }
+
+    async fn get_withdrawal_addresses(
+        &mut self,
+        _request: &WithdrawalAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
+        Err(ClientError::PaperTrading("get_withdrawal_addresses"))
+    }
+
+    async fn get_withdrawal_info(
+        &mut self,
+        _request: &WithdrawalInfoRequest,
+    ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
+        Err(ClientError::PaperTrading("get_withdrawal_info"))
+    }
+
+    async fn withdraw_funds(
+        &mut self,
+        _request: &WithdrawFundsRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        Err(ClientError::PaperTrading("withdraw_funds"))
+    }
+
+    async fn get_status_of_recent_withdrawals(
+        &mut self,
+        _request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
+        Err(ClientError::PaperTrading(
+            "get_status_of_recent_withdrawals",
+        ))
+    }
+
+    async fn request_withdrawal_cancellation(
+        &mut self,
+        _request: &WithdrawCancelRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::PaperTrading("request_withdrawal_cancellation"))
+    }
+
+    async fn request_wallet_transfer(
+        &mut self,
+        _request: &WalletTransferRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        Err(ClientError::PaperTrading("request_wallet_transfer"))
+    }
+
+    async fn create_sub_account(
+        &mut self,
+        _request: &CreateSubAccountRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::PaperTrading("create_sub_account"))
+    }
+
+    async fn account_transfer(
+        &mut self,
+        _request: &AccountTransferRequest,
+    ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
+        Err(ClientError::PaperTrading("account_transfer"))
+    }
+
+    async fn allocate_earn_funds(
+        &mut self,
+        _request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::PaperTrading("allocate_earn_funds"))
+    }
+
+    async fn deallocate_earn_funds(
+        &mut self,
+        _request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        Err(ClientError::PaperTrading("deallocate_earn_funds"))
+    }
+
+    async fn get_earn_allocation_status(
+        &mut self,
+        _request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        Err(ClientError::PaperTrading("get_earn_allocation_status"))
+    }
+
+    async fn get_earn_deallocation_status(
+        &mut self,
+        _request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        Err(ClientError::PaperTrading("get_earn_deallocation_status"))
+    }
+
+    async fn list_earn_strategies(
+        &mut self,
+        _request: &ListEarnStrategiesRequest,
+    ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
+        Err(ClientError::PaperTrading("list_earn_strategies"))
+    }
+
+    async fn list_earn_allocations(
+        &mut self,
+        _request: &ListEarnAllocationsRequest,
+    ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
+        Err(ClientError::PaperTrading("list_earn_allocations"))
+    }
+
+    async fn get_websockets_token(
+        &mut self,
+    ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
+        Err(ClientError::PaperTrading("get_websockets_token"))
+    }
+}
+
+impl<C> PaperTradingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    /// Paper trade with an empty account, using `client` for public endpoints and pair details.
+    pub fn new_with_client(client: C) -> PaperTradingKrakenClient<C> {
+        PaperTradingKrakenClient {
+            core_client: client,
+            account: Arc::new(Mutex::new(PaperAccount::default())),
+        }
+    }
+
+    /// Add `amount` of an asset (e.g. `ZUSD`) to the account's balance.
+    pub async fn deposit(&self, asset: &str, amount: Decimal) {
+        self.account.lock().await.deposit(asset, amount);
+    }
+
+    /// Provide the details of a pair, instead of fetching them when it's first traded.
+    ///
+    /// Orders for the pair can use either `pair` or the `altname` of the details.
+    pub async fn add_pair(&self, pair: &str, details: &TradableAssetPair) {
+        self.account.lock().await.add_pair(pair, details);
+    }
+
+    /// Update the account with a single market data message, filling any orders it crosses.
+    ///
+    /// Only public `trade` and `book` messages are used, all others are ignored.
+    pub async fn process_message(&self, message: &WssMessage) {
+        self.account.lock().await.on_message(message);
+    }
+
+    /// Spawn a task that processes every message of a websocket stream, until the stream ends.
+    ///
+    /// Messages that fail to parse are logged and skipped.
+    pub fn follow_market_data<S>(&self, mut stream: S) -> JoinHandle<()>
+    where
+        S: Stream<Item = Result<WssMessage, WSSError>> + Unpin + Send + 'static,
+    {
+        let account = self.account.clone();
+
+        tokio::spawn(async move {
+            while let Some(message) = stream.next().await {
+                match message {
+                    Ok(message) => account.lock().await.on_message(&message),
+                    Err(err) => warn!("Paper trading skipped market data message: {:?}", err),
+                }
+            }
+        })
+    }
+
+    /// Fetch and register the pair's details if they haven't been provided yet.
+    async fn load_pair(&mut self, pair: &str) -> Result<(), ClientError> {
+        if self.account.lock().await.has_pair(pair) {
+            return Ok(());
+        }
+
+        let request = TradableAssetPairsRequest::builder()
+            .pair(StringCSV::new(vec![pair.to_string()]))
+            .build();
+        let pairs = self.core_client.get_tradable_asset_pairs(&request).await?;
+
+        let details = pairs.result.as_ref().and_then(|pairs| {
+            pairs
+                .iter()
+                .find(|(name, details)| *name == pair || details.alt_name == pair)
+                .map(|(_, details)| details)
+        });
+
+        if let Some(details) = details {
+            self.account.lock().await.add_pair(pair, details);
+        }
+
+        Ok(())
+    }
+}
+
+/// Wrap the result of a simulated call like a Kraken response, and parse it the same way.
+fn respond<T>(result: Result<Value, &'static str>) -> Result<ResultErrorResponse<T>, ClientError>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let body = match result {
+        Ok(result) => json!({"error": [], "result": result}),
+        Err(err) => json!({"error": [err]}),
+    };
+
+    CoreKrakenClient::parse_body_and_errors(&body.to_string())
+}
@@ -1,21 +1,27 @@
 //! A rate-limited [KrakenClient]
-use crate::clients::errors::ClientError;
+use crate::clients::errors::{ClientError, KrakenError};
 use crate::clients::http_response_types::ResultErrorResponse;
 use crate::clients::kraken_client::KrakenClient;
+use crate::clients::pagination;
 use crate::crypto::nonce_provider::NonceProvider;
+use crate::rate_limiting::adaptive_rate_limits::{
+    CooldownPolicy, Cooldowns, RateLimitEvent, RateLimiterKind,
+};
 use crate::rate_limiting::keyed_rate_limits::KeyedRateLimiter;
-use crate::rate_limiting::trading_rate_limits::KrakenTradingRateLimiter;
+use crate::rate_limiting::token_bucket::{RateLimitBudget, RequestPriority, TokenBucket};
+use crate::rate_limiting::trading_rate_limits::{KrakenTradingRateLimiter, TradingCost};
 use crate::request_types::*;
 use crate::response_types::*;
 use crate::secrets::secrets_provider::SecretsProvider;
-use async_rate_limit::limiters::{RateLimiter, VariableCostRateLimiter};
-use async_rate_limit::sliding_window::SlidingWindowRateLimiter;
-use async_rate_limit::token_bucket::{TokenBucketRateLimiter, TokenBucketState};
+use futures_util::Stream;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use time::OffsetDateTime;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+use tracing::warn;
+
+const RATE_LIMIT_EVENT_CAPACITY: usize = 100;
 
 /// A [KrakenClient] implementation that decorates a provided client, and applies rate limiting
 /// according to the Kraken API specs.
@@ -25,14 +31,22 @@ use tokio::sync::Mutex;
 /// - private endpoints follow a token-bucket rate limiting scheme, with some endpoints having higher costs
 /// - trading endpoints implement the Advanced version of Kraken's rate limiting scheme
 ///     - this includes tracking order lifetimes and applying penalties to rapid cancels and edits of orders
+///     - like Kraken's matching engine, each currency pair has its own trading rate limit
 ///
 /// The exact rate limit values and replenishment schedule are determined by a user's
 /// verification tier. Default new methods assume an `Intermediate` verification, so `Pro` users will
 /// want to rely on methods that allow providing a custom verification tier if they want to take full
 /// advantage of their increased rate limits (e.g. `new_with_verification_tier`).
 ///
-/// Calls made that violate the rate limiting policy are made to wait asynchronously, but no error handling
-/// is in place for receiving rate limit errors, these are to be handled/backed-off by the user.
+/// Waiting calls are served by [RequestPriority], so cancels are made before waiting orders on the
+/// same pair, and history queries wait for all other private calls. Cancels, including cancelling
+/// all orders, also skip any cooldown. See `with_priority`.
+///
+/// Calls made that violate the rate limiting policy are made to wait asynchronously. If Kraken still
+/// responds with a rate limit error (e.g. because other processes share the same API key), the
+/// matching limiter is paused according to the client's [CooldownPolicy], and a [RateLimitEvent] is
+/// sent to all subscribers of `subscribe_rate_limit_events`. The failed request itself is returned
+/// to the caller, and can be retried with backoff by wrapping this client in a [RetryingKrakenClient].
 ///
 /// Detailed documentation is available from several locations, including the [overview rate-limiting page],
 /// [api rate-limiting page] and [trading rate-limiting page]. It's worth noting that the token
@@ -54,16 +68,21 @@ use tokio::sync::Mutex;
 /// [overview rate-limiting page]: https://docs.kraken.com/rest/#section/Rate-Limits/Matching-Engine-Rate-Limits
 /// [api rate-limiting page]: https://support.kraken.com/hc/en-us/articles/206548367-What-are-the-API-rate-limits-#3
 /// [trading rate-limiting page]: https://support.kraken.com/hc/en-us/articles/360045239571-Trading-rate-limits
+/// [RetryingKrakenClient]: crate::clients::retrying_kraken_client::RetryingKrakenClient
 #[derive(Debug, Clone)]
 pub struct RateLimitedKrakenClient<C>
 where
     C: KrakenClient,
 {
     core_client: C,
-    private_rate_limiter: TokenBucketRateLimiter,
-    public_rate_limiter: SlidingWindowRateLimiter,
+    private_rate_limiter: TokenBucket,
+    public_rate_limiter: TokenBucket,
     trading_rate_limiter: KrakenTradingRateLimiter,
     pair_rate_limiter: KeyedRateLimiter<String>,
+    cooldowns: Cooldowns,
+    cooldown_policy: CooldownPolicy,
+    rate_limit_events: broadcast::Sender<RateLimitEvent>,
+    priority: Option<RequestPriority>,
 }
 
 impl<C> KrakenClient for RateLimitedKrakenClient<C>
@@ -80,6 +99,10 @@ where
             public_rate_limiter: Self::get_public_rate_limiter(),
             trading_rate_limiter: KrakenTradingRateLimiter::new(VerificationTier::Intermediate),
             pair_rate_limiter: KeyedRateLimiter::new(),
+            cooldowns: Cooldowns::new(),
+            cooldown_policy: CooldownPolicy::default(),
+            rate_limit_events: broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            priority: None,
         }
     }
 
@@ -94,6 +117,10 @@ where
             public_rate_limiter: Self::get_public_rate_limiter(),
             trading_rate_limiter: KrakenTradingRateLimiter::new(VerificationTier::Intermediate),
             pair_rate_limiter: KeyedRateLimiter::new(),
+            cooldowns: Cooldowns::new(),
+            cooldown_policy: CooldownPolicy::default(),
+            rate_limit_events: broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            priority: None,
         }
     }
 
@@ -102,90 +129,130 @@ where
     }
 
     async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_server_time().await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_server_time().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_system_status(
         &mut self,
     ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_system_status().await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_system_status().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_asset_info(
         &mut self,
         request: &AssetInfoRequest,
     ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_asset_info(request).await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_asset_info(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_tradable_asset_pairs(
         &mut self,
         request: &TradableAssetPairsRequest,
     ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_tradable_asset_pairs(request).await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_tradable_asset_pairs(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_ticker_information(
         &mut self,
         request: &TickerRequest,
     ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_ticker_information(request).await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_ticker_information(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_ohlc(
         &mut self,
         request: &OHLCRequest,
     ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
-        self.pair_rate_limiter
-            .wait_until_ready(request.pair.clone())
+        self.pair_rate_limit(&request.pair).await;
+        let response = self.core_client.get_ohlc(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Pair(request.pair.clone()))
             .await;
-        self.core_client.get_ohlc(request).await
+
+        response
     }
 
     async fn get_orderbook(
         &mut self,
         request: &OrderbookRequest,
     ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_orderbook(request).await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_orderbook(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_recent_trades(
         &mut self,
         request: &RecentTradesRequest,
     ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
-        self.pair_rate_limiter
-            .wait_until_ready(request.pair.clone())
+        self.pair_rate_limit(&request.pair).await;
+        self.public_rate_limit().await;
+        let response = self.core_client.get_recent_trades(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Pair(request.pair.clone()))
             .await;
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_recent_trades(request).await
+
+        response
     }
 
     async fn get_recent_spreads(
         &mut self,
         request: &RecentSpreadsRequest,
     ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
-        self.public_rate_limiter.wait_until_ready().await;
-        self.core_client.get_recent_spreads(request).await
+        self.public_rate_limit().await;
+        let response = self.core_client.get_recent_spreads(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Public)
+            .await;
+
+        response
     }
 
     async fn get_account_balance(
         &mut self,
     ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_account_balance().await
+        let response = self.core_client.get_account_balance().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_extended_balances(
         &mut self,
     ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_extended_balances().await
+        let response = self.core_client.get_extended_balances().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_trade_balances(
@@ -193,7 +260,11 @@ where
         request: &TradeBalanceRequest,
     ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_trade_balances(request).await
+        let response = self.core_client.get_trade_balances(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_open_orders(
@@ -201,15 +272,23 @@ where
         request: &OpenOrdersRequest,
     ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_open_orders(request).await
+        let response = self.core_client.get_open_orders(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_closed_orders(
         &mut self,
         request: &ClosedOrdersRequest,
     ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
-        self.private_rate_limit(200).await;
-        self.core_client.get_closed_orders(request).await
+        self.background_rate_limit(200).await;
+        let response = self.core_client.get_closed_orders(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn query_orders_info(
@@ -217,23 +296,35 @@ where
         request: &OrderRequest,
     ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.query_orders_info(request).await
+        let response = self.core_client.query_orders_info(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_trades_history(
         &mut self,
         request: &TradesHistoryRequest,
     ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
-        self.private_rate_limit(200).await;
-        self.core_client.get_trades_history(request).await
+        self.background_rate_limit(200).await;
+        let response = self.core_client.get_trades_history(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn query_trades_info(
         &mut self,
         request: &TradeInfoRequest,
     ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.query_trades_info(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.query_trades_info(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_open_positions(
@@ -241,23 +332,35 @@ where
         request: &OpenPositionsRequest,
     ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_open_positions(request).await
+        let response = self.core_client.get_open_positions(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_ledgers_info(
         &mut self,
         request: &LedgersInfoRequest,
     ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
-        self.private_rate_limit(200).await;
-        self.core_client.get_ledgers_info(request).await
+        self.background_rate_limit(200).await;
+        let response = self.core_client.get_ledgers_info(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn query_ledgers(
         &mut self,
         request: &QueryLedgerRequest,
     ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.query_ledgers(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.query_ledgers(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_trade_volume(
@@ -265,48 +368,73 @@ where
         request: &TradeVolumeRequest,
     ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_trade_volume(request).await
+        let response = self.core_client.get_trade_volume(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn request_export_report(
         &mut self,
         request: &ExportReportRequest,
     ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.request_export_report(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.request_export_report(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_export_report_status(
         &mut self,
         request: &ExportReportStatusRequest,
     ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.get_export_report_status(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.get_export_report_status(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn retrieve_export_report(
         &mut self,
         request: &RetrieveExportReportRequest,
     ) -> Result<Vec<u8>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.retrieve_export_report(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.retrieve_export_report(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn delete_export_report(
         &mut self,
         request: &DeleteExportRequest,
     ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
-        self.private_rate_limit(100).await;
-        self.core_client.delete_export_report(request).await
+        self.background_rate_limit(100).await;
+        let response = self.core_client.delete_export_report(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn add_order(
         &mut self,
         request: &AddOrderRequest,
     ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
-        self.trading_rate_limiter.add_order().await;
+        self.trading_cooldown(&request.pair).await;
+        let cost = self.trading_rate_limiter.add_order_cost(request);
+        self.trading_rate_limit(&cost, RequestPriority::Normal)
+            .await;
         let response = self.core_client.add_order(request).await;
-        self.notify_add_order(&response, request.user_ref).await;
+        self.observe_trading_rate_limits(&response, Some(&request.pair))
+            .await;
+        self.notify_add_order(&response, request).await;
 
         response
     }
@@ -315,8 +443,13 @@ where
         &mut self,
         request: &AddBatchedOrderRequest,
     ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
-        self.trading_rate_limiter.add_order_batch(request).await;
+        self.trading_cooldown(&request.pair).await;
+        let cost = self.trading_rate_limiter.add_order_batch_cost(request);
+        self.trading_rate_limit(&cost, RequestPriority::Normal)
+            .await;
         let response = self.core_client.add_order_batch(request).await;
+        self.observe_trading_rate_limits(&response, Some(&request.pair))
+            .await;
         self.notify_add_order_batched(&response, request).await;
 
         response
@@ -326,9 +459,15 @@ where
         &mut self,
         request: &EditOrderRequest,
     ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
-        self.trading_rate_limiter.edit_order(request).await;
+        let cost = self.trading_rate_limiter.edit_order_cost(request).await;
+        let pair = cost.pair.clone().unwrap_or_else(|| request.pair.clone());
+        self.trading_cooldown(&pair).await;
+        self.trading_rate_limit(&cost, RequestPriority::Normal)
+            .await;
         let response = self.core_client.edit_order(request).await;
-        self.notify_edit_order(&response, request.user_ref).await;
+        self.observe_trading_rate_limits(&response, Some(&pair))
+            .await;
+        self.notify_edit_order(&response, request).await;
         response
     }
 
@@ -336,27 +475,34 @@ where
         &mut self,
         request: &CancelOrderRequest,
     ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
-        match &request.tx_id {
-            IntOrString::Int(i) => {
-                self.trading_rate_limiter.cancel_order_user_ref(i).await;
-            }
-            IntOrString::String(s) => {
-                self.trading_rate_limiter.cancel_order_tx_id(s).await;
-            }
-        }
+        let pair = self.cancel_rate_limit(&request.tx_id).await;
+
+        let response = self.core_client.cancel_order(request).await;
+        self.observe_trading_rate_limits(&response, pair.as_deref())
+            .await;
 
-        self.core_client.cancel_order(request).await
+        response
     }
 
     async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
-        self.core_client.cancel_all_orders().await
+        self.cancel_all_rate_limit().await;
+        let response = self.core_client.cancel_all_orders().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn cancel_all_orders_after(
         &mut self,
         request: &CancelAllOrdersAfterRequest,
     ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
-        self.core_client.cancel_all_orders_after(request).await
+        self.cancel_all_rate_limit().await;
+        let response = self.core_client.cancel_all_orders_after(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     /// Clients can request to cancel in batches using both ref-ids produced by Kraken (Strings), or
@@ -365,20 +511,22 @@ where
         &mut self,
         request: &CancelBatchOrdersRequest,
     ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        let mut pairs = Vec::new();
         for order in &request.orders {
-            match order {
-                IntOrString::Int(user_ref) => {
-                    self.trading_rate_limiter
-                        .cancel_order_user_ref(user_ref)
-                        .await
-                }
-                IntOrString::String(tx_id) => {
-                    self.trading_rate_limiter.cancel_order_tx_id(tx_id).await
-                }
-            }
+            pairs.push(self.cancel_rate_limit(order).await);
         }
 
-        self.core_client.cancel_order_batch(request).await
+        // a rate limit error can only be attributed to a pair if the whole batch was on it
+        pairs.dedup();
+        let pair = match pairs.as_slice() {
+            [Some(pair)] => Some(pair.as_str()),
+            _ => None,
+        };
+
+        let response = self.core_client.cancel_order_batch(request).await;
+        self.observe_trading_rate_limits(&response, pair).await;
+
+        response
     }
 
     async fn get_deposit_methods(
@@ -386,7 +534,11 @@ where
         request: &DepositMethodsRequest,
     ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_deposit_methods(request).await
+        let response = self.core_client.get_deposit_methods(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_deposit_addresses(
@@ -394,7 +546,11 @@ where
         request: &DepositAddressesRequest,
     ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_deposit_addresses(request).await
+        let response = self.core_client.get_deposit_addresses(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_status_of_recent_deposits(
@@ -402,9 +558,14 @@ where
         request: &StatusOfDepositWithdrawRequest,
     ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client
+        let response = self
+            .core_client
             .get_status_of_recent_deposits(request)
-            .await
+            .await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_withdrawal_methods(
@@ -412,7 +573,11 @@ where
         request: &WithdrawalMethodsRequest,
     ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_withdrawal_methods(request).await
+        let response = self.core_client.get_withdrawal_methods(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_withdrawal_addresses(
@@ -420,7 +585,11 @@ where
         request: &WithdrawalAddressesRequest,
     ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_withdrawal_addresses(request).await
+        let response = self.core_client.get_withdrawal_addresses(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_withdrawal_info(
@@ -428,7 +597,11 @@ where
         request: &WithdrawalInfoRequest,
     ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_withdrawal_info(request).await
+        let response = self.core_client.get_withdrawal_info(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn withdraw_funds(
@@ -436,7 +609,11 @@ where
         request: &WithdrawFundsRequest,
     ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.withdraw_funds(request).await
+        let response = self.core_client.withdraw_funds(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_status_of_recent_withdrawals(
@@ -444,9 +621,14 @@ where
         request: &StatusOfDepositWithdrawRequest,
     ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client
+        let response = self
+            .core_client
             .get_status_of_recent_withdrawals(request)
-            .await
+            .await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn request_withdrawal_cancellation(
@@ -454,9 +636,14 @@ where
         request: &WithdrawCancelRequest,
     ) -> Result<ResultErrorResponse<bool>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client
+        let response = self
+            .core_client
             .request_withdrawal_cancellation(request)
-            .await
+            .await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn request_wallet_transfer(
@@ -464,7 +651,11 @@ where
         request: &WalletTransferRequest,
     ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.request_wallet_transfer(request).await
+        let response = self.core_client.request_wallet_transfer(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn create_sub_account(
@@ -472,7 +663,11 @@ where
         request: &CreateSubAccountRequest,
     ) -> Result<ResultErrorResponse<bool>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.create_sub_account(request).await
+        let response = self.core_client.create_sub_account(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn account_transfer(
@@ -480,7 +675,11 @@ where
         request: &AccountTransferRequest,
     ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.account_transfer(request).await
+        let response = self.core_client.account_transfer(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn allocate_earn_funds(
@@ -488,7 +687,11 @@ where
         request: &AllocateEarnFundsRequest,
     ) -> Result<ResultErrorResponse<bool>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.allocate_earn_funds(request).await
+        let response = self.core_client.allocate_earn_funds(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn deallocate_earn_funds(
@@ -496,7 +699,11 @@ where
         request: &AllocateEarnFundsRequest,
     ) -> Result<ResultErrorResponse<bool>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.deallocate_earn_funds(request).await
+        let response = self.core_client.deallocate_earn_funds(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_earn_allocation_status(
@@ -504,7 +711,11 @@ where
         request: &EarnAllocationStatusRequest,
     ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_earn_allocation_status(request).await
+        let response = self.core_client.get_earn_allocation_status(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_earn_deallocation_status(
@@ -512,7 +723,11 @@ where
         request: &EarnAllocationStatusRequest,
     ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_earn_deallocation_status(request).await
+        let response = self.core_client.get_earn_deallocation_status(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn list_earn_strategies(
@@ -520,7 +735,11 @@ where
         request: &ListEarnStrategiesRequest,
     ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.list_earn_strategies(request).await
+        let response = self.core_client.list_earn_strategies(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn list_earn_allocations(
@@ -528,14 +747,22 @@ where
         request: &ListEarnAllocationsRequest,
     ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.list_earn_allocations(request).await
+        let response = self.core_client.list_earn_allocations(request).await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 
     async fn get_websockets_token(
         &mut self,
     ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
         self.private_rate_limit(100).await;
-        self.core_client.get_websockets_token().await
+        let response = self.core_client.get_websockets_token().await;
+        self.observe_rate_limits(&response, RateLimiterKind::Private)
+            .await;
+
+        response
     }
 }
 
@@ -548,7 +775,7 @@ where
     async fn notify_add_order(
         &mut self,
         order_response: &Result<ResultErrorResponse<AddOrder>, ClientError>,
-        user_ref: Option<i64>,
+        request: &AddOrderRequest,
     ) {
         if let Ok(ResultErrorResponse {
             result: Some(result),
@@ -560,7 +787,8 @@ where
                     .notify_add_order(
                         tx_id.clone(),
                         OffsetDateTime::now_utc().unix_timestamp(),
-                        user_ref,
+                        request.user_ref,
+                        request.pair.clone(),
                     )
                     .await;
             }
@@ -579,12 +807,13 @@ where
             ..
         }) = order_response
         {
-            for (order, request) in result.orders.iter().zip(request.orders.iter()) {
+            for (order, order_request) in result.orders.iter().zip(request.orders.iter()) {
                 self.trading_rate_limiter
                     .notify_add_order(
                         order.tx_id.clone(),
                         OffsetDateTime::now_utc().unix_timestamp(),
-                        request.user_ref,
+                        order_request.user_ref,
+                        request.pair.clone(),
                     )
                     .await
             }
@@ -596,7 +825,7 @@ where
     async fn notify_edit_order(
         &mut self,
         order_response: &Result<ResultErrorResponse<OrderEdit>, ClientError>,
-        user_ref: Option<i64>,
+        request: &EditOrderRequest,
     ) {
         if let Ok(ResultErrorResponse {
             result: Some(result),
@@ -607,7 +836,8 @@ where
                 .notify_add_order(
                     result.tx_id.clone(),
                     OffsetDateTime::now_utc().unix_timestamp(),
-                    user_ref,
+                    request.user_ref,
+                    request.pair.clone(),
                 )
                 .await
         }
@@ -629,6 +859,10 @@ where
             public_rate_limiter: Self::get_public_rate_limiter(),
             trading_rate_limiter: KrakenTradingRateLimiter::new(verification),
             pair_rate_limiter: KeyedRateLimiter::new(),
+            cooldowns: Cooldowns::new(),
+            cooldown_policy: CooldownPolicy::default(),
+            rate_limit_events: broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            priority: None,
         }
     }
 
@@ -644,6 +878,10 @@ where
             public_rate_limiter: Self::get_public_rate_limiter(),
             trading_rate_limiter: KrakenTradingRateLimiter::new(verification),
             pair_rate_limiter: KeyedRateLimiter::new(),
+            cooldowns: Cooldowns::new(),
+            cooldown_policy: CooldownPolicy::default(),
+            rate_limit_events: broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            priority: None,
         }
     }
 
@@ -660,33 +898,310 @@ where
             public_rate_limiter: Self::get_public_rate_limiter(),
             trading_rate_limiter: KrakenTradingRateLimiter::new(verification),
             pair_rate_limiter: KeyedRateLimiter::new(),
+            cooldowns: Cooldowns::new(),
+            cooldown_policy: CooldownPolicy::default(),
+            rate_limit_events: broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            priority: None,
         }
     }
 
     /// Get a private endpoint rate limiter, depending on the user's verification level.
     ///
     /// This implements a more involved scheme.
-    pub fn get_private_rate_limiter(user_verification: VerificationTier) -> TokenBucketRateLimiter {
+    pub fn get_private_rate_limiter(user_verification: VerificationTier) -> TokenBucket {
         // tokens are scaled 100x from Kraken's floating-point method to keep as integers
         match user_verification {
-            VerificationTier::Intermediate => {
-                let token_bucket_state = TokenBucketState::new(2000, 50, Duration::from_secs(1));
-                TokenBucketRateLimiter::new(Arc::new(Mutex::new(token_bucket_state)))
+            VerificationTier::Intermediate => TokenBucket::new(2000, 50, Duration::from_secs(1)),
+            VerificationTier::Pro => TokenBucket::new(2000, 100, Duration::from_secs(1)),
+        }
+    }
+
+    /// Get a public rate limiter, which limits calls to 1 per second.
+    ///
+    /// Public calls cost a single, unscaled token.
+    pub fn get_public_rate_limiter() -> TokenBucket {
+        TokenBucket::new(1, 1, Duration::from_secs(1))
+    }
+
+    /// Stream all closed orders matching the request, rate limiting each page that's requested.
+    ///
+    /// See [pagination::closed_orders_stream] for details on de-duplication and resuming from a
+    /// checkpoint.
+    pub fn closed_orders_stream(
+        &mut self,
+        request: ClosedOrdersRequest,
+    ) -> impl Stream<Item = Result<(String, ClosedOrder), ClientError>> + '_ {
+        pagination::closed_orders_stream(self, request)
+    }
+
+    /// Stream all trades matching the request, rate limiting each page that's requested.
+    ///
+    /// See [pagination::trades_history_stream] for details, or
+    /// [pagination::trades_history_stream_since] for incrementally fetching new trades.
+    pub fn trades_history_stream(
+        &mut self,
+        request: TradesHistoryRequest,
+    ) -> impl Stream<Item = Result<(String, Trade), ClientError>> + '_ {
+        pagination::trades_history_stream(self, request)
+    }
+
+    /// Stream all ledger entries matching the request, rate limiting each page that's requested.
+    ///
+    /// See [pagination::ledgers_stream] for details, or [pagination::ledgers_stream_since] for
+    /// incrementally fetching new entries.
+    pub fn ledgers_stream(
+        &mut self,
+        request: LedgersInfoRequest,
+    ) -> impl Stream<Item = Result<(String, LedgerEntry), ClientError>> + '_ {
+        pagination::ledgers_stream(self, request)
+    }
+
+    /// Stream all public trades for a pair until `end`, applying the pair and public rate limits to
+    /// each page that's requested.
+    ///
+    /// See [pagination::recent_trades_stream] for details, or [pagination::recent_trades_pages] for
+    /// resuming from a saved cursor.
+    pub fn recent_trades_stream(
+        &mut self,
+        request: RecentTradesRequest,
+        end: Option<i64>,
+    ) -> impl Stream<Item = Result<RecentTrade, ClientError>> + '_ {
+        pagination::recent_trades_stream(self, request, end)
+    }
+
+    /// Subscribe to [RateLimitEvent]s, which are sent each time Kraken rejects a request for
+    /// exceeding a rate limit and the matching limiter is paused.
+    pub fn subscribe_rate_limit_events(&self) -> broadcast::Receiver<RateLimitEvent> {
+        self.rate_limit_events.subscribe()
+    }
+
+    /// Get the [CooldownPolicy] used to pause limiters after a rate limit error.
+    pub fn cooldown_policy(&self) -> CooldownPolicy {
+        self.cooldown_policy
+    }
+
+    /// Set the [CooldownPolicy] used to pause limiters after a rate limit error.
+    pub fn set_cooldown_policy(&mut self, cooldown_policy: CooldownPolicy) {
+        self.cooldown_policy = cooldown_policy;
+    }
+
+    /// Get the remaining cooldown for the given limiter, if it's currently paused.
+    pub async fn remaining_cooldown(&self, limiter: &RateLimiterKind) -> Option<Duration> {
+        self.cooldowns.remaining(limiter).await
+    }
+
+    /// Get a clone of this client that makes all requests with the given [RequestPriority],
+    /// instead of the default class of each endpoint. The clone shares this client's rate limits.
+    ///
+    /// By default, cancels (including cancelling all orders and the dead man's switch) are
+    /// [RequestPriority::Critical] and skip any cooldown, history queries (closed orders, trades,
+    /// ledgers and export reports) are [RequestPriority::Background], and all others are
+    /// [RequestPriority::Normal].
+    pub fn with_priority(&self, priority: RequestPriority) -> Self
+    where
+        C: Clone,
+    {
+        RateLimitedKrakenClient {
+            priority: Some(priority),
+            ..self.clone()
+        }
+    }
+
+    /// Get the trading rate limiter, e.g. to preview the cost of an edit or cancel with
+    /// [KrakenTradingRateLimiter::edit_order_cost]. The returned limiter shares this client's state,
+    /// so it can also be given to a [RateLimitedMessageStream] to count websocket orders against
+    /// the same limits.
+    ///
+    /// [RateLimitedMessageStream]: crate::wss::v2::rate_limited_message_stream::RateLimitedMessageStream
+    pub fn trading_rate_limiter(&self) -> KrakenTradingRateLimiter {
+        self.trading_rate_limiter.clone()
+    }
+
+    /// Get the remaining trading tokens for a pair, and the time until `cost` could be spent on it,
+    /// including any cooldown of the pair's trading limiter.
+    pub async fn trading_budget(&self, pair: &str, cost: usize) -> RateLimitBudget {
+        let budget = self.trading_rate_limiter.budget(pair, cost).await;
+        self.with_cooldown(budget, &RateLimiterKind::TradingPair(pair.to_string()))
+            .await
+    }
+
+    /// Get the remaining private tokens, and the time until `cost` could be spent, including any
+    /// cooldown of the private limiter.
+    pub async fn private_budget(&self, cost: usize) -> RateLimitBudget {
+        let budget = self.private_rate_limiter.budget(cost);
+        self.with_cooldown(budget, &RateLimiterKind::Private).await
+    }
+
+    /// Get the remaining public tokens, and the time until a public call could be made, including
+    /// any cooldown of the public limiter.
+    pub async fn public_budget(&self) -> RateLimitBudget {
+        let budget = self.public_rate_limiter.budget(1);
+        self.with_cooldown(budget, &RateLimiterKind::Public).await
+    }
+
+    async fn with_cooldown(
+        &self,
+        budget: RateLimitBudget,
+        limiter: &RateLimiterKind,
+    ) -> RateLimitBudget {
+        let cooldown = self.cooldowns.remaining(limiter).await.unwrap_or_default();
+
+        RateLimitBudget {
+            time_until_affordable: budget.time_until_affordable.max(cooldown),
+            ..budget
+        }
+    }
+
+    async fn public_rate_limit(&mut self) {
+        self.cooldowns
+            .wait_until_ready(&RateLimiterKind::Public)
+            .await;
+        self.public_rate_limiter
+            .acquire_with_priority(1, self.priority.unwrap_or_default())
+            .await
+    }
+
+    async fn pair_rate_limit(&mut self, pair: &str) {
+        self.cooldowns
+            .wait_until_ready(&RateLimiterKind::Pair(pair.to_string()))
+            .await;
+        self.pair_rate_limiter
+            .wait_until_ready(pair.to_string())
+            .await
+    }
+
+    /// Wait for a trading cost, using this client's priority if one was set with `with_priority`.
+    async fn trading_rate_limit(&self, cost: &TradingCost, priority: RequestPriority) {
+        self.trading_rate_limiter
+            .acquire(cost, self.priority.unwrap_or(priority))
+            .await
+    }
+
+    /// Wait for the cost of cancelling an order by tx id or user ref, as a critical request,
+    /// returning the pair it was charged to if the order is known.
+    ///
+    /// Critical cancels don't wait for the pair's cooldown, since reducing risk is worth the chance
+    /// of being rejected again.
+    async fn cancel_rate_limit(&self, order: &IntOrString) -> Option<String> {
+        let cost = match order {
+            IntOrString::Int(user_ref) => {
+                self.trading_rate_limiter
+                    .cancel_order_user_ref_cost(user_ref)
+                    .await
             }
-            VerificationTier::Pro => {
-                let token_bucket_state = TokenBucketState::new(2000, 100, Duration::from_secs(1));
-                TokenBucketRateLimiter::new(Arc::new(Mutex::new(token_bucket_state)))
+            IntOrString::String(tx_id) => {
+                self.trading_rate_limiter
+                    .cancel_order_tx_id_cost(tx_id)
+                    .await
             }
+        };
+
+        let priority = self.priority.unwrap_or(RequestPriority::Critical);
+        if let (Some(pair), false) = (&cost.pair, priority == RequestPriority::Critical) {
+            self.trading_cooldown(pair).await;
         }
+        self.trading_rate_limit(&cost, RequestPriority::Critical)
+            .await;
+
+        cost.pair
     }
 
-    /// Get a public rate limiter, which limits calls to 1 per second.
-    pub fn get_public_rate_limiter() -> SlidingWindowRateLimiter {
-        SlidingWindowRateLimiter::new(Duration::from_secs(1), 1)
+    /// Wait for the cost of cancelling all orders, or setting the dead man's switch, as a critical
+    /// private request. Like other critical cancels, this doesn't wait for any cooldown.
+    async fn cancel_all_rate_limit(&self) {
+        let priority = self.priority.unwrap_or(RequestPriority::Critical);
+        if priority != RequestPriority::Critical {
+            self.cooldowns
+                .wait_until_ready(&RateLimiterKind::Private)
+                .await;
+        }
+
+        self.private_rate_limiter
+            .acquire_with_priority(100, priority)
+            .await
+    }
+
+    /// Wait for any cooldown of the pair's trading limiter, leaving other pairs unaffected.
+    async fn trading_cooldown(&self, pair: &str) {
+        self.cooldowns
+            .wait_until_ready(&RateLimiterKind::TradingPair(pair.to_string()))
+            .await
     }
 
     async fn private_rate_limit(&mut self, cost: usize) {
-        self.private_rate_limiter.wait_with_cost(cost).await
+        self.private_rate_limit_with_priority(cost, RequestPriority::Normal)
+            .await
+    }
+
+    /// Rate limit history queries, which wait for all other private requests.
+    async fn background_rate_limit(&mut self, cost: usize) {
+        self.private_rate_limit_with_priority(cost, RequestPriority::Background)
+            .await
+    }
+
+    async fn private_rate_limit_with_priority(&mut self, cost: usize, priority: RequestPriority) {
+        self.cooldowns
+            .wait_until_ready(&RateLimiterKind::Private)
+            .await;
+        self.private_rate_limiter
+            .acquire_with_priority(cost, self.priority.unwrap_or(priority))
+            .await
+    }
+
+    /// Observe the response of an order entry request, pausing only the trading limiter of the
+    /// order's pair if Kraken reports a trading rate limit error. Errors for orders of an unknown
+    /// pair are reported as [RateLimiterKind::Trading].
+    async fn observe_trading_rate_limits<T>(
+        &self,
+        response: &Result<T, ClientError>,
+        pair: Option<&str>,
+    ) {
+        let limiter = match pair {
+            Some(pair) => RateLimiterKind::TradingPair(pair.to_string()),
+            None => RateLimiterKind::Trading,
+        };
+
+        self.observe_rate_limits(response, limiter).await
+    }
+
+    /// Start a cooldown and emit a [RateLimitEvent] if the response is a rate limit error.
+    ///
+    /// Trading rate limit errors pause the trading limiter of the request's pair if it has one,
+    /// while general API rate limit errors pause the limiter that was used for the request.
+    async fn observe_rate_limits<T>(
+        &self,
+        response: &Result<T, ClientError>,
+        limiter: RateLimiterKind,
+    ) {
+        let error = match response {
+            Err(ClientError::Kraken(error)) => error,
+            _ => return,
+        };
+
+        let limiter = match error {
+            KrakenError::TradingRateLimitExceeded(_) | KrakenError::DomainRateLimitExceeded(_) => {
+                match limiter {
+                    RateLimiterKind::TradingPair(_) => limiter,
+                    _ => RateLimiterKind::Trading,
+                }
+            }
+            KrakenError::RateLimitExceeded(_) => limiter,
+            _ => return,
+        };
+
+        let cooldown = self.cooldown_policy.cooldown(&limiter);
+        warn!(
+            "Rate limit exceeded for {:?} limiter, pausing for {:?}",
+            limiter, cooldown
+        );
+        self.cooldowns.start(limiter.clone(), cooldown).await;
+
+        // sending only fails when there are no subscribers
+        let _ = self.rate_limit_events.send(RateLimitEvent {
+            limiter,
+            error: error.clone(),
+            cooldown,
+        });
     }
 }
 
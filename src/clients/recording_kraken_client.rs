@@ -0,0 +1,1201 @@
+//! Record-and-replay [KrakenClient]s for deterministic tests
+//!
+//! [RecordingKrakenClient] captures every HTTP request and raw response made by a
+//! [CoreKrakenClient] into a [Cassette], which can be saved as JSON and served later by a
+//! [ReplayKrakenClient] without any network access.
+//!
+//! Recording happens at the transport level: responses are recorded as the raw body returned by
+//! Kraken, by swapping the HTTP transport of a [CoreKrakenClient]. Both clients therefore only wrap
+//! a [CoreKrakenClient], and can't record or replay an arbitrary [KrakenClient]. Any other
+//! decorator, like [RateLimitedKrakenClient] or [RetryingKrakenClient], is layered on top of them
+//! with its own `new_with_client` instead, and is exercised as normal during replay.
+//!
+//! [RateLimitedKrakenClient]: crate::clients::rate_limited_kraken_client::RateLimitedKrakenClient
+//! [RetryingKrakenClient]: crate::clients::retrying_kraken_client::RetryingKrakenClient
+use crate::clients::core_kraken_client::CoreKrakenClient;
+use crate::clients::errors::ClientError;
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::http_transport::HttpTransport;
+use crate::clients::kraken_client::KrakenClient;
+use crate::crypto::nonce_provider::NonceProvider;
+use crate::request_types::*;
+use crate::response_types::*;
+use crate::secrets::secrets_provider::SecretsProvider;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::future::BoxFuture;
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use url::form_urlencoded;
+
+/// Request parameters that are never written to a cassette, or used for matching.
+const REDACTED_PARAMS: [&str; 2] = ["nonce", "otp"];
+
+/// Response fields under `result` that are replaced with [REDACTED] when recorded.
+const REDACTED_RESULT_FIELDS: [&str; 1] = ["token"];
+
+pub const REDACTED: &str = "REDACTED";
+
+/// A recorded set of requests and their responses, stored as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+/// A single request and the response Kraken returned for it.
+///
+/// Requests are matched by `method`, `endpoint` and `params`, where `params` are the sorted query,
+/// form or json parameters of the request, excluding the nonce and any one-time password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub endpoint: String,
+    pub params: BTreeMap<String, String>,
+    pub response: RecordedResponse,
+}
+
+/// A response status and body, with non-UTF-8 bodies (e.g. zipped export reports) stored as base64.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+}
+
+impl Cassette {
+    /// Read a cassette previously written by [Cassette::save].
+    pub fn load(path: impl AsRef<Path>) -> Result<Cassette, std::io::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write the cassette as pretty-printed JSON, replacing any existing file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// The method, endpoint and normalized parameters used to match a request to an [Interaction].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RequestKey {
+    method: String,
+    endpoint: String,
+    params: BTreeMap<String, String>,
+}
+
+impl RequestKey {
+    fn from_request(request: &Request<String>) -> RequestKey {
+        let mut params = BTreeMap::new();
+
+        if let Some(query) = request.uri().query() {
+            add_form_params(&mut params, query);
+        }
+
+        let is_json = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .is_some_and(|content_type| content_type == "application/json");
+
+        if is_json {
+            if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(request.body()) {
+                for (key, value) in fields {
+                    let value = match value {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    add_param(&mut params, key, value);
+                }
+            }
+        } else {
+            add_form_params(&mut params, request.body());
+        }
+
+        RequestKey {
+            method: request.method().to_string(),
+            endpoint: request.uri().path().to_string(),
+            params,
+        }
+    }
+
+    fn matches(&self, interaction: &Interaction) -> bool {
+        self.method == interaction.method
+            && self.endpoint == interaction.endpoint
+            && self.params == interaction.params
+    }
+}
+
+fn add_form_params(params: &mut BTreeMap<String, String>, encoded: &str) {
+    for (key, value) in form_urlencoded::parse(encoded.as_bytes()) {
+        add_param(params, key.into_owned(), value.into_owned());
+    }
+}
+
+fn add_param(params: &mut BTreeMap<String, String>, key: String, value: String) {
+    if REDACTED_PARAMS.contains(&key.as_str()) {
+        return;
+    }
+
+    params
+        .entry(key)
+        .and_modify(|existing| {
+            existing.push(',');
+            existing.push_str(&value);
+        })
+        .or_insert(value);
+}
+
+fn redact_body(body: &str) -> Option<String> {
+    let mut json: Value = serde_json::from_str(body).ok()?;
+    let result = json.get_mut("result")?.as_object_mut()?;
+
+    let mut redacted = false;
+    for field in REDACTED_RESULT_FIELDS {
+        if let Some(value) = result.get_mut(field) {
+            *value = Value::String(REDACTED.to_string());
+            redacted = true;
+        }
+    }
+
+    redacted.then(|| json.to_string())
+}
+
+impl RecordedResponse {
+    fn new(status: StatusCode, bytes: &Bytes) -> RecordedResponse {
+        match std::str::from_utf8(bytes) {
+            Ok(body) => RecordedResponse {
+                status: status.as_u16(),
+                body: redact_body(body).unwrap_or_else(|| body.to_string()),
+                base64: false,
+            },
+            Err(_) => RecordedResponse {
+                status: status.as_u16(),
+                body: BASE64.encode(bytes),
+                base64: true,
+            },
+        }
+    }
+
+    fn to_response(&self) -> Result<(StatusCode, Bytes), ClientError> {
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|_| ClientError::Parse("Recorded response has an invalid status"))?;
+
+        let bytes = if self.base64 {
+            BASE64
+                .decode(&self.body)
+                .map_err(|_| ClientError::Parse("Recorded response has an invalid base64 body"))?
+        } else {
+            self.body.clone().into_bytes()
+        };
+
+        Ok((status, Bytes::from(bytes)))
+    }
+}
+
+#[derive(Debug)]
+struct RecordingTransport {
+    inner: Arc<dyn HttpTransport>,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl HttpTransport for RecordingTransport {
+    fn send(
+        &self,
+        request: Request<String>,
+    ) -> BoxFuture<'static, Result<(StatusCode, Bytes), ClientError>> {
+        let key = RequestKey::from_request(&request);
+        let response = self.inner.send(request);
+        let cassette = self.cassette.clone();
+
+        Box::pin(async move {
+            let (status, bytes) = response.await?;
+
+            cassette.lock().await.interactions.push(Interaction {
+                method: key.method,
+                endpoint: key.endpoint,
+                params: key.params,
+                response: RecordedResponse::new(status, &bytes),
+            });
+
+            Ok((status, bytes))
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ReplayState {
+    cassette: Cassette,
+    served: Vec<bool>,
+}
+
+impl ReplayState {
+    /// Find the first matching interaction that hasn't been served yet, falling back to the last
+    /// match once all have been served.
+    fn next_response(&mut self, key: &RequestKey) -> Option<&RecordedResponse> {
+        let matching: Vec<usize> = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| key.matches(interaction))
+            .map(|(i, _)| i)
+            .collect();
+
+        let index = matching
+            .iter()
+            .find(|i| !self.served[**i])
+            .or(matching.last())
+            .copied()?;
+
+        self.served[index] = true;
+        Some(&self.cassette.interactions[index].response)
+    }
+}
+
+#[derive(Debug)]
+struct ReplayTransport {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl HttpTransport for ReplayTransport {
+    fn send(
+        &self,
+        request: Request<String>,
+    ) -> BoxFuture<'static, Result<(StatusCode, Bytes), ClientError>> {
+        let key = RequestKey::from_request(&request);
+        let state = self.state.clone();
+
+        Box::pin(async move {
+            match state.lock().await.next_response(&key) {
+                Some(response) => response.to_response(),
+                None => Err(ClientError::Replay(format!(
+                    "No recorded response for {} {} with params {:?}",
+                    key.method, key.endpoint, key.params
+                ))),
+            }
+        })
+    }
+}
+
+/// A [KrakenClient] that records each request made by a [CoreKrakenClient] and its response into
+/// a [Cassette].
+///
+/// API keys, signatures, nonces and one-time passwords are never recorded, and websocket tokens in
+/// responses are replaced with [REDACTED].
+#[derive(Debug, Clone)]
+pub struct RecordingKrakenClient {
+    core_client: CoreKrakenClient,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+/// A [KrakenClient] that serves responses from a [Cassette] with no network access.
+///
+/// Requests are matched by method, endpoint and normalized parameters, and repeated requests are
+/// served the recorded responses in order, repeating the last once all have been served. Requests
+/// with no recorded response fail with `ClientError::Replay`.
+#[derive(Debug, Clone)]
+pub struct ReplayKrakenClient {
+    core_client: CoreKrakenClient,
+}
+
+impl KrakenClient for RecordingKrakenClient {
+    fn new(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Self {
+        RecordingKrakenClient::new_with_client(CoreKrakenClient::new(
+            secrets_provider,
+            nonce_provider,
+        ))
+    }
+
+    fn new_with_url(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        url: String,
+    ) -> Self {
+        RecordingKrakenClient::new_with_client(CoreKrakenClient::new_with_url(
+            secrets_provider,
+            nonce_provider,
+            url,
+        ))
+    }
+
+    async fn set_user_agent(&mut self, user_agent: String) {
+        self.core_client.set_user_agent(user_agent).await;
+    }
+
+    async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
+        self.core_client.get_server_time().await
+    }
+
+    async fn get_system_status(
+        &mut self,
+    ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
+        self.core_client.get_system_status().await
+    }
+
+    async fn get_asset_info(
+        &mut self,
+        request: &AssetInfoRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
+        self.core_client.get_asset_info(request).await
+    }
+
+    async fn get_tradable_asset_pairs(
+        &mut self,
+        request: &TradableAssetPairsRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
+        self.core_client.get_tradable_asset_pairs(request).await
+    }
+
+    async fn get_ticker_information(
+        &mut self,
+        request: &TickerRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
+        self.core_client.get_ticker_information(request).await
+    }
+
+    async fn get_ohlc(
+        &mut self,
+        request: &OHLCRequest,
+    ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
+        self.core_client.get_ohlc(request).await
+    }
+
+    async fn get_orderbook(
+        &mut self,
+        request: &OrderbookRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
+        self.core_client.get_orderbook(request).await
+    }
+
+    async fn get_recent_trades(
+        &mut self,
+        request: &RecentTradesRequest,
+    ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
+        self.core_client.get_recent_trades(request).await
+    }
+
+    async fn get_recent_spreads(
+        &mut self,
+        request: &RecentSpreadsRequest,
+    ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
+        self.core_client.get_recent_spreads(request).await
+    }
+
+    async fn get_account_balance(
+        &mut self,
+    ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
+        self.core_client.get_account_balance().await
+    }
+
+    async fn get_extended_balances(
+        &mut self,
+    ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
+        self.core_client.get_extended_balances().await
+    }
+
+    async fn get_trade_balances(
+        &mut self,
+        request: &TradeBalanceRequest,
+    ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
+        self.core_client.get_trade_balances(request).await
+    }
+
+    async fn get_open_orders(
+        &mut self,
+        request: &OpenOrdersRequest,
+    ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
+        self.core_client.get_open_orders(request).await
+    }
+
+    async fn get_closed_orders(
+        &mut self,
+        request: &ClosedOrdersRequest,
+    ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
+        self.core_client.get_closed_orders(request).await
+    }
+
+    async fn query_orders_info(
+        &mut self,
+        request: &OrderRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
+        self.core_client.query_orders_info(request).await
+    }
+
+    async fn get_trades_history(
+        &mut self,
+        request: &TradesHistoryRequest,
+    ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
+        self.core_client.get_trades_history(request).await
+    }
+
+    async fn query_trades_info(
+        &mut self,
+        request: &TradeInfoRequest,
+    ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
+        self.core_client.query_trades_info(request).await
+    }
+
+    async fn get_open_positions(
+        &mut self,
+        request: &OpenPositionsRequest,
+    ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
+        self.core_client.get_open_positions(request).await
+    }
+
+    async fn get_ledgers_info(
+        &mut self,
+        request: &LedgersInfoRequest,
+    ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
+        self.core_client.get_ledgers_info(request).await
+    }
+
+    async fn query_ledgers(
+        &mut self,
+        request: &QueryLedgerRequest,
+    ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
+        self.core_client.query_ledgers(request).await
+    }
+
+    async fn get_trade_volume(
+        &mut self,
+        request: &TradeVolumeRequest,
+    ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
+        self.core_client.get_trade_volume(request).await
+    }
+
+    async fn request_export_report(
+        &mut self,
+        request: &ExportReportRequest,
+    ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
+        self.core_client.request_export_report(request).await
+    }
+
+    async fn get_export_report_status(
+        &mut self,
+        request: &ExportReportStatusRequest,
+    ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
+        self.core_client.get_export_report_status(request).await
+    }
+
+    async fn retrieve_export_report(
+        &mut self,
+        request: &RetrieveExportReportRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        self.core_client.retrieve_export_report(request).await
+    }
+
+    async fn delete_export_report(
+        &mut self,
+        request: &DeleteExportRequest,
+    ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
+        self.core_client.delete_export_report(request).await
+    }
+
+    async fn add_order(
+        &mut self,
+        request: &AddOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
+        self.core_client.add_order(request).await
+    }
+
+    async fn add_order_batch(
+        &mut self,
+        request: &AddBatchedOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
+        self.core_client.add_order_batch(request).await
+    }
+
+    async fn edit_order(
+        &mut self,
+        request: &EditOrderRequest,
+    ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
+        self.core_client.edit_order(request).await
+    }
+
+    async fn cancel_order(
+        &mut self,
+        request: &CancelOrderRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order(request).await
+    }
+
+    async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_all_orders().await
+    }
+
+    async fn cancel_all_orders_after(
+        &mut self,
+        request: &CancelAllOrdersAfterRequest,
+    ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
+        self.core_client.cancel_all_orders_after(request).await
+    }
+
+    async fn cancel_order_batch(
+        &mut self,
+        request: &CancelBatchOrdersRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order_batch(request).await
+    }
+
+    async fn get_deposit_methods(
+        &mut self,
+        request: &DepositMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
+        self.core_client.get_deposit_methods(request).await
+    }
+
+    async fn get_deposit_addresses(
+        &mut self,
+        request: &DepositAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
+        self.core_client.get_deposit_addresses(request).await
+    }
+
+    async fn get_status_of_recent_deposits(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
+        self.core_client
+            .get_status_of_recent_deposits(request)
+            .await
+    }
+
+    async fn get_withdrawal_methods(
+        &mut self,
+        request: &WithdrawalMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
+        self.core_client.get_withdrawal_methods(request).await
+    }
+
+    async fn get_withdrawal_addresses(
+        &mut self,
+        request: &WithdrawalAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
+        self.core_client.get_withdrawal_addresses(request).await
+    }
+
+    async fn get_withdrawal_info(
+        &mut self,
+        request: &WithdrawalInfoRequest,
+    ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
+        self.core_client.get_withdrawal_info(request).await
+    }
+
+    async fn withdraw_funds(
+        &mut self,
+        request: &WithdrawFundsRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        self.core_client.withdraw_funds(request).await
+    }
+
+    async fn get_status_of_recent_withdrawals(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
+        self.core_client
+            .get_status_of_recent_withdrawals(request)
+            .await
+    }
+
+    async fn request_withdrawal_cancellation(
+        &mut self,
+        request: &WithdrawCancelRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client
+            .request_withdrawal_cancellation(request)
+            .await
+    }
+
+    async fn request_wallet_transfer(
+        &mut self,
+        request: &WalletTransferRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        self.core_client.request_wallet_transfer(request).await
+    }
+
+    async fn create_sub_account(
+        &mut self,
+        request: &CreateSubAccountRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.create_sub_account(request).await
+    }
+
+    async fn account_transfer(
+        &mut self,
+        request: &AccountTransferRequest,
+    ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
+        self.core_client.account_transfer(request).await
+    }
+
+    async fn allocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.allocate_earn_funds(request).await
+    }
+
+    async fn deallocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.deallocate_earn_funds(request).await
+    }
+
+    async fn get_earn_allocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_allocation_status(request).await
+    }
+
+    async fn get_earn_deallocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_deallocation_status(request).await
+    }
+
+    async fn list_earn_strategies(
+        &mut self,
+        request: &ListEarnStrategiesRequest,
+    ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
+        self.core_client.list_earn_strategies(request).await
+    }
+
+    async fn list_earn_allocations(
+        &mut self,
+        request: &ListEarnAllocationsRequest,
+    ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
+        self.core_client.list_earn_allocations(request).await
+    }
+
+    async fn get_websockets_token(
+        &mut self,
+    ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
+        self.core_client.get_websockets_token().await
+    }
+}
+
+impl RecordingKrakenClient {
+    /// Record all requests made by the given client, including any transport configured with
+    /// [CoreKrakenClient::builder].
+    ///
+    /// Only a [CoreKrakenClient] can be recorded, since recording replaces its HTTP transport to
+    /// capture raw responses before they're parsed. Decorators don't expose a transport, so they
+    /// should wrap the recording client instead, e.g. `RetryingKrakenClient<RecordingKrakenClient>`.
+    pub fn new_with_client(client: CoreKrakenClient) -> RecordingKrakenClient {
+        let cassette = Arc::new(Mutex::new(Cassette::default()));
+        let transport = Arc::new(RecordingTransport {
+            inner: client.transport(),
+            cassette: cassette.clone(),
+        });
+
+        RecordingKrakenClient {
+            core_client: client.with_transport(transport),
+            cassette,
+        }
+    }
+
+    /// Get a copy of everything recorded so far.
+    pub async fn cassette(&self) -> Cassette {
+        self.cassette.lock().await.clone()
+    }
+
+    /// Write everything recorded so far to a file, for use with [ReplayKrakenClient::from_file].
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        self.cassette.lock().await.save(path)
+    }
+}
+
+impl KrakenClient for ReplayKrakenClient {
+    fn new(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Self {
+        ReplayKrakenClient::new_with_cassette(secrets_provider, nonce_provider, Cassette::default())
+    }
+
+    fn new_with_url(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        _url: String,
+    ) -> Self {
+        ReplayKrakenClient::new_with_cassette(secrets_provider, nonce_provider, Cassette::default())
+    }
+
+    async fn set_user_agent(&mut self, user_agent: String) {
+        self.core_client.set_user_agent(user_agent).await;
+    }
+
+    async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
+        self.core_client.get_server_time().await
+    }
+
+    async fn get_system_status(
+        &mut self,
+    ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
+        self.core_client.get_system_status().await
+    }
+
+    async fn get_asset_info(
+        &mut self,
+        request: &AssetInfoRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
+        self.core_client.get_asset_info(request).await
+    }
+
+    async fn get_tradable_asset_pairs(
+        &mut self,
+        request: &TradableAssetPairsRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
+        self.core_client.get_tradable_asset_pairs(request).await
+    }
+
+    async fn get_ticker_information(
+        &mut self,
+        request: &TickerRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
+        self.core_client.get_ticker_information(request).await
+    }
+
+    async fn get_ohlc(
+        &mut self,
+        request: &OHLCRequest,
+    ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
+        self.core_client.get_ohlc(request).await
+    }
+
+    async fn get_orderbook(
+        &mut self,
+        request: &OrderbookRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
+        self.core_client.get_orderbook(request).await
+    }
+
+    async fn get_recent_trades(
+        &mut self,
+        request: &RecentTradesRequest,
+    ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
+        self.core_client.get_recent_trades(request).await
+    }
+
+    async fn get_recent_spreads(
+        &mut self,
+        request: &RecentSpreadsRequest,
+    ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
+        self.core_client.get_recent_spreads(request).await
+    }
+
+    async fn get_account_balance(
+        &mut self,
+    ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
+        self.core_client.get_account_balance().await
+    }
+
+    async fn get_extended_balances(
+        &mut self,
+    ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
+        self.core_client.get_extended_balances().await
+    }
+
+    async fn get_trade_balances(
+        &mut self,
+        request: &TradeBalanceRequest,
+    ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
+        self.core_client.get_trade_balances(request).await
+    }
+
+    async fn get_open_orders(
+        &mut self,
+        request: &OpenOrdersRequest,
+    ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
+        self.core_client.get_open_orders(request).await
+    }
+
+    async fn get_closed_orders(
+        &mut self,
+        request: &ClosedOrdersRequest,
+    ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
+        self.core_client.get_closed_orders(request).await
+    }
+
+    async fn query_orders_info(
+        &mut self,
+        request: &OrderRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
+        self.core_client.query_orders_info(request).await
+    }
+
+    async fn get_trades_history(
+        &mut self,
+        request: &TradesHistoryRequest,
+    ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
+        self.core_client.get_trades_history(request).await
+    }
+
+    async fn query_trades_info(
+        &mut self,
+        request: &TradeInfoRequest,
+    ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
+        self.core_client.query_trades_info(request).await
+    }
+
+    async fn get_open_positions(
+        &mut self,
+        request: &OpenPositionsRequest,
+    ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
+        self.core_client.get_open_positions(request).await
+    }
+
+    async fn get_ledgers_info(
+        &mut self,
+        request: &LedgersInfoRequest,
+    ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
+        self.core_client.get_ledgers_info(request).await
+    }
+
+    async fn query_ledgers(
+        &mut self,
+        request: &QueryLedgerRequest,
+    ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
+        self.core_client.query_ledgers(request).await
+    }
+
+    async fn get_trade_volume(
+        &mut self,
+        request: &TradeVolumeRequest,
+    ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
+        self.core_client.get_trade_volume(request).await
+    }
+
+    async fn request_export_report(
+        &mut self,
+        request: &ExportReportRequest,
+    ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
+        self.core_client.request_export_report(request).await
+    }
+
+    async fn get_export_report_status(
+        &mut self,
+        request: &ExportReportStatusRequest,
+    ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
+        self.core_client.get_export_report_status(request).await
+    }
+
+    async fn retrieve_export_report(
+        &mut self,
+        request: &RetrieveExportReportRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        self.core_client.retrieve_export_report(request).await
+    }
+
+    async fn delete_export_report(
+        &mut self,
+        request: &DeleteExportRequest,
+    ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
+        self.core_client.delete_export_report(request).await
+    }
+
+    async fn add_order(
+        &mut self,
+        request: &AddOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
+        self.core_client.add_order(request).await
+    }
+
+    async fn add_order_batch(
+        &mut self,
+        request: &AddBatchedOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
+        self.core_client.add_order_batch(request).await
+    }
+
+    async fn edit_order(
+        &mut self,
+        request: &EditOrderRequest,
+    ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
+        self.core_client.edit_order(request).await
+    }
+
+    async fn cancel_order(
+        &mut self,
+        request: &CancelOrderRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order(request).await
+    }
+
+    async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_all_orders().await
+    }
+
+    async fn cancel_all_orders_after(
+        &mut self,
+        request: &CancelAllOrdersAfterRequest,
+    ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
+        self.core_client.cancel_all_orders_after(request).await
+    }
+
+    async fn cancel_order_batch(
+        &mut self,
+        request: &CancelBatchOrdersRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        self.core_client.cancel_order_batch(request).await
+    }
+
+    async fn get_deposit_methods(
+        &mut self,
+        request: &DepositMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
+        self.core_client.get_deposit_methods(request).await
+    }
+
+    async fn get_deposit_addresses(
+        &mut self,
+        request: &DepositAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
+        self.core_client.get_deposit_addresses(request).await
+    }
+
+    async fn get_status_of_recent_deposits(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
+        self.core_client
+            .get_status_of_recent_deposits(request)
+            .await
+    }
+
+    async fn get_withdrawal_methods(
+        &mut self,
+        request: &WithdrawalMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
+        self.core_client.get_withdrawal_methods(request).await
+    }
+
+    async fn get_withdrawal_addresses(
+        &mut self,
+        request: &WithdrawalAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
+        self.core_client.get_withdrawal_addresses(request).await
+    }
+
+    async fn get_withdrawal_info(
+        &mut self,
+        request: &WithdrawalInfoRequest,
+    ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
+        self.core_client.get_withdrawal_info(request).await
+    }
+
+    async fn withdraw_funds(
+        &mut self,
+        request: &WithdrawFundsRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        self.core_client.withdraw_funds(request).await
+    }
+
+    async fn get_status_of_recent_withdrawals(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
+        self.core_client
+            .get_status_of_recent_withdrawals(request)
+            .await
+    }
+
+    async fn request_withdrawal_cancellation(
+        &mut self,
+        request: &WithdrawCancelRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client
+            .request_withdrawal_cancellation(request)
+            .await
+    }
+
+    async fn request_wallet_transfer(
+        &mut self,
+        request: &WalletTransferRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        self.core_client.request_wallet_transfer(request).await
+    }
+
+    async fn create_sub_account(
+        &mut self,
+        request: &CreateSubAccountRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.create_sub_account(request).await
+    }
+
+    async fn account_transfer(
+        &mut self,
+        request: &AccountTransferRequest,
+    ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
+        self.core_client.account_transfer(request).await
+    }
+
+    async fn allocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.allocate_earn_funds(request).await
+    }
+
+    async fn deallocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        self.core_client.deallocate_earn_funds(request).await
+    }
+
+    async fn get_earn_allocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_allocation_status(request).await
+    }
+
+    async fn get_earn_deallocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        self.core_client.get_earn_deallocation_status(request).await
+    }
+
+    async fn list_earn_strategies(
+        &mut self,
+        request: &ListEarnStrategiesRequest,
+    ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
+        self.core_client.list_earn_strategies(request).await
+    }
+
+    async fn list_earn_allocations(
+        &mut self,
+        request: &ListEarnAllocationsRequest,
+    ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
+        self.core_client.list_earn_allocations(request).await
+    }
+
+    async fn get_websockets_token(
+        &mut self,
+    ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
+        self.core_client.get_websockets_token().await
+    }
+}
+
+impl ReplayKrakenClient {
+    /// Serve responses from the given [Cassette].
+    ///
+    /// The providers are only used to sign requests as usual, so any placeholder secrets will do.
+    pub fn new_with_cassette(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        cassette: Cassette,
+    ) -> ReplayKrakenClient {
+        let served = vec![false; cassette.interactions.len()];
+        let transport = Arc::new(ReplayTransport {
+            state: Arc::new(Mutex::new(ReplayState { cassette, served })),
+        });
+
+        ReplayKrakenClient {
+            core_client: CoreKrakenClient::new(secrets_provider, nonce_provider)
+                .with_transport(transport),
+        }
+    }
+
+    /// Serve responses from a cassette file written by [RecordingKrakenClient::save].
+    pub fn from_file(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        path: impl AsRef<Path>,
+    ) -> Result<ReplayKrakenClient, std::io::Error> {
+        let cassette = Cassette::load(path)?;
+        Ok(ReplayKrakenClient::new_with_cassette(
+            secrets_provider,
+            nonce_provider,
+            cassette,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_key_from_form_request() {
+        let request = Request::post("https://api.kraken.com/0/private/ClosedOrders?b=2&a=1")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body("nonce=123&otp=456&trades=true&userref=7".to_string())
+            .unwrap();
+
+        let key = RequestKey::from_request(&request);
+
+        let expected: BTreeMap<String, String> =
+            [("a", "1"), ("b", "2"), ("trades", "true"), ("userref", "7")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+
+        assert_eq!("POST", key.method);
+        assert_eq!("/0/private/ClosedOrders", key.endpoint);
+        assert_eq!(expected, key.params);
+    }
+
+    #[test]
+    fn test_request_key_from_json_request() {
+        let body =
+            json!({"nonce": 123, "pair": "XBTUSD", "deadline": null, "orders": [{"volume": "1"}]});
+        let request = Request::post("https://api.kraken.com/0/private/AddOrderBatch")
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .unwrap();
+
+        let key = RequestKey::from_request(&request);
+
+        assert_eq!(3, key.params.len());
+        assert_eq!("XBTUSD", key.params["pair"]);
+        assert_eq!("null", key.params["deadline"]);
+        assert_eq!(r#"[{"volume":"1"}]"#, key.params["orders"]);
+    }
+
+    #[test]
+    fn test_redact_body() {
+        let body = json!({"result": {"token": "secret-token", "expires": 900}, "error": []});
+        let redacted: Value =
+            serde_json::from_str(&redact_body(&body.to_string()).unwrap()).unwrap();
+
+        assert_eq!(json!(REDACTED), redacted["result"]["token"]);
+        assert_eq!(json!(900), redacted["result"]["expires"]);
+
+        assert!(redact_body(r#"{"result": {"unixtime": 1}, "error": []}"#).is_none());
+        assert!(redact_body("not json").is_none());
+    }
+
+    #[test]
+    fn test_recorded_response_round_trip() {
+        let bytes = Bytes::from_static(&[0x50, 0x4b, 0x03, 0x04, 0xff]);
+        let recorded = RecordedResponse::new(StatusCode::OK, &bytes);
+
+        assert!(recorded.base64);
+        assert_eq!((StatusCode::OK, bytes), recorded.to_response().unwrap());
+
+        let bytes = Bytes::from_static(b"{\"error\":[]}");
+        let recorded = RecordedResponse::new(StatusCode::BAD_GATEWAY, &bytes);
+
+        assert!(!recorded.base64);
+        assert_eq!(
+            (StatusCode::BAD_GATEWAY, bytes),
+            recorded.to_response().unwrap()
+        );
+    }
+}
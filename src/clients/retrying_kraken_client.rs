@@ -0,0 +1,763 @@
+//! A retrying [KrakenClient]
+use crate::clients::errors::{ClientError, KrakenError};
+use crate::clients::http_response_types::ResultErrorResponse;
+use crate::clients::kraken_client::KrakenClient;
+use crate::crypto::nonce_provider::NonceProvider;
+use crate::request_types::*;
+use crate::response_types::*;
+use crate::secrets::secrets_provider::SecretsProvider;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::warn;
+
+/// Calls the given method on the inner client until it succeeds, fails with an error that is not
+/// retryable, or the [RetryPolicy] is exhausted.
+///
+/// `$retry_safe` determines if the call may be retried at all, which is false for calls that could
+/// be duplicated on the exchange if the first attempt was processed before failing. Alternatively,
+/// `@if` takes a predicate deciding from each error whether the call may be retried.
+macro_rules! retry {
+    (@if $self:ident, $retry_if:expr, $method:ident $(, $arg:expr)*) => {{
+        let retry_if = $retry_if;
+        let mut attempt = 0;
+
+        loop {
+            let response = $self.core_client.$method($($arg),*).await;
+
+            match &response {
+                Err(err) if retry_if(err) && $self.retry_policy.should_retry(err, attempt) => {
+                    let backoff = $self.retry_policy.backoff(attempt);
+                    warn!(
+                        "Retrying {} after {:?} (attempt {}): {}",
+                        stringify!($method),
+                        backoff,
+                        attempt + 1,
+                        err
+                    );
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                _ => break response,
+            }
+        }
+    }};
+    ($self:ident, $retry_safe:expr, $method:ident $(, $arg:expr)*) => {{
+        let retry_safe = $retry_safe;
+        retry!(@if $self, |_: &ClientError| retry_safe, $method $(, $arg)*)
+    }};
+}
+
+/// Configuration for how and when a [RetryingKrakenClient] retries failed calls.
+///
+/// The delay before retry `n` (starting at 0) is `initial_backoff * multiplier^n`, capped at
+/// `max_backoff`. A `jitter` of 0.0 applies no randomness, while a `jitter` of 1.0 applies "full
+/// jitter", uniformly choosing a delay between zero and the computed backoff.
+///
+/// Calls that are not idempotent, like `add_order`, are only retried if the error shows Kraken
+/// rejected the request without processing it (see [RetryPolicy::is_rejected]), if the orders carry
+/// a `cl_ord_id`, or if `retry_non_idempotent` is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Retry up to 3 times, starting at 500ms and doubling up to 10s, with 50% jitter.
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.5,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy that retries up to `max_retries` times with exponential backoff between
+    /// `initial_backoff` and `max_backoff`, using the default multiplier and jitter.
+    pub fn new(max_retries: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+            ..Default::default()
+        }
+    }
+
+    /// Create a policy that never retries.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if the error is transient and the `attempt` (starting at 0) is within the
+    /// maximum number of retries.
+    pub fn should_retry(&self, error: &ClientError, attempt: u32) -> bool {
+        attempt < self.max_retries && Self::is_retryable(error)
+    }
+
+    /// Determine if an error is transient, meaning the same request may succeed if tried again.
+    ///
+    /// This includes connection errors, timeouts, 5xx statuses, and Kraken's rate limit, lockout and service
+    /// availability errors.
+    pub fn is_retryable(error: &ClientError) -> bool {
+        match error {
+            ClientError::Hyper(_) | ClientError::HyperClient(_) | ClientError::Timeout(_) => true,
+            ClientError::HttpStatus(status, _) => (500..600).contains(status),
+            ClientError::Kraken(kraken_error) => matches!(
+                kraken_error,
+                KrakenError::RateLimitExceeded(_)
+                    | KrakenError::TemporaryLockout(_)
+                    | KrakenError::ServiceUnavailable(_)
+                    | KrakenError::ServiceBusy(_)
+            ),
+            _ => false,
+        }
+    }
+
+    /// Determine if an error shows Kraken rejected the request before processing it, so that even a
+    /// request that isn't idempotent can safely be sent again.
+    ///
+    /// Connection errors, timeouts and 5xx statuses are not rejections, since the request may have
+    /// been processed before the response was lost.
+    pub fn is_rejected(error: &ClientError) -> bool {
+        matches!(
+            error,
+            ClientError::Kraken(
+                KrakenError::RateLimitExceeded(_)
+                    | KrakenError::TemporaryLockout(_)
+                    | KrakenError::ServiceUnavailable(_)
+            )
+        )
+    }
+
+    /// Get the delay before making retry number `attempt` (starting at 0), including jitter.
+    ///
+    /// A policy that produces a negative or NaN delay, e.g. from a negative `multiplier`, waits for
+    /// `max_backoff` instead.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.initial_backoff.as_secs_f64()
+            * self.multiplier.powi(attempt.min(i32::MAX as u32) as i32);
+        let capped = exponential.min(self.max_backoff.as_secs_f64());
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let scale = if jitter > 0.0 {
+            1.0 - rand::thread_rng().gen_range(0.0..=jitter)
+        } else {
+            1.0
+        };
+
+        Duration::try_from_secs_f64(capped * scale).unwrap_or(self.max_backoff)
+    }
+}
+
+/// A [KrakenClient] implementation that decorates a provided client, and retries calls that fail
+/// with transient errors according to a [RetryPolicy].
+///
+/// Retried errors are connection-level errors (`ClientError::Hyper`, `ClientError::HyperClient`),
+/// 5xx HTTP statuses, and Kraken errors indicating the request can be tried again later, like
+/// `EAPI:Rate limit exceeded`, `EService:Unavailable` and `EGeneral:Temporary lockout`.
+///
+/// Calls that create something on the exchange are not retried by default, since a request may
+/// have been processed by Kraken before the error occurred. Orders (`add_order`, `add_order_batch`
+/// and `edit_order`) are only retried after errors that show Kraken rejected them unprocessed, like
+/// rate limits, lockouts and `EService:Unavailable`. New orders that carry a `cl_ord_id` (every
+/// order, for a batch) are also retried after connection errors, timeouts and 5xx statuses, since
+/// Kraken refuses a second order with the same client order id. Edits can't carry one, so after any
+/// other error the caller should reconcile with `get_open_orders` (e.g. by `user_ref`) before
+/// sending the order again. Funding, transfer, earn and report requests are only retried if the
+/// policy sets `retry_non_idempotent`.
+///
+/// This composes with [RateLimitedKrakenClient], e.g. `RetryingKrakenClient<RateLimitedKrakenClient<C>>`
+/// will wait on the rate limiters again for each retry.
+///
+/// [RateLimitedKrakenClient]: crate::clients::rate_limited_kraken_client::RateLimitedKrakenClient
+#[derive(Debug, Clone)]
+pub struct RetryingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    core_client: C,
+    retry_policy: RetryPolicy,
+}
+
+impl<C> KrakenClient for RetryingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    fn new(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+    ) -> Self {
+        RetryingKrakenClient {
+            core_client: C::new(secrets_provider, nonce_provider),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    fn new_with_url(
+        secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+        nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+        url: String,
+    ) -> Self {
+        RetryingKrakenClient {
+            core_client: C::new_with_url(secrets_provider, nonce_provider, url),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    async fn set_user_agent(&mut self, user_agent: String) {
+        self.core_client.set_user_agent(user_agent).await;
+    }
+
+    async fn get_server_time(&mut self) -> Result<ResultErrorResponse<SystemTime>, ClientError> {
+        retry!(self, true, get_server_time)
+    }
+
+    async fn get_system_status(
+        &mut self,
+    ) -> Result<ResultErrorResponse<SystemStatusInfo>, ClientError> {
+        retry!(self, true, get_system_status)
+    }
+
+    async fn get_asset_info(
+        &mut self,
+        request: &AssetInfoRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, AssetInfo>>, ClientError> {
+        retry!(self, true, get_asset_info, request)
+    }
+
+    async fn get_tradable_asset_pairs(
+        &mut self,
+        request: &TradableAssetPairsRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, TradableAssetPair>>, ClientError> {
+        retry!(self, true, get_tradable_asset_pairs, request)
+    }
+
+    async fn get_ticker_information(
+        &mut self,
+        request: &TickerRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, RestTickerInfo>>, ClientError> {
+        retry!(self, true, get_ticker_information, request)
+    }
+
+    async fn get_ohlc(
+        &mut self,
+        request: &OHLCRequest,
+    ) -> Result<ResultErrorResponse<OhlcResponse>, ClientError> {
+        retry!(self, true, get_ohlc, request)
+    }
+
+    async fn get_orderbook(
+        &mut self,
+        request: &OrderbookRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Orderbook>>, ClientError> {
+        retry!(self, true, get_orderbook, request)
+    }
+
+    async fn get_recent_trades(
+        &mut self,
+        request: &RecentTradesRequest,
+    ) -> Result<ResultErrorResponse<RecentTrades>, ClientError> {
+        retry!(self, true, get_recent_trades, request)
+    }
+
+    async fn get_recent_spreads(
+        &mut self,
+        request: &RecentSpreadsRequest,
+    ) -> Result<ResultErrorResponse<RecentSpreads>, ClientError> {
+        retry!(self, true, get_recent_spreads, request)
+    }
+
+    async fn get_account_balance(
+        &mut self,
+    ) -> Result<ResultErrorResponse<AccountBalances>, ClientError> {
+        retry!(self, true, get_account_balance)
+    }
+
+    async fn get_extended_balances(
+        &mut self,
+    ) -> Result<ResultErrorResponse<ExtendedBalances>, ClientError> {
+        retry!(self, true, get_extended_balances)
+    }
+
+    async fn get_trade_balances(
+        &mut self,
+        request: &TradeBalanceRequest,
+    ) -> Result<ResultErrorResponse<TradeBalances>, ClientError> {
+        retry!(self, true, get_trade_balances, request)
+    }
+
+    async fn get_open_orders(
+        &mut self,
+        request: &OpenOrdersRequest,
+    ) -> Result<ResultErrorResponse<OpenOrders>, ClientError> {
+        retry!(self, true, get_open_orders, request)
+    }
+
+    async fn get_closed_orders(
+        &mut self,
+        request: &ClosedOrdersRequest,
+    ) -> Result<ResultErrorResponse<ClosedOrders>, ClientError> {
+        retry!(self, true, get_closed_orders, request)
+    }
+
+    async fn query_orders_info(
+        &mut self,
+        request: &OrderRequest,
+    ) -> Result<ResultErrorResponse<HashMap<String, Order>>, ClientError> {
+        retry!(self, true, query_orders_info, request)
+    }
+
+    async fn get_trades_history(
+        &mut self,
+        request: &TradesHistoryRequest,
+    ) -> Result<ResultErrorResponse<TradesHistory>, ClientError> {
+        retry!(self, true, get_trades_history, request)
+    }
+
+    async fn query_trades_info(
+        &mut self,
+        request: &TradeInfoRequest,
+    ) -> Result<ResultErrorResponse<TradesInfo>, ClientError> {
+        retry!(self, true, query_trades_info, request)
+    }
+
+    async fn get_open_positions(
+        &mut self,
+        request: &OpenPositionsRequest,
+    ) -> Result<ResultErrorResponse<OpenPositions>, ClientError> {
+        retry!(self, true, get_open_positions, request)
+    }
+
+    async fn get_ledgers_info(
+        &mut self,
+        request: &LedgersInfoRequest,
+    ) -> Result<ResultErrorResponse<LedgerInfo>, ClientError> {
+        retry!(self, true, get_ledgers_info, request)
+    }
+
+    async fn query_ledgers(
+        &mut self,
+        request: &QueryLedgerRequest,
+    ) -> Result<ResultErrorResponse<QueryLedgerInfo>, ClientError> {
+        retry!(self, true, query_ledgers, request)
+    }
+
+    async fn get_trade_volume(
+        &mut self,
+        request: &TradeVolumeRequest,
+    ) -> Result<ResultErrorResponse<TradeVolume>, ClientError> {
+        retry!(self, true, get_trade_volume, request)
+    }
+
+    async fn request_export_report(
+        &mut self,
+        request: &ExportReportRequest,
+    ) -> Result<ResultErrorResponse<ExportReport>, ClientError> {
+        retry!(self, self.non_idempotent(), request_export_report, request)
+    }
+
+    async fn get_export_report_status(
+        &mut self,
+        request: &ExportReportStatusRequest,
+    ) -> Result<ResultErrorResponse<Vec<ExportReportStatus>>, ClientError> {
+        retry!(self, true, get_export_report_status, request)
+    }
+
+    async fn retrieve_export_report(
+        &mut self,
+        request: &RetrieveExportReportRequest,
+    ) -> Result<Vec<u8>, ClientError> {
+        retry!(self, true, retrieve_export_report, request)
+    }
+
+    async fn delete_export_report(
+        &mut self,
+        request: &DeleteExportRequest,
+    ) -> Result<ResultErrorResponse<DeleteExportReport>, ClientError> {
+        retry!(self, true, delete_export_report, request)
+    }
+
+    async fn add_order(
+        &mut self,
+        request: &AddOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrder>, ClientError> {
+        let retry_safe = self.non_idempotent() || request.cl_ord_id.is_some();
+        retry!(
+            @if self,
+            |err: &ClientError| retry_safe || RetryPolicy::is_rejected(err),
+            add_order,
+            request
+        )
+    }
+
+    async fn add_order_batch(
+        &mut self,
+        request: &AddBatchedOrderRequest,
+    ) -> Result<ResultErrorResponse<AddOrderBatch>, ClientError> {
+        let retry_safe = self.non_idempotent()
+            || (!request.orders.is_empty()
+                && request.orders.iter().all(|order| order.cl_ord_id.is_some()));
+        retry!(
+            @if self,
+            |err: &ClientError| retry_safe || RetryPolicy::is_rejected(err),
+            add_order_batch,
+            request
+        )
+    }
+
+    async fn edit_order(
+        &mut self,
+        request: &EditOrderRequest,
+    ) -> Result<ResultErrorResponse<OrderEdit>, ClientError> {
+        let non_idempotent = self.non_idempotent();
+        retry!(
+            @if self,
+            |err: &ClientError| non_idempotent || RetryPolicy::is_rejected(err),
+            edit_order,
+            request
+        )
+    }
+
+    async fn cancel_order(
+        &mut self,
+        request: &CancelOrderRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        retry!(self, true, cancel_order, request)
+    }
+
+    async fn cancel_all_orders(&mut self) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        retry!(self, true, cancel_all_orders)
+    }
+
+    async fn cancel_all_orders_after(
+        &mut self,
+        request: &CancelAllOrdersAfterRequest,
+    ) -> Result<ResultErrorResponse<CancelAllOrdersAfter>, ClientError> {
+        retry!(self, true, cancel_all_orders_after, request)
+    }
+
+    async fn cancel_order_batch(
+        &mut self,
+        request: &CancelBatchOrdersRequest,
+    ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
+        retry!(self, true, cancel_order_batch, request)
+    }
+
+    async fn get_deposit_methods(
+        &mut self,
+        request: &DepositMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositMethod>>, ClientError> {
+        retry!(self, true, get_deposit_methods, request)
+    }
+
+    async fn get_deposit_addresses(
+        &mut self,
+        request: &DepositAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositAddress>>, ClientError> {
+        retry!(self, true, get_deposit_addresses, request)
+    }
+
+    async fn get_status_of_recent_deposits(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<DepositWithdrawResponse>, ClientError> {
+        retry!(self, true, get_status_of_recent_deposits, request)
+    }
+
+    async fn get_withdrawal_methods(
+        &mut self,
+        request: &WithdrawalMethodsRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawMethod>>, ClientError> {
+        retry!(self, true, get_withdrawal_methods, request)
+    }
+
+    async fn get_withdrawal_addresses(
+        &mut self,
+        request: &WithdrawalAddressesRequest,
+    ) -> Result<ResultErrorResponse<Vec<WithdrawalAddress>>, ClientError> {
+        retry!(self, true, get_withdrawal_addresses, request)
+    }
+
+    async fn get_withdrawal_info(
+        &mut self,
+        request: &WithdrawalInfoRequest,
+    ) -> Result<ResultErrorResponse<Withdrawal>, ClientError> {
+        retry!(self, true, get_withdrawal_info, request)
+    }
+
+    async fn withdraw_funds(
+        &mut self,
+        request: &WithdrawFundsRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        retry!(self, self.non_idempotent(), withdraw_funds, request)
+    }
+
+    async fn get_status_of_recent_withdrawals(
+        &mut self,
+        request: &StatusOfDepositWithdrawRequest,
+    ) -> Result<ResultErrorResponse<Vec<DepositWithdrawal>>, ClientError> {
+        retry!(self, true, get_status_of_recent_withdrawals, request)
+    }
+
+    async fn request_withdrawal_cancellation(
+        &mut self,
+        request: &WithdrawCancelRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        retry!(self, true, request_withdrawal_cancellation, request)
+    }
+
+    async fn request_wallet_transfer(
+        &mut self,
+        request: &WalletTransferRequest,
+    ) -> Result<ResultErrorResponse<ConfirmationRefId>, ClientError> {
+        retry!(
+            self,
+            self.non_idempotent(),
+            request_wallet_transfer,
+            request
+        )
+    }
+
+    async fn create_sub_account(
+        &mut self,
+        request: &CreateSubAccountRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        retry!(self, self.non_idempotent(), create_sub_account, request)
+    }
+
+    async fn account_transfer(
+        &mut self,
+        request: &AccountTransferRequest,
+    ) -> Result<ResultErrorResponse<AccountTransfer>, ClientError> {
+        retry!(self, self.non_idempotent(), account_transfer, request)
+    }
+
+    async fn allocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        retry!(self, self.non_idempotent(), allocate_earn_funds, request)
+    }
+
+    async fn deallocate_earn_funds(
+        &mut self,
+        request: &AllocateEarnFundsRequest,
+    ) -> Result<ResultErrorResponse<bool>, ClientError> {
+        retry!(self, self.non_idempotent(), deallocate_earn_funds, request)
+    }
+
+    async fn get_earn_allocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        retry!(self, true, get_earn_allocation_status, request)
+    }
+
+    async fn get_earn_deallocation_status(
+        &mut self,
+        request: &EarnAllocationStatusRequest,
+    ) -> Result<ResultErrorResponse<AllocationStatus>, ClientError> {
+        retry!(self, true, get_earn_deallocation_status, request)
+    }
+
+    async fn list_earn_strategies(
+        &mut self,
+        request: &ListEarnStrategiesRequest,
+    ) -> Result<ResultErrorResponse<EarnStrategies>, ClientError> {
+        retry!(self, true, list_earn_strategies, request)
+    }
+
+    async fn list_earn_allocations(
+        &mut self,
+        request: &ListEarnAllocationsRequest,
+    ) -> Result<ResultErrorResponse<EarnAllocations>, ClientError> {
+        retry!(self, true, list_earn_allocations, request)
+    }
+
+    async fn get_websockets_token(
+        &mut self,
+    ) -> Result<ResultErrorResponse<WebsocketToken>, ClientError> {
+        retry!(self, true, get_websockets_token)
+    }
+}
+
+impl<C> RetryingKrakenClient<C>
+where
+    C: KrakenClient,
+{
+    /// Create a new retrying client that delegates calls to any type that implements [KrakenClient].
+    pub fn new_with_client(client: C, retry_policy: RetryPolicy) -> RetryingKrakenClient<C> {
+        RetryingKrakenClient {
+            core_client: client,
+            retry_policy,
+        }
+    }
+
+    /// Get the current [RetryPolicy].
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Replace the [RetryPolicy] used for all subsequent calls.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    fn non_idempotent(&self) -> bool {
+        self.retry_policy.retry_non_idempotent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::errors::{ClientError, KrakenError};
+    use crate::clients::retrying_kraken_client::RetryPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_retryable() {
+        let cases = vec![
+            (ClientError::HttpStatus(500, "".to_string()), true),
+            (ClientError::HttpStatus(503, "".to_string()), true),
+            (ClientError::HttpStatus(404, "".to_string()), false),
+            (
+                ClientError::Kraken(KrakenError::RateLimitExceeded(
+                    "EAPI:Rate limit exceeded".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::ServiceUnavailable(
+                    "EService:Unavailable".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::TemporaryLockout(
+                    "EGeneral:Temporary lockout".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::InvalidNonce("EAPI:Invalid nonce".to_string())),
+                false,
+            ),
+            (
+                ClientError::Kraken(KrakenError::PermissionDenied(
+                    "EGeneral:Permission denied".to_string(),
+                )),
+                false,
+            ),
+            (ClientError::Parse("bad body"), false),
+            (ClientError::Timeout("read"), true),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(expected, RetryPolicy::is_retryable(&error), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_rejected() {
+        let cases = vec![
+            (
+                ClientError::Kraken(KrakenError::RateLimitExceeded(
+                    "EAPI:Rate limit exceeded".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::TemporaryLockout(
+                    "EGeneral:Temporary lockout".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::ServiceUnavailable(
+                    "EService:Unavailable".to_string(),
+                )),
+                true,
+            ),
+            (
+                ClientError::Kraken(KrakenError::ServiceBusy("EService:Busy".to_string())),
+                false,
+            ),
+            (ClientError::HttpStatus(502, "".to_string()), false),
+            (ClientError::Timeout("read"), false),
+        ];
+
+        for (error, expected) in cases {
+            assert_eq!(expected, RetryPolicy::is_rejected(&error), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_should_retry_respects_max_retries() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(10));
+        let error = ClientError::Kraken(KrakenError::ServiceUnavailable(
+            "EService:Unavailable".to_string(),
+        ));
+
+        assert!(policy.should_retry(&error, 0));
+        assert!(policy.should_retry(&error, 1));
+        assert!(!policy.should_retry(&error, 2));
+        assert!(!RetryPolicy::none().should_retry(&error, 0));
+    }
+
+    #[test]
+    fn test_backoff_without_jitter() {
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1))
+        };
+
+        assert_eq!(Duration::from_millis(100), policy.backoff(0));
+        assert_eq!(Duration::from_millis(200), policy.backoff(1));
+        assert_eq!(Duration::from_millis(800), policy.backoff(3));
+        assert_eq!(Duration::from_secs(1), policy.backoff(4));
+        assert_eq!(Duration::from_secs(1), policy.backoff(u32::MAX));
+    }
+
+    #[test]
+    fn test_backoff_with_jitter() {
+        let policy = RetryPolicy {
+            jitter: 1.0,
+            ..RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1))
+        };
+
+        for attempt in 0..10 {
+            let backoff = policy.backoff(attempt);
+            let max = Duration::from_millis(100 * 2_u64.pow(attempt)).min(Duration::from_secs(1));
+            assert!(backoff <= max);
+        }
+    }
+
+    #[test]
+    fn test_backoff_with_degenerate_multiplier() {
+        for multiplier in [-2.0, f64::NAN] {
+            let policy = RetryPolicy {
+                multiplier,
+                jitter: 0.0,
+                ..RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1))
+            };
+
+            assert_eq!(Duration::from_secs(1), policy.backoff(1));
+        }
+    }
+}
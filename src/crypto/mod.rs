@@ -1,5 +1,6 @@
 //! Signature generation, nonce providing, etc
 pub mod nonce_provider;
 pub mod nonce_request;
+pub mod request_signer;
 pub mod secrets;
 pub mod signatures;
@@ -1,20 +1,36 @@
 //! Trait and implementation for providing request nonces
-#[allow(unused)]
+use crate::clients::errors::ClientError;
 use crate::clients::kraken_client::KrakenClient;
+use fs4::fs_std::FileExt;
+use std::ffi::OsString;
 use std::fmt::Debug;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Resolution of Kraken's server time, below which clock offsets can't be measured.
+const SERVER_TIME_RESOLUTION_MS: i64 = 1000;
 
 /// A nonce generator that should be used to populate the nonce of every request created by a
 /// [KrakenClient].
 pub trait NonceProvider: Send + Sync + Debug {
     fn get_nonce(&mut self) -> u64;
+
+    /// Generate a nonce, returning an error instead of panicking if one can't be generated safely.
+    ///
+    /// Defaults to [NonceProvider::get_nonce] for providers that can't fail.
+    fn try_get_nonce(&mut self) -> Result<u64, std::io::Error> {
+        Ok(self.get_nonce())
+    }
 }
 
 /// A nonce generator that gives the current epoch in milliseconds, except when called in the same
 /// millisecond, in which case it increases the nonce by 1 so no duplicates are ever returned.
 ///
 /// You may wish to consider setting a `nonce window` on your API key to allow for out of order nonces
-/// within several seconds of each other. Consult the [Kraken docs] for instructions and trade-offs.
+/// within several seconds of each other. Consult the [Kraken docs] for instructions and trade-offs,
+/// and see [ServerTimeNonceProvider] for a provider that accounts for the window.
 ///
 /// [Kraken docs]: https://support.kraken.com/hc/en-us/articles/360001148023-What-is-a-nonce-window-
 #[derive(Debug, Clone, Copy)]
@@ -53,9 +69,281 @@ impl NonceProvider for IncreasingNonceProvider {
     }
 }
 
+/// A nonce generator that persists the highest nonce it has used to a file, so nonces never go
+/// backwards across restarts, and several local processes can share one API key.
+///
+/// Nonces are the current epoch in milliseconds, or one more than the highest nonce in the file if
+/// that is greater. Each access to the file holds an exclusive lock on a sibling `.lock` file, and
+/// the new high-water mark is written to a temporary file and renamed over the original, so a crash
+/// never leaves a partially written file. The file and the directory holding it are synced after
+/// each write, so a reserved nonce is never lost to a power failure.
+///
+/// By default, the file is read and written for every nonce. With [FileNonceProvider::block_size],
+/// a block of nonces is reserved at once and handed out from memory until it is used up. Processes
+/// sharing a key then send nonces from different blocks out of order, which requires a `nonce
+/// window` on the API key (see [IncreasingNonceProvider]).
+///
+/// If the file can't be locked, read or written, [NonceProvider::try_get_nonce] returns the error
+/// (and [NonceProvider::get_nonce] panics) rather than risk reusing a nonce another process sent.
+#[derive(Debug)]
+pub struct FileNonceProvider {
+    path: PathBuf,
+    lock_path: PathBuf,
+    block_size: u64,
+    next: u64,
+    reserved: u64,
+}
+
+impl FileNonceProvider {
+    /// Create a provider that persists nonces to `path`.
+    ///
+    /// The sibling `.lock` file is created immediately, while the nonce file itself is only created
+    /// when the first nonce is reserved. Fails if the lock file can't be created, or an existing
+    /// nonce file can't be read or doesn't contain a nonce.
+    pub fn new(path: impl Into<PathBuf>) -> Result<FileNonceProvider, std::io::Error> {
+        let path = path.into();
+
+        let mut lock_path = OsString::from(path.as_os_str());
+        lock_path.push(".lock");
+
+        let provider = FileNonceProvider {
+            path,
+            lock_path: PathBuf::from(lock_path),
+            block_size: 1,
+            next: 0,
+            reserved: 0,
+        };
+
+        let lock = provider.lock()?;
+        read_high_water_mark(&provider.path)?;
+        drop(lock);
+
+        Ok(provider)
+    }
+
+    /// Reserve `block_size` nonces each time the file is accessed, instead of one.
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    fn lock(&self) -> Result<File, std::io::Error> {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)?;
+        lock.lock_exclusive()?;
+        Ok(lock)
+    }
+
+    /// Reserve the next block of nonces, returning its first nonce.
+    fn reserve(&mut self) -> Result<u64, std::io::Error> {
+        let _lock = self.lock()?;
+
+        let start = now_millis()
+            .max(read_high_water_mark(&self.path)? + 1)
+            .max(self.next);
+        let end = start + self.block_size;
+
+        write_high_water_mark(&self.path, end - 1)?;
+
+        self.reserved = end;
+        Ok(start)
+    }
+}
+
+impl NonceProvider for FileNonceProvider {
+    /// Returns the next nonce, panicking if the file can't be accessed.
+    fn get_nonce(&mut self) -> u64 {
+        self.try_get_nonce().unwrap_or_else(|err| {
+            panic!(
+                "Failed to persist nonce to {}: {}",
+                self.path.display(),
+                err
+            )
+        })
+    }
+
+    /// Returns the next nonce of the reserved block, reserving a new block if needed.
+    fn try_get_nonce(&mut self) -> Result<u64, std::io::Error> {
+        if self.next >= self.reserved {
+            self.next = self.reserve()?;
+        }
+
+        let nonce = self.next;
+        self.next += 1;
+        Ok(nonce)
+    }
+}
+
+/// A nonce generator that adjusts the local clock by its offset from Kraken's server time, so
+/// nonces stay valid alongside other hosts using the same API key when the local clock is skewed.
+///
+/// The offset is measured with [ServerTimeNonceProvider::calibrate], which should be done on
+/// startup and periodically after. To avoid holding a lock on a shared provider during the request,
+/// use [ServerTimeNonceProvider::measure_offset] and [ServerTimeNonceProvider::set_offset] instead.
+///
+/// Nonces never decrease, even if a calibration moves the clock backwards. Like
+/// [IncreasingNonceProvider], the last nonce + 1 is returned if a duplicate would be generated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServerTimeNonceProvider {
+    offset_ms: i64,
+    nonce_window_ms: i64,
+    last: u64,
+}
+
+impl ServerTimeNonceProvider {
+    pub fn new() -> Self {
+        ServerTimeNonceProvider::default()
+    }
+
+    /// Set the nonce window configured for the API key, which can only be changed in Kraken's API
+    /// key settings.
+    ///
+    /// Kraken accepts nonces up to the window behind the highest nonce it has seen, so clock
+    /// offsets within the window are ignored rather than moving nonces forward or backward.
+    pub fn nonce_window(mut self, nonce_window: Duration) -> Self {
+        self.nonce_window_ms = nonce_window.as_millis() as i64;
+        self
+    }
+
+    /// The current offset from the local clock to Kraken's server time, in milliseconds.
+    pub fn offset(&self) -> i64 {
+        self.offset_ms
+    }
+
+    /// Set the offset from the local clock to Kraken's server time, in milliseconds.
+    ///
+    /// Offsets below the one-second resolution of server time, or within the nonce window, are
+    /// treated as no offset.
+    pub fn set_offset(&mut self, offset_ms: i64) {
+        let tolerance = SERVER_TIME_RESOLUTION_MS.max(self.nonce_window_ms);
+
+        self.offset_ms = if offset_ms.abs() <= tolerance {
+            0
+        } else {
+            offset_ms
+        };
+    }
+
+    /// Measure the offset of the local clock from Kraken's server time in milliseconds, assuming
+    /// the server time was taken halfway through the request.
+    pub async fn measure_offset<C: KrakenClient>(client: &mut C) -> Result<i64, ClientError> {
+        let before = now_millis() as i64;
+        let server_time = client
+            .get_server_time()
+            .await?
+            .result
+            .ok_or(ClientError::Parse(
+                "Server time response contained no result",
+            ))?;
+        let after = now_millis() as i64;
+
+        // server time is truncated to the second, so its midpoint is the best estimate
+        let server_millis = server_time.unix_time * 1000 + SERVER_TIME_RESOLUTION_MS / 2;
+
+        Ok(server_millis - (before + after) / 2)
+    }
+
+    /// Measure and set the offset of the local clock from Kraken's server time.
+    pub async fn calibrate<C: KrakenClient>(&mut self, client: &mut C) -> Result<(), ClientError> {
+        let offset = Self::measure_offset(client).await?;
+        self.set_offset(offset);
+        Ok(())
+    }
+}
+
+impl NonceProvider for ServerTimeNonceProvider {
+    /// Returns the current server time in milliseconds, or the last nonce + 1 if a duplicate or
+    /// decreasing nonce would be generated.
+    fn get_nonce(&mut self) -> u64 {
+        let nonce = (now_millis() as i64 + self.offset_ms).max(0) as u64;
+
+        if nonce <= self.last {
+            self.last += 1;
+        } else {
+            self.last = nonce;
+        }
+
+        self.last
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Read the highest nonce used from the file, or 0 if the file doesn't exist or is empty.
+fn read_high_water_mark(path: &Path) -> Result<u64, std::io::Error> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let contents = contents.trim();
+    if contents.is_empty() {
+        return Ok(0);
+    }
+
+    contents
+        .parse()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+/// Write the highest nonce used to a temporary file and atomically rename it over the original.
+fn write_high_water_mark(path: &Path, nonce: u64) -> Result<(), std::io::Error> {
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(".tmp");
+
+    let mut temp = File::create(&temp_path)?;
+    write!(temp, "{}", nonce)?;
+    temp.sync_all()?;
+
+    std::fs::rename(&temp_path, path)?;
+    sync_parent_dir(path)
+}
+
+/// Sync the directory holding `path`, so a rename into it is durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), std::io::Error> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    File::open(parent)?.sync_all()
+}
+
+/// Directories can't be opened as files on other platforms, where renames are left to the OS.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), std::io::Error> {
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+    use crate::crypto::nonce_provider::{
+        FileNonceProvider, IncreasingNonceProvider, NonceProvider, ServerTimeNonceProvider,
+    };
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    fn get_nonce_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("kraken-nonce-{}-{}", std::process::id(), name))
+    }
+
+    fn remove_nonce_files(path: &Path) {
+        for extension in ["", ".lock"] {
+            let mut file = path.as_os_str().to_os_string();
+            file.push(extension);
+            let _ = std::fs::remove_file(file);
+        }
+    }
 
     #[test]
     fn test_increasing_nonce_provider() {
@@ -69,4 +357,121 @@ mod tests {
             last = nonce;
         }
     }
+
+    #[test]
+    fn test_file_nonce_provider_persists_across_restarts() {
+        let path = get_nonce_path("restart");
+        let future = u64::MAX / 2;
+        std::fs::write(&path, future.to_string()).unwrap();
+
+        let mut provider = FileNonceProvider::new(&path).unwrap();
+        assert_eq!(future + 1, provider.get_nonce());
+        assert_eq!(future + 2, provider.get_nonce());
+        drop(provider);
+
+        let mut restarted = FileNonceProvider::new(&path).unwrap();
+        assert_eq!(future + 3, restarted.get_nonce());
+        assert_eq!(
+            (future + 3).to_string(),
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        remove_nonce_files(&path);
+    }
+
+    #[test]
+    fn test_file_nonce_provider_shared_between_providers() {
+        let path = get_nonce_path("shared");
+
+        let mut first = FileNonceProvider::new(&path).unwrap();
+        let mut second = FileNonceProvider::new(&path).unwrap();
+
+        let mut last = 0;
+        for _ in 0..50 {
+            for provider in [&mut first, &mut second] {
+                let nonce = provider.get_nonce();
+                assert!(nonce > last);
+                last = nonce;
+            }
+        }
+
+        remove_nonce_files(&path);
+    }
+
+    #[test]
+    fn test_file_nonce_provider_fails_without_file() {
+        let dir = get_nonce_path("missing");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nonce");
+
+        let mut provider = FileNonceProvider::new(&path).unwrap();
+        assert!(provider.try_get_nonce().is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(provider.try_get_nonce().is_err());
+    }
+
+    #[test]
+    fn test_file_nonce_provider_blocks() {
+        let path = get_nonce_path("blocks");
+
+        let mut first = FileNonceProvider::new(&path).unwrap().block_size(10);
+        let mut second = FileNonceProvider::new(&path).unwrap().block_size(10);
+
+        let first_nonce = first.get_nonce();
+        let second_nonce = second.get_nonce();
+        assert!(second_nonce >= first_nonce + 10);
+        assert_eq!(
+            (second_nonce + 9).to_string(),
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        let mut nonces: Vec<u64> = (0..25).map(|_| first.get_nonce()).collect();
+        nonces.extend((0..25).map(|_| second.get_nonce()));
+        nonces.push(first_nonce);
+        nonces.push(second_nonce);
+        nonces.sort();
+        nonces.dedup();
+        assert_eq!(52, nonces.len());
+
+        remove_nonce_files(&path);
+    }
+
+    #[test]
+    fn test_file_nonce_provider_invalid_file() {
+        let path = get_nonce_path("invalid");
+        std::fs::write(&path, "not a nonce").unwrap();
+
+        assert!(FileNonceProvider::new(&path).is_err());
+
+        remove_nonce_files(&path);
+    }
+
+    #[test]
+    fn test_server_time_nonce_provider_offset() {
+        let mut provider = ServerTimeNonceProvider::new();
+        let local = IncreasingNonceProvider::new().get_nonce();
+
+        provider.set_offset(60_000);
+        assert_eq!(60_000, provider.offset());
+        let ahead = provider.get_nonce();
+        assert!(ahead >= local + 60_000);
+
+        provider.set_offset(-60_000);
+        let behind = provider.get_nonce();
+        assert_eq!(ahead + 1, behind);
+    }
+
+    #[test]
+    fn test_server_time_nonce_provider_tolerance() {
+        let mut provider = ServerTimeNonceProvider::new();
+        provider.set_offset(-800);
+        assert_eq!(0, provider.offset());
+
+        let mut windowed = ServerTimeNonceProvider::new().nonce_window(Duration::from_secs(5));
+        windowed.set_offset(4_000);
+        assert_eq!(0, windowed.offset());
+        windowed.set_offset(-6_000);
+        assert_eq!(-6_000, windowed.offset());
+    }
 }
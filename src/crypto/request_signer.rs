@@ -0,0 +1,203 @@
+//! Trait and implementations for signing private requests
+//!
+//! [CoreKrakenClient](crate::clients::core_kraken_client::CoreKrakenClient) uses a [RequestSigner]
+//! to produce the `API-Key` and `API-Sign` headers of each private request. The default
+//! [HmacRequestSigner] signs in-process with the secret from a secrets provider, while a custom
+//! signer can keep the secret elsewhere entirely, e.g. in a signing daemon or hardware security
+//! module, so it's never held in the client's memory.
+//!
+//! ```
+//! # use futures_util::future::BoxFuture;
+//! # use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+//! # use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+//! # use kraken_async_rs::crypto::request_signer::{RequestSignature, RequestSigner, SigningError};
+//! # use secrecy::Secret;
+//! # use std::sync::Arc;
+//! # use tokio::sync::Mutex;
+//! #[derive(Debug)]
+//! struct DaemonSigner {
+//!     api_key: Secret<String>,
+//! }
+//!
+//! impl RequestSigner for DaemonSigner {
+//!     fn sign<'a>(
+//!         &'a self,
+//!         path: &'a str,
+//!         nonce: u64,
+//!         encoded_data: &'a str,
+//!     ) -> BoxFuture<'a, Result<RequestSignature, SigningError>> {
+//!         Box::pin(async move {
+//!             // send the path, nonce and body to the signing daemon, e.g. over a Unix socket
+//!             let api_sign = String::new();
+//!
+//!             Ok(RequestSignature {
+//!                 api_key: self.api_key.clone(),
+//!                 api_sign,
+//!             })
+//!         })
+//!     }
+//! }
+//!
+//! let signer: Arc<dyn RequestSigner> = Arc::new(DaemonSigner {
+//!     api_key: Secret::new("api-key".to_string()),
+//! });
+//! let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+//!     Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+//!
+//! let client = CoreKrakenClient::builder_with_signer(signer, nonce_provider).build();
+//! ```
+use crate::crypto::signatures::sign_request;
+use crate::secrets::secrets_provider::{AsyncSecretsProvider, SecretsError};
+use futures_util::future::BoxFuture;
+use secrecy::{ExposeSecret, Secret};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Errors from a [RequestSigner] that couldn't sign a request.
+#[derive(Debug)]
+pub enum SigningError {
+    /// The secrets needed to sign the request couldn't be retrieved, or were invalid
+    Secrets(SecretsError),
+    /// The signer itself failed, e.g. a signing daemon was unavailable
+    Signer(String),
+}
+
+impl From<SecretsError> for SigningError {
+    fn from(value: SecretsError) -> Self {
+        Self::Secrets(value)
+    }
+}
+
+impl Display for SigningError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigningError::Secrets(err) => write!(f, "{}", err),
+            SigningError::Signer(message) => write!(f, "Signing error: {}", message),
+        }
+    }
+}
+
+impl Error for SigningError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SigningError::Secrets(err) => Some(err),
+            SigningError::Signer(_) => None,
+        }
+    }
+}
+
+/// The `API-Key` and `API-Sign` header values for a private request.
+#[derive(Debug, Clone)]
+pub struct RequestSignature {
+    pub api_key: Secret<String>,
+    pub api_sign: String,
+}
+
+/// Trait for signing private requests, so the API secret can be kept outside the client.
+///
+/// Signers are given the request path (e.g. `/0/private/Balance`), the nonce, and the encoded
+/// body, which is form or JSON encoded depending on the endpoint and already includes the nonce.
+/// See [generate_signature](crate::crypto::signatures::generate_signature) for the algorithm.
+///
+/// Signers are shared between concurrent requests without a lock, so one that needs mutable state
+/// should guard it internally.
+pub trait RequestSigner: Send + Sync + Debug {
+    fn sign<'a>(
+        &'a self,
+        path: &'a str,
+        nonce: u64,
+        encoded_data: &'a str,
+    ) -> BoxFuture<'a, Result<RequestSignature, SigningError>>;
+}
+
+/// The default [RequestSigner], which signs requests with HMAC-SHA512 in-process, using the
+/// secrets from an [AsyncSecretsProvider].
+#[derive(Debug, Clone)]
+pub struct HmacRequestSigner {
+    secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>>,
+}
+
+impl HmacRequestSigner {
+    pub fn new(secrets_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>>) -> HmacRequestSigner {
+        HmacRequestSigner { secrets_provider }
+    }
+}
+
+impl RequestSigner for HmacRequestSigner {
+    fn sign<'a>(
+        &'a self,
+        path: &'a str,
+        nonce: u64,
+        encoded_data: &'a str,
+    ) -> BoxFuture<'a, Result<RequestSignature, SigningError>> {
+        Box::pin(async move {
+            let secrets = self.secrets_provider.lock().await.get_secrets().await?;
+            let api_sign = sign_request(nonce, secrets.secret.expose_secret(), path, encoded_data)
+                .map_err(|_| SecretsError::Parse("API secret is not valid base64".to_string()))?;
+
+            Ok(RequestSignature {
+                api_key: secrets.key,
+                api_sign,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::request_signer::{HmacRequestSigner, RequestSigner, SigningError};
+    use crate::crypto::signatures::generate_signature;
+    use crate::secrets::secrets_provider::{
+        AsyncSecretsProvider, SecretsError, SecretsProvider, SecretsProviderAdapter,
+        StaticSecretsProvider,
+    };
+    use secrecy::ExposeSecret;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    const SECRET: &str =
+        "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+    fn get_signer(key: &'static str, secret: &'static str) -> HmacRequestSigner {
+        let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> = Box::new(Arc::new(
+            Mutex::new(StaticSecretsProvider::new(key, secret)),
+        ));
+        let async_provider: Box<Arc<Mutex<dyn AsyncSecretsProvider>>> = Box::new(Arc::new(
+            Mutex::new(SecretsProviderAdapter::new(secrets_provider)),
+        ));
+        HmacRequestSigner::new(async_provider)
+    }
+
+    #[tokio::test]
+    async fn test_hmac_request_signer() {
+        let signer = get_signer("api-key", SECRET);
+        let body = "nonce=1616492376594&ordertype=limit&pair=XBTUSD";
+
+        let signature = signer
+            .sign("/0/private/AddOrder", 1616492376594, body)
+            .await
+            .unwrap();
+        let expected = generate_signature(
+            1616492376594,
+            SECRET,
+            "/0/private/AddOrder",
+            body.to_string(),
+        );
+
+        assert_eq!("api-key", signature.api_key.expose_secret());
+        assert_eq!(expected.signature, signature.api_sign);
+    }
+
+    #[tokio::test]
+    async fn test_hmac_request_signer_invalid_secret() {
+        let signer = get_signer("api-key", "not base64!");
+
+        let result = signer.sign("/0/private/Balance", 1, "nonce=1").await;
+        assert!(matches!(
+            result,
+            Err(SigningError::Secrets(SecretsError::Parse(_)))
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 //! Core signature implementation for signing messages
 use base64::engine::general_purpose::STANDARD as base64;
-use base64::Engine;
+use base64::{DecodeError, Engine};
 use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256, Sha512};
 
@@ -26,9 +26,26 @@ pub fn generate_signature(
     endpoint: &str,
     encoded_data: String,
 ) -> Signature {
-    let mut hmac = Hmac::<Sha512>::new_from_slice(&base64.decode(secret.as_bytes()).unwrap())
+    let signature = sign_request(nonce, secret, endpoint, &encoded_data)
         .expect("Could not use private key to create HMAC");
 
+    Signature {
+        body_data: encoded_data,
+        signature,
+    }
+}
+
+/// Generates only the signature of a request, like [generate_signature], but returns an error
+/// instead of panicking if `secret` is not valid base64.
+pub fn sign_request(
+    nonce: u64,
+    secret: &str,
+    endpoint: &str,
+    encoded_data: &str,
+) -> Result<String, DecodeError> {
+    let mut hmac = Hmac::<Sha512>::new_from_slice(&base64.decode(secret.as_bytes())?)
+        .expect("HMAC accepts keys of any length");
+
     let mut sha256 = Sha256::new();
 
     sha256.update(nonce.to_string().as_bytes());
@@ -39,10 +56,7 @@ pub fn generate_signature(
     hmac.update(endpoint.as_bytes());
     hmac.update(&payload[..]);
 
-    Signature {
-        body_data: encoded_data,
-        signature: base64.encode(hmac.finalize().into_bytes()),
-    }
+    Ok(base64.encode(hmac.finalize().into_bytes()))
 }
 
 #[cfg(test)]
@@ -121,4 +135,9 @@ mod tests {
 
         assert_eq!(expected, signature.signature);
     }
+
+    #[test]
+    fn test_sign_request_invalid_secret() {
+        assert!(sign_request(1, "not base64!", "/0/private/Balance", "nonce=1").is_err());
+    }
 }
@@ -0,0 +1,240 @@
+//! Fee schedules for estimating the fees of orders before placing them
+//!
+//! Kraken charges a percentage fee that depends on whether an order adds liquidity (maker) or
+//! removes it (taker), and on the account's 30-day trade volume. The tiers for each pair are given
+//! by `fees` and `fees_maker` of a [TradableAssetPair], while `get_trade_volume` returns the
+//! account's volume and, if requested for specific pairs, the exact [Fees] charged to the account.
+//!
+//! ```
+//! # use kraken_async_rs::fees::{FeeCurrency, FeeSchedule};
+//! # use kraken_async_rs::response_types::{BuySell, TradableAssetPair, TradeVolume};
+//! # use kraken_async_rs::wss::v2::user_data_messages::MakerTaker;
+//! # use rust_decimal_macros::dec;
+//! # fn example(pair: &TradableAssetPair, trade_volume: &TradeVolume) {
+//! // trade_volume is the result of `get_trade_volume` for "XXBTZUSD"
+//! let schedule = FeeSchedule::from_trade_volume("XXBTZUSD", pair, trade_volume);
+//!
+//! let currency = FeeCurrency::for_order(BuySell::Buy, None);
+//! let estimate = schedule.estimate(dec!(0.5), dec!(50000), MakerTaker::Maker, currency);
+//! println!("{} {}", estimate.amount, estimate.asset);
+//! # }
+//! ```
+use crate::request_types::OrderFlags;
+use crate::response_types::{
+    BuySell, FeeByVolume, Fees, OrderFlag, TradableAssetPair, TradeVolume,
+};
+use crate::wss::v2::trading_messages::FeePreference;
+use crate::wss::v2::user_data_messages::MakerTaker;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+/// The currency of a pair that fees are charged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeCurrency {
+    Base,
+    Quote,
+}
+
+impl FeeCurrency {
+    /// The currency fees are charged in for an order with the given `fcib`/`fciq` flags, which is
+    /// the base currency for sells and quote currency for buys if neither is set.
+    pub fn for_order(side: BuySell, order_flags: Option<&OrderFlags>) -> FeeCurrency {
+        match order_flags {
+            Some(flags) if flags.contains(&OrderFlag::FeesInBase) => FeeCurrency::Base,
+            Some(flags) if flags.contains(&OrderFlag::FeesInQuote) => FeeCurrency::Quote,
+            _ => Self::default_for(side),
+        }
+    }
+
+    /// The currency fees are charged in for a v2 websocket order with the given [FeePreference],
+    /// which is the base currency for sells and quote currency for buys if none is given.
+    pub fn for_preference(side: BuySell, preference: Option<&FeePreference>) -> FeeCurrency {
+        match preference {
+            Some(FeePreference::Base) => FeeCurrency::Base,
+            Some(FeePreference::Quote) => FeeCurrency::Quote,
+            None => Self::default_for(side),
+        }
+    }
+
+    fn default_for(side: BuySell) -> FeeCurrency {
+        match side {
+            BuySell::Buy => FeeCurrency::Quote,
+            BuySell::Sell => FeeCurrency::Base,
+        }
+    }
+}
+
+/// A fee percentage that applies from a given 30-day volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeTier {
+    pub volume: Decimal,
+    pub fee: Decimal,
+}
+
+/// The estimated fee of a hypothetical order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Fee percentage, e.g. 0.26 for 0.26%
+    pub fee: Decimal,
+    /// Amount charged, in `asset`
+    pub amount: Decimal,
+    pub currency: FeeCurrency,
+    /// Asset the fee is charged in, e.g. `ZUSD`
+    pub asset: String,
+}
+
+/// The maker and taker fees of a single pair at a given 30-day volume.
+///
+/// When created from a [TradeVolume] that includes [Fees] for the pair, the account's exact fees
+/// are used in place of the pair's published tiers. Pairs without maker tiers charge taker fees
+/// for all trades, like Kraken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeSchedule {
+    base: String,
+    quote: String,
+    volume: Decimal,
+    taker_tiers: Vec<FeeTier>,
+    maker_tiers: Vec<FeeTier>,
+    taker_fees: Option<Fees>,
+    maker_fees: Option<Fees>,
+}
+
+impl FeeSchedule {
+    /// Create a schedule from the pair's published tiers, for an account with the given 30-day
+    /// volume in the pair's `fee_volume_currency`.
+    pub fn new(pair: &TradableAssetPair, volume: Decimal) -> FeeSchedule {
+        FeeSchedule {
+            base: pair.base.clone(),
+            quote: pair.quote.clone(),
+            volume,
+            taker_tiers: to_tiers(&pair.fees),
+            maker_tiers: to_tiers(&pair.fees_maker),
+            taker_fees: None,
+            maker_fees: None,
+        }
+    }
+
+    /// Create a schedule for the pair with REST key `pair_key` from the result of
+    /// `get_trade_volume`, using the account's exact fees for the pair if they were requested.
+    pub fn from_trade_volume(
+        pair_key: &str,
+        pair: &TradableAssetPair,
+        trade_volume: &TradeVolume,
+    ) -> FeeSchedule {
+        let find = |fees: Option<&HashMap<String, Fees>>| {
+            fees.and_then(|fees| fees.get(pair_key).or_else(|| fees.get(&pair.alt_name)))
+                .cloned()
+        };
+
+        FeeSchedule {
+            taker_fees: find(trade_volume.fees.as_ref()),
+            maker_fees: find(trade_volume.fees_maker.as_ref()),
+            ..FeeSchedule::new(pair, trade_volume.volume)
+        }
+    }
+
+    /// The 30-day volume used to select fee tiers.
+    pub fn volume(&self) -> Decimal {
+        self.volume
+    }
+
+    /// Current taker fee percentage.
+    pub fn taker_fee(&self) -> Decimal {
+        self.fee(MakerTaker::Taker)
+    }
+
+    /// Current maker fee percentage.
+    pub fn maker_fee(&self) -> Decimal {
+        self.fee(MakerTaker::Maker)
+    }
+
+    /// Current fee percentage for the given liquidity.
+    pub fn fee(&self, liquidity: MakerTaker) -> Decimal {
+        if let Some(fees) = self.fees(liquidity) {
+            return fees.fee;
+        }
+
+        self.tiers(liquidity)
+            .iter()
+            .rev()
+            .find(|tier| tier.volume <= self.volume)
+            .map(|tier| tier.fee)
+            .unwrap_or_default()
+    }
+
+    /// The next tier that would lower the fee for the given liquidity, if there is one.
+    pub fn next_tier(&self, liquidity: MakerTaker) -> Option<FeeTier> {
+        if let Some(fees) = self.fees(liquidity) {
+            return match (fees.next_volume, fees.next_fee) {
+                (Some(volume), Some(fee)) => Some(FeeTier { volume, fee }),
+                _ => None,
+            };
+        }
+
+        self.tiers(liquidity)
+            .iter()
+            .find(|tier| tier.volume > self.volume)
+            .copied()
+    }
+
+    /// Additional 30-day volume needed to reach the next tier for the given liquidity.
+    pub fn volume_to_next_tier(&self, liquidity: MakerTaker) -> Option<Decimal> {
+        self.next_tier(liquidity)
+            .map(|tier| (tier.volume - self.volume).max(Decimal::ZERO))
+    }
+
+    /// Estimate the fee of an order for `volume` at `price`, charged in the given currency.
+    ///
+    /// Fees in the quote currency are a percentage of the order's cost, while fees in the base
+    /// currency are a percentage of its volume.
+    pub fn estimate(
+        &self,
+        volume: Decimal,
+        price: Decimal,
+        liquidity: MakerTaker,
+        currency: FeeCurrency,
+    ) -> FeeEstimate {
+        let fee = self.fee(liquidity);
+
+        let (amount, asset) = match currency {
+            FeeCurrency::Base => (volume * fee / Decimal::ONE_HUNDRED, &self.base),
+            FeeCurrency::Quote => (volume * price * fee / Decimal::ONE_HUNDRED, &self.quote),
+        };
+
+        FeeEstimate {
+            fee,
+            amount: amount.normalize(),
+            currency,
+            asset: asset.clone(),
+        }
+    }
+
+    fn fees(&self, liquidity: MakerTaker) -> Option<&Fees> {
+        match liquidity {
+            MakerTaker::Maker if self.maker_tiers.is_empty() => {
+                self.maker_fees.as_ref().or(self.taker_fees.as_ref())
+            }
+            MakerTaker::Maker => self.maker_fees.as_ref(),
+            MakerTaker::Taker => self.taker_fees.as_ref(),
+        }
+    }
+
+    fn tiers(&self, liquidity: MakerTaker) -> &[FeeTier] {
+        match liquidity {
+            MakerTaker::Maker if !self.maker_tiers.is_empty() => &self.maker_tiers,
+            _ => &self.taker_tiers,
+        }
+    }
+}
+
+fn to_tiers(fees: &[FeeByVolume]) -> Vec<FeeTier> {
+    fees.iter()
+        .filter_map(|tier| {
+            Some(FeeTier {
+                volume: Decimal::from_f64(tier.volume)?,
+                fee: Decimal::from_f64(tier.fee)?,
+            })
+        })
+        .collect()
+}
@@ -5,10 +5,14 @@
 //! on a layer above this client (whatever your ideal API is), and then uses this library to
 //! implement your version on Kraken.
 //!
+pub mod asset_registry;
 pub mod clients;
 pub mod crypto;
+pub mod fees;
+pub mod order_validation;
 pub mod rate_limiting;
 pub mod request_types;
 pub mod response_types;
 pub mod secrets;
+pub mod simulated_exchange;
 pub mod wss;
@@ -0,0 +1,614 @@
+//! Pre-flight validation of orders against each pair's trading rules
+//!
+//! Kraken rejects orders with too many decimals, prices off the pair's tick size, volumes or costs
+//! below the pair's minimums, unsupported leverage, or order types the pair's current status doesn't
+//! allow. Each rejection still costs a request and rate limit tokens, so [OrderValidator] checks
+//! orders against cached [TradableAssetPair] details before they're sent.
+//!
+//! Validation can either reject an order with an [OrderValidationError], or first round prices and
+//! volumes to valid values with the `round_*` methods.
+//!
+//! ```
+//! # use kraken_async_rs::order_validation::{OrderValidationError, OrderValidator};
+//! # use kraken_async_rs::request_types::AddOrderRequest;
+//! # use kraken_async_rs::response_types::{BuySell, OrderType, TradableAssetPair};
+//! # use rust_decimal_macros::dec;
+//! # use std::collections::HashMap;
+//! # fn example(pairs: HashMap<String, TradableAssetPair>) {
+//! // pairs are the result of `get_tradable_asset_pairs`
+//! let validator = OrderValidator::new(pairs);
+//!
+//! let mut order =
+//!     AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(0.123456789), "XBTUSD".into())
+//!         .price(dec!(50000.17))
+//!         .build();
+//!
+//! assert!(matches!(
+//!     validator.validate_add_order(&order),
+//!     Err(OrderValidationError::PricePrecision { .. })
+//! ));
+//!
+//! // rounds the price down to the tick size and truncates the volume to `lot_decimals`
+//! validator.round_add_order(&mut order).unwrap();
+//! # }
+//! ```
+use crate::asset_registry::AssetRegistry;
+use crate::request_types::{AddOrderRequest, BatchedOrderRequest, EditOrderRequest};
+use crate::response_types::{
+    BuySell, OrderFlag, OrderType, TradableAssetPair, TradableAssetStatus,
+};
+use crate::wss::v2::trading_messages::{AddOrderParams, PriceType};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// The reason an order would be rejected by Kraken.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderValidationError {
+    /// No pair is known by this name
+    UnknownPair(String),
+    /// The pair's status doesn't allow this order, e.g. any new order when `cancel_only`
+    PairStatus(TradableAssetStatus),
+    /// A price has more decimals than the pair's `pair_decimals`
+    PricePrecision { price: Decimal, decimals: i64 },
+    /// A price is not a multiple of the pair's `tick_size`
+    PriceNotOnTick { price: Decimal, tick_size: Decimal },
+    /// The volume has more decimals than the pair's `lot_decimals`
+    VolumePrecision { volume: Decimal, decimals: i64 },
+    /// The volume is below the pair's `order_min`
+    VolumeBelowMinimum { volume: Decimal, minimum: Decimal },
+    /// A volume given in the quote currency has more decimals than the pair's `cost_decimals`
+    CostPrecision { cost: Decimal, decimals: i64 },
+    /// The cost of the order is below the pair's `cost_min`
+    CostBelowMinimum { cost: Decimal, minimum: Decimal },
+    /// The leverage (or margin, when `leverage` is `None`) isn't available for this side of the pair
+    LeverageNotAllowed {
+        side: BuySell,
+        leverage: Option<i64>,
+        allowed: Vec<i64>,
+    },
+}
+
+impl Display for OrderValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderValidationError::UnknownPair(pair) => write!(f, "Unknown pair: {}", pair),
+            OrderValidationError::PairStatus(status) => {
+                write!(f, "Pair status {:?} does not allow this order", status)
+            }
+            OrderValidationError::PricePrecision { price, decimals } => {
+                write!(f, "Price {} has more than {} decimals", price, decimals)
+            }
+            OrderValidationError::PriceNotOnTick { price, tick_size } => {
+                write!(
+                    f,
+                    "Price {} is not a multiple of tick size {}",
+                    price, tick_size
+                )
+            }
+            OrderValidationError::VolumePrecision { volume, decimals } => {
+                write!(f, "Volume {} has more than {} decimals", volume, decimals)
+            }
+            OrderValidationError::VolumeBelowMinimum { volume, minimum } => {
+                write!(f, "Volume {} is below the minimum of {}", volume, minimum)
+            }
+            OrderValidationError::CostPrecision { cost, decimals } => {
+                write!(f, "Cost {} has more than {} decimals", cost, decimals)
+            }
+            OrderValidationError::CostBelowMinimum { cost, minimum } => {
+                write!(f, "Cost {} is below the minimum of {}", cost, minimum)
+            }
+            OrderValidationError::LeverageNotAllowed {
+                side,
+                leverage,
+                allowed,
+            } => match leverage {
+                Some(leverage) => write!(
+                    f,
+                    "Leverage {} is not allowed for {}, allowed: {:?}",
+                    leverage, side, allowed
+                ),
+                None => write!(f, "Margin is not allowed for {}", side),
+            },
+        }
+    }
+}
+
+impl Error for OrderValidationError {}
+
+/// Whether the order's volume is given in the base or quote currency.
+#[derive(Debug, Clone, Copy)]
+enum Volume {
+    Base(Decimal),
+    Quote(Decimal),
+}
+
+/// The parts of any order request that are checked, in a common format.
+///
+/// Fields that can't be known for a request (like the side of an edited order) are `None`, and
+/// any check depending on them is skipped.
+#[derive(Debug)]
+struct OrderFields {
+    side: Option<BuySell>,
+    order_type: Option<OrderType>,
+    volume: Volume,
+    prices: Vec<Decimal>,
+    /// Prices given as an offset in the quote currency, which are checked for precision and tick
+    /// size but don't price the order
+    offsets: Vec<Decimal>,
+    leverage: Option<i64>,
+    margin: bool,
+    post_only: bool,
+    reduce_only: Option<bool>,
+}
+
+impl OrderFields {
+    /// The price used to estimate the cost of the order, if it has one.
+    ///
+    /// Market orders have no price, and trailing stops are priced as an offset.
+    fn cost_price(&self) -> Option<Decimal> {
+        match self.order_type {
+            Some(OrderType::Market)
+            | Some(OrderType::TrailingStop)
+            | Some(OrderType::TrailingStopLimit)
+            | Some(OrderType::SettlePosition) => None,
+            _ => self.prices.first().copied(),
+        }
+    }
+}
+
+/// Validates orders against cached [TradableAssetPair] details.
+///
+/// Pairs are resolved by an [AssetRegistry], so they can be referred to by any of their names, e.g.
+/// REST key (`XXBTZUSD`), `alt_name` (`XBTUSD`), `ws_name` (`XBT/USD`) or v2 websocket symbol
+/// (`BTC/USD`).
+///
+/// Rounding moves prices to the passive side of the book (down for buys, up for sells, and to the
+/// nearest tick for edits, where the side is unknown), and truncates volumes. Volumes are never
+/// rounded up to the pair's minimum.
+#[derive(Debug, Clone)]
+pub struct OrderValidator {
+    registry: AssetRegistry,
+}
+
+impl Default for OrderValidator {
+    fn default() -> Self {
+        // the validator is only ever updated explicitly, so the registry is never considered stale
+        OrderValidator::from_registry(AssetRegistry::new(Duration::MAX))
+    }
+}
+
+impl OrderValidator {
+    /// Create a validator from the result of `get_tradable_asset_pairs`.
+    pub fn new(pairs: HashMap<String, TradableAssetPair>) -> OrderValidator {
+        let mut validator = OrderValidator::default();
+        validator.update(pairs);
+        validator
+    }
+
+    /// Create a validator that checks orders against the pairs of an already populated registry.
+    pub fn from_registry(registry: AssetRegistry) -> OrderValidator {
+        OrderValidator { registry }
+    }
+
+    /// Replace all cached pairs, e.g. after periodically re-requesting them to pick up status changes.
+    ///
+    /// This replaces the whole contents of the validator's registry, including any assets.
+    pub fn update(&mut self, pairs: HashMap<String, TradableAssetPair>) {
+        self.registry.update(HashMap::new(), pairs);
+    }
+
+    /// Get the cached details for a pair by any of its names.
+    pub fn get_pair(&self, name: &str) -> Option<&TradableAssetPair> {
+        self.registry.pair(name).map(|pair| &pair.details)
+    }
+
+    /// Check an [AddOrderRequest] against its pair's rules.
+    pub fn validate_add_order(
+        &self,
+        request: &AddOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&request.pair)?;
+        check(pair, &Self::add_order_fields(request))
+    }
+
+    /// Round the prices and volume of an [AddOrderRequest] to valid values, then validate it.
+    pub fn round_add_order(
+        &self,
+        request: &mut AddOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&request.pair)?;
+        let side = Some(request.side);
+        let volume_in_quote = request
+            .order_flags
+            .as_ref()
+            .is_some_and(|flags| flags.contains(&OrderFlag::OrderVolumeInQuote));
+
+        request.price = request.price.map(|price| round_price(pair, side, price));
+        request.price_2 = request.price_2.map(|price| round_price(pair, side, price));
+        request.volume = round_volume(pair, request.volume, volume_in_quote);
+
+        check(pair, &Self::add_order_fields(request))
+    }
+
+    /// Check a [BatchedOrderRequest] against the rules of the batch's pair.
+    pub fn validate_batched_order(
+        &self,
+        pair: &str,
+        order: &BatchedOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(pair)?;
+        check(pair, &Self::batched_order_fields(order))
+    }
+
+    /// Round the prices and volume of a [BatchedOrderRequest] to valid values, then validate it.
+    pub fn round_batched_order(
+        &self,
+        pair: &str,
+        order: &mut BatchedOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(pair)?;
+        let side = Some(order.side);
+        let volume_in_quote = order
+            .order_flags
+            .as_ref()
+            .is_some_and(|flags| flags.contains(&OrderFlag::OrderVolumeInQuote));
+
+        order.price = order.price.map(|price| round_price(pair, side, price));
+        order.price_2 = order.price_2.map(|price| round_price(pair, side, price));
+        order.volume = round_volume(pair, order.volume, volume_in_quote);
+
+        check(pair, &Self::batched_order_fields(order))
+    }
+
+    /// Check an [EditOrderRequest] against its pair's rules.
+    ///
+    /// The side, type and leverage of the original order aren't part of the request, so only
+    /// prices, volume, cost and the pair's status are checked.
+    pub fn validate_edit_order(
+        &self,
+        request: &EditOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&request.pair)?;
+        check(pair, &Self::edit_order_fields(request))
+    }
+
+    /// Round the prices and volume of an [EditOrderRequest] to valid values, then validate it.
+    pub fn round_edit_order(
+        &self,
+        request: &mut EditOrderRequest,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&request.pair)?;
+
+        request.price = request.price.map(|price| round_price(pair, None, price));
+        request.price_2 = request.price_2.map(|price| round_price(pair, None, price));
+        request.volume = round_volume(pair, request.volume, false);
+
+        check(pair, &Self::edit_order_fields(request))
+    }
+
+    /// Check a v2 websocket [AddOrderParams] against its pair's rules.
+    pub fn validate_add_order_params(
+        &self,
+        params: &AddOrderParams,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&params.symbol)?;
+        check(pair, &Self::add_order_params_fields(params))
+    }
+
+    /// Round the prices and quantities of a v2 websocket [AddOrderParams] to valid values, then
+    /// validate it.
+    ///
+    /// Offsets in the quote currency are rounded to the nearest tick, and percentage offsets are
+    /// left unchanged.
+    pub fn round_add_order_params(
+        &self,
+        params: &mut AddOrderParams,
+    ) -> Result<(), OrderValidationError> {
+        let pair = self.pair(&params.symbol)?;
+        let side = Some(params.side);
+
+        params.limit_price = params
+            .limit_price
+            .map(|price| round_price_of_type(pair, side, price, params.limit_price_type.as_ref()));
+        if let Some(triggers) = params.triggers.as_mut() {
+            triggers.price =
+                round_price_of_type(pair, side, triggers.price, triggers.price_type.as_ref());
+        }
+
+        params.order_quantity = round_volume(pair, params.order_quantity, false);
+        params.cash_order_quantity = params
+            .cash_order_quantity
+            .map(|quantity| round_volume(pair, quantity, true));
+
+        check(pair, &Self::add_order_params_fields(params))
+    }
+
+    fn pair(&self, name: &str) -> Result<&TradableAssetPair, OrderValidationError> {
+        self.get_pair(name)
+            .ok_or_else(|| OrderValidationError::UnknownPair(name.to_string()))
+    }
+
+    fn add_order_fields(request: &AddOrderRequest) -> OrderFields {
+        let has_flag = |flag: OrderFlag| {
+            request
+                .order_flags
+                .as_ref()
+                .is_some_and(|flags| flags.contains(&flag))
+        };
+
+        OrderFields {
+            side: Some(request.side),
+            order_type: Some(request.order_type),
+            volume: if has_flag(OrderFlag::OrderVolumeInQuote) {
+                Volume::Quote(request.volume)
+            } else {
+                Volume::Base(request.volume)
+            },
+            prices: request.price.into_iter().chain(request.price_2).collect(),
+            offsets: Vec::new(),
+            leverage: request.leverage,
+            margin: false,
+            post_only: has_flag(OrderFlag::Post),
+            reduce_only: Some(request.reduce_only.unwrap_or(false)),
+        }
+    }
+
+    fn batched_order_fields(order: &BatchedOrderRequest) -> OrderFields {
+        let has_flag = |flag: OrderFlag| {
+            order
+                .order_flags
+                .as_ref()
+                .is_some_and(|flags| flags.contains(&flag))
+        };
+
+        OrderFields {
+            side: Some(order.side),
+            order_type: Some(order.order_type),
+            volume: if has_flag(OrderFlag::OrderVolumeInQuote) {
+                Volume::Quote(order.volume)
+            } else {
+                Volume::Base(order.volume)
+            },
+            prices: order.price.into_iter().chain(order.price_2).collect(),
+            offsets: Vec::new(),
+            leverage: order.leverage,
+            margin: false,
+            post_only: has_flag(OrderFlag::Post),
+            reduce_only: Some(order.reduce_only.unwrap_or(false)),
+        }
+    }
+
+    fn edit_order_fields(request: &EditOrderRequest) -> OrderFields {
+        OrderFields {
+            side: None,
+            order_type: None,
+            volume: Volume::Base(request.volume),
+            prices: request.price.into_iter().chain(request.price_2).collect(),
+            offsets: Vec::new(),
+            leverage: None,
+            margin: false,
+            post_only: request
+                .order_flags
+                .as_ref()
+                .is_some_and(|flags| flags.contains(&OrderFlag::Post)),
+            reduce_only: None,
+        }
+    }
+
+    /// Fields of a v2 websocket order, where only static prices are checked as prices.
+    ///
+    /// Prices relative to the last trade are offsets, either in the quote currency (which still
+    /// have to be on the pair's tick size) or as a percentage (which aren't checked).
+    fn add_order_params_fields(params: &AddOrderParams) -> OrderFields {
+        let mut prices = Vec::new();
+        let mut offsets = Vec::new();
+
+        let limit_price = params
+            .limit_price
+            .map(|price| (price, params.limit_price_type.as_ref()));
+        let trigger_price = params
+            .triggers
+            .as_ref()
+            .map(|triggers| (triggers.price, triggers.price_type.as_ref()));
+
+        for (price, price_type) in limit_price.into_iter().chain(trigger_price) {
+            match price_type {
+                None | Some(PriceType::Static) => prices.push(price),
+                Some(PriceType::Quote) => offsets.push(price),
+                Some(PriceType::Percent) => {}
+            }
+        }
+
+        OrderFields {
+            side: Some(params.side),
+            order_type: Some(params.order_type),
+            volume: match params.cash_order_quantity {
+                Some(quantity) => Volume::Quote(quantity),
+                None => Volume::Base(params.order_quantity),
+            },
+            prices,
+            offsets,
+            leverage: None,
+            margin: params.margin.unwrap_or(false),
+            post_only: params.post_only.unwrap_or(false),
+            reduce_only: Some(params.reduce_only.unwrap_or(false)),
+        }
+    }
+}
+
+/// Smallest price increment of the pair, falling back to its `pair_decimals` if no tick size is given.
+fn tick_size(pair: &TradableAssetPair) -> Decimal {
+    if pair.tick_size > Decimal::ZERO {
+        pair.tick_size
+    } else {
+        Decimal::new(1, pair.pair_decimals as u32)
+    }
+}
+
+fn decimals(value: Decimal) -> i64 {
+    value.normalize().scale() as i64
+}
+
+fn round_price(pair: &TradableAssetPair, side: Option<BuySell>, price: Decimal) -> Decimal {
+    let strategy = match side {
+        Some(BuySell::Buy) => RoundingStrategy::ToNegativeInfinity,
+        Some(BuySell::Sell) => RoundingStrategy::ToPositiveInfinity,
+        None => RoundingStrategy::MidpointAwayFromZero,
+    };
+    let tick_size = tick_size(pair);
+
+    ((price / tick_size).round_dp_with_strategy(0, strategy) * tick_size)
+        .round_dp(pair.pair_decimals as u32)
+        .normalize()
+}
+
+/// Round a v2 websocket price according to its [PriceType].
+fn round_price_of_type(
+    pair: &TradableAssetPair,
+    side: Option<BuySell>,
+    price: Decimal,
+    price_type: Option<&PriceType>,
+) -> Decimal {
+    match price_type {
+        None | Some(PriceType::Static) => round_price(pair, side, price),
+        Some(PriceType::Quote) => round_price(pair, None, price),
+        Some(PriceType::Percent) => price,
+    }
+}
+
+fn round_volume(pair: &TradableAssetPair, volume: Decimal, in_quote: bool) -> Decimal {
+    let decimals = if in_quote {
+        pair.cost_decimals
+    } else {
+        pair.lot_decimals
+    };
+
+    volume
+        .round_dp_with_strategy(decimals as u32, RoundingStrategy::ToZero)
+        .normalize()
+}
+
+fn check(pair: &TradableAssetPair, order: &OrderFields) -> Result<(), OrderValidationError> {
+    check_status(pair, order)?;
+
+    for price in &order.prices {
+        check_price(pair, *price)?;
+    }
+
+    for offset in &order.offsets {
+        check_price(pair, offset.abs())?;
+    }
+
+    match order.volume {
+        Volume::Base(volume) => {
+            if decimals(volume) > pair.lot_decimals {
+                return Err(OrderValidationError::VolumePrecision {
+                    volume,
+                    decimals: pair.lot_decimals,
+                });
+            }
+
+            if volume < pair.order_min {
+                return Err(OrderValidationError::VolumeBelowMinimum {
+                    volume,
+                    minimum: pair.order_min,
+                });
+            }
+
+            if let Some(price) = order.cost_price() {
+                check_minimum_cost(pair, volume * price)?;
+            }
+        }
+        Volume::Quote(cost) => {
+            if decimals(cost) > pair.cost_decimals {
+                return Err(OrderValidationError::CostPrecision {
+                    cost,
+                    decimals: pair.cost_decimals,
+                });
+            }
+
+            check_minimum_cost(pair, cost)?;
+        }
+    }
+
+    check_leverage(pair, order)
+}
+
+fn check_status(pair: &TradableAssetPair, order: &OrderFields) -> Result<(), OrderValidationError> {
+    let is_limit = order
+        .order_type
+        .is_none_or(|order_type| order_type == OrderType::Limit);
+
+    let allowed = match pair.status {
+        TradableAssetStatus::Online => true,
+        TradableAssetStatus::CancelOnly => false,
+        TradableAssetStatus::PostOnly => is_limit && order.post_only,
+        TradableAssetStatus::LimitOnly => is_limit,
+        TradableAssetStatus::ReduceOnly => order.reduce_only.unwrap_or(true),
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(OrderValidationError::PairStatus(pair.status))
+    }
+}
+
+fn check_price(pair: &TradableAssetPair, price: Decimal) -> Result<(), OrderValidationError> {
+    if decimals(price) > pair.pair_decimals {
+        return Err(OrderValidationError::PricePrecision {
+            price,
+            decimals: pair.pair_decimals,
+        });
+    }
+
+    let tick_size = tick_size(pair);
+    if !(price % tick_size).is_zero() {
+        return Err(OrderValidationError::PriceNotOnTick { price, tick_size });
+    }
+
+    Ok(())
+}
+
+fn check_minimum_cost(pair: &TradableAssetPair, cost: Decimal) -> Result<(), OrderValidationError> {
+    if cost < pair.cost_min {
+        Err(OrderValidationError::CostBelowMinimum {
+            cost,
+            minimum: pair.cost_min,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_leverage(
+    pair: &TradableAssetPair,
+    order: &OrderFields,
+) -> Result<(), OrderValidationError> {
+    let Some(side) = order.side else {
+        return Ok(());
+    };
+
+    let allowed = match side {
+        BuySell::Buy => &pair.leverage_buy,
+        BuySell::Sell => &pair.leverage_sell,
+    };
+
+    let is_allowed = match order.leverage {
+        Some(leverage) => allowed.contains(&leverage),
+        None => !order.margin || !allowed.is_empty(),
+    };
+
+    if is_allowed {
+        Ok(())
+    } else {
+        Err(OrderValidationError::LeverageNotAllowed {
+            side,
+            leverage: order.leverage,
+            allowed: allowed.clone(),
+        })
+    }
+}
@@ -0,0 +1,174 @@
+//! Cooldowns applied to rate limiters after the server reports that a rate limit was exceeded
+use crate::clients::errors::KrakenError;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// The rate limiter that a server-side rate limit error was attributed to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RateLimiterKind {
+    Public,
+    Private,
+    /// Trading rate limit errors that can't be attributed to a pair, e.g. from a private endpoint
+    /// other than order entry. These are reported, but don't pause order entry on any pair.
+    Trading,
+    /// The per-pair public limiter used for OHLC and recent trades, keyed by the requested pair.
+    Pair(String),
+    /// The per-pair trading limiter, keyed by the pair of the order placed, edited or cancelled.
+    TradingPair(String),
+}
+
+/// Emitted when Kraken rejects a request for exceeding a rate limit, meaning the client's local
+/// estimate of the rate limits had drifted from the server's.
+///
+/// This usually happens when other processes or clients are using the same API key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitEvent {
+    pub limiter: RateLimiterKind,
+    pub error: KrakenError,
+    pub cooldown: Duration,
+}
+
+/// The durations each limiter is paused for after Kraken reports a rate limit error.
+///
+/// The defaults are conservative estimates of how long Kraken's counters take to decay enough to
+/// allow further requests for an `Intermediate` user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CooldownPolicy {
+    pub public: Duration,
+    pub private: Duration,
+    pub trading: Duration,
+    pub pair: Duration,
+}
+
+impl Default for CooldownPolicy {
+    fn default() -> Self {
+        CooldownPolicy {
+            public: Duration::from_secs(5),
+            private: Duration::from_secs(10),
+            trading: Duration::from_secs(10),
+            pair: Duration::from_secs(5),
+        }
+    }
+}
+
+impl CooldownPolicy {
+    /// Get the cooldown for the given kind of rate limiter.
+    pub fn cooldown(&self, limiter: &RateLimiterKind) -> Duration {
+        match limiter {
+            RateLimiterKind::Public => self.public,
+            RateLimiterKind::Private => self.private,
+            RateLimiterKind::Trading | RateLimiterKind::TradingPair(_) => self.trading,
+            RateLimiterKind::Pair(_) => self.pair,
+        }
+    }
+}
+
+/// Tracks when each paused rate limiter may resume.
+///
+/// Cloned instances share the same underlying state, so all clones of a client respect cooldowns
+/// started by any of them.
+#[derive(Debug, Clone, Default)]
+pub struct Cooldowns {
+    resume_at: Arc<Mutex<BTreeMap<RateLimiterKind, Instant>>>,
+}
+
+impl Cooldowns {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Pause the given limiter for `duration`, extending any cooldown already in progress.
+    pub async fn start(&self, limiter: RateLimiterKind, duration: Duration) {
+        let resume_at = Instant::now() + duration;
+        let mut guard = self.resume_at.lock().await;
+        let entry = guard.entry(limiter).or_insert(resume_at);
+
+        if *entry < resume_at {
+            *entry = resume_at;
+        }
+    }
+
+    /// Wait until any cooldown for the given limiter has elapsed, returning immediately if there
+    /// is none.
+    pub async fn wait_until_ready(&self, limiter: &RateLimiterKind) {
+        loop {
+            let resume_at = {
+                let mut guard = self.resume_at.lock().await;
+                match guard.get(limiter) {
+                    Some(resume_at) if *resume_at > Instant::now() => *resume_at,
+                    Some(_) => {
+                        guard.remove(limiter);
+                        return;
+                    }
+                    None => return,
+                }
+            };
+
+            // cooldowns may be extended while waiting, so check again after sleeping
+            sleep_until(resume_at).await;
+        }
+    }
+
+    /// Get the remaining cooldown for a limiter, if any.
+    pub async fn remaining(&self, limiter: &RateLimiterKind) -> Option<Duration> {
+        let guard = self.resume_at.lock().await;
+        guard
+            .get(limiter)
+            .map(|resume_at| resume_at.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::pause;
+
+    #[tokio::test]
+    async fn test_wait_without_cooldown_is_immediate() {
+        pause();
+        let cooldowns = Cooldowns::new();
+        let start = Instant::now();
+
+        cooldowns.wait_until_ready(&RateLimiterKind::Private).await;
+
+        assert_eq!(Duration::ZERO, start.elapsed());
+        assert_eq!(None, cooldowns.remaining(&RateLimiterKind::Private).await);
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_pauses_only_matching_limiter() {
+        pause();
+        let cooldowns = Cooldowns::new();
+        let start = Instant::now();
+
+        cooldowns
+            .start(RateLimiterKind::Trading, Duration::from_secs(3))
+            .await;
+
+        cooldowns.wait_until_ready(&RateLimiterKind::Private).await;
+        assert_eq!(Duration::ZERO, start.elapsed());
+
+        cooldowns.wait_until_ready(&RateLimiterKind::Trading).await;
+        assert!(start.elapsed() >= Duration::from_secs(3));
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_is_extended_not_shortened() {
+        pause();
+        let cooldowns = Cooldowns::new();
+        let pair = RateLimiterKind::Pair("XBTUSD".to_string());
+        let start = Instant::now();
+
+        cooldowns.start(pair.clone(), Duration::from_secs(5)).await;
+        cooldowns.start(pair.clone(), Duration::from_secs(1)).await;
+
+        cooldowns.wait_until_ready(&pair).await;
+        assert!(start.elapsed() >= Duration::from_secs(5));
+        assert!(start.elapsed() < Duration::from_secs(6));
+    }
+}
@@ -5,7 +5,10 @@
 //! in this scheme, and a replenishment rate of 2.34/s in the docs will be 234/s here.
 //!
 //! This was a simplification that allowed using Semaphore permits as the core rate limiting concept
-//! under the hood.
+//! under the hood, and keeps the remaining tokens of a [token_bucket::TokenBucket] exact when
+//! previewing costs.
+pub mod adaptive_rate_limits;
 pub mod keyed_rate_limits;
+pub mod token_bucket;
 pub mod trading_rate_limits;
 pub mod ttl_cache;
@@ -0,0 +1,412 @@
+//! A token bucket rate limiter that can be inspected without waiting
+use async_rate_limit::limiters::{RateLimiter, VariableCostRateLimiter};
+use std::collections::BTreeSet;
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::{sleep_until, Instant};
+
+/// The class of a request waiting on a rate limiter. Waiting requests of a higher class are
+/// always served first, and requests of the same class in the order they started waiting.
+///
+/// Classes are ordered from highest to lowest, so `Critical < Normal < Background`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RequestPriority {
+    /// Requests that reduce risk, e.g. cancelling orders
+    Critical,
+    #[default]
+    Normal,
+    /// Requests that can wait for others, e.g. history queries
+    Background,
+}
+
+/// A snapshot of a rate limiter's budget, in the same scaled tokens as the limiter itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitBudget {
+    pub remaining_tokens: usize,
+    pub max_tokens: usize,
+    /// How long until the requested cost could be spent, ignoring any callers already waiting.
+    pub time_until_affordable: Duration,
+}
+
+/// A token bucket that holds at most `max_tokens`, and replaces `replace_amount` tokens every
+/// `replace_duration` once tokens have been spent.
+///
+/// Unlike [TokenBucketRateLimiter](async_rate_limit::token_bucket::TokenBucketRateLimiter), the
+/// remaining tokens can be read at any time without acquiring them, which is used to preview
+/// whether a request could be made now.
+///
+/// Callers waiting on the bucket are served by [RequestPriority], then in the order they started
+/// waiting. A caller at the front of the queue is preempted by any higher class that starts
+/// waiting before its tokens are available. Costs above `max_tokens` wait for a full bucket and
+/// then empty it, rather than waiting forever.
+///
+/// Cloned instances share the same underlying state.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    state: Arc<std::sync::Mutex<TokenBucketState>>,
+    queue_changed: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct TokenBucketState {
+    max_tokens: usize,
+    replace_amount: usize,
+    replace_duration: Duration,
+    tokens: usize,
+    /// The last time tokens were replaced, or the bucket was last seen full
+    replenished_at: Instant,
+    /// Callers waiting for tokens, with the next to be served first
+    waiting: BTreeSet<(RequestPriority, u64)>,
+    next_ticket: u64,
+}
+
+impl TokenBucketState {
+    fn replenish(&mut self, now: Instant) {
+        if self.tokens < self.max_tokens {
+            let elapsed = now.saturating_duration_since(self.replenished_at);
+            let intervals = (elapsed.as_nanos() / self.replace_duration.as_nanos()) as usize;
+
+            self.tokens = self
+                .tokens
+                .saturating_add(intervals.saturating_mul(self.replace_amount));
+            self.replenished_at += self.replace_duration * intervals.min(u32::MAX as usize) as u32;
+        }
+
+        if self.tokens >= self.max_tokens {
+            self.tokens = self.max_tokens;
+            self.replenished_at = now;
+        }
+    }
+
+    fn time_until_affordable(&self, cost: usize, now: Instant) -> Duration {
+        let cost = cost.min(self.max_tokens);
+
+        if self.tokens >= cost {
+            return Duration::ZERO;
+        }
+
+        let intervals = (cost - self.tokens).div_ceil(self.replace_amount);
+        let affordable_at = self.replenished_at + self.replace_duration * intervals as u32;
+
+        affordable_at.saturating_duration_since(now)
+    }
+}
+
+impl TokenBucket {
+    /// Create a new bucket that starts full.
+    ///
+    /// A `replace_amount` or `replace_duration` of zero is raised to the smallest non-zero value, so
+    /// a zero duration replaces tokens as quickly as possible rather than never.
+    pub fn new(
+        max_tokens: usize,
+        replace_amount: usize,
+        replace_duration: Duration,
+    ) -> TokenBucket {
+        let state = TokenBucketState {
+            max_tokens,
+            replace_amount: replace_amount.max(1),
+            replace_duration: replace_duration.max(Duration::from_nanos(1)),
+            tokens: max_tokens,
+            replenished_at: Instant::now(),
+            waiting: BTreeSet::new(),
+            next_ticket: 0,
+        };
+
+        TokenBucket {
+            state: Arc::new(std::sync::Mutex::new(state)),
+            queue_changed: Default::default(),
+        }
+    }
+
+    /// Get the number of tokens that could be spent right now.
+    pub fn remaining_tokens(&self) -> usize {
+        self.with_state(|state, _| state.tokens)
+    }
+
+    /// Get the maximum number of tokens the bucket holds.
+    pub fn max_tokens(&self) -> usize {
+        self.with_state(|state, _| state.max_tokens)
+    }
+
+    /// Get the time until `cost` tokens could be spent, which is zero if they're available now.
+    pub fn time_until_affordable(&self, cost: usize) -> Duration {
+        self.with_state(|state, now| state.time_until_affordable(cost, now))
+    }
+
+    /// Get the remaining tokens and time until `cost` tokens could be spent in one snapshot.
+    pub fn budget(&self, cost: usize) -> RateLimitBudget {
+        self.with_state(|state, now| RateLimitBudget {
+            remaining_tokens: state.tokens,
+            max_tokens: state.max_tokens,
+            time_until_affordable: state.time_until_affordable(cost, now),
+        })
+    }
+
+    /// Correct the number of remaining tokens, e.g. from a count reported by the server.
+    ///
+    /// Callers already waiting recheck the corrected tokens when they next wake.
+    pub fn set_remaining_tokens(&self, tokens: usize) {
+        self.with_state(|state, _| state.tokens = tokens.min(state.max_tokens));
+        self.queue_changed.notify_waiters();
+    }
+
+    /// Change the maximum tokens and replenishment amount, keeping the number of tokens spent.
+    pub fn set_limits(&self, max_tokens: usize, replace_amount: usize) {
+        self.with_state(|state, _| {
+            let spent = state.max_tokens - state.tokens;
+
+            state.max_tokens = max_tokens;
+            state.replace_amount = replace_amount.max(1);
+            state.tokens = max_tokens.saturating_sub(spent);
+        });
+        self.queue_changed.notify_waiters();
+    }
+
+    /// Wait until `cost` tokens are available, then spend them, as a [RequestPriority::Normal]
+    /// request.
+    pub async fn acquire(&self, cost: usize) {
+        self.acquire_with_priority(cost, RequestPriority::Normal)
+            .await
+    }
+
+    /// Wait until this caller is first in line for its class and `cost` tokens are available,
+    /// then spend them.
+    pub async fn acquire_with_priority(&self, cost: usize, priority: RequestPriority) {
+        let ticket = self.with_state(|state, _| {
+            let ticket = (priority, state.next_ticket);
+            state.next_ticket += 1;
+            state.waiting.insert(ticket);
+            ticket
+        });
+        // leaves the queue if the caller stops waiting, and lets the next caller check its turn
+        let _waiting = WaitingGuard {
+            bucket: self,
+            ticket,
+        };
+
+        // a lower class at the front of the queue may need to step back for this caller
+        self.queue_changed.notify_waiters();
+
+        loop {
+            let queue_changed = self.queue_changed.notified();
+            tokio::pin!(queue_changed);
+            queue_changed.as_mut().enable();
+
+            let wait = self.with_state(|state, now| {
+                if state.waiting.first() != Some(&ticket) {
+                    return None;
+                }
+
+                let cost = cost.min(state.max_tokens);
+                let wait = state.time_until_affordable(cost, now);
+
+                if wait.is_zero() {
+                    state.tokens -= cost;
+                }
+
+                Some(wait)
+            });
+
+            match wait {
+                Some(wait) if wait.is_zero() => return,
+                Some(wait) => {
+                    tokio::select! {
+                        _ = sleep_until(Instant::now() + wait) => {}
+                        _ = queue_changed => {}
+                    }
+                }
+                None => queue_changed.await,
+            }
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut TokenBucketState, Instant) -> T) -> T {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.replenish(now);
+        f(&mut state, now)
+    }
+}
+
+struct WaitingGuard<'a> {
+    bucket: &'a TokenBucket,
+    ticket: (RequestPriority, u64),
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.bucket
+            .with_state(|state, _| state.waiting.remove(&self.ticket));
+        self.bucket.queue_changed.notify_waiters();
+    }
+}
+
+impl RateLimiter for TokenBucket {
+    async fn wait_until_ready(&mut self) {
+        self.acquire(1).await
+    }
+}
+
+impl VariableCostRateLimiter for TokenBucket {
+    async fn wait_with_cost(&mut self, cost: usize) {
+        self.acquire(cost).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limiting::token_bucket::{RateLimitBudget, RequestPriority, TokenBucket};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::task::yield_now;
+    use tokio::time::{advance, pause, timeout, Instant};
+
+    #[tokio::test]
+    async fn test_budget_replenishes_per_interval() {
+        pause();
+        let bucket = TokenBucket::new(1000, 100, Duration::from_secs(1));
+
+        bucket.acquire(950).await;
+        assert_eq!(
+            RateLimitBudget {
+                remaining_tokens: 50,
+                max_tokens: 1000,
+                time_until_affordable: Duration::from_secs(3),
+            },
+            bucket.budget(300)
+        );
+
+        advance(Duration::from_millis(1500)).await;
+        assert_eq!(150, bucket.remaining_tokens());
+        assert_eq!(
+            Duration::from_millis(1500),
+            bucket.time_until_affordable(300)
+        );
+        assert_eq!(Duration::ZERO, bucket.time_until_affordable(150));
+
+        // tokens never exceed the maximum, and a cost above it only waits for a full bucket
+        advance(Duration::from_secs(60)).await;
+        assert_eq!(1000, bucket.remaining_tokens());
+        assert_eq!(Duration::ZERO, bucket.time_until_affordable(5000));
+    }
+
+    #[tokio::test]
+    async fn test_zero_replace_duration_is_clamped() {
+        pause();
+        let bucket = TokenBucket::new(100, 0, Duration::ZERO);
+
+        bucket.acquire(100).await;
+        assert_eq!(0, bucket.remaining_tokens());
+        assert_eq!(Duration::from_nanos(100), bucket.time_until_affordable(100));
+
+        advance(Duration::from_nanos(100)).await;
+        assert_eq!(100, bucket.remaining_tokens());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_tokens() {
+        pause();
+        let bucket = TokenBucket::new(300, 100, Duration::from_secs(1));
+        let start = Instant::now();
+
+        bucket.acquire(300).await;
+        assert_eq!(Duration::ZERO, start.elapsed());
+
+        // sleeping may overshoot by a millisecond, but doesn't delay later replenishment
+        bucket.acquire(250).await;
+        assert!(start.elapsed() >= Duration::from_secs(3));
+        assert!(start.elapsed() < Duration::from_millis(3010));
+        assert_eq!(50, bucket.remaining_tokens());
+
+        // a clone shares the same tokens
+        bucket.clone().acquire(100).await;
+        assert!(start.elapsed() >= Duration::from_secs(4));
+        assert!(start.elapsed() < Duration::from_millis(4010));
+    }
+
+    #[tokio::test]
+    async fn test_corrections_keep_tokens_in_bounds() {
+        pause();
+        let bucket = TokenBucket::new(1000, 100, Duration::from_secs(1));
+
+        bucket.set_remaining_tokens(5000);
+        assert_eq!(1000, bucket.remaining_tokens());
+
+        bucket.set_remaining_tokens(200);
+        assert_eq!(Duration::from_secs(1), bucket.time_until_affordable(300));
+
+        // 800 tokens were spent, which still counts against a larger or smaller maximum
+        bucket.set_limits(1500, 300);
+        assert_eq!(700, bucket.remaining_tokens());
+        assert_eq!(1500, bucket.max_tokens());
+
+        bucket.set_limits(500, 100);
+        assert_eq!(0, bucket.remaining_tokens());
+    }
+
+    #[tokio::test]
+    async fn test_higher_priorities_served_first() {
+        pause();
+        let bucket = TokenBucket::new(100, 100, Duration::from_secs(1));
+        let served = Arc::new(Mutex::new(Vec::new()));
+        let start = Instant::now();
+
+        bucket.acquire(100).await;
+
+        let mut tasks = Vec::new();
+        for priority in [
+            RequestPriority::Background,
+            RequestPriority::Normal,
+            RequestPriority::Background,
+            RequestPriority::Critical,
+        ] {
+            let bucket = bucket.clone();
+            let served = served.clone();
+            tasks.push(tokio::spawn(async move {
+                bucket.acquire_with_priority(100, priority).await;
+                served
+                    .lock()
+                    .unwrap()
+                    .push((priority, start.elapsed().as_secs()));
+            }));
+            // let each task start waiting before the next
+            yield_now().await;
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // the first background request was first in line, but is preempted by later arrivals
+        assert_eq!(
+            vec![
+                (RequestPriority::Critical, 1),
+                (RequestPriority::Normal, 2),
+                (RequestPriority::Background, 3),
+                (RequestPriority::Background, 4),
+            ],
+            *served.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiters_leave_queue() {
+        pause();
+        let bucket = TokenBucket::new(100, 100, Duration::from_secs(1));
+        let start = Instant::now();
+
+        bucket.acquire(100).await;
+
+        let abandoned = timeout(
+            Duration::from_millis(500),
+            bucket.acquire_with_priority(100, RequestPriority::Critical),
+        )
+        .await;
+        assert!(abandoned.is_err());
+
+        bucket.acquire(100).await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert!(start.elapsed() < Duration::from_millis(1010));
+    }
+}
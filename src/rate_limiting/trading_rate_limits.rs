@@ -1,22 +1,167 @@
+use crate::asset_registry::AssetRegistry;
+use crate::rate_limiting::token_bucket::{RateLimitBudget, RequestPriority, TokenBucket};
 use crate::rate_limiting::ttl_cache::{TtlCache, TtlEntry};
-use crate::request_types::{AddBatchedOrderRequest, EditOrderRequest};
+use crate::request_types::{AddBatchedOrderRequest, AddOrderRequest, EditOrderRequest};
 use crate::response_types::VerificationTier;
-use async_rate_limit::limiters::VariableCostRateLimiter;
-use async_rate_limit::token_bucket::{TokenBucketRateLimiter, TokenBucketState};
+use crate::wss::errors::WSSError;
+use crate::wss::v2::base_messages::{ChannelMessage, MethodMessage, WssMessage};
+use crate::wss::v2::trading_messages::{AddOrderParams, BatchOrderParams, EditOrderParams};
+use crate::wss::v2::user_data_messages::SubscriptionResult;
+use futures_util::{Stream, StreamExt};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 use tokio::sync::Mutex;
+use tracing::{debug, warn};
 
 // 300 seconds in microseconds
 const ORDER_TTL_US: i128 = 300_i128 * 10_i128.pow(6);
 
+/// The placement time and pair of an order, used to determine which pair's counter an edit or
+/// cancel is charged to, and the penalty for the order's lifetime.
+#[derive(Debug, Clone)]
+struct OrderPlacement {
+    placement_time: i64,
+    pair: String,
+    user_ref: Option<i64>,
+}
+
+impl OrderPlacement {
+    fn lifetime(&self) -> i64 {
+        OffsetDateTime::now_utc().unix_timestamp() - self.placement_time
+    }
+}
+
+/// The cost a trading request would incur if it were made now, in tokens scaled by 100.
+///
+/// `cost` includes `penalty`, which is the part due to the order being edited or cancelled soon
+/// after it was placed. `pair` is the pair whose counter is charged, which is `None` only when
+/// cancelling an order the limiter doesn't know of, which is free.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradingCost {
+    pub pair: Option<String>,
+    pub cost: usize,
+    pub penalty: usize,
+}
+
+/// The threshold and decay rate of Kraken's per-pair trading counter, scaled by 100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingLimits {
+    pub max_tokens: usize,
+    pub decay_per_second: usize,
+}
+
+impl TradingLimits {
+    /// The published limits of the Starter, Intermediate and Pro tiers, in that order.
+    const TIERS: [TradingLimits; 3] = [
+        TradingLimits {
+            max_tokens: 6000,
+            decay_per_second: 100,
+        },
+        TradingLimits {
+            max_tokens: 12500,
+            decay_per_second: 234,
+        },
+        TradingLimits {
+            max_tokens: 18000,
+            decay_per_second: 375,
+        },
+    ];
+
+    /// Get the limits for a [VerificationTier].
+    pub fn for_tier(user_verification: VerificationTier) -> TradingLimits {
+        match user_verification {
+            VerificationTier::Intermediate => Self::TIERS[1],
+            VerificationTier::Pro => Self::TIERS[2],
+        }
+    }
+
+    /// Get the limits for the `maxratecount` reported when subscribing to executions.
+    ///
+    /// The decay rate isn't reported, so it's taken from the highest tier with a threshold at or
+    /// below the reported one.
+    pub fn from_max_rate_count(max_rate_count: i64) -> TradingLimits {
+        let max_tokens = max_rate_count.max(1) as usize * 100;
+        let decay_per_second = Self::TIERS
+            .iter()
+            .rev()
+            .find(|tier| tier.max_tokens <= max_tokens)
+            .unwrap_or(&Self::TIERS[0])
+            .decay_per_second;
+
+        TradingLimits {
+            max_tokens,
+            decay_per_second,
+        }
+    }
+
+    fn rate_limiter(&self) -> TokenBucket {
+        // tokens are scaled 100x from Kraken's floating-point method to keep as integers
+        TokenBucket::new(
+            self.max_tokens,
+            self.decay_per_second,
+            Duration::from_secs(1),
+        )
+    }
+}
+
+/// The rate limiter of each pair, shared by all clones of a [KrakenTradingRateLimiter].
+#[derive(Debug)]
+struct PairRateLimiters {
+    limits: TradingLimits,
+    asset_registry: Option<AssetRegistry>,
+    rate_limiters: BTreeMap<String, TokenBucket>,
+    /// The last time tokens were acquired locally for each pair
+    charged_at: BTreeMap<String, OffsetDateTime>,
+    warned_unregistered: bool,
+}
+
+impl PairRateLimiters {
+    /// Resolve any name of a pair to the same key, if the pair is in the asset registry.
+    fn key(&self, pair: &str) -> String {
+        self.asset_registry
+            .as_ref()
+            .and_then(|registry| registry.pair(pair))
+            .map(|registered| registered.key.clone())
+            .unwrap_or_else(|| pair.to_string())
+    }
+
+    fn get(&self, pair: &str) -> Option<&TokenBucket> {
+        self.rate_limiters.get(&self.key(pair))
+    }
+
+    fn get_or_insert(&mut self, pair: &str) -> TokenBucket {
+        let key = self.key(pair);
+        let limits = self.limits;
+
+        self.rate_limiters
+            .entry(key)
+            .or_insert_with(|| limits.rate_limiter())
+            .clone()
+    }
+
+    fn charged(&mut self, pair: &str) {
+        let key = self.key(pair);
+        self.charged_at.insert(key, OffsetDateTime::now_utc());
+    }
+}
+
 /// An implementation of the most accurate trading rate limits given by Kraken
+///
+/// Kraken keeps a separate rate counter for each currency pair, so each pair is given its own
+/// token bucket, with the thresholds and decay rates of the user's [VerificationTier].
+///
+/// The limiter can also be kept in sync with Kraken's own counters using the v2 executions feed,
+/// see [KrakenTradingRateLimiter::observe_message].
 #[derive(Debug, Clone)]
 pub struct KrakenTradingRateLimiter {
-    ttl_ref_id_cache: Arc<Mutex<TtlCache<String, i64>>>,
-    ttl_user_ref_cache: Arc<Mutex<TtlCache<i64, i64>>>,
-    rate_limiter: TokenBucketRateLimiter,
+    ttl_ref_id_cache: Arc<Mutex<TtlCache<String, OrderPlacement>>>,
+    ttl_user_ref_cache: Arc<Mutex<TtlCache<i64, OrderPlacement>>>,
+    rate_limiters: Arc<Mutex<PairRateLimiters>>,
 }
 
 /// Implements the Advanced rate limiting scheme that requires knowing each order's lifetime.
@@ -33,100 +178,391 @@ impl KrakenTradingRateLimiter {
         KrakenTradingRateLimiter {
             ttl_ref_id_cache: Default::default(),
             ttl_user_ref_cache: Default::default(),
-            rate_limiter: Self::get_rate_limiter(user_verification),
+            rate_limiters: Arc::new(Mutex::new(PairRateLimiters {
+                limits: TradingLimits::for_tier(user_verification),
+                asset_registry: None,
+                rate_limiters: BTreeMap::new(),
+                charged_at: BTreeMap::new(),
+                warned_unregistered: false,
+            })),
         }
     }
 
-    /// Wait for the fixed cost of placing an order
-    pub async fn add_order(&mut self) {
-        self.rate_limiter.wait_with_cost(100).await;
+    /// Wait for the fixed cost of placing an order on the request's pair
+    pub async fn add_order(&mut self, add_order_request: &AddOrderRequest) {
+        let cost = self.add_order_cost(add_order_request);
+        self.acquire(&cost, RequestPriority::Normal).await;
     }
 
     /// Determine the cost and wait appropriately for the given [AddBatchedOrderRequest].
     ///
     /// The cost of a batch is n / 2, where n is the number of orders in the batch.
     pub async fn add_order_batch(&mut self, add_batched_order_request: &AddBatchedOrderRequest) {
-        let cost = 1.0 + (add_batched_order_request.orders.len() as f64 / 2.0);
-        self.rate_limiter
-            .wait_with_cost((cost * 100.0) as usize)
-            .await;
+        let cost = self.add_order_batch_cost(add_batched_order_request);
+        self.acquire(&cost, RequestPriority::Normal).await;
     }
 
     /// Determine the cost of editing an order and wait if necessary
     ///
-    /// This is inclusive of penalties for orders edited soon after creation.
+    /// This is inclusive of penalties for orders edited soon after creation. The cost is charged to
+    /// the pair the order was placed on if it's known, and the request's pair otherwise.
     pub async fn edit_order(&mut self, edit_order_request: &EditOrderRequest) {
-        let now_seconds = OffsetDateTime::now_utc().unix_timestamp();
-        let request_id = edit_order_request.tx_id.clone();
-
-        let mut cache_guard = self.ttl_ref_id_cache.lock().await;
-        let order_lifetime = cache_guard
-            .get(&request_id)
-            .map(|ttl_entry| now_seconds - ttl_entry.data)
-            .unwrap_or(i64::MAX);
-
-        let penalty = Self::edit_order_penalty(order_lifetime);
-        let cost = (penalty + 1) * 100;
-
-        self.rate_limiter.wait_with_cost(cost as usize).await
+        let cost = self.edit_order_cost(edit_order_request).await;
+        self.acquire(&cost, RequestPriority::Normal).await;
     }
 
     /// Determine the cost of cancelling the provided order id and wait appropriately
     ///
-    /// This is inclusive of penalties for orders cancelled soon after creation.
+    /// This is inclusive of penalties for orders cancelled soon after creation. Cancels are
+    /// [RequestPriority::Critical], so they're served before waiting orders on the same pair.
     pub async fn cancel_order_tx_id(&mut self, id: &String) {
-        let now_seconds = OffsetDateTime::now_utc().unix_timestamp();
+        let cost = self.cancel_order_tx_id_cost(id).await;
+        self.acquire(&cost, RequestPriority::Critical).await;
+    }
+
+    /// Determine the cost of cancelling the provided user ref and wait appropriately
+    ///
+    /// This is inclusive of penalties for orders cancelled soon after creation. Cancels are
+    /// [RequestPriority::Critical], so they're served before waiting orders on the same pair.
+    pub async fn cancel_order_user_ref(&mut self, id: &i64) {
+        let cost = self.cancel_order_user_ref_cost(id).await;
+        self.acquire(&cost, RequestPriority::Critical).await;
+    }
 
+    /// Get the cost of placing an order right now, without waiting.
+    pub fn add_order_cost(&self, add_order_request: &AddOrderRequest) -> TradingCost {
+        Self::placement_cost(&add_order_request.pair)
+    }
+
+    /// Get the cost of placing an order over the v2 websocket API right now, without waiting.
+    pub fn add_order_params_cost(&self, add_order_params: &AddOrderParams) -> TradingCost {
+        Self::placement_cost(&add_order_params.symbol)
+    }
+
+    /// Get the cost of placing a batch of orders right now, without waiting.
+    pub fn add_order_batch_cost(
+        &self,
+        add_batched_order_request: &AddBatchedOrderRequest,
+    ) -> TradingCost {
+        Self::batch_cost(
+            &add_batched_order_request.pair,
+            add_batched_order_request.orders.len(),
+        )
+    }
+
+    /// Get the cost of placing a batch of orders over the v2 websocket API right now, without
+    /// waiting.
+    pub fn batch_order_params_cost(&self, batch_order_params: &BatchOrderParams) -> TradingCost {
+        Self::batch_cost(&batch_order_params.symbol, batch_order_params.orders.len())
+    }
+
+    /// Get the cost of editing an order right now, including any penalty for the order's age,
+    /// without waiting.
+    pub async fn edit_order_cost(&self, edit_order_request: &EditOrderRequest) -> TradingCost {
+        self.edit_cost(&edit_order_request.tx_id, &edit_order_request.pair)
+            .await
+    }
+
+    /// Get the cost of editing an order over the v2 websocket API right now, including any penalty
+    /// for the order's age, without waiting.
+    pub async fn edit_order_params_cost(&self, edit_order_params: &EditOrderParams) -> TradingCost {
+        self.edit_cost(&edit_order_params.order_id, &edit_order_params.symbol)
+            .await
+    }
+
+    /// Get the user ref an order was placed with, if the order is known and had one.
+    pub(crate) async fn order_user_ref(&self, tx_id: &String) -> Option<i64> {
         let mut cache_guard = self.ttl_ref_id_cache.lock().await;
-        let order_lifetime = cache_guard
-            .get(id)
-            .map(|ttl_entry| now_seconds - ttl_entry.data)
-            .unwrap_or(i64::MAX);
+        cache_guard
+            .get(tx_id)
+            .and_then(|ttl_entry| ttl_entry.data.user_ref)
+    }
+
+    fn placement_cost(pair: &str) -> TradingCost {
+        TradingCost {
+            pair: Some(pair.to_string()),
+            cost: 100,
+            penalty: 0,
+        }
+    }
+
+    fn batch_cost(pair: &str, orders: usize) -> TradingCost {
+        let cost = 1.0 + (orders as f64 / 2.0);
+
+        TradingCost {
+            pair: Some(pair.to_string()),
+            cost: (cost * 100.0) as usize,
+            penalty: 0,
+        }
+    }
+
+    async fn edit_cost(&self, tx_id: &String, pair: &str) -> TradingCost {
+        let mut cache_guard = self.ttl_ref_id_cache.lock().await;
+        let placement = cache_guard
+            .get(tx_id)
+            .map(|ttl_entry| ttl_entry.data.clone());
         drop(cache_guard);
 
-        self.cancel_with_penalty(order_lifetime).await;
+        let (order_lifetime, pair) = match placement {
+            Some(placement) => (placement.lifetime(), placement.pair),
+            None => (i64::MAX, pair.to_string()),
+        };
+
+        let penalty = (Self::edit_order_penalty(order_lifetime) * 100) as usize;
+
+        TradingCost {
+            pair: Some(pair),
+            cost: penalty + 100,
+            penalty,
+        }
     }
 
-    /// Determine the cost of cancelling the provided user ref and wait appropriately
+    /// Get the cost of cancelling the provided order id right now, without waiting.
     ///
-    /// This is inclusive of penalties for orders cancelled soon after creation.
-    pub async fn cancel_order_user_ref(&mut self, id: &i64) {
-        let now_seconds = OffsetDateTime::now_utc().unix_timestamp();
+    /// Orders that aren't known were either placed elsewhere or more than 300s ago, so cancelling
+    /// them is free and there's no pair to charge.
+    pub async fn cancel_order_tx_id_cost(&self, id: &String) -> TradingCost {
+        let mut cache_guard = self.ttl_ref_id_cache.lock().await;
+        let placement = cache_guard.get(id).map(|ttl_entry| ttl_entry.data.clone());
+        drop(cache_guard);
+
+        Self::cancel_cost(placement)
+    }
 
+    /// Get the cost of cancelling the provided user ref right now, without waiting.
+    ///
+    /// Orders that aren't known were either placed elsewhere or more than 300s ago, so cancelling
+    /// them is free and there's no pair to charge.
+    pub async fn cancel_order_user_ref_cost(&self, id: &i64) -> TradingCost {
         let mut cache_guard = self.ttl_user_ref_cache.lock().await;
-        let order_lifetime = cache_guard
-            .get(id)
-            .map(|ttl_entry| now_seconds - ttl_entry.data)
-            .unwrap_or(i64::MAX);
+        let placement = cache_guard.get(id).map(|ttl_entry| ttl_entry.data.clone());
         drop(cache_guard);
 
-        self.cancel_with_penalty(order_lifetime).await;
+        Self::cancel_cost(placement)
+    }
+
+    /// Get the remaining tokens of a pair, and the time until `cost` could be spent on it.
+    pub async fn budget(&self, pair: &str, cost: usize) -> RateLimitBudget {
+        let rate_limiters = self.rate_limiters.lock().await;
+
+        match rate_limiters.get(pair) {
+            Some(rate_limiter) => rate_limiter.budget(cost),
+            None => rate_limiters.limits.rate_limiter().budget(cost),
+        }
+    }
+
+    /// Wait for a previewed cost's tokens from its pair's rate limiter with the given priority,
+    /// creating the limiter if this is the first request for the pair.
+    pub async fn acquire(&self, cost: &TradingCost, priority: RequestPriority) {
+        let pair = match &cost.pair {
+            Some(pair) if cost.cost > 0 => pair,
+            _ => return,
+        };
+
+        // clone the pair's limiter, which shares its state, so other pairs aren't blocked while
+        // this one waits
+        let rate_limiter = self.rate_limiters.lock().await.get_or_insert(pair);
+
+        rate_limiter
+            .acquire_with_priority(cost.cost, priority)
+            .await;
+
+        self.rate_limiters.lock().await.charged(pair);
+    }
+
+    /// Get the limits currently applied to each pair.
+    pub async fn limits(&self) -> TradingLimits {
+        self.rate_limiters.lock().await.limits
+    }
+
+    /// Apply new limits to every pair, keeping the tokens each pair has already spent.
+    pub async fn set_limits(&self, limits: TradingLimits) {
+        let mut rate_limiters = self.rate_limiters.lock().await;
+        rate_limiters.limits = limits;
+
+        for rate_limiter in rate_limiters.rate_limiters.values() {
+            rate_limiter.set_limits(limits.max_tokens, limits.decay_per_second);
+        }
+    }
+
+    /// Use an [AssetRegistry] to resolve pair names, so the REST name of a pair (e.g. `XBTUSD`)
+    /// and its websocket symbol (e.g. `BTC/USD`) share one rate limiter.
+    ///
+    /// Without a registry, pairs are keyed by the name they're given, so REST requests and
+    /// websocket messages for the same pair are counted separately.
+    pub async fn set_asset_registry(&self, asset_registry: AssetRegistry) {
+        let mut rate_limiters = self.rate_limiters.lock().await;
+        rate_limiters.asset_registry = Some(asset_registry);
+
+        // re-key existing limiters, keeping the most spent of any that now share a key
+        let charged_at = std::mem::take(&mut rate_limiters.charged_at);
+        for (pair, at) in charged_at {
+            let key = rate_limiters.key(&pair);
+            let latest = rate_limiters.charged_at.entry(key).or_insert(at);
+            *latest = (*latest).max(at);
+        }
+
+        let existing = std::mem::take(&mut rate_limiters.rate_limiters);
+        for (pair, rate_limiter) in existing {
+            let key = rate_limiters.key(&pair);
+            match rate_limiters.rate_limiters.get(&key) {
+                Some(other) if other.remaining_tokens() <= rate_limiter.remaining_tokens() => {}
+                _ => {
+                    rate_limiters.rate_limiters.insert(key, rate_limiter);
+                }
+            }
+        }
+    }
+
+    /// Seed the limits from the `maxratecount` reported when subscribing to executions, see
+    /// [TradingLimits::from_max_rate_count].
+    pub async fn observe_max_rate_count(&self, max_rate_count: i64) {
+        let limits = TradingLimits::from_max_rate_count(max_rate_count);
+        debug!("Setting trading limits to {:?}", limits);
+        self.set_limits(limits).await;
+    }
+
+    /// Correct the local estimate of a pair's counter with Kraken's reported `rate_count`, as of
+    /// `observed_at`.
+    ///
+    /// A count observed before the pair was last charged locally doesn't include that charge yet,
+    /// so it can only raise the local count, never lower it.
+    ///
+    /// Executions report v2 symbols (e.g. `BTC/USD`), so without an [AssetRegistry] (see
+    /// [KrakenTradingRateLimiter::set_asset_registry]) these correct a different counter than REST
+    /// orders placed on the same pair.
+    pub async fn observe_rate_count(
+        &self,
+        pair: &str,
+        rate_count: Decimal,
+        observed_at: OffsetDateTime,
+    ) {
+        let spent = (rate_count * Decimal::ONE_HUNDRED)
+            .round()
+            .to_usize()
+            .unwrap_or(0);
+
+        let mut rate_limiters = self.rate_limiters.lock().await;
+        if rate_limiters.asset_registry.is_none() && !rate_limiters.warned_unregistered {
+            warn!(
+                "Observing rate counts without an AssetRegistry, so {} is counted separately from its REST name",
+                pair
+            );
+            rate_limiters.warned_unregistered = true;
+        }
+
+        let stale = rate_limiters
+            .charged_at
+            .get(&rate_limiters.key(pair))
+            .is_some_and(|charged_at| observed_at < *charged_at);
+
+        let rate_limiter = rate_limiters.get_or_insert(pair);
+        let max_tokens = rate_limiter.max_tokens();
+        let remaining = max_tokens.saturating_sub(spent);
+
+        if stale {
+            rate_limiter.set_remaining_tokens(remaining.min(rate_limiter.remaining_tokens()));
+        } else {
+            rate_limiter.set_remaining_tokens(remaining);
+        }
+    }
+
+    /// Update the limiter from a v2 websocket message, which seeds the limits from the executions
+    /// subscription's `maxratecount`, and corrects each pair's counter from the `rate_count` of
+    /// executions. All other messages are ignored.
+    ///
+    /// The executions subscription must set `rate_counter` for counts to be reported.
+    pub async fn observe_message(&self, message: &WssMessage) {
+        match message {
+            WssMessage::Method(MethodMessage::Subscription(response)) => {
+                if let Some(SubscriptionResult::Execution(result)) = &response.result {
+                    if let Some(max_rate_count) = result.max_rate_count {
+                        self.observe_max_rate_count(max_rate_count).await;
+                    }
+                }
+            }
+            WssMessage::Channel(ChannelMessage::Execution(response)) => {
+                for execution in &response.data {
+                    if let (Some(symbol), Some(rate_count)) =
+                        (&execution.symbol, execution.rate_count)
+                    {
+                        // an unreadable timestamp is treated as old, so it can only raise the count
+                        let observed_at = OffsetDateTime::parse(&execution.timestamp, &Rfc3339)
+                            .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+                        self.observe_rate_count(symbol, rate_count, observed_at)
+                            .await;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Observe every message of a stream, e.g. a `KrakenMessageStream<WssMessage>`, with
+    /// [KrakenTradingRateLimiter::observe_message] before passing it on unchanged.
+    pub fn observe_stream<S>(
+        &self,
+        stream: S,
+    ) -> impl Stream<Item = Result<WssMessage, WSSError>> + Send
+    where
+        S: Stream<Item = Result<WssMessage, WSSError>> + Send,
+    {
+        let limiter = self.clone();
+
+        stream.then(move |message| {
+            let limiter = limiter.clone();
+            async move {
+                if let Ok(message) = &message {
+                    limiter.observe_message(message).await;
+                }
+                message
+            }
+        })
     }
 
-    async fn cancel_with_penalty(&mut self, order_lifetime: i64) {
-        let penalty = Self::cancel_order_penalty(order_lifetime);
-        let cost = penalty * 100;
+    fn cancel_cost(placement: Option<OrderPlacement>) -> TradingCost {
+        match placement {
+            Some(placement) => {
+                let penalty = (Self::cancel_order_penalty(placement.lifetime()) * 100) as usize;
 
-        self.rate_limiter.wait_with_cost(cost as usize).await
+                TradingCost {
+                    pair: Some(placement.pair),
+                    cost: penalty,
+                    penalty,
+                }
+            }
+            None => TradingCost {
+                pair: None,
+                cost: 0,
+                penalty: 0,
+            },
+        }
     }
 
     /// Notify the rate limiter of a new order being created -- this is essential to the rate limiting scheme!
     ///
     /// Order lifetimes must be known in order to determine the penalties for editing or cancelling
-    /// orders that were placed less than 300s ago.
+    /// orders that were placed less than 300s ago, and the pair is needed to charge those penalties
+    /// to the right pair's counter.
     pub async fn notify_add_order(
         &mut self,
         tx_id: String,
         placement_time: i64,
         user_ref: Option<i64>,
+        pair: String,
     ) {
-        let ttl_ref_entry = TtlEntry::new(tx_id, ORDER_TTL_US, placement_time);
+        let placement = OrderPlacement {
+            placement_time,
+            pair,
+            user_ref,
+        };
+        let ttl_ref_entry = TtlEntry::new(tx_id, ORDER_TTL_US, placement.clone());
 
         let mut cache_guard = self.ttl_ref_id_cache.lock().await;
         cache_guard.insert(ttl_ref_entry);
 
         if let Some(user_ref) = user_ref {
-            let ttl_user_ref_entry = TtlEntry::new(user_ref, ORDER_TTL_US, placement_time);
+            let ttl_user_ref_entry = TtlEntry::new(user_ref, ORDER_TTL_US, placement);
             let mut cache_guard = self.ttl_user_ref_cache.lock().await;
             cache_guard.insert(ttl_user_ref_entry);
         }
@@ -165,35 +601,31 @@ impl KrakenTradingRateLimiter {
             0
         }
     }
-
-    fn get_rate_limiter(user_verification: VerificationTier) -> TokenBucketRateLimiter {
-        // tokens are scaled 100x from Kraken's floating-point method to keep as integers
-        match user_verification {
-            VerificationTier::Intermediate => {
-                let token_bucket_state = TokenBucketState::new(12500, 234, Duration::from_secs(1));
-                TokenBucketRateLimiter::new(Arc::new(Mutex::new(token_bucket_state)))
-            }
-            VerificationTier::Pro => {
-                let token_bucket_state = TokenBucketState::new(18000, 375, Duration::from_secs(1));
-                TokenBucketRateLimiter::new(Arc::new(Mutex::new(token_bucket_state)))
-            }
-        }
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::rate_limiting::trading_rate_limits::KrakenTradingRateLimiter;
+    use crate::rate_limiting::trading_rate_limits::{KrakenTradingRateLimiter, TradingLimits};
     /// Tests use Tokio's pause() functionality to have instantaneous testing that relies on Tokio
     /// keeping track of time elapsed by fast-forwarding when there are no pending tasks on the
     /// event loop.
     ///
     /// Tests are done at a high enough level that execution time of Rust is thought to be negligible.
     ///
+    use crate::request_types::AddOrderRequest;
     use crate::response_types::VerificationTier::{Intermediate, Pro};
+    use crate::response_types::{BuySell, OrderType};
+    use crate::wss::v2::base_messages::WssMessage;
+    use futures_util::StreamExt;
+    use rust_decimal_macros::dec;
     use std::time::Duration;
+    use time::OffsetDateTime;
     use tokio::time::{pause, Instant};
 
+    fn add_order_request(pair: &str) -> AddOrderRequest {
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(1), pair.to_string()).build()
+    }
+
     #[tokio::test]
     async fn test_trading_rate_limiter_intermediate_add_order_limit() {
         pause();
@@ -203,7 +635,7 @@ mod tests {
         let start = Instant::now();
         // 126 calls should push limiter over the 12500 limit, requiring waiting 1s
         for _ in 0..126 {
-            limiter.add_order().await;
+            limiter.add_order(&add_order_request("XBTUSD")).await;
         }
 
         let end = Instant::now();
@@ -222,7 +654,7 @@ mod tests {
         let start = Instant::now();
         // 181 calls should push limiter over the 18000 limit, requiring waiting 1s
         for _ in 0..181 {
-            limiter.add_order().await;
+            limiter.add_order(&add_order_request("XBTUSD")).await;
         }
 
         let end = Instant::now();
@@ -243,7 +675,7 @@ mod tests {
         //  Replenishing at 234/s means that an additional 15 orders (costing 100 * 15 = 1500 total) should
         //  take another (1500 / 234 = ) 6.4s wait
         for _ in 0..(126 + 15) {
-            limiter.add_order().await;
+            limiter.add_order(&add_order_request("XBTUSD")).await;
         }
 
         let end = Instant::now();
@@ -266,7 +698,7 @@ mod tests {
         //  Replenishing at 375/s means that each additional 4 orders (costing 400 total) should
         //  take another 1s wait for that batch of 4
         for _ in 0..(181 + (4 * 3)) {
-            limiter.add_order().await;
+            limiter.add_order(&add_order_request("XBTUSD")).await;
         }
 
         let end = Instant::now();
@@ -278,6 +710,137 @@ mod tests {
         assert!(elapsed < Duration::from_secs(5));
     }
 
+    #[tokio::test]
+    async fn test_trading_rate_limiter_pairs_limited_separately() {
+        pause();
+
+        let mut limiter = KrakenTradingRateLimiter::new(Intermediate);
+        let xbt = add_order_request("XBTUSD");
+        let eth = add_order_request("ETHUSD");
+
+        let start = Instant::now();
+        // 125 orders on each pair exhaust both pairs' tokens, but neither pair should wait
+        for _ in 0..125 {
+            limiter.add_order(&xbt).await;
+            limiter.add_order(&eth).await;
+        }
+
+        assert!(start.elapsed() < Duration::from_millis(10));
+
+        // the next order on either pair waits for that pair's replenishment
+        limiter.add_order(&eth).await;
+        assert!(start.elapsed() > Duration::from_secs(1));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_trading_rate_limiter_cancel_charges_order_pair() {
+        pause();
+
+        let mut limiter = KrakenTradingRateLimiter::new(Intermediate);
+        let xbt = add_order_request("XBTUSD");
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        // exhaust the tokens of XBTUSD, leaving 0
+        for _ in 0..125 {
+            limiter.add_order(&xbt).await;
+        }
+
+        limiter
+            .notify_add_order("ETH-ORDER".to_string(), now, Some(7), "ETHUSD".to_string())
+            .await;
+        limiter
+            .notify_add_order("XBT-ORDER".to_string(), now, None, "XBTUSD".to_string())
+            .await;
+
+        // cancelling a fresh ETHUSD order, or one that isn't known, shouldn't wait on XBTUSD
+        let start = Instant::now();
+        limiter.cancel_order_user_ref(&7).await;
+        limiter.cancel_order_tx_id(&"UNKNOWN".to_string()).await;
+        assert!(start.elapsed() < Duration::from_millis(10));
+
+        // cancelling a fresh XBTUSD order costs 800, requiring 4 replenishments of 234
+        limiter.cancel_order_tx_id(&"XBT-ORDER".to_string()).await;
+        assert!(start.elapsed() > Duration::from_secs(3));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_limits_from_max_rate_count() {
+        let cases = vec![
+            (60, 6000, 100),
+            (125, 12500, 234),
+            (150, 15000, 234),
+            (180, 18000, 375),
+        ];
+
+        for (max_rate_count, max_tokens, decay_per_second) in cases {
+            assert_eq!(
+                TradingLimits {
+                    max_tokens,
+                    decay_per_second
+                },
+                TradingLimits::from_max_rate_count(max_rate_count)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trading_rate_limiter_observes_executions() {
+        pause();
+
+        let limiter = KrakenTradingRateLimiter::new(Intermediate);
+        let subscribed = r#"{"method":"subscribe","req_id":0,"result":{"channel":"executions","maxratecount":180,"snapshot":true},"success":true,"time_in":"2024-05-19T19:30:36.343170Z","time_out":"2024-05-19T19:30:36.350083Z"}"#;
+        let execution = r#"{"channel":"executions","type":"update","data":[{"timestamp":"2024-05-18T12:58:40.165132Z","order_status":"new","exec_type":"new","order_id":"KIUEL4-G3PWU-HOJTYU","symbol":"BTC/USD","ratecount":50.5}],"sequence":143}"#;
+
+        let messages: Vec<WssMessage> = [subscribed, execution]
+            .iter()
+            .map(|message| serde_json::from_str(message).unwrap())
+            .collect();
+
+        let observed: Vec<_> = limiter
+            .observe_stream(futures_util::stream::iter(messages.into_iter().map(Ok)))
+            .collect()
+            .await;
+        assert_eq!(2, observed.len());
+
+        assert_eq!(TradingLimits::for_tier(Pro), limiter.limits().await);
+        assert_eq!(12950, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+        assert_eq!(18000, limiter.budget("ETH/USD", 100).await.remaining_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_trading_rate_limiter_stale_rate_count_only_raises() {
+        pause();
+
+        let mut limiter = KrakenTradingRateLimiter::new(Intermediate);
+        let request =
+            AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(1), "BTC/USD".into())
+                .build();
+
+        let before_charge = OffsetDateTime::now_utc() - time::Duration::seconds(1);
+        limiter.add_order(&request).await;
+        assert_eq!(12400, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+
+        // a lower count from before the charge doesn't undo it, but a higher one is applied
+        limiter
+            .observe_rate_count("BTC/USD", dec!(0), before_charge)
+            .await;
+        assert_eq!(12400, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+
+        limiter
+            .observe_rate_count("BTC/USD", dec!(5), before_charge)
+            .await;
+        assert_eq!(12000, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+
+        // a count from after the charge includes it, so it's applied either way
+        let after_charge = OffsetDateTime::now_utc() + time::Duration::seconds(1);
+        limiter
+            .observe_rate_count("BTC/USD", dec!(2), after_charge)
+            .await;
+        assert_eq!(12300, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+    }
+
     #[test]
     fn test_edit_order_penalties() {
         let cases = vec![
@@ -145,6 +145,11 @@ impl OrderFlags {
     pub fn new(order_flags: Vec<OrderFlag>) -> OrderFlags {
         OrderFlags(order_flags)
     }
+
+    /// Whether the given flag is set.
+    pub fn contains(&self, order_flag: &OrderFlag) -> bool {
+        self.0.contains(order_flag)
+    }
 }
 
 impl From<OrderFlag> for OrderFlags {
@@ -389,7 +394,8 @@ pub struct OrderbookRequest {
 /// `since` can be set to 0 to get the very first trades recorded on Kraken, or set to the `last`
 /// value provided in the response for full pagination.
 ///
-/// See examples/live_retrieving_recent_traders.rs for an example of completing a paginated request.
+/// See [recent_trades_stream](crate::clients::pagination::recent_trades_stream) for a stream that
+/// completes the pagination, as used in examples/live_retrieving_recent_trades.rs.
 #[derive(Debug, Clone, QueryParams, Builder)]
 pub struct RecentTradesRequest {
     #[query(required)]
@@ -574,6 +580,8 @@ pub struct DeleteExportRequest {
 pub struct AddOrderRequest {
     #[query(rename = "userref")]
     pub user_ref: Option<i64>,
+    /// Client order id, which Kraken de-duplicates among open orders
+    pub cl_ord_id: Option<String>,
     #[builder(required)]
     #[query(required, rename = "ordertype")]
     pub order_type: OrderType,
@@ -635,6 +643,8 @@ pub struct AddBatchedOrderRequest {
 pub struct BatchedOrderRequest {
     #[serde(rename = "userref")]
     pub user_ref: Option<i64>,
+    /// Client order id, which Kraken de-duplicates among open orders
+    pub cl_ord_id: Option<String>,
     #[builder(required)]
     #[serde(rename = "ordertype")]
     pub order_type: OrderType,
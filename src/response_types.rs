@@ -290,11 +290,17 @@ impl Display for LedgerEntryType {
 }
 
 /// Status of a requested export report
+///
+/// Any status other than these, e.g. one Kraken adds later, is parsed as `Unknown`.
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq, Copy)]
 pub enum ExportReportStatusType {
     Queued,
     Processing,
     Processed,
+    Failed,
+    Deleted,
+    #[serde(other)]
+    Unknown,
 }
 
 impl Display for ExportReportStatusType {
@@ -303,6 +309,9 @@ impl Display for ExportReportStatusType {
             ExportReportStatusType::Queued => write!(f, "Queued"),
             ExportReportStatusType::Processing => write!(f, "Processing"),
             ExportReportStatusType::Processed => write!(f, "Processed"),
+            ExportReportStatusType::Failed => write!(f, "Failed"),
+            ExportReportStatusType::Deleted => write!(f, "Deleted"),
+            ExportReportStatusType::Unknown => write!(f, "Unknown"),
         }
     }
 }
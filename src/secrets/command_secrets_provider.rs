@@ -0,0 +1,132 @@
+//! A [SecretsProvider] that retrieves the API key and secret from an external command
+//!
+//! This allows secrets to be kept in a password manager or vault, e.g. with `pass`, where the
+//! entry holds the key on its first line and the secret on its second:
+//!
+//! ```no_run
+//! # use kraken_async_rs::secrets::command_secrets_provider::CommandSecretsProvider;
+//! # use kraken_async_rs::secrets::secrets_provider::SecretsProvider;
+//! # use std::sync::Arc;
+//! # use tokio::sync::Mutex;
+//! let provider = CommandSecretsProvider::new("pass", &["show", "kraken/api"]).unwrap();
+//! let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+//!     Box::new(Arc::new(Mutex::new(provider)));
+//! ```
+use crate::secrets::file_secrets_provider::parse_json_secrets;
+use crate::secrets::secrets_provider::{Secrets, SecretsError, SecretsProvider};
+use secrecy::Secret;
+use std::process::{Command, Stdio};
+
+/// A [SecretsProvider] that runs a command once on creation and caches the secrets it outputs.
+///
+/// The command's stdout must be either a JSON object with `key` and `secret` fields, or the key
+/// and secret on its first two non-empty lines. Its stderr is passed through, so prompts from the
+/// command (e.g. for a GPG passphrase) are still shown.
+#[derive(Debug, Clone)]
+pub struct CommandSecretsProvider {
+    secrets: Secrets,
+}
+
+impl CommandSecretsProvider {
+    /// Run `program` with `args` and parse its output, returning an error if it can't be run, exits
+    /// unsuccessfully, or doesn't output a key and secret.
+    pub fn new(program: &str, args: &[&str]) -> Result<CommandSecretsProvider, SecretsError> {
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|err| SecretsError::Command(format!("failed to run {}: {}", program, err)))?;
+
+        if !output.status.success() {
+            return Err(SecretsError::Command(format!(
+                "{} exited with {}",
+                program, output.status
+            )));
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|_| SecretsError::Parse(format!("output of {} is not UTF-8", program)))?;
+
+        Ok(CommandSecretsProvider {
+            secrets: parse_output(&stdout)?,
+        })
+    }
+}
+
+impl SecretsProvider for CommandSecretsProvider {
+    fn get_secrets(&mut self) -> Secrets {
+        self.secrets.clone()
+    }
+}
+
+fn parse_output(stdout: &str) -> Result<Secrets, SecretsError> {
+    if stdout.trim_start().starts_with('{') {
+        return parse_json_secrets(stdout);
+    }
+
+    let mut lines = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+
+    match (lines.next(), lines.next()) {
+        (Some(key), Some(secret)) => Ok(Secrets {
+            key: Secret::new(key.to_string()),
+            secret: Secret::new(secret.to_string()),
+        }),
+        _ => Err(SecretsError::Parse(
+            "expected a JSON object, or the key and secret on separate lines".to_string(),
+        )),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::secrets::command_secrets_provider::CommandSecretsProvider;
+    use crate::secrets::secrets_provider::{SecretsError, SecretsProvider};
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn test_line_output() {
+        let mut provider =
+            CommandSecretsProvider::new("sh", &["-c", "printf 'line-key\\n\\nline-secret\\n'"])
+                .unwrap();
+
+        let secrets = provider.get_secrets();
+        assert_eq!("line-key", secrets.key.expose_secret());
+        assert_eq!("line-secret", secrets.secret.expose_secret());
+    }
+
+    #[test]
+    fn test_json_output() {
+        let mut provider = CommandSecretsProvider::new(
+            "sh",
+            &[
+                "-c",
+                r#"echo '{"key": "json-key", "secret": "json-secret"}'"#,
+            ],
+        )
+        .unwrap();
+
+        let secrets = provider.get_secrets();
+        assert_eq!("json-key", secrets.key.expose_secret());
+        assert_eq!("json-secret", secrets.secret.expose_secret());
+    }
+
+    #[test]
+    fn test_command_errors() {
+        let failed = CommandSecretsProvider::new("sh", &["-c", "echo leaked-key; exit 3"]);
+        let err = failed.unwrap_err();
+        assert!(matches!(err, SecretsError::Command(_)));
+        assert!(!err.to_string().contains("leaked-key"));
+
+        let missing = CommandSecretsProvider::new("kraken-async-rs-missing-command", &[]);
+        assert!(matches!(missing, Err(SecretsError::Command(_))));
+
+        let incomplete = CommandSecretsProvider::new("sh", &["-c", "echo leaked-key"]);
+        let err = incomplete.unwrap_err();
+        assert!(matches!(err, SecretsError::Parse(_)));
+        assert!(!err.to_string().contains("leaked-key"));
+    }
+}
@@ -0,0 +1,191 @@
+//! A [SecretsProvider] that reads the API key and secret from a JSON or TOML file
+//!
+//! The file must contain `key` and `secret` fields, e.g. in JSON:
+//!
+//! ```json
+//! {"key": "<api key>", "secret": "<api secret>"}
+//! ```
+//!
+//! or in TOML:
+//!
+//! ```toml
+//! key = "<api key>"
+//! secret = "<api secret>"
+//! ```
+//!
+//! ```no_run
+//! # use kraken_async_rs::secrets::file_secrets_provider::FileSecretsProvider;
+//! # use kraken_async_rs::secrets::secrets_provider::SecretsProvider;
+//! # use std::sync::Arc;
+//! # use tokio::sync::Mutex;
+//! let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> = Box::new(Arc::new(Mutex::new(
+//!     FileSecretsProvider::new("/etc/kraken/secrets.toml").unwrap(),
+//! )));
+//! ```
+use crate::secrets::secrets_provider::{Secrets, SecretsError, SecretsProvider};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// A [SecretsProvider] that reads secrets from a file once on creation and caches them.
+///
+/// Files ending in `.toml` are parsed as TOML, and all others as JSON. On Unix, the file must not
+/// be readable or writable by group or others (e.g. mode `600` or `400`), so secrets aren't
+/// exposed to other users of a shared host.
+#[derive(Debug, Clone)]
+pub struct FileSecretsProvider {
+    secrets: Secrets,
+}
+
+impl FileSecretsProvider {
+    /// Read and parse secrets from the file at `path`, returning an error if the file is missing,
+    /// has insecure permissions, or doesn't contain a key and secret.
+    pub fn new(path: impl AsRef<Path>) -> Result<FileSecretsProvider, SecretsError> {
+        let path = path.as_ref();
+        // permissions are checked on the opened handle, so the file can't be swapped in between
+        let mut file = File::open(path)?;
+        check_permissions(path, &file)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let secrets = match path.extension() {
+            Some(extension) if extension == "toml" => parse_toml_secrets(&contents)?,
+            _ => parse_json_secrets(&contents)?,
+        };
+
+        Ok(FileSecretsProvider { secrets })
+    }
+}
+
+impl SecretsProvider for FileSecretsProvider {
+    fn get_secrets(&mut self) -> Secrets {
+        self.secrets.clone()
+    }
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &Path, file: &File) -> Result<(), SecretsError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = file.metadata()?.permissions().mode();
+
+    if mode & 0o077 != 0 {
+        return Err(SecretsError::Permissions(format!(
+            "{} has mode {:o}, but must not be accessible by group or others",
+            path.display(),
+            mode & 0o777
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path, _file: &File) -> Result<(), SecretsError> {
+    Ok(())
+}
+
+/// Parse secrets from a JSON object, without including any of the input in errors.
+pub(crate) fn parse_json_secrets(text: &str) -> Result<Secrets, SecretsError> {
+    serde_json::from_str(text).map_err(|err| {
+        SecretsError::Parse(format!(
+            "expected a JSON object with key and secret, error at line {} column {}",
+            err.line(),
+            err.column()
+        ))
+    })
+}
+
+/// Parse secrets from a TOML table, without including any of the input in errors.
+pub(crate) fn parse_toml_secrets(text: &str) -> Result<Secrets, SecretsError> {
+    toml::from_str(text).map_err(|err| {
+        let position = err
+            .span()
+            .map(|span| format!(", error at byte {}", span.start))
+            .unwrap_or_default();
+
+        SecretsError::Parse(format!(
+            "expected a TOML table with key and secret{}",
+            position
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::secrets::file_secrets_provider::FileSecretsProvider;
+    use crate::secrets::secrets_provider::{SecretsError, SecretsProvider};
+    use secrecy::ExposeSecret;
+    use std::path::PathBuf;
+
+    fn write_secrets_file(name: &str, contents: &str, mode: u32) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("kraken-secrets-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        path
+    }
+
+    #[test]
+    fn test_json_and_toml_files() {
+        let json = write_secrets_file(
+            "secrets.json",
+            r#"{"key": "json-key", "secret": "json-secret"}"#,
+            0o600,
+        );
+        let toml = write_secrets_file(
+            "secrets.toml",
+            "key = \"toml-key\"\nsecret = \"toml-secret\"\n",
+            0o400,
+        );
+
+        let secrets = FileSecretsProvider::new(&json).unwrap().get_secrets();
+        assert_eq!("json-key", secrets.key.expose_secret());
+        assert_eq!("json-secret", secrets.secret.expose_secret());
+
+        let secrets = FileSecretsProvider::new(&toml).unwrap().get_secrets();
+        assert_eq!("toml-key", secrets.key.expose_secret());
+        assert_eq!("toml-secret", secrets.secret.expose_secret());
+
+        std::fs::remove_file(json).unwrap();
+        std::fs::remove_file(toml).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let missing = std::env::temp_dir().join("kraken-secrets-missing.json");
+        assert!(matches!(
+            FileSecretsProvider::new(missing),
+            Err(SecretsError::Io(_))
+        ));
+
+        let invalid = write_secrets_file("invalid.json", r#"{"key": "leaked-key"}"#, 0o600);
+        let err = FileSecretsProvider::new(&invalid).unwrap_err();
+        assert!(matches!(err, SecretsError::Parse(_)));
+        assert!(!err.to_string().contains("leaked-key"));
+
+        std::fs::remove_file(invalid).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_insecure_permissions() {
+        let readable = write_secrets_file(
+            "readable.json",
+            r#"{"key": "key", "secret": "secret"}"#,
+            0o644,
+        );
+
+        let err = FileSecretsProvider::new(&readable).unwrap_err();
+        assert!(matches!(err, SecretsError::Permissions(_)));
+        assert!(err.to_string().contains("644"));
+
+        std::fs::remove_file(readable).unwrap();
+    }
+}
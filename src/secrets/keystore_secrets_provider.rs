@@ -0,0 +1,136 @@
+//! A [SecretsProvider] that decrypts the API key and secret from a passphrase-encrypted keystore
+//!
+//! Keystores are [age](https://age-encryption.org) files encrypted with a passphrase, containing
+//! the same JSON or TOML as a [FileSecretsProvider](crate::secrets::file_secrets_provider::FileSecretsProvider)
+//! file. They can be created with the `age` CLI, e.g. `age --passphrase --armor -o secrets.age secrets.toml`,
+//! after which the plaintext file should be deleted.
+//!
+//! Requires the `keystore` feature.
+//!
+//! ```no_run
+//! # use kraken_async_rs::secrets::keystore_secrets_provider::KeystoreSecretsProvider;
+//! # use kraken_async_rs::secrets::secrets_provider::SecretsProvider;
+//! # use secrecy::Secret;
+//! # use std::sync::Arc;
+//! # use tokio::sync::Mutex;
+//! # fn example(passphrase: Secret<String>) {
+//! let provider = KeystoreSecretsProvider::new("secrets.age", &passphrase).unwrap();
+//! let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+//!     Box::new(Arc::new(Mutex::new(provider)));
+//! # }
+//! ```
+use crate::secrets::file_secrets_provider::{parse_json_secrets, parse_toml_secrets};
+use crate::secrets::secrets_provider::{Secrets, SecretsError, SecretsProvider};
+use age::scrypt::Identity;
+use age::secrecy::SecretString;
+use secrecy::{ExposeSecret, Secret};
+use std::path::Path;
+
+/// A [SecretsProvider] that decrypts a keystore once on creation and caches the secrets.
+///
+/// Both binary and ASCII-armored keystores are supported.
+#[derive(Debug, Clone)]
+pub struct KeystoreSecretsProvider {
+    secrets: Secrets,
+}
+
+impl KeystoreSecretsProvider {
+    /// Decrypt the keystore at `path` with `passphrase`, returning an error if the file is missing,
+    /// the passphrase is incorrect, or the decrypted contents don't contain a key and secret.
+    pub fn new(
+        path: impl AsRef<Path>,
+        passphrase: &Secret<String>,
+    ) -> Result<KeystoreSecretsProvider, SecretsError> {
+        let ciphertext = std::fs::read(path)?;
+
+        let identity = Identity::new(SecretString::from(passphrase.expose_secret().clone()));
+        let plaintext = Secret::new(
+            age::decrypt(&identity, &ciphertext)
+                .map_err(|err| SecretsError::Decrypt(err.to_string()))?,
+        );
+
+        let contents = std::str::from_utf8(plaintext.expose_secret())
+            .map_err(|_| SecretsError::Parse("decrypted keystore is not UTF-8".to_string()))?;
+
+        let secrets = if contents.trim_start().starts_with('{') {
+            parse_json_secrets(contents)?
+        } else {
+            parse_toml_secrets(contents)?
+        };
+
+        Ok(KeystoreSecretsProvider { secrets })
+    }
+}
+
+impl SecretsProvider for KeystoreSecretsProvider {
+    fn get_secrets(&mut self) -> Secrets {
+        self.secrets.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::secrets::keystore_secrets_provider::KeystoreSecretsProvider;
+    use crate::secrets::secrets_provider::{SecretsError, SecretsProvider};
+    use age::scrypt::Recipient;
+    use age::secrecy::SecretString;
+    use secrecy::{ExposeSecret, Secret};
+    use std::path::PathBuf;
+
+    fn write_keystore(name: &str, plaintext: &str, passphrase: &str) -> PathBuf {
+        let mut recipient = Recipient::new(SecretString::from(passphrase.to_string()));
+        // keep tests fast, the default targets a second of work on the current machine
+        recipient.set_work_factor(10);
+
+        let path =
+            std::env::temp_dir().join(format!("kraken-keystore-{}-{}", std::process::id(), name));
+        let ciphertext = age::encrypt_and_armor(&recipient, plaintext.as_bytes()).unwrap();
+        std::fs::write(&path, ciphertext).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_decrypt_keystore() {
+        let passphrase = Secret::new("correct horse".to_string());
+        let json = write_keystore(
+            "json.age",
+            r#"{"key": "json-key", "secret": "json-secret"}"#,
+            "correct horse",
+        );
+        let toml = write_keystore(
+            "toml.age",
+            "key = \"toml-key\"\nsecret = \"toml-secret\"\n",
+            "correct horse",
+        );
+
+        let secrets = KeystoreSecretsProvider::new(&json, &passphrase)
+            .unwrap()
+            .get_secrets();
+        assert_eq!("json-key", secrets.key.expose_secret());
+        assert_eq!("json-secret", secrets.secret.expose_secret());
+
+        let secrets = KeystoreSecretsProvider::new(&toml, &passphrase)
+            .unwrap()
+            .get_secrets();
+        assert_eq!("toml-key", secrets.key.expose_secret());
+        assert_eq!("toml-secret", secrets.secret.expose_secret());
+
+        std::fs::remove_file(json).unwrap();
+        std::fs::remove_file(toml).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let keystore = write_keystore(
+            "wrong.age",
+            r#"{"key": "key", "secret": "secret"}"#,
+            "correct horse",
+        );
+
+        let result = KeystoreSecretsProvider::new(&keystore, &Secret::new("wrong".to_string()));
+        assert!(matches!(result, Err(SecretsError::Decrypt(_))));
+
+        std::fs::remove_file(keystore).unwrap();
+    }
+}
@@ -1,2 +1,6 @@
 //! Common handling for API secret retrieval and usage
+pub mod command_secrets_provider;
+pub mod file_secrets_provider;
+#[cfg(feature = "keystore")]
+pub mod keystore_secrets_provider;
 pub mod secrets_provider;
@@ -1,22 +1,114 @@
 //! Trait and implementations for retrieving API keys and secrets needed for private calls
 use dotenvy::dotenv;
+use futures_util::future::BoxFuture;
 use secrecy::Secret;
+use serde::Deserialize;
 use std::env;
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
 /// A struct containing the API key and secret (using [secrecy::Secret])
-#[derive(Debug, Clone)]
+///
+/// Deserializes from an object with `key` and `secret` fields, which is the format read by
+/// file-backed providers like [FileSecretsProvider](crate::secrets::file_secrets_provider::FileSecretsProvider).
+#[derive(Debug, Clone, Deserialize)]
 pub struct Secrets {
     pub key: Secret<String>,
     pub secret: Secret<String>,
 }
 
+/// Errors encountered while retrieving secrets.
+///
+/// Messages describe where retrieval failed, but never include the contents of a secrets file or
+/// command output, in case they contain part of the key or secret.
+#[derive(Debug)]
+pub enum SecretsError {
+    Io(std::io::Error),
+    /// The secrets could not be found, e.g. an environment variable is not set
+    NotFound(String),
+    /// The secrets file is accessible by users other than its owner
+    Permissions(String),
+    /// The secrets were found, but could not be parsed
+    Parse(String),
+    /// An external command failed to provide secrets
+    Command(String),
+    /// An encrypted keystore could not be decrypted
+    Decrypt(String),
+}
+
+impl From<std::io::Error> for SecretsError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Display for SecretsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretsError::Io(err) => write!(f, "{}", err),
+            SecretsError::NotFound(message) => write!(f, "Secrets not found: {}", message),
+            SecretsError::Permissions(message) => write!(f, "Insecure permissions: {}", message),
+            SecretsError::Parse(message) => write!(f, "Parse error: {}", message),
+            SecretsError::Command(message) => write!(f, "Command error: {}", message),
+            SecretsError::Decrypt(message) => write!(f, "Decryption error: {}", message),
+        }
+    }
+}
+
+impl Error for SecretsError {}
+
 /// Trait that exposes a method for retrieving secrets.
 ///
 /// Clients are generic over [SecretsProvider] so the client can specify how to retrieve the API
 /// key and secret at runtime.
+///
+/// Providers that need to fetch secrets without blocking, e.g. from a remote secret store, should
+/// implement [AsyncSecretsProvider] instead.
 pub trait SecretsProvider: Send + Sync + Debug {
     fn get_secrets(&mut self) -> Secrets;
+
+    /// Retrieve secrets, returning an error instead of panicking if they're unavailable.
+    ///
+    /// Defaults to [SecretsProvider::get_secrets] for providers that can't fail.
+    fn try_get_secrets(&mut self) -> Result<Secrets, SecretsError> {
+        Ok(self.get_secrets())
+    }
+}
+
+/// An async and fallible version of [SecretsProvider], for retrieving secrets over the network or
+/// from other sources that may be unavailable.
+///
+/// Existing [SecretsProvider]s can be used anywhere an [AsyncSecretsProvider] is expected by
+/// wrapping them in a [SecretsProviderAdapter].
+pub trait AsyncSecretsProvider: Send + Sync + Debug {
+    fn get_secrets(&mut self) -> BoxFuture<'_, Result<Secrets, SecretsError>>;
+}
+
+/// Adapts a shared [SecretsProvider] to an [AsyncSecretsProvider], using
+/// [SecretsProvider::try_get_secrets] so errors are returned rather than panicking.
+#[derive(Debug, Clone)]
+pub struct SecretsProviderAdapter {
+    secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>,
+}
+
+impl SecretsProviderAdapter {
+    pub fn new(secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>>) -> SecretsProviderAdapter {
+        SecretsProviderAdapter { secrets_provider }
+    }
+}
+
+impl From<Box<Arc<Mutex<dyn SecretsProvider>>>> for SecretsProviderAdapter {
+    fn from(value: Box<Arc<Mutex<dyn SecretsProvider>>>) -> Self {
+        SecretsProviderAdapter::new(value)
+    }
+}
+
+impl AsyncSecretsProvider for SecretsProviderAdapter {
+    fn get_secrets(&mut self) -> BoxFuture<'_, Result<Secrets, SecretsError>> {
+        Box::pin(async move { self.secrets_provider.lock().await.try_get_secrets() })
+    }
 }
 
 /// A common implementation that retrieves the key and secret from the given environment variable names.
@@ -43,29 +135,37 @@ impl<'a> EnvSecretsProvider<'a> {
 }
 
 impl<'a> SecretsProvider for EnvSecretsProvider<'a> {
+    /// Panics if either variable is not set, see [SecretsProvider::try_get_secrets] for a
+    /// fallible alternative.
     fn get_secrets(&mut self) -> Secrets {
+        match self.try_get_secrets() {
+            Ok(secrets) => secrets,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn try_get_secrets(&mut self) -> Result<Secrets, SecretsError> {
         if self.secrets.is_none() {
-            self.set_secrets();
+            self.set_secrets()?;
         }
 
-        self.secrets.clone().unwrap()
+        Ok(self.secrets.clone().unwrap())
     }
 }
 
 impl<'a> EnvSecretsProvider<'a> {
-    fn set_secrets(&mut self) {
+    fn set_secrets(&mut self) -> Result<(), SecretsError> {
         dotenv().ok();
-        let key = Secret::new(match env::var(self.key_name) {
-            Ok(secret) => secret,
-            _ => panic!("Error retrieving Kraken key from env"),
-        });
+        let key = Secret::new(env::var(self.key_name).map_err(|_| {
+            SecretsError::NotFound(format!("Kraken key in env var {}", self.key_name))
+        })?);
 
-        let secret = Secret::new(match env::var(self.secret_name) {
-            Ok(secret) => secret,
-            _ => panic!("Error retrieving Kraken secret from env"),
-        });
+        let secret = Secret::new(env::var(self.secret_name).map_err(|_| {
+            SecretsError::NotFound(format!("Kraken secret in env var {}", self.secret_name))
+        })?);
 
         self.secrets = Some(Secrets { key, secret });
+        Ok(())
     }
 }
 
@@ -99,8 +199,13 @@ impl<'a> SecretsProvider for StaticSecretsProvider<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::secrets::secrets_provider::{EnvSecretsProvider, SecretsProvider};
+    use crate::secrets::secrets_provider::{
+        AsyncSecretsProvider, EnvSecretsProvider, SecretsError, SecretsProvider,
+        SecretsProviderAdapter, StaticSecretsProvider,
+    };
     use secrecy::ExposeSecret;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
 
     #[test]
     fn test_env_secrets_provider() {
@@ -119,4 +224,23 @@ mod tests {
         assert_eq!(key, secrets.key.expose_secret());
         assert_eq!(secret, secrets.secret.expose_secret());
     }
+
+    #[tokio::test]
+    async fn test_secrets_provider_adapter() {
+        let found: Box<Arc<Mutex<dyn SecretsProvider>>> = Box::new(Arc::new(Mutex::new(
+            StaticSecretsProvider::new("api-key", "api-secret"),
+        )));
+        let secrets = SecretsProviderAdapter::new(found)
+            .get_secrets()
+            .await
+            .unwrap();
+        assert_eq!("api-key", secrets.key.expose_secret());
+        assert_eq!("api-secret", secrets.secret.expose_secret());
+
+        let missing: Box<Arc<Mutex<dyn SecretsProvider>>> = Box::new(Arc::new(Mutex::new(
+            EnvSecretsProvider::new("TEST_MISSING_KEY", "TEST_MISSING_SECRET"),
+        )));
+        let result = SecretsProviderAdapter::from(missing).get_secrets().await;
+        assert!(matches!(result, Err(SecretsError::NotFound(_))));
+    }
 }
@@ -0,0 +1,952 @@
+//! Accounts, balances and a price-time priority order book for the simulated exchange
+use crate::fees::FeeCurrency;
+use crate::response_types::{BuySell, OrderStatus, OrderType};
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const INSUFFICIENT_FUNDS: &str = "EOrder:Insufficient funds";
+pub(crate) const INVALID_PRICE: &str = "EOrder:Invalid price";
+pub(crate) const POST_ONLY: &str = "EOrder:Post only order";
+pub(crate) const UNKNOWN_ORDER: &str = "EOrder:Unknown order";
+pub(crate) const UNKNOWN_PAIR: &str = "EQuery:Unknown asset pair";
+pub(crate) const UNSUPPORTED_ORDER_TYPE: &str = "EGeneral:Invalid arguments:ordertype";
+pub(crate) const INVALID_VOLUME: &str = "EGeneral:Invalid arguments:volume";
+
+/// Number of closed orders returned per page, matching Kraken.
+pub(crate) const CLOSED_ORDERS_PAGE_SIZE: usize = 50;
+
+/// A pair that can be traded on the [SimulatedExchange](crate::simulated_exchange::server::SimulatedExchange).
+///
+/// Fees are percentages of the trade cost. The simulated exchange always charges them in the quote
+/// asset, ignoring `fcib` and `fciq` order flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedPair {
+    pub name: String,
+    pub base: String,
+    pub quote: String,
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
+}
+
+impl SimulatedPair {
+    /// Create a pair with no trading fees, e.g. `SimulatedPair::new("XBTUSD", "XXBT", "ZUSD")`.
+    pub fn new(name: impl Into<String>, base: impl Into<String>, quote: impl Into<String>) -> Self {
+        SimulatedPair {
+            name: name.into(),
+            base: base.into(),
+            quote: quote.into(),
+            maker_fee: Decimal::ZERO,
+            taker_fee: Decimal::ZERO,
+        }
+    }
+
+    /// Set the maker and taker fees, as percentages.
+    pub fn fees(mut self, maker_fee: Decimal, taker_fee: Decimal) -> Self {
+        self.maker_fee = maker_fee;
+        self.taker_fee = taker_fee;
+        self
+    }
+}
+
+/// An order as submitted to [ExchangeState::add_order].
+#[derive(Debug, Clone)]
+pub(crate) struct NewOrder {
+    pub pair: String,
+    pub side: BuySell,
+    pub order_type: OrderType,
+    pub volume: Decimal,
+    pub price: Option<Decimal>,
+    pub user_ref: Option<i64>,
+    pub post_only: bool,
+    pub fee_currency: FeeCurrency,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatedOrder {
+    pub id: String,
+    pub account: String,
+    pub pair: String,
+    pub side: BuySell,
+    pub order_type: OrderType,
+    pub volume: Decimal,
+    pub volume_executed: Decimal,
+    pub price: Option<Decimal>,
+    pub cost: Decimal,
+    pub fee: Decimal,
+    pub user_ref: Option<i64>,
+    pub post_only: bool,
+    pub fee_currency: FeeCurrency,
+    pub status: OrderStatus,
+    pub open_time: f64,
+    pub close_time: Option<f64>,
+    pub trades: Vec<String>,
+}
+
+impl SimulatedOrder {
+    /// Validate the price and volume of a new order for `account`, before it's given an id.
+    pub fn new(account: &str, new_order: NewOrder) -> Result<Self, &'static str> {
+        let price = match new_order.order_type {
+            OrderType::Limit => Some(new_order.price.ok_or(INVALID_PRICE)?),
+            OrderType::Market => None,
+            _ => return Err(UNSUPPORTED_ORDER_TYPE),
+        };
+
+        if new_order.volume <= Decimal::ZERO {
+            return Err(INVALID_VOLUME);
+        }
+
+        if price.is_some_and(|price| price <= Decimal::ZERO) {
+            return Err(INVALID_PRICE);
+        }
+
+        Ok(SimulatedOrder {
+            id: String::new(),
+            account: account.to_string(),
+            pair: new_order.pair,
+            side: new_order.side,
+            order_type: new_order.order_type,
+            volume: new_order.volume,
+            volume_executed: Decimal::ZERO,
+            price,
+            cost: Decimal::ZERO,
+            fee: Decimal::ZERO,
+            user_ref: new_order.user_ref,
+            post_only: new_order.post_only,
+            fee_currency: new_order.fee_currency,
+            status: OrderStatus::Open,
+            open_time: now(),
+            close_time: None,
+            trades: Vec::new(),
+        })
+    }
+
+    pub fn remaining(&self) -> Decimal {
+        self.volume - self.volume_executed
+    }
+
+    pub fn description(&self) -> String {
+        match self.price {
+            Some(price) => format!(
+                "{} {:.8} {} @ {} {}",
+                self.side, self.volume, self.pair, self.order_type, price
+            ),
+            None => format!(
+                "{} {:.8} {} @ {}",
+                self.side, self.volume, self.pair, self.order_type
+            ),
+        }
+    }
+
+    /// Whether this order would trade with liquidity at `price`, which is always true for market
+    /// orders.
+    pub fn crosses_price(&self, price: Decimal) -> bool {
+        match (self.side, self.price) {
+            (_, None) => true,
+            (BuySell::Buy, Some(limit)) => price <= limit,
+            (BuySell::Sell, Some(limit)) => price >= limit,
+        }
+    }
+
+    /// Walk `levels` (best price first) without executing to find the cost of filling the order.
+    pub fn fill_cost(&self, levels: &[(Decimal, Decimal)]) -> Decimal {
+        let mut remaining = self.volume;
+        let mut cost = Decimal::ZERO;
+        for (price, quantity) in levels {
+            if !self.crosses_price(*price) {
+                break;
+            }
+            let quantity = remaining.min(*quantity);
+            cost += quantity * price;
+            remaining -= quantity;
+        }
+
+        cost
+    }
+
+    /// Fee for a fill of `quantity` costing `cost`, at `fee` percent in the order's fee currency.
+    pub fn fill_fee(&self, quantity: Decimal, cost: Decimal, fee: Decimal) -> Decimal {
+        let charged_on = match self.fee_currency {
+            FeeCurrency::Base => quantity,
+            FeeCurrency::Quote => cost,
+        };
+
+        (charged_on * fee / Decimal::ONE_HUNDRED).round_dp(8)
+    }
+
+    /// Change to the account's base and quote balances from a fill, with `fee` charged in the
+    /// order's fee currency.
+    pub fn settlement(&self, quantity: Decimal, cost: Decimal, fee: Decimal) -> (Decimal, Decimal) {
+        let (base, quote) = match self.side {
+            BuySell::Buy => (quantity, -cost),
+            BuySell::Sell => (-quantity, cost),
+        };
+
+        match self.fee_currency {
+            FeeCurrency::Base => (base - fee, quote),
+            FeeCurrency::Quote => (base, quote - fee),
+        }
+    }
+
+    /// Record a trade against the order, closing it once fully filled.
+    pub fn execute(&mut self, quantity: Decimal, cost: Decimal, fee: Decimal, trade_id: &str) {
+        self.volume_executed += quantity;
+        self.cost += cost;
+        self.fee += fee;
+        self.trades.push(trade_id.to_string());
+
+        if self.remaining().is_zero() {
+            self.status = OrderStatus::Closed;
+            self.close_time = Some(now());
+        }
+    }
+
+    /// Close a market order once it's taken all the liquidity it can, since market orders never
+    /// rest on the book.
+    pub fn close_market(&mut self) {
+        if self.status == OrderStatus::Open && self.order_type == OrderType::Market {
+            self.status = if self.volume_executed.is_zero() {
+                OrderStatus::Canceled
+            } else {
+                OrderStatus::Closed
+            };
+            self.close_time = Some(now());
+        }
+    }
+
+    /// Order json, shaped like [Order](crate::response_types::Order) and
+    /// [ClosedOrder](crate::response_types::ClosedOrder).
+    pub fn to_json(&self, include_trades: bool) -> Value {
+        let average_price = if self.volume_executed.is_zero() {
+            Decimal::ZERO
+        } else {
+            (self.cost / self.volume_executed).round_dp(8)
+        };
+
+        let mut order = json!({
+            "refid": null,
+            "userref": self.user_ref.unwrap_or(0),
+            "status": status_str(self.status),
+            "opentm": self.open_time,
+            "starttm": 0,
+            "expiretm": 0,
+            "descr": {
+                "pair": self.pair,
+                "type": self.side.to_string(),
+                "ordertype": self.order_type.to_string(),
+                "price": self.price.unwrap_or_default().to_string(),
+                "price2": "0",
+                "leverage": "none",
+                "order": self.description(),
+                "close": ""
+            },
+            "vol": format!("{:.8}", self.volume),
+            "vol_exec": format!("{:.8}", self.volume_executed),
+            "cost": format!("{:.8}", self.cost),
+            "fee": format!("{:.8}", self.fee),
+            "price": average_price.to_string(),
+            "stopprice": "0.00000000",
+            "limitprice": "0.00000000",
+            "misc": "",
+            "oflags": self.flags(),
+        });
+
+        if self.close_time.is_some() {
+            order["closetm"] = json!(self.close_time);
+        }
+
+        if include_trades && !self.trades.is_empty() {
+            order["trades"] = json!(self.trades);
+        }
+
+        order
+    }
+}
+
+impl SimulatedOrder {
+    fn flags(&self) -> String {
+        let fee_flag = match self.fee_currency {
+            FeeCurrency::Base => "fcib",
+            FeeCurrency::Quote => "fciq",
+        };
+
+        if self.post_only {
+            format!("post,{fee_flag}")
+        } else {
+            fee_flag.to_string()
+        }
+    }
+}
+
+fn status_str(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "pending",
+        OrderStatus::Open => "open",
+        OrderStatus::Closed => "closed",
+        OrderStatus::Canceled => "canceled",
+        OrderStatus::Expired => "expired",
+    }
+}
+
+pub(crate) fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Format a sequence number as a Kraken-style id, e.g. `O00000-00000-000001`.
+pub(crate) fn kraken_id(prefix: char, sequence: u64) -> String {
+    let digits = format!("{:016}", sequence);
+    format!(
+        "{prefix}{}-{}-{}",
+        &digits[..5],
+        &digits[5..10],
+        &digits[10..]
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Account {
+    pub secret: String,
+    pub last_nonce: u64,
+    pub balances: HashMap<String, Decimal>,
+}
+
+/// Balances and pairs that an [OrderBook] holds funds against.
+pub(crate) trait Ledger {
+    fn balance(&self, account: &str, asset: &str) -> Decimal;
+
+    /// The pair with its current fees.
+    fn pair(&self, name: &str) -> Option<SimulatedPair>;
+}
+
+/// Orders of every account, keyed by the sequence they were added in.
+///
+/// This is the bookkeeping shared by the [ExchangeState] matching engine and paper-trading
+/// accounts, which differ only in what orders fill against.
+#[derive(Debug, Default)]
+pub(crate) struct OrderBook {
+    orders: BTreeMap<u64, SimulatedOrder>,
+    sequence: u64,
+}
+
+/// An edit in progress, where the original order is cancelled until the replacement is added.
+#[derive(Debug)]
+pub(crate) struct Edit {
+    sequence: u64,
+    original_id: String,
+    pub replacement: NewOrder,
+}
+
+impl OrderBook {
+    /// Next sequence number, shared by order and trade ids.
+    pub fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    pub fn insert(&mut self, sequence: u64, order: SimulatedOrder) {
+        self.orders.insert(sequence, order);
+    }
+
+    pub fn get(&self, sequence: u64) -> &SimulatedOrder {
+        &self.orders[&sequence]
+    }
+
+    pub fn get_mut(&mut self, sequence: u64) -> &mut SimulatedOrder {
+        self.orders.get_mut(&sequence).expect("order exists")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &SimulatedOrder)> {
+        self.orders
+            .iter()
+            .map(|(sequence, order)| (*sequence, order))
+    }
+
+    /// Balance not held by open orders, where buys hold their remaining cost and sells hold their
+    /// remaining volume, plus the maker fee if it's charged in the held asset.
+    pub fn available(&self, ledger: &impl Ledger, account: &str, asset: &str) -> Decimal {
+        let held: Decimal = self
+            .orders
+            .values()
+            .filter(|order| order.account == account && order.status == OrderStatus::Open)
+            .filter_map(|order| {
+                let pair = ledger.pair(&order.pair)?;
+                let remaining = order.remaining();
+                let cost = remaining * order.price.unwrap_or_default();
+                let held = match order.side {
+                    BuySell::Buy if pair.quote == asset => cost,
+                    BuySell::Sell if pair.base == asset => remaining,
+                    _ => return None,
+                };
+                let fee = match order.fee_currency {
+                    FeeCurrency::Base if pair.base == asset => remaining,
+                    FeeCurrency::Quote if pair.quote == asset => cost,
+                    _ => Decimal::ZERO,
+                } * pair.maker_fee
+                    / Decimal::ONE_HUNDRED;
+
+                Some(held + fee)
+            })
+            .sum();
+
+        ledger.balance(account, asset) - held
+    }
+
+    /// Check the order's account can pay for it, where market buys are costed by walking `levels`.
+    pub fn check_funds(
+        &self,
+        ledger: &impl Ledger,
+        order: &SimulatedOrder,
+        pair: &SimulatedPair,
+        levels: &[(Decimal, Decimal)],
+    ) -> Result<(), &'static str> {
+        let (cost, fee) = match order.price {
+            Some(price) => (order.volume * price, pair.maker_fee.max(pair.taker_fee)),
+            None => (order.fill_cost(levels), pair.taker_fee),
+        };
+        let (mut base, mut quote) = match order.side {
+            BuySell::Buy => (Decimal::ZERO, cost),
+            BuySell::Sell => (order.volume, Decimal::ZERO),
+        };
+        match order.fee_currency {
+            FeeCurrency::Base => base += order.volume * fee / Decimal::ONE_HUNDRED,
+            FeeCurrency::Quote => quote += cost * fee / Decimal::ONE_HUNDRED,
+        }
+
+        if self.available(ledger, &order.account, &pair.base) >= base
+            && self.available(ledger, &order.account, &pair.quote) >= quote
+        {
+            Ok(())
+        } else {
+            Err(INSUFFICIENT_FUNDS)
+        }
+    }
+
+    /// Open orders on the opposite side of the book from `incoming`, by price then time.
+    pub fn resting(&self, incoming: &SimulatedOrder) -> Vec<u64> {
+        let mut resting: Vec<(u64, &SimulatedOrder)> = self
+            .iter()
+            .filter(|(_, order)| {
+                order.status == OrderStatus::Open
+                    && order.pair == incoming.pair
+                    && order.side != incoming.side
+            })
+            .collect();
+
+        resting.sort_by(|(a_seq, a), (b_seq, b)| {
+            let by_price = match incoming.side {
+                BuySell::Buy => a.price.cmp(&b.price),
+                BuySell::Sell => b.price.cmp(&a.price),
+            };
+            by_price.then(a_seq.cmp(b_seq))
+        });
+
+        resting.into_iter().map(|(sequence, _)| sequence).collect()
+    }
+
+    /// Price and remaining volume of the orders `incoming` could trade with, best price first.
+    pub fn resting_levels(&self, incoming: &SimulatedOrder) -> Vec<(Decimal, Decimal)> {
+        self.resting(incoming)
+            .into_iter()
+            .map(|sequence| {
+                let order = self.get(sequence);
+                (order.price.unwrap_or_default(), order.remaining())
+            })
+            .collect()
+    }
+
+    pub fn find_order(&self, account: &str, id: &str) -> Option<u64> {
+        self.orders
+            .iter()
+            .find(|(_, order)| order.account == account && order.id == id)
+            .map(|(sequence, _)| *sequence)
+    }
+
+    fn cancel(&mut self, sequence: u64) {
+        if let Some(order) = self.orders.get_mut(&sequence) {
+            order.status = OrderStatus::Canceled;
+            order.close_time = Some(now());
+        }
+    }
+
+    /// Cancel open orders by txid or userref, returning the number of orders cancelled.
+    pub fn cancel_order(&mut self, account: &str, id: &str) -> Result<i64, &'static str> {
+        let user_ref = id.parse::<i64>().ok();
+
+        let cancelled: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.account == account && order.status == OrderStatus::Open)
+            .filter(|(_, order)| {
+                order.id == id || (user_ref.is_some() && order.user_ref == user_ref)
+            })
+            .map(|(sequence, _)| *sequence)
+            .collect();
+
+        if cancelled.is_empty() {
+            return Err(UNKNOWN_ORDER);
+        }
+
+        for sequence in &cancelled {
+            self.cancel(*sequence);
+        }
+
+        Ok(cancelled.len() as i64)
+    }
+
+    /// Cancel an open order so its funds are released, returning the replacement to add in its
+    /// place.
+    ///
+    /// Like Kraken, the volume executed by the original order counts towards the new volume.
+    pub fn begin_edit(
+        &mut self,
+        account: &str,
+        id: &str,
+        volume: Decimal,
+        price: Option<Decimal>,
+        user_ref: Option<i64>,
+    ) -> Result<Edit, &'static str> {
+        let sequence = self
+            .find_order(account, id)
+            .filter(|sequence| self.orders[sequence].status == OrderStatus::Open)
+            .ok_or(UNKNOWN_ORDER)?;
+        let original = &self.orders[&sequence];
+
+        let edit = Edit {
+            sequence,
+            original_id: original.id.clone(),
+            replacement: NewOrder {
+                pair: original.pair.clone(),
+                side: original.side,
+                order_type: original.order_type,
+                volume: volume - original.volume_executed,
+                price: price.or(original.price),
+                user_ref: user_ref.or(original.user_ref),
+                post_only: original.post_only,
+                fee_currency: original.fee_currency,
+            },
+        };
+
+        self.cancel(sequence);
+        Ok(edit)
+    }
+
+    /// Complete an edit with the result of adding its replacement, re-opening the original order
+    /// if the replacement was only validated or was rejected.
+    pub fn finish_edit(
+        &mut self,
+        edit: Edit,
+        result: Result<(Option<String>, String), &'static str>,
+        validate: bool,
+    ) -> Result<Value, &'static str> {
+        if validate || result.is_err() {
+            let original = self.get_mut(edit.sequence);
+            original.status = OrderStatus::Open;
+            original.close_time = None;
+        }
+
+        let (tx_id, description) = result?;
+
+        Ok(json!({
+            "status": "ok",
+            "txid": tx_id.unwrap_or_default(),
+            "originaltxid": edit.original_id,
+            "volume": format!("{:.8}", edit.replacement.volume),
+            "price": edit.replacement.price.unwrap_or_default().to_string(),
+            "orders_cancelled": if validate { 0 } else { 1 },
+            "descr": {"order": description},
+        }))
+    }
+
+    pub fn open_orders(&self, account: &str, user_ref: Option<i64>, trades: bool) -> Value {
+        let open: Map<String, Value> = self
+            .orders
+            .values()
+            .filter(|order| order.account == account && order.status == OrderStatus::Open)
+            .filter(|order| user_ref.is_none() || order.user_ref == user_ref)
+            .map(|order| (order.id.clone(), order.to_json(trades)))
+            .collect();
+
+        json!({ "open": open })
+    }
+
+    /// Closed and cancelled orders, newest first, paginated by `offset` like Kraken.
+    pub fn closed_orders(
+        &self,
+        account: &str,
+        user_ref: Option<i64>,
+        trades: bool,
+        offset: usize,
+    ) -> Value {
+        let closed: Vec<&SimulatedOrder> = self
+            .orders
+            .values()
+            .rev()
+            .filter(|order| order.account == account && order.status != OrderStatus::Open)
+            .filter(|order| user_ref.is_none() || order.user_ref == user_ref)
+            .collect();
+
+        let page: Map<String, Value> = closed
+            .iter()
+            .skip(offset)
+            .take(CLOSED_ORDERS_PAGE_SIZE)
+            .map(|order| (order.id.clone(), order.to_json(trades)))
+            .collect();
+
+        json!({ "closed": page, "count": closed.len() })
+    }
+}
+
+/// All state of the simulated exchange, shared by every request to the server.
+#[derive(Debug, Default)]
+pub(crate) struct ExchangeState {
+    pub pairs: HashMap<String, SimulatedPair>,
+    pub accounts: HashMap<String, Account>,
+    orders: OrderBook,
+}
+
+impl Ledger for ExchangeState {
+    fn balance(&self, account: &str, asset: &str) -> Decimal {
+        self.accounts
+            .get(account)
+            .and_then(|account| account.balances.get(asset))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn pair(&self, name: &str) -> Option<SimulatedPair> {
+        self.pairs.get(name).cloned()
+    }
+}
+
+impl ExchangeState {
+    pub fn new(pairs: Vec<SimulatedPair>) -> Self {
+        ExchangeState {
+            pairs: pairs
+                .into_iter()
+                .map(|pair| (pair.name.clone(), pair))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn credit(&mut self, account: &str, asset: &str, amount: Decimal) {
+        if let Some(account) = self.accounts.get_mut(account) {
+            *account.balances.entry(asset.to_string()).or_default() += amount;
+        }
+    }
+
+    /// Validate, match and (for limit orders) rest a new order, returning the order's id and
+    /// description.
+    pub fn add_order(
+        &mut self,
+        account: &str,
+        new_order: NewOrder,
+        validate: bool,
+    ) -> Result<(Option<String>, String), &'static str> {
+        let pair = self.pair(&new_order.pair).ok_or(UNKNOWN_PAIR)?;
+        let mut order = SimulatedOrder::new(account, new_order)?;
+
+        let levels = self.orders.resting_levels(&order);
+        self.orders.check_funds(self, &order, &pair, &levels)?;
+
+        if order.post_only
+            && levels
+                .first()
+                .is_some_and(|(price, _)| order.crosses_price(*price))
+        {
+            return Err(POST_ONLY);
+        }
+
+        let description = order.description();
+        if validate {
+            return Ok((None, description));
+        }
+
+        let sequence = self.orders.next_sequence();
+        order.id = kraken_id('O', sequence);
+        let id = order.id.clone();
+
+        self.match_order(&mut order, &pair);
+        order.close_market();
+        self.orders.insert(sequence, order);
+
+        Ok((Some(id), description))
+    }
+
+    /// Fill the incoming order against resting orders until it's filled, no longer crosses the
+    /// book, or would trade with another order from the same account.
+    ///
+    /// Self-trades cancel the remainder of the incoming order, like Kraken's default
+    /// `cancel-newest` self-trade prevention.
+    fn match_order(&mut self, incoming: &mut SimulatedOrder, pair: &SimulatedPair) {
+        while incoming.remaining() > Decimal::ZERO {
+            let Some(&sequence) = self.orders.resting(incoming).first() else {
+                break;
+            };
+
+            let resting = self.orders.get(sequence);
+            if !resting
+                .price
+                .is_some_and(|price| incoming.crosses_price(price))
+            {
+                break;
+            }
+
+            if resting.account == incoming.account {
+                incoming.status = OrderStatus::Canceled;
+                incoming.close_time = Some(now());
+                return;
+            }
+
+            let quantity = incoming.remaining().min(resting.remaining());
+            let price = resting.price.unwrap_or_default();
+            let cost = quantity * price;
+            let maker_fee = resting.fill_fee(quantity, cost, pair.maker_fee);
+            let taker_fee = incoming.fill_fee(quantity, cost, pair.taker_fee);
+            let maker = (
+                resting.account.clone(),
+                resting.settlement(quantity, cost, maker_fee),
+            );
+            let taker = (
+                incoming.account.clone(),
+                incoming.settlement(quantity, cost, taker_fee),
+            );
+
+            for (account, (base, quote)) in [maker, taker] {
+                self.credit(&account, &pair.base, base);
+                self.credit(&account, &pair.quote, quote);
+            }
+
+            let trade_id = kraken_id('T', self.orders.next_sequence());
+            self.orders
+                .get_mut(sequence)
+                .execute(quantity, cost, maker_fee, &trade_id);
+            incoming.execute(quantity, cost, taker_fee, &trade_id);
+        }
+    }
+
+    /// Cancel open orders by txid or userref, returning the number of orders cancelled.
+    pub fn cancel_order(&mut self, account: &str, id: &str) -> Result<i64, &'static str> {
+        self.orders.cancel_order(account, id)
+    }
+
+    /// Replace an open order with a new order for the given volume and price.
+    pub fn edit_order(
+        &mut self,
+        account: &str,
+        id: &str,
+        volume: Decimal,
+        price: Option<Decimal>,
+        user_ref: Option<i64>,
+        validate: bool,
+    ) -> Result<Value, &'static str> {
+        let edit = self
+            .orders
+            .begin_edit(account, id, volume, price, user_ref)?;
+        let result = self.add_order(account, edit.replacement.clone(), validate);
+        self.orders.finish_edit(edit, result, validate)
+    }
+
+    pub fn open_orders(&self, account: &str, user_ref: Option<i64>, trades: bool) -> Value {
+        self.orders.open_orders(account, user_ref, trades)
+    }
+
+    /// Closed and cancelled orders, newest first, paginated by `offset` like Kraken.
+    pub fn closed_orders(
+        &self,
+        account: &str,
+        user_ref: Option<i64>,
+        trades: bool,
+        offset: usize,
+    ) -> Value {
+        self.orders.closed_orders(account, user_ref, trades, offset)
+    }
+
+    pub fn balances(&self, account: &str) -> Option<Value> {
+        let balances: Map<String, Value> = self
+            .accounts
+            .get(account)?
+            .balances
+            .iter()
+            .map(|(asset, balance)| (asset.clone(), json!(format!("{:.8}", balance))))
+            .collect();
+
+        Some(Value::Object(balances))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const MAKER: &str = "maker";
+    const TAKER: &str = "taker";
+
+    fn get_state() -> ExchangeState {
+        let mut state = ExchangeState::new(vec![
+            SimulatedPair::new("XBTUSD", "XXBT", "ZUSD").fees(dec!(0.25), dec!(0.40))
+        ]);
+
+        for key in [MAKER, TAKER] {
+            state.accounts.insert(
+                key.to_string(),
+                Account {
+                    balances: HashMap::from([
+                        ("XXBT".to_string(), dec!(10)),
+                        ("ZUSD".to_string(), dec!(100000)),
+                    ]),
+                    ..Default::default()
+                },
+            );
+        }
+
+        state
+    }
+
+    fn limit(side: BuySell, volume: Decimal, price: Decimal) -> NewOrder {
+        NewOrder {
+            pair: "XBTUSD".to_string(),
+            side,
+            order_type: OrderType::Limit,
+            volume,
+            price: Some(price),
+            user_ref: None,
+            post_only: false,
+            fee_currency: FeeCurrency::Quote,
+        }
+    }
+
+    fn market(side: BuySell, volume: Decimal) -> NewOrder {
+        NewOrder {
+            order_type: OrderType::Market,
+            price: None,
+            ..limit(side, volume, Decimal::ZERO)
+        }
+    }
+
+    #[test]
+    fn test_kraken_id() {
+        assert_eq!("O00000-00000-000001", kraken_id('O', 1));
+        assert_eq!("T00000-00001-234567", kraken_id('T', 1234567));
+    }
+
+    #[test]
+    fn test_price_time_priority() {
+        let mut state = get_state();
+
+        let (first, _) = state
+            .add_order(MAKER, limit(BuySell::Sell, dec!(1), dec!(50100)), false)
+            .unwrap();
+        let (second, _) = state
+            .add_order(MAKER, limit(BuySell::Sell, dec!(1), dec!(50000)), false)
+            .unwrap();
+        let (third, _) = state
+            .add_order(MAKER, limit(BuySell::Sell, dec!(1), dec!(50000)), false)
+            .unwrap();
+
+        state
+            .add_order(TAKER, market(BuySell::Buy, dec!(1.5)), false)
+            .unwrap();
+
+        let open = state.open_orders(MAKER, None, false);
+        let open = open["open"].as_object().unwrap();
+
+        assert!(open.contains_key(first.as_ref().unwrap()));
+        assert!(!open.contains_key(second.as_ref().unwrap()));
+        assert_eq!(
+            json!("0.50000000"),
+            open[third.as_ref().unwrap()]["vol_exec"]
+        );
+    }
+
+    #[test]
+    fn test_fills_settle_balances_and_fees() {
+        let mut state = get_state();
+
+        state
+            .add_order(MAKER, limit(BuySell::Sell, dec!(1), dec!(50000)), false)
+            .unwrap();
+        let (taker_id, _) = state
+            .add_order(TAKER, limit(BuySell::Buy, dec!(1), dec!(50500)), false)
+            .unwrap();
+
+        assert_eq!(dec!(9), state.balance(MAKER, "XXBT"));
+        assert_eq!(dec!(149875), state.balance(MAKER, "ZUSD"));
+        assert_eq!(dec!(11), state.balance(TAKER, "XXBT"));
+        assert_eq!(dec!(49800), state.balance(TAKER, "ZUSD"));
+
+        let closed = state.closed_orders(TAKER, None, true, 0);
+        let order = &closed["closed"][taker_id.unwrap()];
+        assert_eq!(json!("closed"), order["status"]);
+        assert_eq!(json!("50000"), order["price"]);
+        assert_eq!(1, order["trades"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_open_orders_hold_funds() {
+        let mut state = get_state();
+
+        state
+            .add_order(TAKER, limit(BuySell::Buy, dec!(1.5), dec!(60000)), false)
+            .unwrap();
+
+        let result = state.add_order(TAKER, limit(BuySell::Buy, dec!(0.2), dec!(60000)), false);
+        assert_eq!(Err(INSUFFICIENT_FUNDS), result);
+
+        let result = state.add_order(TAKER, limit(BuySell::Sell, dec!(11), dec!(60000)), false);
+        assert_eq!(Err(INSUFFICIENT_FUNDS), result);
+    }
+
+    #[test]
+    fn test_post_only_and_self_trade() {
+        let mut state = get_state();
+
+        state
+            .add_order(MAKER, limit(BuySell::Sell, dec!(1), dec!(50000)), false)
+            .unwrap();
+
+        let post_only = NewOrder {
+            post_only: true,
+            ..limit(BuySell::Buy, dec!(1), dec!(50000))
+        };
+        assert_eq!(Err(POST_ONLY), state.add_order(TAKER, post_only, false));
+
+        let (self_trade, _) = state
+            .add_order(MAKER, limit(BuySell::Buy, dec!(1), dec!(50000)), false)
+            .unwrap();
+        let closed = state.closed_orders(MAKER, None, false, 0);
+        assert_eq!(
+            json!("canceled"),
+            closed["closed"][self_trade.unwrap()]["status"]
+        );
+        assert_eq!(dec!(10), state.balance(MAKER, "XXBT"));
+    }
+
+    #[test]
+    fn test_edit_and_cancel() {
+        let mut state = get_state();
+
+        let (id, _) = state
+            .add_order(MAKER, limit(BuySell::Buy, dec!(1), dec!(40000)), false)
+            .unwrap();
+        let id = id.unwrap();
+
+        let edit = state
+            .edit_order(MAKER, &id, dec!(2), Some(dec!(41000)), None, false)
+            .unwrap();
+        let new_id = edit["txid"].as_str().unwrap();
+
+        assert_eq!(json!(id), edit["originaltxid"]);
+        assert_eq!(Err(UNKNOWN_ORDER), state.cancel_order(MAKER, &id));
+        assert_eq!(Ok(1), state.cancel_order(MAKER, new_id));
+        assert_eq!(
+            json!(2),
+            state.closed_orders(MAKER, None, false, 0)["count"]
+        );
+    }
+}
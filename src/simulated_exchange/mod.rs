@@ -0,0 +1,17 @@
+//! A local, simulated Kraken exchange for integration testing
+//!
+//! [SimulatedExchange] serves a subset of the private REST API over HTTP, backed by a simple
+//! price-time priority matching engine and per-account balances. Responses are shaped like the
+//! real API, so any [KrakenClient] can be pointed at it to test full trading workflows, including
+//! request signing and nonce ordering.
+//!
+//! The same order bookkeeping backs the single account of [PaperTradingKrakenClient], which fills
+//! orders against live market data instead of other accounts.
+//!
+//! [KrakenClient]: crate::clients::kraken_client::KrakenClient
+//! [PaperTradingKrakenClient]: crate::clients::paper_trading_kraken_client::PaperTradingKrakenClient
+//! [SimulatedExchange]: crate::simulated_exchange::server::SimulatedExchange
+
+pub mod matching_engine;
+pub(crate) mod paper_account;
+pub mod server;
@@ -0,0 +1,681 @@
+//! A single simulated account that fills orders against public market data, for paper trading
+use crate::fees::FeeSchedule;
+use crate::response_types::{AccountBalances, BuySell, OrderStatus, TradableAssetPair};
+use crate::simulated_exchange::matching_engine::{
+    kraken_id, now, Ledger, NewOrder, OrderBook, SimulatedOrder, SimulatedPair, POST_ONLY,
+    UNKNOWN_PAIR,
+};
+use crate::wss::v2::base_messages::{ChannelMessage, WssMessage};
+use crate::wss::v2::market_data_messages::{BidAsk, Trade, L2};
+use crate::wss::v2::v2_symbol;
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+pub(crate) const INSUFFICIENT_LIQUIDITY: &str = "EOrder:Insufficient liquidity";
+
+const PAPER_ACCOUNT: &str = "paper";
+
+/// Number of trades returned per page of trade history, matching Kraken.
+const TRADES_PAGE_SIZE: usize = 50;
+
+/// Trades older than this no longer count towards the volume used for fee tiers.
+const FEE_VOLUME_WINDOW_SECS: f64 = 30.0 * 24.0 * 60.0 * 60.0;
+
+/// A [TradableAssetPair] and its v2 websocket symbol.
+#[derive(Debug, Clone)]
+struct PaperPair {
+    symbol: String,
+    details: TradableAssetPair,
+}
+
+impl PaperPair {
+    fn new(pair: &TradableAssetPair) -> Self {
+        PaperPair {
+            symbol: v2_symbol(&pair.ws_name),
+            details: pair.clone(),
+        }
+    }
+
+    fn base(&self) -> &str {
+        &self.details.base
+    }
+
+    fn quote(&self) -> &str {
+        &self.details.quote
+    }
+
+    /// The pair with the fees of the tiers reached by `volume`.
+    fn terms(&self, volume: Decimal) -> SimulatedPair {
+        let schedule = FeeSchedule::new(&self.details, volume);
+        SimulatedPair::new(&self.symbol, self.base(), self.quote())
+            .fees(schedule.maker_fee(), schedule.taker_fee())
+    }
+}
+
+/// Level 2 book for a symbol, as price -> quantity.
+#[derive(Debug, Clone, Default)]
+struct Book {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl Book {
+    fn update(levels: &mut BTreeMap<Decimal, Decimal>, updates: &[BidAsk]) {
+        for level in updates {
+            if level.quantity.is_zero() {
+                levels.remove(&level.price);
+            } else {
+                levels.insert(level.price, level.quantity);
+            }
+        }
+    }
+
+    /// Levels an order on `side` can trade against, best price first.
+    fn opposite(&self, side: BuySell) -> Vec<(Decimal, Decimal)> {
+        match side {
+            BuySell::Buy => self.asks.iter().map(|(p, q)| (*p, *q)).collect(),
+            BuySell::Sell => self.bids.iter().rev().map(|(p, q)| (*p, *q)).collect(),
+        }
+    }
+
+    /// Remove liquidity taken by an order on `side`, until the next book update for the level.
+    fn take(&mut self, side: BuySell, price: Decimal, quantity: Decimal) {
+        let levels = match side {
+            BuySell::Buy => &mut self.asks,
+            BuySell::Sell => &mut self.bids,
+        };
+
+        if let Some(level) = levels.get_mut(&price) {
+            *level -= quantity;
+            if *level <= Decimal::ZERO {
+                levels.remove(&price);
+            }
+        }
+    }
+}
+
+/// Balances, orders and trades of a paper-trading account, and the latest book for each traded
+/// symbol.
+///
+/// Orders that cross the book when added fill immediately as a taker, walking the book's levels.
+/// Resting limit orders fill as a maker at their limit price when a public trade prints at or
+/// through the limit, or when the book moves through it.
+#[derive(Debug, Default)]
+pub(crate) struct PaperAccount {
+    pairs: HashMap<String, PaperPair>,
+    balances: AccountBalances,
+    orders: OrderBook,
+    trades: BTreeMap<u64, (String, Value)>,
+    books: HashMap<String, Book>,
+    /// Time, quote asset and cost of each trade in the fee volume window, oldest first.
+    volume: VecDeque<(f64, String, Decimal)>,
+}
+
+impl Ledger for PaperAccount {
+    fn balance(&self, _account: &str, asset: &str) -> Decimal {
+        self.balances.get(asset).copied().unwrap_or_default()
+    }
+
+    fn pair(&self, name: &str) -> Option<SimulatedPair> {
+        let pair = self.pairs.get(name)?;
+        Some(pair.terms(self.fee_volume(&pair.details.fee_volume_currency)))
+    }
+}
+
+impl PaperAccount {
+    /// Register a pair under its requested name and `altname`, e.g. `XBTUSD`.
+    pub fn add_pair(&mut self, name: &str, pair: &TradableAssetPair) {
+        let paper_pair = PaperPair::new(pair);
+        self.pairs.insert(pair.alt_name.clone(), paper_pair.clone());
+        self.pairs.insert(name.to_string(), paper_pair);
+    }
+
+    pub fn has_pair(&self, name: &str) -> bool {
+        self.pairs.contains_key(name)
+    }
+
+    pub fn deposit(&mut self, asset: &str, amount: Decimal) {
+        *self.balances.entry(asset.to_string()).or_default() += amount;
+    }
+
+    pub fn balances(&self) -> Value {
+        let balances: Map<String, Value> = self
+            .balances
+            .iter()
+            .map(|(asset, balance)| (asset.clone(), json!(format!("{:.8}", balance))))
+            .collect();
+
+        Value::Object(balances)
+    }
+
+    /// Update books and fill orders from public `trade` and `book` messages, ignoring all others.
+    pub fn on_message(&mut self, message: &WssMessage) {
+        match message {
+            WssMessage::Channel(ChannelMessage::Trade(trades)) => {
+                for trade in &trades.data {
+                    self.on_trade(trade);
+                }
+            }
+            WssMessage::Channel(ChannelMessage::Orderbook(book)) => self.on_book(&book.data),
+            _ => {}
+        }
+    }
+
+    fn on_trade(&mut self, trade: &Trade) {
+        for side in [BuySell::Buy, BuySell::Sell] {
+            self.fill_resting(&trade.symbol, side, &[(trade.price, trade.quantity)]);
+        }
+    }
+
+    fn on_book(&mut self, update: &L2) {
+        let (symbol, bids, asks) = match update {
+            L2::Orderbook(snapshot) => {
+                self.books.remove(&snapshot.symbol);
+                (&snapshot.symbol, &snapshot.bids, &snapshot.asks)
+            }
+            L2::Update(update) => (&update.symbol, &update.bids, &update.asks),
+        };
+
+        let book = self.books.entry(symbol.clone()).or_default();
+        Book::update(&mut book.bids, bids);
+        Book::update(&mut book.asks, asks);
+
+        for side in [BuySell::Buy, BuySell::Sell] {
+            let levels = self.books[symbol].opposite(side);
+            let taken = self.fill_resting(symbol, side, &levels);
+
+            if let Some(book) = self.books.get_mut(symbol) {
+                for (price, quantity) in taken {
+                    book.take(side, price, quantity);
+                }
+            }
+        }
+    }
+
+    /// Cost of trades in the last 30 days, in `currency`.
+    ///
+    /// Trades in other quote assets are converted at the mid price of a followed pair from that
+    /// asset to `currency`, and are left out if there isn't one.
+    fn fee_volume(&self, currency: &str) -> Decimal {
+        let cutoff = now() - FEE_VOLUME_WINDOW_SECS;
+
+        self.volume
+            .iter()
+            .filter(|(time, _, _)| *time >= cutoff)
+            .filter_map(|(_, quote, cost)| {
+                if quote == currency {
+                    Some(*cost)
+                } else {
+                    Some(*cost * self.mid_price(quote, currency)?)
+                }
+            })
+            .sum()
+    }
+
+    fn mid_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        let pair = self
+            .pairs
+            .values()
+            .find(|pair| pair.base() == base && pair.quote() == quote)?;
+        let book = self.books.get(&pair.symbol)?;
+        let (bid, _) = book.bids.last_key_value()?;
+        let (ask, _) = book.asks.first_key_value()?;
+
+        Some((bid + ask) / Decimal::TWO)
+    }
+
+    /// Validate and add an order, filling it against the book as a taker, and returning the order's
+    /// id and description.
+    ///
+    /// Market orders are rejected until a book has been received for the pair, and any volume
+    /// the book can't fill is cancelled.
+    pub fn add_order(
+        &mut self,
+        new_order: NewOrder,
+        validate: bool,
+    ) -> Result<(Option<String>, String), &'static str> {
+        let pair = self
+            .pairs
+            .get(&new_order.pair)
+            .cloned()
+            .ok_or(UNKNOWN_PAIR)?;
+
+        let mut order = SimulatedOrder::new(PAPER_ACCOUNT, new_order)?;
+
+        let levels = self
+            .books
+            .get(&pair.symbol)
+            .map(|book| book.opposite(order.side))
+            .unwrap_or_default();
+
+        if order.price.is_none() && levels.is_empty() {
+            return Err(INSUFFICIENT_LIQUIDITY);
+        }
+
+        let terms = self.pair(&order.pair).ok_or(UNKNOWN_PAIR)?;
+        self.orders.check_funds(self, &order, &terms, &levels)?;
+
+        if order.post_only
+            && levels
+                .first()
+                .is_some_and(|(price, _)| order.crosses_price(*price))
+        {
+            return Err(POST_ONLY);
+        }
+
+        let description = order.description();
+        if validate {
+            return Ok((None, description));
+        }
+
+        let sequence = self.orders.next_sequence();
+        order.id = kraken_id('O', sequence);
+        let id = order.id.clone();
+        let side = order.side;
+        self.orders.insert(sequence, order);
+
+        for (price, quantity) in levels {
+            let order = self.orders.get(sequence);
+            if order.remaining().is_zero() || !order.crosses_price(price) {
+                break;
+            }
+
+            let quantity = quantity.min(order.remaining());
+            self.fill(sequence, &pair, price, quantity, false);
+
+            if let Some(book) = self.books.get_mut(&pair.symbol) {
+                book.take(side, price, quantity);
+            }
+        }
+
+        self.orders.get_mut(sequence).close_market();
+
+        Ok((Some(id), description))
+    }
+
+    /// Fill open orders on `side` for the symbol against `levels` (best price first), in
+    /// price-time priority, returning the price and quantity taken from each level.
+    ///
+    /// Orders are filled at their own limit price as the maker, since the liquidity arrived after
+    /// the order was resting.
+    fn fill_resting(
+        &mut self,
+        symbol: &str,
+        side: BuySell,
+        levels: &[(Decimal, Decimal)],
+    ) -> Vec<(Decimal, Decimal)> {
+        let mut resting: Vec<(u64, Decimal)> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.status == OrderStatus::Open && order.side == side)
+            .filter(|(_, order)| {
+                self.pairs
+                    .get(&order.pair)
+                    .is_some_and(|pair| pair.symbol == symbol)
+            })
+            .filter_map(|(sequence, order)| Some((sequence, order.price?)))
+            .collect();
+
+        resting.sort_by(|(a_seq, a_price), (b_seq, b_price)| {
+            let by_price = match side {
+                BuySell::Buy => b_price.cmp(a_price),
+                BuySell::Sell => a_price.cmp(b_price),
+            };
+            by_price.then(a_seq.cmp(b_seq))
+        });
+
+        let mut levels = levels.to_vec();
+        let mut taken = Vec::new();
+
+        for (sequence, limit) in resting {
+            for (price, quantity) in levels.iter_mut() {
+                let order = self.orders.get(sequence);
+                if order.remaining().is_zero() || !order.crosses_price(*price) {
+                    break;
+                }
+                if quantity.is_zero() {
+                    continue;
+                }
+
+                let fill = order.remaining().min(*quantity);
+                let pair = self.pairs[&order.pair].clone();
+                self.fill(sequence, &pair, limit, fill, true);
+
+                *quantity -= fill;
+                taken.push((*price, fill));
+            }
+        }
+
+        taken
+    }
+
+    /// Execute part of an order, settling balances with fees charged in the order's fee currency,
+    /// and recording the trade.
+    fn fill(
+        &mut self,
+        sequence: u64,
+        pair: &PaperPair,
+        price: Decimal,
+        quantity: Decimal,
+        maker: bool,
+    ) {
+        let cost = quantity * price;
+        let terms = pair.terms(self.fee_volume(&pair.details.fee_volume_currency));
+        let fee_percent = if maker {
+            terms.maker_fee
+        } else {
+            terms.taker_fee
+        };
+        let order = self.orders.get(sequence);
+        let side = order.side;
+        let fee = order.fill_fee(quantity, cost, fee_percent);
+
+        let (base, quote) = order.settlement(quantity, cost, fee);
+        self.deposit(pair.base(), base);
+        self.deposit(pair.quote(), quote);
+        self.record_volume(pair.quote(), cost);
+
+        let trade_sequence = self.orders.next_sequence();
+        let trade_id = kraken_id('T', trade_sequence);
+
+        let order = self.orders.get_mut(sequence);
+        order.execute(quantity, cost, fee, &trade_id);
+
+        // shaped like Trade in response_types
+        let trade = json!({
+            "ordertxid": order.id,
+            "postxid": "",
+            "pair": order.pair,
+            "time": now(),
+            "type": side.to_string(),
+            "ordertype": order.order_type.to_string(),
+            "price": price.to_string(),
+            "cost": format!("{:.8}", cost),
+            "fee": format!("{:.8}", fee),
+            "vol": format!("{:.8}", quantity),
+            "margin": "0.00000000",
+            "misc": "",
+            "maker": maker,
+        });
+
+        self.trades.insert(trade_sequence, (trade_id, trade));
+    }
+
+    /// Cancel open orders by txid or userref, returning the number of orders cancelled.
+    pub fn cancel_order(&mut self, id: &str) -> Result<i64, &'static str> {
+        self.orders.cancel_order(PAPER_ACCOUNT, id)
+    }
+
+    /// Replace an open order with a new order for the given volume and price.
+    pub fn edit_order(
+        &mut self,
+        id: &str,
+        volume: Decimal,
+        price: Option<Decimal>,
+        user_ref: Option<i64>,
+        validate: bool,
+    ) -> Result<Value, &'static str> {
+        let edit = self
+            .orders
+            .begin_edit(PAPER_ACCOUNT, id, volume, price, user_ref)?;
+        let result = self.add_order(edit.replacement.clone(), validate);
+        self.orders.finish_edit(edit, result, validate)
+    }
+
+    pub fn open_orders(&self, user_ref: Option<i64>, trades: bool) -> Value {
+        self.orders.open_orders(PAPER_ACCOUNT, user_ref, trades)
+    }
+
+    /// Closed and cancelled orders, newest first, paginated by `offset` like Kraken.
+    pub fn closed_orders(&self, user_ref: Option<i64>, trades: bool, offset: usize) -> Value {
+        self.orders
+            .closed_orders(PAPER_ACCOUNT, user_ref, trades, offset)
+    }
+
+    fn record_volume(&mut self, quote: &str, cost: Decimal) {
+        let time = now();
+        while self
+            .volume
+            .front()
+            .is_some_and(|(traded, _, _)| *traded < time - FEE_VOLUME_WINDOW_SECS)
+        {
+            self.volume.pop_front();
+        }
+
+        self.volume.push_back((time, quote.to_string(), cost));
+    }
+
+    /// Trades, newest first, paginated by `offset` like Kraken.
+    pub fn trades_history(&self, offset: usize) -> Value {
+        let page: Map<String, Value> = self
+            .trades
+            .values()
+            .rev()
+            .skip(offset)
+            .take(TRADES_PAGE_SIZE)
+            .map(|(id, trade)| (id.clone(), trade.clone()))
+            .collect();
+
+        json!({ "trades": page, "count": self.trades.len() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fees::FeeCurrency;
+    use crate::response_types::OrderType;
+    use rust_decimal_macros::dec;
+
+    fn get_pair(ws_name: &str, base: &str, quote: &str) -> PaperPair {
+        let details: TradableAssetPair = serde_json::from_value(json!({
+            "altname": ws_name.replace('/', ""),
+            "wsname": ws_name,
+            "aclass_base": "currency",
+            "base": base,
+            "aclass_quote": "currency",
+            "quote": quote,
+            "lot": "unit",
+            "cost_decimals": 5,
+            "pair_decimals": 1,
+            "lot_decimals": 8,
+            "lot_multiplier": 1,
+            "leverage_buy": [],
+            "leverage_sell": [],
+            "fees": [[0, 0.26], [50000, 0.24]],
+            "fees_maker": [[0, 0.16], [50000, 0.14]],
+            "fee_volume_currency": "ZUSD",
+            "margin_call": 80,
+            "margin_stop": 40,
+            "ordermin": "0.0001",
+            "costmin": "0.5",
+            "tick_size": "0.1",
+            "status": "online",
+        }))
+        .unwrap();
+
+        PaperPair::new(&details)
+    }
+
+    fn get_account() -> PaperAccount {
+        let mut account = PaperAccount::default();
+        account
+            .pairs
+            .insert("XBTUSD".to_string(), get_pair("XBT/USD", "XXBT", "ZUSD"));
+        account.deposit("ZUSD", dec!(100000));
+        account.deposit("XXBT", dec!(1));
+        account
+    }
+
+    fn level(price: Decimal, quantity: Decimal) -> BidAsk {
+        BidAsk { price, quantity }
+    }
+
+    fn set_book(account: &mut PaperAccount, bids: Vec<BidAsk>, asks: Vec<BidAsk>) {
+        let book = account.books.entry("BTC/USD".to_string()).or_default();
+        Book::update(&mut book.bids, &bids);
+        Book::update(&mut book.asks, &asks);
+    }
+
+    fn find_order<'a>(account: &'a PaperAccount, id: &str) -> &'a SimulatedOrder {
+        account
+            .orders
+            .get(account.orders.find_order(PAPER_ACCOUNT, id).unwrap())
+    }
+
+    fn limit(side: BuySell, volume: Decimal, price: Decimal) -> NewOrder {
+        NewOrder {
+            pair: "XBTUSD".to_string(),
+            side,
+            order_type: OrderType::Limit,
+            volume,
+            price: Some(price),
+            user_ref: None,
+            post_only: false,
+            fee_currency: FeeCurrency::for_order(side, None),
+        }
+    }
+
+    #[test]
+    fn test_fee_tiers() {
+        let pair = get_pair("XBT/USD", "XXBT", "ZUSD");
+
+        assert_eq!(dec!(0.26), pair.terms(dec!(0)).taker_fee);
+        assert_eq!(dec!(0.16), pair.terms(dec!(49999)).maker_fee);
+        assert_eq!(dec!(0.24), pair.terms(dec!(50000)).taker_fee);
+
+        let mut taker_only = get_pair("XBT/USD", "XXBT", "ZUSD");
+        taker_only.details.fees_maker.clear();
+        assert_eq!(dec!(0.26), taker_only.terms(dec!(0)).maker_fee);
+    }
+
+    #[test]
+    fn test_fee_volume_is_rolling_and_in_fee_volume_currency() {
+        let mut account = get_account();
+        account
+            .pairs
+            .insert("ETHXBT".to_string(), get_pair("ETH/XBT", "XETH", "XXBT"));
+        set_book(
+            &mut account,
+            vec![level(dec!(49000), dec!(1))],
+            vec![level(dec!(51000), dec!(1))],
+        );
+
+        account.volume.push_back((
+            now() - FEE_VOLUME_WINDOW_SECS - 60.0,
+            "ZUSD".to_string(),
+            dec!(100000),
+        ));
+        account.record_volume("ZUSD", dec!(30000));
+        assert_eq!(1, account.volume.len());
+        assert_eq!(dec!(0.26), account.pair("XBTUSD").unwrap().taker_fee);
+
+        // 0.4 XBT of ETHXBT volume counts as 20000 USD at the XBT/USD mid price
+        account.record_volume("XXBT", dec!(0.4));
+        assert_eq!(dec!(50000), account.fee_volume("ZUSD"));
+        assert_eq!(dec!(0.24), account.pair("XBTUSD").unwrap().taker_fee);
+        assert_eq!(dec!(0.14), account.pair("ETHXBT").unwrap().maker_fee);
+    }
+
+    #[test]
+    fn test_fees_charged_in_order_fee_currency() {
+        let mut account = get_account();
+        set_book(
+            &mut account,
+            vec![level(dec!(49900), dec!(1))],
+            vec![level(dec!(50000), dec!(1))],
+        );
+
+        let buy_fcib = NewOrder {
+            fee_currency: FeeCurrency::Base,
+            ..limit(BuySell::Buy, dec!(0.5), dec!(50000))
+        };
+        account.add_order(buy_fcib, false).unwrap();
+        assert_eq!(dec!(1.4987), account.balance(PAPER_ACCOUNT, "XXBT"));
+        assert_eq!(dec!(75000), account.balance(PAPER_ACCOUNT, "ZUSD"));
+
+        let sell_fciq = NewOrder {
+            fee_currency: FeeCurrency::Quote,
+            ..limit(BuySell::Sell, dec!(0.5), dec!(49900))
+        };
+        account.add_order(sell_fciq, false).unwrap();
+        assert_eq!(dec!(0.9987), account.balance(PAPER_ACCOUNT, "XXBT"));
+        assert_eq!(
+            dec!(75000) + dec!(24950) - dec!(64.87),
+            account.balance(PAPER_ACCOUNT, "ZUSD")
+        );
+    }
+
+    #[test]
+    fn test_crossing_order_walks_book() {
+        let mut account = get_account();
+        set_book(
+            &mut account,
+            vec![],
+            vec![level(dec!(50000), dec!(0.5)), level(dec!(50100), dec!(1))],
+        );
+
+        let (id, _) = account
+            .add_order(limit(BuySell::Buy, dec!(1), dec!(50100)), false)
+            .unwrap();
+
+        let order = find_order(&account, &id.unwrap());
+        assert_eq!(OrderStatus::Closed, order.status);
+        assert_eq!(dec!(50050), order.cost);
+        assert_eq!(2, order.trades.len());
+        assert_eq!(dec!(2), account.balance(PAPER_ACCOUNT, "XXBT"));
+        assert_eq!(
+            vec![(dec!(50100), dec!(0.5))],
+            account.books["BTC/USD"].opposite(BuySell::Buy)
+        );
+    }
+
+    #[test]
+    fn test_resting_order_fills_at_limit_price() {
+        let mut account = get_account();
+
+        account
+            .add_order(limit(BuySell::Sell, dec!(0.9), dec!(51000)), false)
+            .unwrap();
+
+        set_book(&mut account, vec![level(dec!(51500), dec!(0.4))], vec![]);
+        let levels = account.books["BTC/USD"].opposite(BuySell::Sell);
+        let taken = account.fill_resting("BTC/USD", BuySell::Sell, &levels);
+
+        assert_eq!(vec![(dec!(51500), dec!(0.4))], taken);
+        // sells are charged fees in the base asset by default
+        assert_eq!(
+            dec!(0.6) - dec!(0.00064),
+            account.balance(PAPER_ACCOUNT, "XXBT")
+        );
+        assert_eq!(dec!(120400), account.balance(PAPER_ACCOUNT, "ZUSD"));
+
+        let trades = account.trades_history(0);
+        let (_, trade) = trades["trades"].as_object().unwrap().iter().next().unwrap();
+        assert_eq!(json!(true), trade["maker"]);
+        assert_eq!(json!("51000"), trade["price"]);
+    }
+
+    #[test]
+    fn test_market_order_requires_book() {
+        let mut account = get_account();
+        let market = NewOrder {
+            order_type: OrderType::Market,
+            price: None,
+            ..limit(BuySell::Buy, dec!(1), Decimal::ZERO)
+        };
+
+        assert_eq!(
+            Err(INSUFFICIENT_LIQUIDITY),
+            account.add_order(market.clone(), false)
+        );
+
+        set_book(&mut account, vec![], vec![level(dec!(50000), dec!(0.25))]);
+        let (id, _) = account.add_order(market, false).unwrap();
+
+        let order = find_order(&account, &id.unwrap());
+        assert_eq!(OrderStatus::Closed, order.status);
+        assert_eq!(dec!(0.25), order.volume_executed);
+    }
+}
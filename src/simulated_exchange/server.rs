@@ -0,0 +1,330 @@
+//! HTTP server, authentication and request parsing for the simulated exchange
+use crate::clients::kraken_client::endpoints::*;
+use crate::crypto::signatures::generate_signature;
+use crate::fees::FeeCurrency;
+use crate::request_types::OrderFlags;
+use crate::response_types::{AccountBalances, BuySell, OrderFlag, OrderType};
+use crate::simulated_exchange::matching_engine::{Account, ExchangeState, NewOrder, SimulatedPair};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response};
+use hyper_util::rt::TokioIo;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use url::form_urlencoded;
+
+const INVALID_KEY: &str = "EAPI:Invalid key";
+const INVALID_SIGNATURE: &str = "EAPI:Invalid signature";
+const INVALID_NONCE: &str = "EAPI:Invalid nonce";
+const UNKNOWN_METHOD: &str = "EGeneral:Unknown Method";
+
+/// An in-process stand-in for the Kraken REST API, for testing trading workflows end-to-end.
+///
+/// The exchange listens on a random local port, and is shut down when dropped. Point any client at
+/// it with [SimulatedExchange::uri], e.g. `CoreKrakenClient::new_with_url(.., exchange.uri())`.
+///
+/// Supported endpoints are `AddOrder`, `EditOrder`, `CancelOrder`, `OpenOrders`, `ClosedOrders`
+/// and `Balance`, for limit and market orders on the configured [SimulatedPair]s. Orders match with
+/// price-time priority against orders from other accounts, and every request must be signed with
+/// an account's secret and use a strictly increasing nonce.
+///
+/// ```
+/// # use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+/// # use kraken_async_rs::clients::kraken_client::KrakenClient;
+/// # use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+/// # use kraken_async_rs::secrets::secrets_provider::{SecretsProvider, StaticSecretsProvider};
+/// # use kraken_async_rs::simulated_exchange::matching_engine::SimulatedPair;
+/// # use kraken_async_rs::simulated_exchange::server::SimulatedExchange;
+/// # use rust_decimal_macros::dec;
+/// # use std::collections::HashMap;
+/// # use std::sync::Arc;
+/// # use tokio::sync::Mutex;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let exchange = SimulatedExchange::start(vec![SimulatedPair::new("XBTUSD", "XXBT", "ZUSD")])
+///     .await
+///     .unwrap();
+///
+/// let secret = "c2VjcmV0";
+/// exchange
+///     .add_account("key", secret, HashMap::from([("ZUSD".to_string(), dec!(1000))]))
+///     .await;
+///
+/// let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+///     Box::new(Arc::new(Mutex::new(StaticSecretsProvider::new("key", secret))));
+/// let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+///     Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+/// let mut client = CoreKrakenClient::new_with_url(secrets_provider, nonce_provider, exchange.uri());
+///
+/// let balances = client.get_account_balance().await.unwrap().result.unwrap();
+/// assert_eq!(dec!(1000), balances["ZUSD"]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SimulatedExchange {
+    state: Arc<Mutex<ExchangeState>>,
+    address: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl SimulatedExchange {
+    /// Start serving the given pairs on a random local port.
+    pub async fn start(pairs: Vec<SimulatedPair>) -> Result<SimulatedExchange, std::io::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(ExchangeState::new(pairs)));
+
+        let server = tokio::spawn(serve(listener, state.clone()));
+
+        Ok(SimulatedExchange {
+            state,
+            address,
+            server,
+        })
+    }
+
+    /// Base url of the exchange, to be used in place of `KRAKEN_BASE_URL`.
+    pub fn uri(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Add (or replace) an account with the given API key, base64-encoded secret and balances.
+    pub async fn add_account(
+        &self,
+        key: impl Into<String>,
+        secret: impl Into<String>,
+        balances: AccountBalances,
+    ) {
+        let account = Account {
+            secret: secret.into(),
+            last_nonce: 0,
+            balances,
+        };
+
+        self.state.lock().await.accounts.insert(key.into(), account);
+    }
+
+    /// Get the current balances of an account, or `None` if there's no account for the key.
+    pub async fn balances(&self, key: &str) -> Option<AccountBalances> {
+        self.state
+            .lock()
+            .await
+            .accounts
+            .get(key)
+            .map(|account| account.balances.clone())
+    }
+}
+
+impl Drop for SimulatedExchange {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<ExchangeState>>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                warn!("Simulated exchange failed to accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(|request| handle(state.clone(), request));
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Simulated exchange connection failed: {}", err);
+            }
+        });
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<ExchangeState>>,
+    request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = match body.collect().await {
+        Ok(collected) => String::from_utf8_lossy(&collected.to_bytes()).into_owned(),
+        Err(_) => String::new(),
+    };
+
+    let path = parts.uri.path().to_string();
+    let params: HashMap<String, String> = form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect();
+
+    let result = {
+        let mut state = state.lock().await;
+        match authenticate(&mut state, &parts, &path, &body, &params) {
+            Ok(account) => route(&mut state, &parts.method, &path, &account, &params),
+            Err(err) => Err(err.to_string()),
+        }
+    };
+
+    let json = match result {
+        Ok(result) => json!({"error": [], "result": result}),
+        Err(err) => json!({"error": [err]}),
+    };
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(json.to_string())))
+        .expect("static response parts are valid"))
+}
+
+/// Verify the request was signed by a known account with a nonce greater than any previous nonce
+/// for that account, returning the account's key.
+fn authenticate(
+    state: &mut ExchangeState,
+    parts: &hyper::http::request::Parts,
+    path: &str,
+    body: &str,
+    params: &HashMap<String, String>,
+) -> Result<String, &'static str> {
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    let key = header("API-Key").ok_or(INVALID_KEY)?;
+    let account = state.accounts.get_mut(key).ok_or(INVALID_KEY)?;
+
+    let nonce = params
+        .get("nonce")
+        .and_then(|nonce| nonce.parse::<u64>().ok())
+        .ok_or(INVALID_NONCE)?;
+
+    let expected = generate_signature(nonce, &account.secret, path, body.to_string());
+    if header("API-Sign") != Some(expected.signature.as_str()) {
+        return Err(INVALID_SIGNATURE);
+    }
+
+    if nonce <= account.last_nonce {
+        return Err(INVALID_NONCE);
+    }
+    account.last_nonce = nonce;
+
+    Ok(key.to_string())
+}
+
+fn route(
+    state: &mut ExchangeState,
+    method: &Method,
+    path: &str,
+    account: &str,
+    params: &HashMap<String, String>,
+) -> Result<Value, String> {
+    if method != Method::POST {
+        return Err(UNKNOWN_METHOD.to_string());
+    }
+
+    let user_ref = optional::<i64>(params, "userref")?;
+    let trades = optional::<bool>(params, "trades")?.unwrap_or(false);
+    let validate = optional::<bool>(params, "validate")?.unwrap_or(false);
+
+    match path {
+        ACCOUNT_BALANCE_ENDPOINT => Ok(state.balances(account).unwrap_or_default()),
+        OPEN_ORDERS_ENDPOINT => Ok(state.open_orders(account, user_ref, trades)),
+        CLOSED_ORDERS_ENDPOINT => {
+            let offset = optional::<usize>(params, "ofs")?.unwrap_or(0);
+            Ok(state.closed_orders(account, user_ref, trades, offset))
+        }
+        ADD_ORDER_ENDPOINT => {
+            let side = required_enum::<BuySell>(params, "type")?;
+            let order_flags = optional_flags(params, "oflags")?;
+            let order = NewOrder {
+                pair: required(params, "pair")?,
+                side,
+                order_type: required_enum::<OrderType>(params, "ordertype")?,
+                volume: required(params, "volume")?,
+                price: optional::<Decimal>(params, "price")?,
+                user_ref,
+                post_only: order_flags
+                    .as_ref()
+                    .is_some_and(|flags| flags.contains(&OrderFlag::Post)),
+                fee_currency: FeeCurrency::for_order(side, order_flags.as_ref()),
+            };
+
+            let (tx_id, description) = state.add_order(account, order, validate)?;
+            Ok(json!({
+                "txid": tx_id.into_iter().collect::<Vec<String>>(),
+                "descr": {"order": description},
+            }))
+        }
+        EDIT_ORDER_ENDPOINT => {
+            let tx_id: String = required(params, "txid")?;
+            let volume = required(params, "volume")?;
+            let price = optional::<Decimal>(params, "price")?;
+            Ok(state.edit_order(account, &tx_id, volume, price, user_ref, validate)?)
+        }
+        CANCEL_ORDER_ENDPOINT => {
+            let tx_id: String = required(params, "txid")?;
+            let count = state.cancel_order(account, &tx_id)?;
+            Ok(json!({ "count": count }))
+        }
+        _ => Err(UNKNOWN_METHOD.to_string()),
+    }
+}
+
+fn invalid_argument(name: &str) -> String {
+    format!("EGeneral:Invalid arguments:{}", name)
+}
+
+fn optional<T: FromStr>(params: &HashMap<String, String>, name: &str) -> Result<Option<T>, String> {
+    params
+        .get(name)
+        .map(|value| value.parse::<T>().map_err(|_| invalid_argument(name)))
+        .transpose()
+}
+
+fn required<T: FromStr>(params: &HashMap<String, String>, name: &str) -> Result<T, String> {
+    optional(params, name)?.ok_or_else(|| invalid_argument(name))
+}
+
+/// Parse a parameter using the same serde representation as the request types.
+fn optional_flags(
+    params: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<OrderFlags>, String> {
+    params
+        .get(name)
+        .map(|flags| {
+            flags
+                .split(',')
+                .map(|flag| {
+                    serde_json::from_value(Value::String(flag.to_string()))
+                        .map_err(|_| invalid_argument(name))
+                })
+                .collect::<Result<Vec<OrderFlag>, String>>()
+                .map(OrderFlags::new)
+        })
+        .transpose()
+}
+
+fn required_enum<T: DeserializeOwned>(
+    params: &HashMap<String, String>,
+    name: &str,
+) -> Result<T, String> {
+    let value = params.get(name).ok_or_else(|| invalid_argument(name))?;
+    serde_json::from_value(Value::String(value.clone())).map_err(|_| invalid_argument(name))
+}
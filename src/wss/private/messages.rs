@@ -124,6 +124,7 @@ mod tests {
     const OPEN_ORDERS_NEW_ORDER: &str = r#"[[{"O7AIWV-HEBBH-COCEUU":{"avg_price":"0.00000","cost":"0.00000","descr":{"close":null,"leverage":null,"order":"buy 1.00000000 SOL/USD @ limit 25.00000","ordertype":"limit","pair":"SOL/USD","price":"25.00000","price2":"0.00000","type":"buy"},"expiretm":null,"fee":"0.00000","limitprice":"0.00000","misc":"","oflags":"fciq","opentm":"1700220661.985020","refid":null,"starttm":null,"status":"pending","stopprice":"0.00000","timeinforce":"GTC","userref":0,"vol":"1.00000000","vol_exec":"0.00000000"}}],"openOrders",{"sequence":2}]"#;
     const OPEN_ORDERS_OPEN: &str =
         r#"[[{"O7AIWV-HEBBH-COCEUU":{"status":"open","userref":0}}],"openOrders",{"sequence":3}]"#;
+    #[allow(unused)]
     const OPEN_ORDERS_USER_REQUESTED_CANCEL: &str = r#"[[{"O7AIWV-HEBBH-COCEUU":{"lastupdated":"1700220675.012776","status":"canceled","vol_exec":"0.00000000","cost":"0.00000","fee":"0.00000","avg_price":"0.00000","userref":0,"cancel_reason":"User requested"}}],"openOrders",{"sequence":4}]"#;
     const OPEN_ORDERS_TRADE_EXEC: &str = r#"[[{"OX6J4U-3FWTH-NPST2W":{"vol_exec":"0.01000000","cost":"19.67680","fee":"0.03148","avg_price":"1967.68000","userref":0}}],"openOrders",{"sequence":10}]"#;
     const SUBSCRIBE_OWN_TRADES: &str = r#"{"channelName":"ownTrades","event":"subscriptionStatus","reqid":0,"status":"subscribed","subscription":{"name":"ownTrades"}}"#;
@@ -2,7 +2,10 @@
 use crate::wss::errors::WSSError;
 use crate::wss::v2::base_messages::Message;
 use futures_util::SinkExt;
+use serde::ser::Error;
 use serde::{Deserialize, Serialize};
+use serde_json::Error as SerdeError;
+use serde_json::Value;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::pin::Pin;
@@ -17,6 +20,9 @@ use url::Url;
 const WS_KRAKEN: &str = "wss://ws.kraken.com/v2";
 const WS_KRAKEN_AUTH: &str = "wss://ws-auth.kraken.com/v2";
 
+/// Methods that accept a `validate` param, which is forced on by validate-only streams.
+const VALIDATED_METHODS: [&str; 3] = ["add_order", "batch_add", "edit_order"];
+
 type RawStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
 /// A client for connecting to Kraken websockets via the V2 protocol.
@@ -24,6 +30,7 @@ type RawStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 pub struct KrakenWSSClient {
     base_url: String,
     auth_url: String,
+    validate_only: bool,
 }
 
 impl Default for KrakenWSSClient {
@@ -38,6 +45,7 @@ impl KrakenWSSClient {
         KrakenWSSClient {
             base_url: WS_KRAKEN.to_string(),
             auth_url: WS_KRAKEN_AUTH.to_string(),
+            validate_only: false,
         }
     }
 
@@ -45,7 +53,17 @@ impl KrakenWSSClient {
     ///
     /// This is most useful for use with a proxy, or for testing.
     pub fn new_with_urls(base_url: String, auth_url: String) -> KrakenWSSClient {
-        KrakenWSSClient { base_url, auth_url }
+        KrakenWSSClient {
+            base_url,
+            auth_url,
+            validate_only: false,
+        }
+    }
+
+    /// Make all streams connected after this call validate-only, see
+    /// [KrakenMessageStream::set_validate_only].
+    pub fn set_validate_only(&mut self, validate_only: bool) {
+        self.validate_only = validate_only;
     }
 
     /// Connect to the Kraken public websocket channel, returning a [`Result`] containing a
@@ -76,6 +94,7 @@ impl KrakenWSSClient {
 
         Ok(KrakenMessageStream {
             stream: raw_stream,
+            validate_only: self.validate_only,
             phantom: PhantomData,
         })
     }
@@ -99,6 +118,7 @@ where
     T: for<'a> Deserialize<'a>,
 {
     stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    validate_only: bool,
     phantom: PhantomData<T>,
 }
 
@@ -114,14 +134,42 @@ where
     T: for<'a> Deserialize<'a>,
 {
     /// Send an arbitrary serializable message through the stream.
+    ///
+    /// If the stream is validate-only, `validate` is set to true on any `add_order`, `batch_add`
+    /// or `edit_order` message, and these messages fail without being sent if their params don't
+    /// serialize to an object that `validate` can be set on.
     #[tracing::instrument(skip(self))]
     pub async fn send<M>(&mut self, message: &Message<M>) -> Result<(), WSSError>
     where
         M: Serialize + Debug,
     {
+        if self.validate_only && VALIDATED_METHODS.contains(&message.method.as_str()) {
+            let mut params = serde_json::to_value(&message.params)?;
+            let Value::Object(fields) = &mut params else {
+                return Err(WSSError::Serde(SerdeError::custom(format!(
+                    "cannot validate {} params that are not an object",
+                    message.method
+                ))));
+            };
+            fields.insert("validate".to_string(), Value::Bool(true));
+
+            let validated = Message {
+                method: message.method.clone(),
+                params,
+                req_id: message.req_id,
+            };
+            return Self::send_as_str(&mut self.stream, &validated).await;
+        }
+
         Self::send_as_str(&mut self.stream, message).await
     }
 
+    /// Force `validate=true` on every order sent through this stream, so orders are checked by
+    /// Kraken but never placed.
+    pub fn set_validate_only(&mut self, validate_only: bool) {
+        self.validate_only = validate_only;
+    }
+
     #[tracing::instrument(skip(stream))]
     async fn send_as_str<M>(stream: &mut RawStream, message: &Message<M>) -> Result<(), WSSError>
     where
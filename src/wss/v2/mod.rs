@@ -2,5 +2,31 @@ pub mod admin_messages;
 pub mod base_messages;
 pub mod kraken_wss_client;
 pub mod market_data_messages;
+pub mod rate_limited_message_stream;
 pub mod trading_messages;
 pub mod user_data_messages;
+
+/// Convert a REST `wsname` like `XBT/USD` to the symbol used by the v2 websocket API, `BTC/USD`.
+pub(crate) fn v2_symbol(ws_name: &str) -> String {
+    ws_name
+        .split('/')
+        .map(|asset| match asset {
+            "XBT" => "BTC",
+            "XDG" => "DOGE",
+            other => other,
+        })
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_symbol() {
+        assert_eq!("BTC/USD", v2_symbol("XBT/USD"));
+        assert_eq!("DOGE/EUR", v2_symbol("XDG/EUR"));
+        assert_eq!("ETH/BTC", v2_symbol("ETH/XBT"));
+    }
+}
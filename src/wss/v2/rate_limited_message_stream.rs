@@ -0,0 +1,391 @@
+//! A rate-limited [KrakenMessageStream] for trading over the v2 websocket API
+use crate::rate_limiting::token_bucket::RequestPriority;
+use crate::rate_limiting::trading_rate_limits::KrakenTradingRateLimiter;
+use crate::request_types::IntOrString;
+use crate::wss::errors::WSSError;
+use crate::wss::v2::base_messages::{Message, MethodMessage, WssMessage};
+use crate::wss::v2::kraken_wss_client::KrakenMessageStream;
+use crate::wss::v2::trading_messages::{
+    AddOrderParams, AddOrderResult, BatchCancelParams, BatchOrderParams, CancelOrderParams,
+    EditOrderParams,
+};
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::time::Instant;
+use tokio_stream::Stream;
+
+/// How long to wait for the response to an order before forgetting it was sent
+const PENDING_ORDER_TTL: Duration = Duration::from_secs(60);
+
+/// Methods whose responses record placed orders
+const ORDER_METHODS: [&str; 3] = ["add_order", "batch_add", "edit_order"];
+
+/// An order sent and awaiting a response.
+#[derive(Debug)]
+struct PendingOrder {
+    pair: String,
+    /// The user ref of an edited order, which isn't included in the edit's response
+    user_ref: Option<i64>,
+    sent_at: Instant,
+}
+
+/// A [KrakenMessageStream] of [WssMessage]s that applies Kraken's trading rate limits to orders
+/// placed, edited and cancelled over the v2 websocket API.
+///
+/// Orders sent over websockets draw from the same per-pair matching engine counters as REST
+/// orders, so the [KrakenTradingRateLimiter] should be shared with any [RateLimitedKrakenClient]
+/// trading on the same account, e.g. using its `trading_rate_limiter`. Websocket messages name
+/// pairs by their v2 symbol (e.g. `BTC/USD`) rather than their REST name (e.g. `XBTUSD`), so the
+/// limiter also needs an [AssetRegistry](crate::asset_registry::AssetRegistry) to count both
+/// against the same pair, see [KrakenTradingRateLimiter::set_asset_registry].
+///
+/// Like the REST client, cancels are [RequestPriority::Critical] and are served before waiting
+/// orders on the same pair. Sending a trading message waits until it can be made, during which
+/// the stream isn't polled, but messages received in the meantime are buffered by the connection.
+///
+/// Placement times are recorded from the `add_order`, `batch_add` and `edit_order` responses read
+/// from the stream, so cancel and edit penalties stay accurate. Responses are matched to orders by
+/// `req_id`, in the order orders were sent if a `req_id` is reused, and orders without a response
+/// after 60s are forgotten. Orders can be cancelled by order id, client order id or user ref. Every
+/// message read is also passed to
+/// [KrakenTradingRateLimiter::observe_message], so subscribing to executions with `rate_counter`
+/// keeps the limiter in sync with Kraken's counters.
+///
+/// ```no_run
+/// # use kraken_async_rs::rate_limiting::trading_rate_limits::KrakenTradingRateLimiter;
+/// # use kraken_async_rs::response_types::VerificationTier;
+/// # use kraken_async_rs::wss::v2::base_messages::WssMessage;
+/// # use kraken_async_rs::wss::v2::kraken_wss_client::KrakenWSSClient;
+/// # use kraken_async_rs::wss::v2::rate_limited_message_stream::RateLimitedMessageStream;
+/// # async fn example() {
+/// let trading_rate_limiter = KrakenTradingRateLimiter::new(VerificationTier::Intermediate);
+///
+/// let mut client = KrakenWSSClient::new();
+/// let stream = client.connect_auth::<WssMessage>().await.unwrap();
+/// let mut stream = RateLimitedMessageStream::new(stream, trading_rate_limiter);
+/// # }
+/// ```
+///
+/// [RateLimitedKrakenClient]: crate::clients::rate_limited_kraken_client::RateLimitedKrakenClient
+pub struct RateLimitedMessageStream {
+    stream: KrakenMessageStream<WssMessage>,
+    trading_rate_limiter: KrakenTradingRateLimiter,
+    /// Orders sent and awaiting a response by `req_id`, in the order they were sent, since
+    /// callers may reuse a `req_id` for several messages in flight
+    pending_orders: HashMap<i64, VecDeque<PendingOrder>>,
+    /// A received message being observed by the limiter before it's returned
+    observing: Option<BoxFuture<'static, WssMessage>>,
+}
+
+impl RateLimitedMessageStream {
+    /// Wrap a connected stream, applying rate limits from the given limiter.
+    pub fn new(
+        stream: KrakenMessageStream<WssMessage>,
+        trading_rate_limiter: KrakenTradingRateLimiter,
+    ) -> RateLimitedMessageStream {
+        RateLimitedMessageStream {
+            stream,
+            trading_rate_limiter,
+            pending_orders: HashMap::new(),
+            observing: None,
+        }
+    }
+
+    /// Get a clone of the trading rate limiter, which shares its state with this stream.
+    pub fn trading_rate_limiter(&self) -> KrakenTradingRateLimiter {
+        self.trading_rate_limiter.clone()
+    }
+
+    /// Wait for the cost of placing an order on its symbol, then send it.
+    pub async fn add_order(&mut self, message: &Message<AddOrderParams>) -> Result<(), WSSError> {
+        let cost = self
+            .trading_rate_limiter
+            .add_order_params_cost(&message.params);
+        self.trading_rate_limiter
+            .acquire(&cost, RequestPriority::Normal)
+            .await;
+
+        self.send_order(message, message.params.symbol.clone(), None)
+            .await
+    }
+
+    /// Wait for the cost of placing a batch of orders on its symbol, then send it.
+    pub async fn batch_add(&mut self, message: &Message<BatchOrderParams>) -> Result<(), WSSError> {
+        let cost = self
+            .trading_rate_limiter
+            .batch_order_params_cost(&message.params);
+        self.trading_rate_limiter
+            .acquire(&cost, RequestPriority::Normal)
+            .await;
+
+        self.send_order(message, message.params.symbol.clone(), None)
+            .await
+    }
+
+    /// Wait for the cost of editing an order, including penalties for orders edited soon after
+    /// they were placed, then send it.
+    pub async fn edit_order(&mut self, message: &Message<EditOrderParams>) -> Result<(), WSSError> {
+        let cost = self
+            .trading_rate_limiter
+            .edit_order_params_cost(&message.params)
+            .await;
+        self.trading_rate_limiter
+            .acquire(&cost, RequestPriority::Normal)
+            .await;
+
+        // the edited order keeps the original's user ref unless the edit sets a new one
+        let user_ref = match message.params.order_user_ref {
+            Some(user_ref) => Some(user_ref),
+            None => {
+                self.trading_rate_limiter
+                    .order_user_ref(&message.params.order_id)
+                    .await
+            }
+        };
+
+        let pair = cost.pair.unwrap_or_else(|| message.params.symbol.clone());
+        self.send_order(message, pair, user_ref).await
+    }
+
+    /// Wait for the penalties of cancelling each order, then send the cancel.
+    pub async fn cancel_order(
+        &mut self,
+        message: &Message<CancelOrderParams>,
+    ) -> Result<(), WSSError> {
+        let params = &message.params;
+        let orders = params
+            .order_id
+            .iter()
+            .chain(params.client_order_id.iter())
+            .flatten()
+            .cloned()
+            .map(IntOrString::String)
+            .chain(
+                params
+                    .order_user_ref
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .map(IntOrString::Int),
+            );
+
+        for order in orders {
+            self.cancel_rate_limit(&order).await;
+        }
+
+        self.stream.send(message).await
+    }
+
+    /// Wait for the penalties of cancelling each order in the batch, then send it.
+    pub async fn batch_cancel(
+        &mut self,
+        message: &Message<BatchCancelParams>,
+    ) -> Result<(), WSSError> {
+        let params = &message.params;
+        let client_order_ids = params
+            .client_order_id
+            .iter()
+            .flatten()
+            .cloned()
+            .map(IntOrString::String);
+
+        for order in params.orders.iter().cloned().chain(client_order_ids) {
+            self.cancel_rate_limit(&order).await;
+        }
+
+        self.stream.send(message).await
+    }
+
+    /// Send any other message, e.g. a subscription, without rate limiting.
+    ///
+    /// Trading messages sent this way aren't counted, so they should be sent with their own method.
+    pub async fn send<M>(&mut self, message: &Message<M>) -> Result<(), WSSError>
+    where
+        M: Serialize + Debug,
+    {
+        self.stream.send(message).await
+    }
+
+    /// See [KrakenMessageStream::set_validate_only].
+    pub fn set_validate_only(&mut self, validate_only: bool) {
+        self.stream.set_validate_only(validate_only);
+    }
+
+    async fn send_order<M>(
+        &mut self,
+        message: &Message<M>,
+        pair: String,
+        user_ref: Option<i64>,
+    ) -> Result<(), WSSError>
+    where
+        M: Serialize + Debug,
+    {
+        self.stream.send(message).await?;
+
+        // forget orders that were never answered, e.g. if the connection dropped a response
+        let now = Instant::now();
+        self.pending_orders.retain(|_, pending| {
+            pending.retain(|order| now.duration_since(order.sent_at) < PENDING_ORDER_TTL);
+            !pending.is_empty()
+        });
+
+        self.pending_orders
+            .entry(message.req_id)
+            .or_default()
+            .push_back(PendingOrder {
+                pair,
+                user_ref,
+                sent_at: now,
+            });
+
+        Ok(())
+    }
+
+    async fn cancel_rate_limit(&self, order: &IntOrString) {
+        let cost = match order {
+            IntOrString::Int(user_ref) => {
+                self.trading_rate_limiter
+                    .cancel_order_user_ref_cost(user_ref)
+                    .await
+            }
+            IntOrString::String(id) => self.trading_rate_limiter.cancel_order_tx_id_cost(id).await,
+        };
+
+        self.trading_rate_limiter
+            .acquire(&cost, RequestPriority::Critical)
+            .await
+    }
+
+    /// Take the oldest pending order with the `req_id` this message responds to, if any.
+    fn take_pending_order(&mut self, message: &WssMessage) -> Option<PendingOrder> {
+        let req_id = match message {
+            WssMessage::Method(MethodMessage::AddOrder(response)) => response.req_id,
+            WssMessage::Method(MethodMessage::BatchOrder(response)) => response.req_id,
+            WssMessage::Method(MethodMessage::EditOrder(response)) => response.req_id,
+            WssMessage::Error(response) if ORDER_METHODS.contains(&response.method.as_str()) => {
+                response.req_id
+            }
+            _ => return None,
+        };
+
+        let pending = self.pending_orders.get_mut(&req_id)?;
+        let order = pending.pop_front();
+        if pending.is_empty() {
+            self.pending_orders.remove(&req_id);
+        }
+
+        order
+    }
+
+    /// Take the pending order this message responds to, and return a future that records the
+    /// placed orders and observes the message before returning it.
+    fn observe(&mut self, message: WssMessage) -> BoxFuture<'static, WssMessage> {
+        let pending_order = self.take_pending_order(&message);
+        let mut trading_rate_limiter = self.trading_rate_limiter.clone();
+
+        Box::pin(async move {
+            if let Some(PendingOrder { pair, user_ref, .. }) = pending_order {
+                let placement_time = OffsetDateTime::now_utc().unix_timestamp();
+
+                match &message {
+                    WssMessage::Method(MethodMessage::AddOrder(response)) => {
+                        if let Some(result) = &response.result {
+                            notify_add_order(
+                                &mut trading_rate_limiter,
+                                result,
+                                placement_time,
+                                &pair,
+                            )
+                            .await;
+                        }
+                    }
+                    WssMessage::Method(MethodMessage::BatchOrder(response)) => {
+                        for result in response.result.iter().flatten() {
+                            notify_add_order(
+                                &mut trading_rate_limiter,
+                                result,
+                                placement_time,
+                                &pair,
+                            )
+                            .await;
+                        }
+                    }
+                    WssMessage::Method(MethodMessage::EditOrder(response)) => {
+                        // the edited order has a new id and a fresh lifetime
+                        if let Some(result) = &response.result {
+                            trading_rate_limiter
+                                .notify_add_order(
+                                    result.order_id.clone(),
+                                    placement_time,
+                                    user_ref,
+                                    pair.clone(),
+                                )
+                                .await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            trading_rate_limiter.observe_message(&message).await;
+            message
+        })
+    }
+}
+
+/// Record a placed order by its order id, and also by its client order id if it has one, so
+/// cancels by either are charged to its pair.
+async fn notify_add_order(
+    trading_rate_limiter: &mut KrakenTradingRateLimiter,
+    result: &AddOrderResult,
+    placement_time: i64,
+    pair: &str,
+) {
+    trading_rate_limiter
+        .notify_add_order(
+            result.order_id.clone(),
+            placement_time,
+            result.order_user_ref,
+            pair.to_string(),
+        )
+        .await;
+
+    if let Some(client_order_id) = &result.client_order_id {
+        trading_rate_limiter
+            .notify_add_order(
+                client_order_id.clone(),
+                placement_time,
+                None,
+                pair.to_string(),
+            )
+            .await;
+    }
+}
+
+impl Stream for RateLimitedMessageStream {
+    type Item = Result<WssMessage, WSSError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(observing) = self.observing.as_mut() {
+                let message = ready!(observing.as_mut().poll(cx));
+                self.observing = None;
+                return Poll::Ready(Some(Ok(message)));
+            }
+
+            match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                Some(Ok(message)) => {
+                    let observing = self.observe(message);
+                    self.observing = Some(observing);
+                }
+                other => return Poll::Ready(other),
+            }
+        }
+    }
+}
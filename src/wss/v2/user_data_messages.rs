@@ -17,7 +17,7 @@ pub enum ExecutionResponseType {
     Update,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum MakerTaker {
     #[serde(rename = "m")]
     Maker,
@@ -126,6 +126,7 @@ pub struct ExecutionSubscription {
     pub channel: String,
     pub token: Token,
     pub snapshot_trades: Option<bool>,
+    #[serde(rename = "ratecounter")]
     pub rate_counter: Option<bool>,
     pub snapshot: Option<bool>,
 }
@@ -257,6 +258,9 @@ pub struct ExecutionResult {
     pub order_user_ref: Option<i64>,
     pub post_only: Option<bool>,
     pub position_status: Option<PositionStatusV2>,
+    /// Kraken's trading rate counter for the pair, if the subscription set `rate_counter`
+    #[serde(rename = "ratecount")]
+    pub rate_count: Option<Decimal>,
     pub reduce_only: Option<bool>,
     pub side: Option<BuySell>,
     pub symbol: Option<String>,
@@ -367,6 +371,7 @@ mod tests {
             order_user_ref: None,
             post_only: None,
             position_status: None,
+            rate_count: None,
             reduce_only: None,
             side: Some(BuySell::Buy),
             symbol: Some("KAR/USD".to_string()),
@@ -415,6 +420,7 @@ mod tests {
             order_user_ref: Some(0),
             post_only: None,
             position_status: None,
+            rate_count: None,
             reduce_only: None,
             side: None,
             symbol: None,
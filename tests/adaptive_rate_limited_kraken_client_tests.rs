@@ -0,0 +1,303 @@
+mod resources;
+
+use crate::resources::kraken_responses::account_response_json::get_account_balance_json;
+use crate::resources::kraken_responses::trading_response_json::{
+    get_add_order_json, get_cancel_order_json,
+};
+use crate::resources::test_fixtures::get_mock_client;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::{ClientError, KrakenError};
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::clients::rate_limited_kraken_client::RateLimitedKrakenClient;
+use kraken_async_rs::rate_limiting::adaptive_rate_limits::{CooldownPolicy, RateLimiterKind};
+use kraken_async_rs::request_types::{
+    AddOrderRequest, CancelOrderRequest, IntOrString, OHLCRequest,
+};
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::time::Instant;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn get_adaptive_client(mock_server: &MockServer) -> RateLimitedKrakenClient<CoreKrakenClient> {
+    let mut client: RateLimitedKrakenClient<CoreKrakenClient> = get_mock_client(mock_server);
+
+    client.set_cooldown_policy(CooldownPolicy {
+        public: Duration::from_millis(300),
+        private: Duration::from_millis(300),
+        trading: Duration::from_millis(300),
+        pair: Duration::from_millis(300),
+    });
+
+    client
+}
+
+#[tokio::test]
+async fn test_private_rate_limit_error_pauses_private_limiter() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_adaptive_client(&mock_server);
+    let mut events = client.subscribe_rate_limit_events();
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EAPI:Rate limit exceeded"]})),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_account_balance_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.get_account_balance().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::RateLimitExceeded(_)))
+    ));
+
+    let event = events.try_recv().unwrap();
+    assert_eq!(RateLimiterKind::Private, event.limiter);
+    assert_eq!(
+        KrakenError::RateLimitExceeded("EAPI:Rate limit exceeded".to_string()),
+        event.error
+    );
+    assert_eq!(Duration::from_millis(300), event.cooldown);
+
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Private)
+        .await
+        .is_some());
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Trading)
+        .await
+        .is_none());
+
+    let start = Instant::now();
+    let resp = client.get_account_balance().await;
+
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    assert!(resp.unwrap().result.is_some());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_trading_rate_limit_error_pauses_only_order_pair() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_adaptive_client(&mock_server);
+    let mut events = client.subscribe_rate_limit_events();
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EOrder:Rate limit exceeded"]})),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_json()))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let request =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5.0), "USDCUSD".into())
+            .price(dec!(0.90))
+            .build();
+
+    let resp = client.add_order(&request).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::TradingRateLimitExceeded(
+            _
+        )))
+    ));
+
+    let event = events.try_recv().unwrap();
+    assert_eq!(
+        RateLimiterKind::TradingPair("USDCUSD".to_string()),
+        event.limiter
+    );
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Private)
+        .await
+        .is_none());
+
+    // orders on other pairs aren't paused
+    let other_pair =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5.0), "XBTUSD".into())
+            .price(dec!(90000))
+            .build();
+
+    let start = Instant::now();
+    let resp = client.add_order(&other_pair).await;
+
+    assert!(start.elapsed() < Duration::from_millis(250));
+    assert!(resp.unwrap().result.is_some());
+    assert!(!client
+        .trading_budget("USDCUSD", 100)
+        .await
+        .time_until_affordable
+        .is_zero());
+    assert!(client
+        .trading_budget("XBTUSD", 100)
+        .await
+        .time_until_affordable
+        .is_zero());
+
+    let start = Instant::now();
+    let resp = client.add_order(&request).await;
+
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    assert!(resp.unwrap().result.is_some());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_pair_rate_limit_error_pauses_pair_limiter() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_adaptive_client(&mock_server);
+    let mut events = client.subscribe_rate_limit_events();
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/OHLC"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EAPI:Rate limit exceeded"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = OHLCRequest::builder("XETHZUSD".to_string()).build();
+    let _ = client.get_ohlc(&request).await;
+
+    let event = events.try_recv().unwrap();
+    assert_eq!(RateLimiterKind::Pair("XETHZUSD".to_string()), event.limiter);
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Pair("XETHZUSD".to_string()))
+        .await
+        .is_some());
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Pair("XBTUSD".to_string()))
+        .await
+        .is_none());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_other_errors_do_not_emit_events() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_adaptive_client(&mock_server);
+    let mut events = client.subscribe_rate_limit_events();
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EGeneral:Permission denied"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let _ = client.get_account_balance().await;
+
+    assert_eq!(TryRecvError::Empty, events.try_recv().unwrap_err());
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Private)
+        .await
+        .is_none());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_cancels_skip_trading_cooldown() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_adaptive_client(&mock_server);
+    let mut events = client.subscribe_rate_limit_events();
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_json()))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EOrder:Rate limit exceeded"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/CancelOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_cancel_order_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/CancelAll"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EAPI:Rate limit exceeded"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5.0), "USDCUSD".into())
+            .price(dec!(0.90))
+            .build();
+
+    client.add_order(&request).await.unwrap();
+    let _ = client.add_order(&request).await;
+    let event = events.try_recv().unwrap();
+    assert_eq!(
+        RateLimiterKind::TradingPair("USDCUSD".to_string()),
+        event.limiter
+    );
+
+    // the order's pair is paused, but cancelling it is critical and goes ahead immediately
+    let start = Instant::now();
+    let cancel =
+        CancelOrderRequest::builder(IntOrString::String("AKB9L1-XC5U3-CYCTO1".into())).build();
+    let resp = client.cancel_order(&cancel).await;
+
+    assert!(start.elapsed() < Duration::from_millis(250));
+    assert!(resp.unwrap().result.is_some());
+
+    // cancelling all orders is rate limited and observed like other private calls
+    let resp = client.cancel_all_orders().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::RateLimitExceeded(_)))
+    ));
+    assert_eq!(RateLimiterKind::Private, events.try_recv().unwrap().limiter);
+    assert!(client
+        .remaining_cooldown(&RateLimiterKind::Private)
+        .await
+        .is_some());
+    mock_server.verify().await;
+}
@@ -0,0 +1,168 @@
+mod resources;
+
+use crate::resources::kraken_responses::public_response_json::{
+    get_asset_info_json, get_tradable_asset_pairs_json,
+};
+use crate::resources::test_fixtures::get_mock_client;
+use kraken_async_rs::asset_registry::AssetRegistry;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::rate_limiting::trading_rate_limits::KrakenTradingRateLimiter;
+use kraken_async_rs::request_types::AddOrderRequest;
+use kraken_async_rs::response_types::{
+    AssetInfo, BuySell, OrderType, TradableAssetPair, VerificationTier,
+};
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::time::Duration;
+use time::OffsetDateTime;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mount_assets_and_pairs(mock_server: &MockServer, expected: u64) {
+    Mock::given(method("GET"))
+        .and(path("/0/public/Assets"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_asset_info_json()))
+        .expect(expected)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/AssetPairs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_tradable_asset_pairs_json()))
+        .expect(expected)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_resolve_names() {
+    let mock_server = MockServer::start().await;
+    mount_assets_and_pairs(&mock_server, 1).await;
+
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+    let mut registry = AssetRegistry::new(Duration::from_secs(3600));
+    registry.refresh(&mut client).await.unwrap();
+
+    for name in [
+        "XXBTZUSD", "XBTUSD", "XBT/USD", "BTC/USD", "BTCUSD", "btc/usd",
+    ] {
+        let pair = registry.pair(name).unwrap();
+        assert_eq!("XXBTZUSD", pair.key);
+        assert_eq!("BTC/USD", pair.symbol);
+        assert_eq!("BTCUSD", pair.ticker);
+        assert_eq!(1, pair.details.pair_decimals);
+    }
+
+    let eth = registry.pair("ETHBTC").unwrap();
+    assert_eq!("XETHXXBT", eth.key);
+    assert_eq!("ETH/XBT", eth.details.ws_name);
+
+    for name in ["XXBT", "XBT", "BTC"] {
+        let asset = registry.asset(name).unwrap();
+        assert_eq!("XXBT", asset.key);
+        assert_eq!("BTC", asset.symbol);
+        assert_eq!("currency", asset.info.asset_class);
+        assert_eq!(10, asset.info.decimals);
+    }
+
+    assert_eq!("XXBT", registry.base_asset("BTCUSD").unwrap().key);
+    assert_eq!("ZUSD", registry.quote_asset("XBT/USD").unwrap().key);
+    assert_eq!(3, registry.pairs().count());
+    assert_eq!(3, registry.assets().count());
+
+    assert!(registry.pair("ETHUSD").is_none());
+    assert!(registry.asset("ETH").is_none());
+    assert!(registry.base_asset("ETH/BTC").is_none());
+
+    mock_server.verify().await;
+}
+
+#[test]
+fn test_name_collisions() {
+    let assets: HashMap<String, AssetInfo> =
+        serde_json::from_value(get_asset_info_json()["result"].clone()).unwrap();
+    let mut pairs: HashMap<String, TradableAssetPair> =
+        serde_json::from_value(get_tradable_asset_pairs_json()["result"].clone()).unwrap();
+
+    // a pair keyed by another pair's altname, with no websocket name
+    let mut keyed_by_altname = pairs["XXBTZUSD"].clone();
+    keyed_by_altname.ws_name = String::new();
+    pairs.insert("XBTUSD".to_string(), keyed_by_altname);
+
+    // a pair sharing another pair's websocket name
+    let mut shared_ws_name = pairs["XXBTZUSD"].clone();
+    shared_ws_name.alt_name = "XBTUSD.Z".to_string();
+    pairs.insert("ZXBTUSD".to_string(), shared_ws_name);
+
+    let mut registry = AssetRegistry::new(Duration::from_secs(3600));
+    registry.update(assets, pairs);
+
+    // REST keys take precedence over altnames
+    assert_eq!("XBTUSD", registry.pair("XBTUSD").unwrap().key);
+    assert_eq!("XXBTZUSD", registry.pair("XXBTZUSD").unwrap().key);
+
+    // names of the same kind resolve to the first REST key
+    for name in ["XBT/USD", "BTC/USD", "BTCUSD"] {
+        assert_eq!("XXBTZUSD", registry.pair(name).unwrap().key);
+    }
+    assert_eq!("ZXBTUSD", registry.pair("XBTUSD.Z").unwrap().key);
+
+    // empty names are never indexed
+    assert!(registry.pair("").is_none());
+    assert!(registry.asset("").is_none());
+}
+
+#[tokio::test]
+async fn test_refresh_if_stale() {
+    let mock_server = MockServer::start().await;
+    mount_assets_and_pairs(&mock_server, 2).await;
+
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+
+    let mut registry = AssetRegistry::new(Duration::from_secs(3600));
+    assert!(registry.is_stale());
+    assert!(registry.refresh_if_stale(&mut client).await.unwrap());
+    assert!(!registry.refresh_if_stale(&mut client).await.unwrap());
+
+    let mut always_stale = AssetRegistry::new(Duration::ZERO);
+    assert!(always_stale.refresh_if_stale(&mut client).await.unwrap());
+    assert!(always_stale.is_stale());
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_registry_keys_trading_rate_limiter() {
+    let mock_server = MockServer::start().await;
+    mount_assets_and_pairs(&mock_server, 1).await;
+
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+    let mut registry = AssetRegistry::new(Duration::from_secs(3600));
+    registry.refresh(&mut client).await.unwrap();
+
+    let mut limiter = KrakenTradingRateLimiter::new(VerificationTier::Intermediate);
+    let request =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(1), "XBTUSD".into()).build();
+
+    // the order placed before the registry was set moves to the pair's REST key
+    limiter.add_order(&request).await;
+    limiter.set_asset_registry(registry).await;
+    limiter.add_order(&request).await;
+    assert_eq!(12300, limiter.budget("BTC/USD", 100).await.remaining_tokens);
+
+    // executions report v2 symbols, which correct the same counter as REST orders
+    limiter
+        .observe_rate_count("BTC/USD", dec!(10), OffsetDateTime::now_utc())
+        .await;
+    assert_eq!(
+        11500,
+        limiter.budget("XXBTZUSD", 100).await.remaining_tokens
+    );
+
+    // unknown pairs are keyed by the name given
+    limiter
+        .observe_rate_count("ETH/USD", dec!(1), OffsetDateTime::now_utc())
+        .await;
+    assert_eq!(12400, limiter.budget("ETH/USD", 100).await.remaining_tokens);
+    assert_eq!(12500, limiter.budget("ETHUSD", 100).await.remaining_tokens);
+}
@@ -1,12 +1,24 @@
 mod resources;
 
+use crate::resources::kraken_responses::account_response_json::get_account_balance_json;
 use crate::resources::kraken_responses::public_response_json::get_server_time_json;
 use crate::resources::test_auth::get_null_secrets_provider;
+use crate::resources::test_fixtures::get_mock_client;
+use futures_util::future::BoxFuture;
 use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::{ClientError, KrakenError};
 use kraken_async_rs::clients::kraken_client::endpoints::KRAKEN_BASE_URL;
 use kraken_async_rs::clients::kraken_client::KrakenClient;
-use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::crypto::nonce_provider::{
+    IncreasingNonceProvider, NonceProvider, ServerTimeNonceProvider,
+};
+use kraken_async_rs::crypto::request_signer::{RequestSignature, RequestSigner, SigningError};
+use kraken_async_rs::request_types::RetrieveExportReportRequest;
+use kraken_async_rs::secrets::secrets_provider::{AsyncSecretsProvider, Secrets, SecretsError};
+use secrecy::Secret;
+use serde_json::json;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -54,3 +66,331 @@ async fn client_user_agent() {
     let _resp = client.get_server_time().await;
     mock_server.verify().await;
 }
+
+fn invalid_nonce_response() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({"error": ["EAPI:Invalid nonce"]}))
+}
+
+#[tokio::test]
+async fn client_retries_invalid_nonce() {
+    let mock_server = MockServer::start().await;
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(invalid_nonce_response())
+        .up_to_n_times(1)
+        .with_priority(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_account_balance_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let balances = client.get_account_balance().await.unwrap();
+    assert!(balances.result.is_some());
+    mock_server.verify().await;
+
+    let bodies: Vec<String> = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| String::from_utf8(request.body.clone()).unwrap())
+        .collect();
+    assert_eq!(2, bodies.len());
+    assert_ne!(bodies[0], bodies[1]);
+}
+
+#[tokio::test]
+async fn client_returns_repeated_invalid_nonce() {
+    let mock_server = MockServer::start().await;
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(invalid_nonce_response())
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let result = client.get_account_balance().await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Kraken(KrakenError::InvalidNonce(_)))
+    ));
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn client_retries_invalid_nonce_for_binary_response() {
+    let mock_server = MockServer::start().await;
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+    let report = vec![b'P', b'K', 3, 4];
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RetrieveExport"))
+        .respond_with(invalid_nonce_response())
+        .up_to_n_times(1)
+        .with_priority(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RetrieveExport"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(report.clone()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = RetrieveExportReportRequest::builder("KQMO".to_string()).build();
+    assert_eq!(
+        report,
+        client.retrieve_export_report(&request).await.unwrap()
+    );
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn client_returns_error_body_for_binary_response() {
+    let mock_server = MockServer::start().await;
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RetrieveExport"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"error": ["EQuery:Unknown export"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = RetrieveExportReportRequest::builder("KQMO".to_string()).build();
+    let result = client.retrieve_export_report(&request).await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Kraken(KrakenError::Unknown(raw))) if raw == "EQuery:Unknown export"
+    ));
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn server_time_nonce_provider_calibrates() {
+    let mock_server = MockServer::start().await;
+    let mut client: CoreKrakenClient = get_mock_client(&mock_server);
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let server_time = now.as_secs() + 3600;
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "error": [],
+            "result": {"unixtime": server_time, "rfc1123": "Thu, 06 Jul 23 18:50:48 +0000"}
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let mut provider = ServerTimeNonceProvider::new();
+    provider.calibrate(&mut client).await.unwrap();
+
+    let offset = provider.offset();
+    assert!((3_598_000..=3_602_000).contains(&offset), "{offset}");
+    assert!(provider.get_nonce() >= (server_time - 2) * 1000);
+
+    // an hour of skew is still detected with a wider nonce window
+    let mut windowed = ServerTimeNonceProvider::new().nonce_window(Duration::from_secs(10));
+    windowed.calibrate(&mut client).await.unwrap();
+    assert!(windowed.offset() > 0);
+
+    mock_server.verify().await;
+}
+
+/// Stands in for a remote secret store, returning `key` or failing if there is none.
+#[derive(Debug)]
+struct RemoteSecretsProvider {
+    key: Option<&'static str>,
+}
+
+impl AsyncSecretsProvider for RemoteSecretsProvider {
+    fn get_secrets(&mut self) -> BoxFuture<'_, Result<Secrets, SecretsError>> {
+        Box::pin(async move {
+            tokio::task::yield_now().await;
+            let key = self.key.ok_or(SecretsError::NotFound(
+                "remote store unavailable".to_string(),
+            ))?;
+
+            Ok(Secrets {
+                key: Secret::new(key.to_string()),
+                secret: Secret::new("dGVzdC1zZWNyZXQ=".to_string()),
+            })
+        })
+    }
+}
+
+fn remote_secrets(key: Option<&'static str>) -> Box<Arc<Mutex<dyn AsyncSecretsProvider>>> {
+    Box::new(Arc::new(Mutex::new(RemoteSecretsProvider { key })))
+}
+
+fn new_nonce_provider() -> Box<Arc<Mutex<dyn NonceProvider>>> {
+    Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())))
+}
+
+async fn mount_balance_for_key(mock_server: &MockServer, key: &str, expected: u64) {
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .and(header("API-Key", key))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(get_account_balance_json())
+                .set_delay(Duration::from_millis(200)),
+        )
+        .expect(expected)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn client_uses_async_secrets_provider() {
+    let mock_server = MockServer::start().await;
+    mount_balance_for_key(&mock_server, "remote-key", 1).await;
+
+    let mut client = CoreKrakenClient::builder_with_async_secrets(
+        remote_secrets(Some("remote-key")),
+        new_nonce_provider(),
+    )
+    .url(mock_server.uri())
+    .build();
+    assert!(client.get_account_balance().await.is_ok());
+
+    let mut unavailable =
+        CoreKrakenClient::builder_with_async_secrets(remote_secrets(None), new_nonce_provider())
+            .url(mock_server.uri())
+            .build();
+    let result = unavailable.get_account_balance().await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Secrets(SecretsError::NotFound(_)))
+    ));
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn client_rotates_key() {
+    let mock_server = MockServer::start().await;
+    mount_balance_for_key(&mock_server, "old-key", 2).await;
+    mount_balance_for_key(&mock_server, "new-key", 2).await;
+
+    let mut client = CoreKrakenClient::builder_with_async_secrets(
+        remote_secrets(Some("old-key")),
+        new_nonce_provider(),
+    )
+    .url(mock_server.uri())
+    .build();
+    let rotator = client.clone();
+    assert!(client.get_account_balance().await.is_ok());
+
+    // a request signed before rotating finishes with the old key
+    let mut in_flight = client.clone();
+    let request = tokio::spawn(async move { in_flight.get_account_balance().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    rotator
+        .rotate_key(remote_secrets(Some("new-key")), new_nonce_provider())
+        .await
+        .unwrap();
+    assert!(request.await.unwrap().is_ok());
+
+    // an unavailable key is never switched to
+    let failed = rotator
+        .rotate_key(remote_secrets(None), new_nonce_provider())
+        .await;
+    assert!(matches!(failed, Err(SecretsError::NotFound(_))));
+
+    assert!(client.get_account_balance().await.is_ok());
+    assert!(rotator.clone().get_account_balance().await.is_ok());
+
+    mock_server.verify().await;
+}
+
+/// Stands in for a signing daemon, recording what it was asked to sign.
+#[derive(Debug, Default)]
+struct DaemonSigner {
+    available: bool,
+    signed: std::sync::Mutex<Vec<(String, u64, String)>>,
+}
+
+impl RequestSigner for DaemonSigner {
+    fn sign<'a>(
+        &'a self,
+        path: &'a str,
+        nonce: u64,
+        encoded_data: &'a str,
+    ) -> BoxFuture<'a, Result<RequestSignature, SigningError>> {
+        Box::pin(async move {
+            if !self.available {
+                return Err(SigningError::Signer("daemon unavailable".to_string()));
+            }
+
+            self.signed
+                .lock()
+                .unwrap()
+                .push((path.to_string(), nonce, encoded_data.to_string()));
+
+            Ok(RequestSignature {
+                api_key: Secret::new("daemon-key".to_string()),
+                api_sign: format!("signed-{}", nonce),
+            })
+        })
+    }
+}
+
+#[tokio::test]
+async fn client_uses_request_signer() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .and(header("API-Key", "daemon-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_account_balance_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let signer = Arc::new(DaemonSigner {
+        available: true,
+        ..Default::default()
+    });
+    let mut client = CoreKrakenClient::builder_with_signer(signer.clone(), new_nonce_provider())
+        .url(mock_server.uri())
+        .build();
+
+    assert!(client.get_account_balance().await.is_ok());
+
+    let (path, nonce, body) = signer.signed.lock().unwrap().pop().unwrap();
+    assert_eq!("/0/private/Balance", path);
+    assert_eq!(format!("nonce={}", nonce), body);
+
+    let request = &mock_server.received_requests().await.unwrap()[0];
+    assert_eq!(
+        format!("signed-{}", nonce),
+        request.headers.get("API-Sign").unwrap().to_str().unwrap()
+    );
+
+    client.rotate_signer(Arc::new(DaemonSigner::default()), new_nonce_provider());
+    let result = client.get_account_balance().await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Signing(SigningError::Signer(_)))
+    ));
+
+    mock_server.verify().await;
+}
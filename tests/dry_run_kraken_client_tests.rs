@@ -0,0 +1,123 @@
+mod resources;
+
+use crate::resources::kraken_responses::trading_response_json::{
+    get_add_order_batch_json, get_add_order_json, get_edit_order_json,
+};
+use crate::resources::test_fixtures::get_mock_client;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::dry_run_kraken_client::DryRunKrakenClient;
+use kraken_async_rs::clients::errors::ClientError;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::request_types::{
+    AccountTransferRequest, AddBatchedOrderRequest, AddOrderRequest, AllocateEarnFundsRequest,
+    BatchedOrderRequest, CreateSubAccountRequest, EditOrderRequest, WalletTransferRequest,
+    WithdrawFundsRequest,
+};
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test_orders_are_validated() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .and(body_string_contains("validate=true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrderBatch"))
+        .and(body_partial_json(json!({"validate": true})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_batch_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/EditOrder"))
+        .and(body_string_contains("validate=true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_edit_order_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut client: DryRunKrakenClient<CoreKrakenClient> = get_mock_client(&mock_server);
+
+    let add_order =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5), "USDCUSD".into())
+            .price(dec!(0.9))
+            .validate(false)
+            .build();
+    assert!(client.add_order(&add_order).await.is_ok());
+
+    let batch_order = BatchedOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5))
+        .price(dec!(0.9))
+        .build();
+    let add_order_batch =
+        AddBatchedOrderRequest::builder(vec![batch_order], "USDCUSD".into()).build();
+    assert!(client.add_order_batch(&add_order_batch).await.is_ok());
+
+    let edit_order =
+        EditOrderRequest::builder("7BD466-BKZVM-FT2E2L".into(), dec!(5.1), "USDCUSD".into())
+            .price(dec!(0.89))
+            .build();
+    assert!(client.edit_order(&edit_order).await.is_ok());
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_funds_movements_are_refused() {
+    let mock_server = MockServer::start().await;
+    let mut client: DryRunKrakenClient<CoreKrakenClient> = get_mock_client(&mock_server);
+
+    let withdraw = WithdrawFundsRequest::builder("XBT".into(), "key".into(), dec!(1)).build();
+    let resp = client.withdraw_funds(&withdraw).await;
+    assert!(matches!(resp, Err(ClientError::DryRun("withdraw_funds"))));
+
+    let wallet_transfer = WalletTransferRequest::builder(
+        "XBT".into(),
+        "Futures Wallet".into(),
+        "Spot Wallet".into(),
+        dec!(1),
+    )
+    .build();
+    let resp = client.request_wallet_transfer(&wallet_transfer).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::DryRun("request_wallet_transfer"))
+    ));
+
+    let account_transfer =
+        AccountTransferRequest::builder("XBT".into(), dec!(1), "from".into(), "to".into()).build();
+    let resp = client.account_transfer(&account_transfer).await;
+    assert!(matches!(resp, Err(ClientError::DryRun("account_transfer"))));
+
+    let allocation = AllocateEarnFundsRequest::builder(dec!(1), "strategy".into()).build();
+    let resp = client.allocate_earn_funds(&allocation).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::DryRun("allocate_earn_funds"))
+    ));
+    let resp = client.deallocate_earn_funds(&allocation).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::DryRun("deallocate_earn_funds"))
+    ));
+
+    let sub_account =
+        CreateSubAccountRequest::builder("user".into(), "user@example.com".into()).build();
+    let resp = client.create_sub_account(&sub_account).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::DryRun("create_sub_account"))
+    ));
+
+    assert!(mock_server.received_requests().await.unwrap().is_empty());
+}
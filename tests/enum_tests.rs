@@ -186,27 +186,78 @@ fn test_cursor_display() {
 
 #[test]
 fn test_kraken_error_display() {
-    test_display_output(KrakenError::PermissionDenied, "PermissionDenied");
-    test_display_output(KrakenError::InvalidKey, "InvalidKey");
-    test_display_output(KrakenError::UnknownAssetPair, "UnknownAssetPair");
     test_display_output(
-        KrakenError::InvalidArguments("InvalidArguments:type".to_string()),
-        "InvalidArguments:type",
+        KrakenError::PermissionDenied("EGeneral:Permission denied".to_string()),
+        "EGeneral:Permission denied",
     );
-    test_display_output(KrakenError::InvalidSignature, "InvalidSignature");
-    test_display_output(KrakenError::InvalidNonce, "InvalidNonce");
-    test_display_output(KrakenError::InvalidSession, "InvalidSession");
-    test_display_output(KrakenError::BadRequest, "BadRequest");
-    test_display_output(KrakenError::UnknownMethod, "UnknownMethod");
-    test_display_output(KrakenError::RateLimitExceeded, "RateLimitExceeded");
     test_display_output(
-        KrakenError::TradingRateLimitExceeded,
-        "TradingRateLimitExceeded",
+        KrakenError::InvalidKey("EAPI:Invalid key".to_string()),
+        "EAPI:Invalid key",
+    );
+    test_display_output(
+        KrakenError::UnknownAssetPair("EQuery:Unknown asset pair".to_string()),
+        "EQuery:Unknown asset pair",
+    );
+    test_display_output(
+        KrakenError::InvalidArguments("EGeneral:Invalid arguments:type".to_string()),
+        "EGeneral:Invalid arguments:type",
+    );
+    test_display_output(
+        KrakenError::InvalidSignature("EAPI:Invalid signature".to_string()),
+        "EAPI:Invalid signature",
+    );
+    test_display_output(
+        KrakenError::InvalidNonce("EAPI:Invalid nonce".to_string()),
+        "EAPI:Invalid nonce",
+    );
+    test_display_output(
+        KrakenError::InvalidSession("ESession:Invalid session".to_string()),
+        "ESession:Invalid session",
+    );
+    test_display_output(
+        KrakenError::BadRequest("EAPI:Bad request".to_string()),
+        "EAPI:Bad request",
+    );
+    test_display_output(
+        KrakenError::UnknownMethod("EGeneral:Unknown Method".to_string()),
+        "EGeneral:Unknown Method",
+    );
+    test_display_output(
+        KrakenError::RateLimitExceeded("EAPI:Rate limit exceeded".to_string()),
+        "EAPI:Rate limit exceeded",
+    );
+    test_display_output(
+        KrakenError::TradingRateLimitExceeded("EOrder:Rate limit exceeded".to_string()),
+        "EOrder:Rate limit exceeded",
+    );
+    test_display_output(
+        KrakenError::TemporaryLockout("EGeneral:Temporary lockout".to_string()),
+        "EGeneral:Temporary lockout",
+    );
+    test_display_output(
+        KrakenError::ServiceUnavailable("EService:Unavailable".to_string()),
+        "EService:Unavailable",
+    );
+    test_display_output(
+        KrakenError::ServiceBusy("EService:Busy".to_string()),
+        "EService:Busy",
+    );
+    test_display_output(
+        KrakenError::InternalError("EGeneral:Internal error".to_string()),
+        "EGeneral:Internal error",
+    );
+    test_display_output(
+        KrakenError::InsufficientFunds("EOrder:Insufficient funds".to_string()),
+        "EOrder:Insufficient funds",
+    );
+    test_display_output(
+        KrakenError::FundingInsufficientFunds("EFunding:Insufficient funds".to_string()),
+        "EFunding:Insufficient funds",
+    );
+    test_display_output(
+        KrakenError::Unknown("EGeneral:Something new".to_string()),
+        "EGeneral:Something new",
     );
-    test_display_output(KrakenError::TemporaryLockout, "TemporaryLockout");
-    test_display_output(KrakenError::ServiceUnavailable, "ServiceUnavailable");
-    test_display_output(KrakenError::ServiceBusy, "ServiceBusy");
-    test_display_output(KrakenError::InternalError, "InternalError");
 }
 
 #[test]
@@ -214,4 +265,7 @@ fn test_export_report_status_type_display() {
     test_display_output(ExportReportStatusType::Queued, "Queued");
     test_display_output(ExportReportStatusType::Processing, "Processing");
     test_display_output(ExportReportStatusType::Processed, "Processed");
+    test_display_output(ExportReportStatusType::Failed, "Failed");
+    test_display_output(ExportReportStatusType::Deleted, "Deleted");
+    test_display_output(ExportReportStatusType::Unknown, "Unknown");
 }
@@ -0,0 +1,271 @@
+mod resources;
+
+use crate::resources::kraken_responses::account_response_json::{
+    get_delete_export_report_json, get_export_report_status_json, get_request_export_report_json,
+};
+use crate::resources::test_auth::get_null_secrets_provider;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::export_reports::{
+    export_ledgers, export_trades, wait_for_export_report, ExportReportError, ExportReportOptions,
+};
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::request_types::{ExportReportRequest, ReportType};
+use kraken_async_rs::response_types::{ExportReportStatusType, LedgerEntryType};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+const LEDGERS_CSV: &str = r#""txid","refid","time","type","subtype","aclass","asset","wallet","amount","fee","balance"
+"LVTSFS-NHZVM-EXNZ5M","TZX2WP-XSEOP-FP7WYR","2024-01-02 03:04:05.5","trade","","currency","XXBT","spot / main","0.01","0","1.01"
+"LJNFMJ-2GBXG-JSZHVS","TZX2WP-XSEOP-FP7WYR","2024-01-02 03:04:05.5","trade","","currency","ZUSD","spot / main","-420.001","1.09","1000.00"
+"#;
+
+const TRADES_CSV: &str = r#""txid","ordertxid","pair","time","type","ordertype","price","cost","fee","vol","margin","misc","ledgers"
+"TZX2WP-XSEOP-FP7WYR","OAAVL3-5NWXO-SLDNKV","XXBTZUSD","2024-01-02 03:04:05.5","buy","limit","42000.1","420.001","1.09","0.01","0","","LVTSFS-NHZVM-EXNZ5M,LJNFMJ-2GBXG-JSZHVS"
+"#;
+
+fn get_test_client(mock_server: &MockServer) -> CoreKrakenClient {
+    let secrets_provider = get_null_secrets_provider();
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    CoreKrakenClient::new_with_url(secrets_provider, nonce_provider, mock_server.uri())
+}
+
+fn get_fast_options(delete_after: bool) -> ExportReportOptions {
+    ExportReportOptions {
+        poll_interval: Duration::from_millis(5),
+        max_poll_interval: Duration::from_millis(20),
+        timeout: Duration::from_secs(5),
+        delete_after,
+    }
+}
+
+fn get_export_request() -> ExportReportRequest {
+    ExportReportRequest::builder(ReportType::Ledgers, "TestExport".to_string()).build()
+}
+
+fn get_zipped_report(file_name: &str, contents: &str) -> Vec<u8> {
+    let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer
+        .start_file(file_name, SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(contents.as_bytes()).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+fn get_export_status_json(status: &str) -> Value {
+    let mut json = get_export_report_status_json();
+    json["result"][0]["status"] = json!(status);
+    json
+}
+
+async fn mount_export(mock_server: &MockServer, report: Vec<u8>, expected_deletes: u64) {
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddExport"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_request_export_report_json()))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ExportStatus"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_export_status_json("Queued")))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ExportStatus"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_export_status_json("Processed")))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RetrieveExport"))
+        .and(body_string_contains("id=KQMO"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(report))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RemoveExport"))
+        .and(body_string_contains("id=KQMO"))
+        .and(body_string_contains("type=delete"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_delete_export_report_json()))
+        .expect(expected_deletes)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_export_ledgers_end_to_end() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_test_client(&mock_server);
+
+    mount_export(
+        &mock_server,
+        get_zipped_report("ledgers.csv", LEDGERS_CSV),
+        1,
+    )
+    .await;
+
+    let entries = export_ledgers(&mut client, get_export_request(), get_fast_options(true))
+        .await
+        .unwrap();
+
+    mock_server.verify().await;
+    assert_eq!(2, entries.len());
+    assert_eq!("LVTSFS-NHZVM-EXNZ5M", entries[0].id);
+    assert_eq!(LedgerEntryType::Trade, entries[0].entry_type);
+    assert_eq!(dec!(-420.001), entries[1].amount);
+    assert_eq!(dec!(1000.00), entries[1].balance);
+}
+
+#[tokio::test]
+async fn test_export_trades_keeps_report_by_default() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_test_client(&mock_server);
+
+    mount_export(&mock_server, get_zipped_report("trades.csv", TRADES_CSV), 0).await;
+
+    let trades = export_trades(&mut client, get_export_request(), get_fast_options(false))
+        .await
+        .unwrap();
+
+    mock_server.verify().await;
+    assert_eq!(1, trades.len());
+    assert_eq!("OAAVL3-5NWXO-SLDNKV", trades[0].order_tx_id);
+    assert_eq!(dec!(0.01), trades[0].volume);
+    assert_eq!(2, trades[0].ledgers.len());
+}
+
+#[tokio::test]
+async fn test_export_times_out_while_processing() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_test_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddExport"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_request_export_report_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ExportStatus"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(get_export_status_json("Processing")),
+        )
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/RetrieveExport"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let options = ExportReportOptions {
+        timeout: Duration::from_millis(100),
+        ..get_fast_options(true)
+    };
+
+    let result = export_ledgers(&mut client, get_export_request(), options).await;
+
+    mock_server.verify().await;
+    assert!(matches!(result, Err(ExportReportError::Timeout(id)) if id == "KQMO"));
+}
+
+#[tokio::test]
+async fn test_export_fails_without_waiting_for_terminal_status() {
+    for (status, expected) in [
+        ("Failed", ExportReportStatusType::Failed),
+        ("Deleted", ExportReportStatusType::Deleted),
+        ("Cancelled", ExportReportStatusType::Unknown),
+    ] {
+        let mock_server = MockServer::start().await;
+        let mut client = get_test_client(&mock_server);
+
+        Mock::given(method("POST"))
+            .and(path("/0/private/AddExport"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(get_request_export_report_json()),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/0/private/ExportStatus"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(get_export_status_json(status)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/0/private/RetrieveExport"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let result =
+            export_ledgers(&mut client, get_export_request(), get_fast_options(true)).await;
+
+        mock_server.verify().await;
+        assert!(matches!(
+            result,
+            Err(ExportReportError::NotProcessed(id, status)) if id == "KQMO" && status == expected
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_export_keeps_report_that_fails_to_parse() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_test_client(&mock_server);
+
+    mount_export(&mock_server, vec![0, 1, 0, 1], 0).await;
+
+    let result = export_ledgers(&mut client, get_export_request(), get_fast_options(true)).await;
+
+    mock_server.verify().await;
+    assert!(matches!(result, Err(ExportReportError::Zip(_))));
+}
+
+#[tokio::test]
+async fn test_export_polls_once_with_short_timeout() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_test_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ExportStatus"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_export_status_json("Processed")))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let options = ExportReportOptions {
+        timeout: Duration::from_millis(1),
+        ..get_fast_options(false)
+    };
+
+    let status = wait_for_export_report(&mut client, ReportType::Ledgers, "KQMO", options)
+        .await
+        .unwrap();
+
+    mock_server.verify().await;
+    assert_eq!("KQMO", status.id);
+}
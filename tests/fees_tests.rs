@@ -0,0 +1,138 @@
+mod resources;
+
+use crate::resources::kraken_responses::account_response_json::get_trade_volume_per_pair_json;
+use crate::resources::kraken_responses::public_response_json::get_tradable_asset_pairs_json;
+use kraken_async_rs::fees::{FeeCurrency, FeeEstimate, FeeSchedule, FeeTier};
+use kraken_async_rs::request_types::OrderFlags;
+use kraken_async_rs::response_types::{BuySell, OrderFlag, TradableAssetPair, TradeVolume};
+use kraken_async_rs::wss::v2::trading_messages::FeePreference;
+use kraken_async_rs::wss::v2::user_data_messages::MakerTaker;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+fn get_pair() -> TradableAssetPair {
+    let mut pairs: HashMap<String, TradableAssetPair> =
+        serde_json::from_value(get_tradable_asset_pairs_json()["result"].clone()).unwrap();
+    pairs.remove("XXBTZUSD").unwrap()
+}
+
+#[test]
+fn test_published_tiers() {
+    let pair = get_pair();
+
+    let schedule = FeeSchedule::new(&pair, dec!(75000));
+    assert_eq!(dec!(0.24), schedule.taker_fee());
+    assert_eq!(dec!(0.14), schedule.maker_fee());
+    assert_eq!(
+        Some(FeeTier {
+            volume: dec!(100000),
+            fee: dec!(0.12)
+        }),
+        schedule.next_tier(MakerTaker::Maker)
+    );
+    assert_eq!(
+        Some(dec!(25000)),
+        schedule.volume_to_next_tier(MakerTaker::Taker)
+    );
+
+    let top_tier = FeeSchedule::new(&pair, dec!(20000000));
+    assert_eq!(dec!(0.1), top_tier.taker_fee());
+    assert_eq!(None, top_tier.next_tier(MakerTaker::Taker));
+
+    let taker_only = TradableAssetPair {
+        fees_maker: vec![],
+        ..pair
+    };
+    let schedule = FeeSchedule::new(&taker_only, dec!(0));
+    assert_eq!(dec!(0.26), schedule.maker_fee());
+}
+
+#[test]
+fn test_trade_volume_fees() {
+    let pair = get_pair();
+    let mut trade_volume: TradeVolume =
+        serde_json::from_value(get_trade_volume_per_pair_json()["result"].clone()).unwrap();
+
+    // the account's exact fees take precedence over the published tiers
+    trade_volume
+        .fees
+        .as_mut()
+        .unwrap()
+        .get_mut("XXBTZUSD")
+        .unwrap()
+        .fee = dec!(0.25);
+
+    let schedule = FeeSchedule::from_trade_volume("XXBTZUSD", &pair, &trade_volume);
+    assert_eq!(dec!(294.0219), schedule.volume());
+    assert_eq!(dec!(0.25), schedule.taker_fee());
+    assert_eq!(dec!(0.16), schedule.maker_fee());
+    assert_eq!(
+        Some(FeeTier {
+            volume: dec!(50000),
+            fee: dec!(0.14)
+        }),
+        schedule.next_tier(MakerTaker::Maker)
+    );
+
+    let other_pair = FeeSchedule::from_trade_volume("XETHXXBT", &pair, &trade_volume);
+    assert_eq!(dec!(0.26), other_pair.taker_fee());
+}
+
+#[test]
+fn test_estimate() {
+    let schedule = FeeSchedule::new(&get_pair(), dec!(0));
+
+    assert_eq!(
+        FeeEstimate {
+            fee: dec!(0.16),
+            amount: dec!(40),
+            currency: FeeCurrency::Quote,
+            asset: "ZUSD".to_string(),
+        },
+        schedule.estimate(
+            dec!(0.5),
+            dec!(50000),
+            MakerTaker::Maker,
+            FeeCurrency::Quote
+        )
+    );
+
+    let base = schedule.estimate(dec!(0.5), dec!(50000), MakerTaker::Taker, FeeCurrency::Base);
+    assert_eq!(dec!(0.0013), base.amount);
+    assert_eq!("XXBT", base.asset);
+}
+
+#[test]
+fn test_fee_currency() {
+    assert_eq!(
+        FeeCurrency::Quote,
+        FeeCurrency::for_order(BuySell::Buy, None)
+    );
+    assert_eq!(
+        FeeCurrency::Base,
+        FeeCurrency::for_order(BuySell::Sell, None)
+    );
+    assert_eq!(
+        FeeCurrency::Base,
+        FeeCurrency::for_order(BuySell::Buy, Some(&OrderFlag::FeesInBase.into()))
+    );
+    assert_eq!(
+        FeeCurrency::Quote,
+        FeeCurrency::for_order(
+            BuySell::Sell,
+            Some(&OrderFlags::new(vec![
+                OrderFlag::Post,
+                OrderFlag::FeesInQuote
+            ]))
+        )
+    );
+
+    assert_eq!(
+        FeeCurrency::Base,
+        FeeCurrency::for_preference(BuySell::Sell, None)
+    );
+    assert_eq!(
+        FeeCurrency::Quote,
+        FeeCurrency::for_preference(BuySell::Sell, Some(&FeePreference::Quote))
+    );
+}
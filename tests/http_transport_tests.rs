@@ -0,0 +1,178 @@
+mod resources;
+
+use crate::resources::kraken_responses::public_response_json::get_server_time_json;
+use crate::resources::test_auth::get_null_secrets_provider;
+use hyper_util::client::legacy::connect::HttpConnector;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::ClientError;
+use kraken_async_rs::clients::http_transport::CoreKrakenClientBuilder;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{copy_bidirectional, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn get_builder(mock_server: &MockServer) -> CoreKrakenClientBuilder {
+    let secrets_provider = get_null_secrets_provider();
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    CoreKrakenClient::builder(secrets_provider, nonce_provider).url(mock_server.uri())
+}
+
+async fn mount_server_time(mock_server: &MockServer, delay: Duration) {
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(get_server_time_json())
+                .set_delay(delay),
+        )
+        .expect(1)
+        .mount(mock_server)
+        .await;
+}
+
+/// Minimal proxy that accepts a single `CONNECT`, returning the request line and headers after
+/// tunneling the connection to the requested host.
+async fn start_connect_proxy() -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let (mut inbound, _) = listener.accept().await.unwrap();
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(inbound.read_u8().await.unwrap());
+        }
+        let head = String::from_utf8(head).unwrap();
+        let target = head.split_whitespace().nth(1).unwrap().to_string();
+
+        let mut outbound = TcpStream::connect(target).await.unwrap();
+        inbound
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await
+            .unwrap();
+
+        tokio::spawn(async move {
+            let _ = copy_bidirectional(&mut inbound, &mut outbound).await;
+        });
+
+        head
+    });
+
+    (uri, handle)
+}
+
+#[tokio::test]
+async fn test_builder_defaults() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::ZERO).await;
+
+    let mut client = get_builder(&mock_server)
+        .pool_idle_timeout(Duration::from_secs(30))
+        .pool_max_idle_per_host(2)
+        .tcp_keepalive(Duration::from_secs(60))
+        .build();
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
+
+#[tokio::test]
+async fn test_read_timeout() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::from_millis(500)).await;
+
+    let mut client = get_builder(&mock_server)
+        .read_timeout(Duration::from_millis(50))
+        .build();
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(matches!(resp, Err(ClientError::Timeout("read"))));
+}
+
+#[tokio::test]
+async fn test_request_timeout() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::from_millis(500)).await;
+
+    let mut client = get_builder(&mock_server)
+        .read_timeout(Duration::from_secs(5))
+        .request_timeout(Duration::from_millis(50))
+        .build();
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(matches!(resp, Err(ClientError::Timeout("request"))));
+}
+
+#[tokio::test]
+async fn test_response_within_timeouts() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::from_millis(20)).await;
+
+    let mut client = get_builder(&mock_server)
+        .connect_timeout(Duration::from_secs(1))
+        .read_timeout(Duration::from_secs(1))
+        .request_timeout(Duration::from_secs(2))
+        .build();
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
+
+#[tokio::test]
+async fn test_proxy_tunnel() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::ZERO).await;
+
+    let (proxy_uri, proxy) = start_connect_proxy().await;
+    let proxy_uri = proxy_uri.replace("http://", "http://user:pass@");
+
+    let mut client = get_builder(&mock_server)
+        .proxy(proxy_uri.parse().unwrap())
+        .unwrap()
+        .build();
+
+    let resp = client.get_server_time().await;
+    let head = proxy.await.unwrap();
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+    assert!(head.starts_with(&format!("CONNECT {} ", mock_server.address())));
+    assert!(head.contains("Proxy-Authorization: Basic dXNlcjpwYXNz"));
+}
+
+#[tokio::test]
+async fn test_https_proxy_is_rejected() {
+    let mock_server = MockServer::start().await;
+
+    let result = get_builder(&mock_server).proxy("https://proxy.internal:3128".parse().unwrap());
+
+    assert!(matches!(result, Err(ClientError::Proxy(_))));
+}
+
+#[tokio::test]
+async fn test_build_with_connector() {
+    let mock_server = MockServer::start().await;
+    mount_server_time(&mock_server, Duration::ZERO).await;
+
+    let mut client = get_builder(&mock_server).build_with_connector(HttpConnector::new());
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
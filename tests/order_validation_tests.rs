@@ -0,0 +1,295 @@
+mod resources;
+
+use crate::resources::kraken_responses::public_response_json::get_tradable_asset_pairs_json;
+use crate::resources::test_fixtures::limit_order;
+use kraken_async_rs::crypto::secrets::Token;
+use kraken_async_rs::order_validation::{OrderValidationError, OrderValidator};
+use kraken_async_rs::request_types::{
+    AddOrderRequest, BatchedOrderRequest, EditOrderRequest, OrderFlags,
+};
+use kraken_async_rs::response_types::{
+    BuySell, OrderFlag, OrderType, TradableAssetPair, TradableAssetStatus,
+};
+use kraken_async_rs::wss::v2::trading_messages::{AddOrderParams, PriceType, TriggerParams};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+fn get_pairs() -> HashMap<String, TradableAssetPair> {
+    serde_json::from_value(get_tradable_asset_pairs_json()["result"].clone()).unwrap()
+}
+
+fn get_validator() -> OrderValidator {
+    OrderValidator::new(get_pairs())
+}
+
+fn add_order_params(symbol: &str, volume: Decimal, price: Option<Decimal>) -> AddOrderParams {
+    AddOrderParams {
+        order_type: OrderType::Limit,
+        side: BuySell::Sell,
+        symbol: symbol.to_string(),
+        limit_price: price,
+        limit_price_type: None,
+        triggers: None,
+        time_in_force: None,
+        order_quantity: volume,
+        margin: None,
+        post_only: None,
+        reduce_only: None,
+        effective_time: None,
+        expire_time: None,
+        deadline: None,
+        order_user_ref: None,
+        conditional: None,
+        display_quantity: None,
+        fee_preference: None,
+        no_market_price_protection: None,
+        stp_type: None,
+        cash_order_quantity: None,
+        validate: None,
+        token: Token::new("aToken".to_string()),
+        client_order_id: None,
+    }
+}
+
+#[test]
+fn test_pair_names() {
+    let validator = get_validator();
+
+    for name in [
+        "XXBTZUSD", "XBTUSD", "XBT/USD", "BTC/USD", "BTCUSD", "xbt/usd",
+    ] {
+        assert_eq!("XBTUSD", validator.get_pair(name).unwrap().alt_name);
+    }
+
+    let unknown =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(1), "ETHUSD".into()).build();
+    assert_eq!(
+        Err(OrderValidationError::UnknownPair("ETHUSD".to_string())),
+        validator.validate_add_order(&unknown)
+    );
+}
+
+#[test]
+fn test_validate_add_order() {
+    let validator = get_validator();
+
+    let valid = limit_order(BuySell::Buy, dec!(0.0001), dec!(50000.1));
+    assert_eq!(Ok(()), validator.validate_add_order(&valid));
+
+    let price_precision = limit_order(BuySell::Buy, dec!(1), dec!(50000.15));
+    assert_eq!(
+        Err(OrderValidationError::PricePrecision {
+            price: dec!(50000.15),
+            decimals: 1
+        }),
+        validator.validate_add_order(&price_precision)
+    );
+
+    let volume_precision = limit_order(BuySell::Buy, dec!(0.123456789), dec!(50000));
+    assert_eq!(
+        Err(OrderValidationError::VolumePrecision {
+            volume: dec!(0.123456789),
+            decimals: 8
+        }),
+        validator.validate_add_order(&volume_precision)
+    );
+
+    let below_minimum = limit_order(BuySell::Buy, dec!(0.00005), dec!(50000));
+    assert_eq!(
+        Err(OrderValidationError::VolumeBelowMinimum {
+            volume: dec!(0.00005),
+            minimum: dec!(0.0001)
+        }),
+        validator.validate_add_order(&below_minimum)
+    );
+
+    let below_cost = limit_order(BuySell::Buy, dec!(0.0001), dec!(4000));
+    assert_eq!(
+        Err(OrderValidationError::CostBelowMinimum {
+            cost: dec!(0.4),
+            minimum: dec!(0.5)
+        }),
+        validator.validate_add_order(&below_cost)
+    );
+
+    let leveraged =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Sell, dec!(1), "XBTUSD".into())
+            .leverage(10)
+            .build();
+    assert_eq!(
+        Err(OrderValidationError::LeverageNotAllowed {
+            side: BuySell::Sell,
+            leverage: Some(10),
+            allowed: vec![2, 3, 4, 5]
+        }),
+        validator.validate_add_order(&leveraged)
+    );
+
+    let volume_in_quote =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(0.25), "XBTUSD".into())
+            .order_flags(OrderFlag::OrderVolumeInQuote.into())
+            .build();
+    assert_eq!(
+        Err(OrderValidationError::CostBelowMinimum {
+            cost: dec!(0.25),
+            minimum: dec!(0.5)
+        }),
+        validator.validate_add_order(&volume_in_quote)
+    );
+}
+
+#[test]
+fn test_pair_status() {
+    let mut pairs = get_pairs();
+    pairs.get_mut("XXBTZUSD").unwrap().status = TradableAssetStatus::PostOnly;
+    let mut validator = OrderValidator::new(pairs.clone());
+
+    let order = limit_order(BuySell::Buy, dec!(1), dec!(50000));
+    assert_eq!(
+        Err(OrderValidationError::PairStatus(
+            TradableAssetStatus::PostOnly
+        )),
+        validator.validate_add_order(&order)
+    );
+
+    let post_only =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(1), "XBTUSD".into())
+            .price(dec!(50000))
+            .order_flags(OrderFlags::new(vec![OrderFlag::Post]))
+            .build();
+    assert_eq!(Ok(()), validator.validate_add_order(&post_only));
+
+    pairs.get_mut("XXBTZUSD").unwrap().status = TradableAssetStatus::CancelOnly;
+    validator.update(pairs);
+
+    assert_eq!(
+        Err(OrderValidationError::PairStatus(
+            TradableAssetStatus::CancelOnly
+        )),
+        validator.validate_add_order(&post_only)
+    );
+}
+
+#[test]
+fn test_round_add_order() {
+    let validator = get_validator();
+
+    let mut buy = limit_order(BuySell::Buy, dec!(0.123456789), dec!(50000.19));
+    assert_eq!(Ok(()), validator.round_add_order(&mut buy));
+    assert_eq!(dec!(0.12345678), buy.volume);
+    assert_eq!(Some(dec!(50000.1)), buy.price);
+
+    let mut sell = limit_order(BuySell::Sell, dec!(1), dec!(50000.11));
+    assert_eq!(Ok(()), validator.round_add_order(&mut sell));
+    assert_eq!(Some(dec!(50000.2)), sell.price);
+
+    let mut too_small = limit_order(BuySell::Buy, dec!(0.000099999), dec!(50000));
+    assert_eq!(
+        Err(OrderValidationError::VolumeBelowMinimum {
+            volume: dec!(0.00009999),
+            minimum: dec!(0.0001)
+        }),
+        validator.round_add_order(&mut too_small)
+    );
+}
+
+#[test]
+fn test_batched_and_edited_orders() {
+    let validator = get_validator();
+
+    let mut batched = BatchedOrderRequest::builder(OrderType::Limit, BuySell::Sell, dec!(4))
+        .price(dec!(1.0001))
+        .build();
+    assert_eq!(
+        Err(OrderValidationError::VolumeBelowMinimum {
+            volume: dec!(4),
+            minimum: dec!(5)
+        }),
+        validator.validate_batched_order("USDCUSD", &batched)
+    );
+
+    batched.volume = dec!(5);
+    batched.price = Some(dec!(1.00005));
+    assert_eq!(
+        Ok(()),
+        validator.round_batched_order("USDCUSD", &mut batched)
+    );
+    assert_eq!(Some(dec!(1.0001)), batched.price);
+
+    let mut edit = EditOrderRequest::builder("OTXID".into(), dec!(0.5), "XXBTZUSD".into())
+        .price(dec!(49999.96))
+        .build();
+    assert!(matches!(
+        validator.validate_edit_order(&edit),
+        Err(OrderValidationError::PricePrecision { .. })
+    ));
+    assert_eq!(Ok(()), validator.round_edit_order(&mut edit));
+    assert_eq!(Some(dec!(50000)), edit.price);
+}
+
+#[test]
+fn test_add_order_params() {
+    let validator = get_validator();
+
+    let mut params = add_order_params("BTC/USD", dec!(0.5), Some(dec!(50000.05)));
+    params.triggers = Some(TriggerParams {
+        price: dec!(49000.01),
+        price_type: None,
+        reference: None,
+    });
+    assert!(matches!(
+        validator.validate_add_order_params(&params),
+        Err(OrderValidationError::PricePrecision { .. })
+    ));
+
+    assert_eq!(Ok(()), validator.round_add_order_params(&mut params));
+    assert_eq!(Some(dec!(50000.1)), params.limit_price);
+    assert_eq!(dec!(49000.1), params.triggers.unwrap().price);
+
+    let mut margin = add_order_params("ETH/BTC", dec!(1), Some(dec!(0.05)));
+    margin.margin = Some(true);
+    assert_eq!(Ok(()), validator.validate_add_order_params(&margin));
+
+    let mut cash = add_order_params("USDC/USD", dec!(0), None);
+    cash.order_type = OrderType::Market;
+    cash.cash_order_quantity = Some(dec!(0.123456789));
+    assert_eq!(
+        Err(OrderValidationError::CostPrecision {
+            cost: dec!(0.123456789),
+            decimals: 8
+        }),
+        validator.validate_add_order_params(&cash)
+    );
+}
+
+#[test]
+fn test_add_order_params_relative_prices() {
+    let validator = get_validator();
+
+    // a percentage offset is neither a price on the tick size nor used to cost the order
+    let mut percent = add_order_params("BTC/USD", dec!(0.0001), Some(dec!(-1.25)));
+    percent.limit_price_type = Some(PriceType::Percent);
+    assert_eq!(Ok(()), validator.validate_add_order_params(&percent));
+
+    assert_eq!(Ok(()), validator.round_add_order_params(&mut percent));
+    assert_eq!(Some(dec!(-1.25)), percent.limit_price);
+
+    let mut quote = add_order_params("BTC/USD", dec!(0.0001), None);
+    quote.order_type = OrderType::StopLoss;
+    quote.triggers = Some(TriggerParams {
+        price: dec!(-150.05),
+        price_type: Some(PriceType::Quote),
+        reference: None,
+    });
+    assert_eq!(
+        Err(OrderValidationError::PricePrecision {
+            price: dec!(150.05),
+            decimals: 1
+        }),
+        validator.validate_add_order_params(&quote)
+    );
+
+    assert_eq!(Ok(()), validator.round_add_order_params(&mut quote));
+    assert_eq!(dec!(-150.1), quote.triggers.unwrap().price);
+}
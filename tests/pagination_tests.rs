@@ -0,0 +1,434 @@
+mod resources;
+
+use crate::resources::kraken_responses::paginated_closed_orders::{
+    get_closed_orders_error_json, get_closed_orders_page_1_json, get_closed_orders_page_2_json,
+    get_closed_orders_page_3_json,
+};
+use crate::resources::test_auth::get_null_secrets_provider;
+use futures_util::StreamExt;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::ClientError;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::clients::pagination::{
+    closed_orders_stream, ledgers_stream_since, recent_trades_pages, recent_trades_stream,
+    trades_history_stream_since,
+};
+use kraken_async_rs::clients::rate_limited_kraken_client::RateLimitedKrakenClient;
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::request_types::{
+    ClosedOrdersRequest, LedgersInfoRequest, RecentTradesRequest, TradesHistoryRequest,
+};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use wiremock::matchers::{body_string_contains, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn get_rate_limited_client(mock_server: &MockServer) -> RateLimitedKrakenClient<CoreKrakenClient> {
+    let secrets_provider = get_null_secrets_provider();
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    RateLimitedKrakenClient::new_with_url(secrets_provider, nonce_provider, mock_server.uri())
+}
+
+fn get_core_client(mock_server: &MockServer) -> CoreKrakenClient {
+    let secrets_provider = get_null_secrets_provider();
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    CoreKrakenClient::new_with_url(secrets_provider, nonce_provider, mock_server.uri())
+}
+
+async fn mount_page(mock_server: &MockServer, offset: i64, body: Value, expected: u64) {
+    mount_endpoint_page(
+        mock_server,
+        "/0/private/ClosedOrders",
+        offset,
+        body,
+        expected,
+    )
+    .await;
+}
+
+async fn mount_endpoint_page(
+    mock_server: &MockServer,
+    endpoint: &str,
+    offset: i64,
+    body: Value,
+    expected: u64,
+) {
+    Mock::given(method("POST"))
+        .and(path(endpoint))
+        .and(body_string_contains(format!("ofs={}", offset)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .expect(expected)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_closed_orders_stream_walks_all_pages() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+
+    mount_page(&mock_server, 0, get_closed_orders_page_1_json(), 1).await;
+    mount_page(&mock_server, 50, get_closed_orders_page_2_json(), 1).await;
+    mount_page(&mock_server, 100, get_closed_orders_page_3_json(), 1).await;
+
+    let request = ClosedOrdersRequest::builder().build();
+    let orders: Vec<_> = client.closed_orders_stream(request).collect().await;
+
+    let ids: HashSet<String> = orders.into_iter().map(|order| order.unwrap().0).collect();
+    assert_eq!(103, ids.len());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_closed_orders_stream_resumes_from_offset() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+
+    mount_page(&mock_server, 0, get_closed_orders_page_1_json(), 0).await;
+    mount_page(&mock_server, 50, get_closed_orders_page_2_json(), 1).await;
+    mount_page(&mock_server, 100, get_closed_orders_page_3_json(), 1).await;
+
+    let request = ClosedOrdersRequest::builder().offset(50).build();
+    let orders: Vec<_> = client.closed_orders_stream(request).collect().await;
+
+    assert_eq!(53, orders.len());
+    assert!(orders.iter().all(|order| order.is_ok()));
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_closed_orders_stream_skips_shifted_orders() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+
+    // an order closed while paginating, shifting an order of page 1 onto page 2
+    let page_1 = get_closed_orders_page_1_json();
+    let (shifted_id, shifted_order) = page_1["result"]["closed"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .next()
+        .map(|(id, order)| (id.clone(), order.clone()))
+        .unwrap();
+
+    let mut page_2 = get_closed_orders_page_2_json();
+    page_2["result"]["closed"][shifted_id] = shifted_order;
+    page_2["result"]["count"] = json!(104);
+
+    let mut page_3 = get_closed_orders_page_3_json();
+    page_3["result"]["count"] = json!(104);
+
+    mount_page(&mock_server, 0, page_1, 1).await;
+    mount_page(&mock_server, 50, page_2, 1).await;
+    mount_page(&mock_server, 101, page_3, 1).await;
+
+    let request = ClosedOrdersRequest::builder().build();
+    let orders: Vec<_> = closed_orders_stream(&mut client, request).collect().await;
+
+    let ids: HashSet<&String> = orders
+        .iter()
+        .map(|order| &order.as_ref().unwrap().0)
+        .collect();
+    assert_eq!(103, orders.len());
+    assert_eq!(103, ids.len());
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_closed_orders_stream_ends_after_error() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+
+    mount_page(&mock_server, 0, get_closed_orders_page_1_json(), 1).await;
+    mount_page(&mock_server, 50, get_closed_orders_error_json(), 1).await;
+    mount_page(&mock_server, 100, get_closed_orders_page_3_json(), 0).await;
+
+    let request = ClosedOrdersRequest::builder().build();
+    let orders: Vec<_> = client.closed_orders_stream(request).collect().await;
+
+    assert_eq!(51, orders.len());
+    assert!(orders[..50].iter().all(|order| order.is_ok()));
+    assert!(orders[50].is_err());
+    mock_server.verify().await;
+}
+
+/// Create a page of trades with ids T{n} for n in `ids`, where higher n are older trades.
+fn get_trades_page(ids: std::ops::Range<i64>, count: i64) -> Value {
+    let trades: serde_json::Map<String, Value> = ids
+        .map(|n| {
+            let trade = json!({
+                "ordertxid": "R0X871-F5PY6-A5707B",
+                "postxid": "BV7YXR-DTHM9-AZRON4",
+                "pair": "ATOMUSD",
+                "time": 1700000000 - n,
+                "type": "buy",
+                "ordertype": "limit",
+                "price": "8.250000",
+                "cost": "44.250000",
+                "fee": "0.079000",
+                "vol": "4.00000000",
+                "margin": "0.000000",
+                "leverage": "0",
+                "misc": "",
+                "trade_id": n,
+                "maker": true
+            });
+            (format!("T{}", n), trade)
+        })
+        .collect();
+
+    json!({"error": [], "result": {"trades": trades, "count": count}})
+}
+
+/// Create a page of ledger entries with ids L{n} for n in `ids`, where higher n are older entries.
+fn get_ledgers_page(ids: std::ops::Range<i64>, count: i64) -> Value {
+    let ledger: serde_json::Map<String, Value> = ids
+        .map(|n| {
+            let entry = json!({
+                "aclass": "currency",
+                "amount": "5.00000000",
+                "asset": "ATOM",
+                "balance": "10.00000000",
+                "fee": "0.00000000",
+                "refid": "sU8gV7R-9gjgqXS7dKojLDNE07vHRV",
+                "time": 1700000000 - n,
+                "type": "deposit",
+                "subtype": ""
+            });
+            (format!("L{}", n), entry)
+        })
+        .collect();
+
+    json!({"error": [], "result": {"ledger": ledger, "count": count}})
+}
+
+#[tokio::test]
+async fn test_trades_history_stream_walks_all_pages() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+    let endpoint = "/0/private/TradesHistory";
+
+    mount_endpoint_page(&mock_server, endpoint, 0, get_trades_page(0..50, 60), 1).await;
+    mount_endpoint_page(&mock_server, endpoint, 50, get_trades_page(50..60, 60), 1).await;
+
+    let request = TradesHistoryRequest::builder().build();
+    let trades: Vec<String> = client
+        .trades_history_stream(request)
+        .map(|trade| trade.unwrap().0)
+        .collect()
+        .await;
+
+    let expected: Vec<String> = (0..60).map(|n| format!("T{}", n)).collect();
+    assert_eq!(expected, trades);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_trades_history_stream_since_stops_at_trade() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+    let endpoint = "/0/private/TradesHistory";
+
+    mount_endpoint_page(&mock_server, endpoint, 0, get_trades_page(0..50, 500), 1).await;
+    mount_endpoint_page(&mock_server, endpoint, 50, get_trades_page(50..100, 500), 1).await;
+    mount_endpoint_page(
+        &mock_server,
+        endpoint,
+        100,
+        get_trades_page(100..150, 500),
+        0,
+    )
+    .await;
+
+    let request = TradesHistoryRequest::builder().build();
+    let trades: Vec<String> = trades_history_stream_since(&mut client, request, "T72".to_string())
+        .map(|trade| trade.unwrap().0)
+        .collect()
+        .await;
+
+    let expected: Vec<String> = (0..72).map(|n| format!("T{}", n)).collect();
+    assert_eq!(expected, trades);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_ledgers_stream_walks_all_pages() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+    let endpoint = "/0/private/Ledgers";
+
+    mount_endpoint_page(&mock_server, endpoint, 0, get_ledgers_page(0..50, 75), 1).await;
+    mount_endpoint_page(&mock_server, endpoint, 50, get_ledgers_page(50..75, 75), 1).await;
+
+    let request = LedgersInfoRequest::builder().build();
+    let entries: Vec<String> = client
+        .ledgers_stream(request)
+        .map(|entry| entry.unwrap().0)
+        .collect()
+        .await;
+
+    let expected: Vec<String> = (0..75).map(|n| format!("L{}", n)).collect();
+    assert_eq!(expected, entries);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_ledgers_stream_since_stops_at_entry() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+    let endpoint = "/0/private/Ledgers";
+
+    mount_endpoint_page(&mock_server, endpoint, 0, get_ledgers_page(0..50, 500), 1).await;
+    mount_endpoint_page(
+        &mock_server,
+        endpoint,
+        50,
+        get_ledgers_page(50..100, 500),
+        0,
+    )
+    .await;
+
+    let request = LedgersInfoRequest::builder().build();
+    let entries: Vec<String> = ledgers_stream_since(&mut client, request, "L3".to_string())
+        .map(|entry| entry.unwrap().0)
+        .collect()
+        .await;
+
+    assert_eq!(vec!["L0", "L1", "L2"], entries);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_ledgers_stream_since_errors_when_entry_is_missing() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+    let endpoint = "/0/private/Ledgers";
+
+    mount_endpoint_page(&mock_server, endpoint, 0, get_ledgers_page(0..50, 60), 1).await;
+    mount_endpoint_page(&mock_server, endpoint, 50, get_ledgers_page(50..60, 60), 1).await;
+
+    let request = LedgersInfoRequest::builder().build();
+    let results: Vec<_> = ledgers_stream_since(&mut client, request, "L999".to_string())
+        .collect()
+        .await;
+
+    assert_eq!(61, results.len());
+    assert!(results[..60].iter().all(Result::is_ok));
+    assert!(matches!(
+        results.last(),
+        Some(Err(ClientError::Pagination(message))) if message.contains("L999")
+    ));
+    mock_server.verify().await;
+}
+
+/// Create a page of public trades with trade ids and times given by `ids`, and the cursor `last`.
+fn get_recent_trades_page(ids: std::ops::Range<i64>, last: i64) -> Value {
+    let trades: Vec<Value> = ids
+        .map(|n| json!(["37247.00000", "0.00026451", n as f64 + 0.5, "b", "l", "", n]))
+        .collect();
+
+    json!({"error": [], "result": {"XXBTZUSD": trades, "last": last.to_string()}})
+}
+
+async fn mount_recent_trades_page(
+    mock_server: &MockServer,
+    since: i64,
+    body: Value,
+    expected: u64,
+) {
+    Mock::given(method("GET"))
+        .and(path("/0/public/Trades"))
+        .and(query_param("since", since.to_string()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .expect(expected)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_recent_trades_stream_follows_cursor_until_caught_up() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_core_client(&mock_server);
+
+    mount_recent_trades_page(&mock_server, 0, get_recent_trades_page(0..3, 100), 1).await;
+    mount_recent_trades_page(&mock_server, 100, get_recent_trades_page(3..6, 200), 1).await;
+    mount_recent_trades_page(&mock_server, 200, get_recent_trades_page(6..7, 300), 1).await;
+
+    let request = RecentTradesRequest::builder("XXBTZUSD".to_string())
+        .since(0)
+        .count(3)
+        .build();
+    let trade_ids: Vec<i64> = recent_trades_stream(&mut client, request, None)
+        .map(|trade| trade.unwrap().trade_id)
+        .collect()
+        .await;
+
+    assert_eq!((0..7).collect::<Vec<i64>>(), trade_ids);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_recent_trades_stream_stops_at_end() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_core_client(&mock_server);
+
+    mount_recent_trades_page(&mock_server, 0, get_recent_trades_page(10..13, 100), 1).await;
+    mount_recent_trades_page(&mock_server, 100, get_recent_trades_page(13..16, 200), 1).await;
+    mount_recent_trades_page(&mock_server, 200, get_recent_trades_page(16..19, 300), 0).await;
+
+    let request = RecentTradesRequest::builder("XXBTZUSD".to_string())
+        .since(0)
+        .count(3)
+        .build();
+    let trade_ids: Vec<i64> = recent_trades_stream(&mut client, request, Some(15))
+        .map(|trade| trade.unwrap().trade_id)
+        .collect()
+        .await;
+
+    assert_eq!(vec![10, 11, 12, 13, 14], trade_ids);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_recent_trades_pages_resume_from_cursor() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_core_client(&mock_server);
+
+    mount_recent_trades_page(&mock_server, 0, get_recent_trades_page(0..3, 100), 0).await;
+    mount_recent_trades_page(&mock_server, 100, get_recent_trades_page(3..6, 200), 1).await;
+    mount_recent_trades_page(&mock_server, 200, get_recent_trades_page(6..7, 300), 1).await;
+
+    let request = RecentTradesRequest::builder("XXBTZUSD".to_string())
+        .since(100)
+        .count(3)
+        .build();
+    let cursors: Vec<i64> = recent_trades_pages(&mut client, request, None)
+        .map(|page| page.unwrap().last)
+        .collect()
+        .await;
+
+    assert_eq!(vec![200, 300], cursors);
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_rate_limited_recent_trades_stream() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_rate_limited_client(&mock_server);
+
+    mount_recent_trades_page(&mock_server, 0, get_recent_trades_page(0..3, 100), 1).await;
+    mount_recent_trades_page(&mock_server, 100, get_recent_trades_page(3..4, 200), 1).await;
+
+    let request = RecentTradesRequest::builder("XXBTZUSD".to_string())
+        .since(0)
+        .count(3)
+        .build();
+    let trades: Vec<_> = client.recent_trades_stream(request, None).collect().await;
+
+    assert_eq!(4, trades.len());
+    mock_server.verify().await;
+}
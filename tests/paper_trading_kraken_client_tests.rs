@@ -0,0 +1,221 @@
+mod resources;
+
+use crate::resources::kraken_responses::public_response_json::get_tradable_asset_pairs_json;
+use crate::resources::test_fixtures::{get_mock_client, limit_order};
+use futures_util::stream;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::{ClientError, KrakenError};
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::clients::paper_trading_kraken_client::PaperTradingKrakenClient;
+use kraken_async_rs::request_types::{
+    AddOrderRequest, CancelOrderRequest, ClosedOrdersRequest, EditOrderRequest, IntOrString,
+    LedgersInfoRequest, OpenOrdersRequest, TradesHistoryRequest,
+};
+use kraken_async_rs::response_types::{BuySell, OrderFlag, OrderStatus, OrderType};
+use kraken_async_rs::wss::v2::base_messages::WssMessage;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn get_client(mock_server: &MockServer) -> PaperTradingKrakenClient<CoreKrakenClient> {
+    let client: PaperTradingKrakenClient<CoreKrakenClient> = get_mock_client(mock_server);
+
+    client.deposit("ZUSD", dec!(100000)).await;
+    client
+}
+
+async fn mount_asset_pairs(mock_server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/0/public/AssetPairs"))
+        .and(query_param("pair", "XBTUSD"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_tradable_asset_pairs_json()))
+        .expect(1)
+        .mount(mock_server)
+        .await;
+}
+
+fn book_snapshot() -> WssMessage {
+    serde_json::from_value(json!({
+        "channel": "book",
+        "type": "snapshot",
+        "data": [{
+            "symbol": "BTC/USD",
+            "bids": [{"price": 49900.0, "qty": 1.0}],
+            "asks": [{"price": 50000.0, "qty": 0.5}, {"price": 50100.0, "qty": 1.0}],
+            "checksum": 0
+        }]
+    }))
+    .unwrap()
+}
+
+fn book_update(price: f64, quantity: f64) -> WssMessage {
+    serde_json::from_value(json!({
+        "channel": "book",
+        "type": "update",
+        "data": [{
+            "symbol": "BTC/USD",
+            "bids": [{"price": price, "qty": quantity}],
+            "asks": [],
+            "checksum": 0,
+            "timestamp": "2024-05-19T16:45:24.204654Z"
+        }]
+    }))
+    .unwrap()
+}
+
+fn trade(side: &str, price: f64, quantity: f64) -> WssMessage {
+    serde_json::from_value(json!({
+        "channel": "trade",
+        "type": "update",
+        "data": [{
+            "symbol": "BTC/USD",
+            "side": side,
+            "price": price,
+            "qty": quantity,
+            "ord_type": "market",
+            "trade_id": 1,
+            "timestamp": "2024-05-27T12:33:10.826003Z"
+        }]
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_paper_trading_workflow() {
+    let mock_server = MockServer::start().await;
+    mount_asset_pairs(&mock_server).await;
+
+    let mut client = get_client(&mock_server).await;
+    client
+        .follow_market_data(stream::iter(vec![Ok(book_snapshot())]))
+        .await
+        .unwrap();
+
+    let market_buy =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(0.3), "XBTUSD".into())
+            .build();
+    let market_order = client.add_order(&market_buy).await.unwrap().result.unwrap();
+    assert_eq!("buy 0.30000000 XBTUSD @ market", market_order.descr.order);
+
+    let sell = client
+        .add_order(&limit_order(BuySell::Sell, dec!(0.2), dec!(50500)))
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    let buy = client
+        .add_order(&limit_order(BuySell::Buy, dec!(0.5), dec!(49800)))
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+
+    client.process_message(&trade("sell", 49750.0, 0.4)).await;
+    client.process_message(&book_update(50600.0, 1.0)).await;
+
+    let balances = client.get_account_balance().await.unwrap().result.unwrap();
+    // the sell pays its 0.16% maker fee in the base asset, and the buys pay theirs in the quote
+    assert_eq!(dec!(0.49968), balances["XXBT"]);
+    // 15000 + 0.26% taker fee, 19920 + 0.16% maker fee, and 10100
+    assert_eq!(dec!(75109.128), balances["ZUSD"]);
+
+    let open = client
+        .get_open_orders(&OpenOrdersRequest::builder().trades(true).build())
+        .await
+        .unwrap()
+        .result
+        .unwrap()
+        .open;
+    let resting = &open[&buy.tx_id[0]];
+    assert_eq!(1, open.len());
+    assert_eq!(dec!(0.4), resting.volume_executed);
+    assert_eq!(dec!(49800), resting.price);
+
+    let closed = client
+        .get_closed_orders(&ClosedOrdersRequest::builder().build())
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(2, closed.count);
+    assert_eq!(
+        OrderStatus::Closed,
+        closed.closed[&market_order.tx_id[0]].status
+    );
+    assert_eq!(dec!(39), closed.closed[&market_order.tx_id[0]].fee);
+    assert_eq!(dec!(10100), closed.closed[&sell.tx_id[0]].cost);
+
+    let trades = client
+        .get_trades_history(&TradesHistoryRequest::builder().build())
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(3, trades.count);
+    assert_eq!(2, trades.trades.values().filter(|t| t.maker).count());
+
+    let edit = EditOrderRequest::builder(buy.tx_id[0].clone(), dec!(0.6), "XBTUSD".into())
+        .price(dec!(49700))
+        .build();
+    let edited = client.edit_order(&edit).await.unwrap().result.unwrap();
+    assert_eq!(dec!(0.2), edited.volume);
+
+    let cancel = CancelOrderRequest::builder(IntOrString::String(edited.tx_id)).build();
+    let cancelled = client.cancel_order(&cancel).await.unwrap().result.unwrap();
+    assert_eq!(1, cancelled.count);
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_paper_trading_rejections() {
+    let mock_server = MockServer::start().await;
+    mount_asset_pairs(&mock_server).await;
+
+    let mut client = get_client(&mock_server).await;
+
+    let market_buy =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(0.1), "XBTUSD".into())
+            .build();
+    let resp = client.add_order(&market_buy).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::Unknown(message))) if message == "EOrder:Insufficient liquidity"
+    ));
+
+    client.process_message(&book_snapshot()).await;
+
+    let resp = client
+        .add_order(&limit_order(BuySell::Sell, dec!(1), dec!(50000)))
+        .await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::InsufficientFunds(_)))
+    ));
+
+    let post_only = AddOrderRequest::builder(
+        OrderType::Limit,
+        BuySell::Buy,
+        dec!(0.1),
+        "XBTUSD".to_string(),
+    )
+    .price(dec!(50000))
+    .order_flags(OrderFlag::Post.into())
+    .build();
+    let resp = client.add_order(&post_only).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::PostOnlyRejected(_)))
+    ));
+
+    let resp = client
+        .get_ledgers_info(&LedgersInfoRequest::builder().build())
+        .await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::PaperTrading("get_ledgers_info"))
+    ));
+
+    mock_server.verify().await;
+}
@@ -246,7 +246,7 @@ async fn test_get_asset_info_error() {
     assert!(resp.is_err());
     assert!(matches!(
         resp,
-        Err(ClientError::Kraken(KrakenError::UnknownAssetPair))
+        Err(ClientError::Kraken(KrakenError::UnknownAssetPair(_)))
     ));
 }
 
@@ -268,7 +268,7 @@ async fn test_get_account_balance_error() {
     assert!(resp.is_err());
     assert!(matches!(
         resp,
-        Err(ClientError::Kraken(KrakenError::InvalidKey))
+        Err(ClientError::Kraken(KrakenError::InvalidKey(_)))
     ));
 }
 
@@ -296,7 +296,7 @@ async fn test_cancel_order_batch_error() {
     assert!(resp.is_err());
     assert!(matches!(
         resp,
-        Err(ClientError::Kraken(KrakenError::PermissionDenied))
+        Err(ClientError::Kraken(KrakenError::PermissionDenied(_)))
     ));
 }
 
@@ -0,0 +1,198 @@
+mod resources;
+
+use crate::resources::kraken_responses::account_response_json::{
+    get_account_balance_json, get_closed_orders_json,
+};
+use crate::resources::kraken_responses::public_response_json::get_ticker_information_json;
+use crate::resources::kraken_responses::websockets_json::get_websockets_token_json;
+use crate::resources::test_auth::get_null_secrets_provider;
+use kraken_async_rs::clients::errors::ClientError;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::clients::recording_kraken_client::{
+    Cassette, RecordingKrakenClient, ReplayKrakenClient, REDACTED,
+};
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::request_types::{ClosedOrdersRequest, StringCSV, TickerRequest};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn get_nonce_provider() -> Box<Arc<Mutex<dyn NonceProvider>>> {
+    Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())))
+}
+
+fn get_recording_client(mock_server: &MockServer) -> RecordingKrakenClient {
+    RecordingKrakenClient::new_with_url(
+        get_null_secrets_provider(),
+        get_nonce_provider(),
+        mock_server.uri(),
+    )
+}
+
+fn get_replay_client(cassette: Cassette) -> ReplayKrakenClient {
+    ReplayKrakenClient::new_with_cassette(
+        get_null_secrets_provider(),
+        get_nonce_provider(),
+        cassette,
+    )
+}
+
+fn get_cassette_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "kraken-cassette-{}-{}.json",
+        std::process::id(),
+        name
+    ))
+}
+
+fn get_ticker_request() -> TickerRequest {
+    TickerRequest::builder()
+        .pair(StringCSV::new(vec!["BTCUSD".to_string()]))
+        .build()
+}
+
+#[tokio::test]
+async fn test_record_and_replay_from_file() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/Ticker"))
+        .and(query_param("pair", "BTCUSD"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_ticker_information_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_account_balance_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut recording_client = get_recording_client(&mock_server);
+    let recorded_ticker = recording_client
+        .get_ticker_information(&get_ticker_request())
+        .await
+        .unwrap();
+    let recorded_balance = recording_client.get_account_balance().await.unwrap();
+    mock_server.verify().await;
+
+    let cassette_path = get_cassette_path("record-and-replay");
+    recording_client.save(&cassette_path).await.unwrap();
+    drop(mock_server);
+
+    let mut replay_client = ReplayKrakenClient::from_file(
+        get_null_secrets_provider(),
+        get_nonce_provider(),
+        &cassette_path,
+    )
+    .unwrap();
+    std::fs::remove_file(&cassette_path).unwrap();
+
+    let replayed_ticker = replay_client
+        .get_ticker_information(&get_ticker_request())
+        .await
+        .unwrap();
+    let replayed_balance = replay_client.get_account_balance().await.unwrap();
+
+    assert_eq!(recorded_ticker.result, replayed_ticker.result);
+    assert_eq!(recorded_balance.result, replayed_balance.result);
+}
+
+#[tokio::test]
+async fn test_recording_redacts_secrets_and_nonces() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/GetWebSocketsToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_websockets_token_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut client = get_recording_client(&mock_server);
+    let token = client.get_websockets_token().await.unwrap();
+    let token = serde_json::to_value(token.result.unwrap().token).unwrap();
+    assert_ne!(json!(REDACTED), token);
+
+    let cassette = client.cassette().await;
+    let serialized = serde_json::to_string(&cassette).unwrap();
+
+    assert_eq!(1, cassette.interactions.len());
+    assert!(cassette.interactions[0].params.is_empty());
+    assert!(!serialized.contains("nonce"));
+    assert!(!serialized.contains("nmc39wCfFqn0mirRrpHMFOu0xfq4VVghFy"));
+
+    let mut replay_client = get_replay_client(cassette);
+    let replayed = replay_client.get_websockets_token().await.unwrap();
+    let replayed = serde_json::to_value(replayed.result.unwrap().token).unwrap();
+    assert_eq!(json!(REDACTED), replayed);
+}
+
+#[tokio::test]
+async fn test_replay_matches_params_in_order() {
+    let mock_server = MockServer::start().await;
+
+    let mut first_page = get_closed_orders_json();
+    first_page["result"]["count"] = json!(10);
+    let mut second_page = get_closed_orders_json();
+    second_page["result"]["count"] = json!(20);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ClosedOrders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(first_page))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/ClosedOrders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(second_page))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = ClosedOrdersRequest::builder().trades(true).build();
+
+    let mut client = get_recording_client(&mock_server);
+    client.get_closed_orders(&request).await.unwrap();
+    client.get_closed_orders(&request).await.unwrap();
+    mock_server.verify().await;
+
+    let mut replay_client = get_replay_client(client.cassette().await);
+
+    let counts = [
+        replay_client.get_closed_orders(&request).await,
+        replay_client.get_closed_orders(&request).await,
+        replay_client.get_closed_orders(&request).await,
+    ]
+    .map(|resp| resp.unwrap().result.unwrap().count);
+
+    assert_eq!([10, 20, 20], counts);
+
+    let unmatched = ClosedOrdersRequest::builder().trades(false).build();
+    let resp = replay_client.get_closed_orders(&unmatched).await;
+
+    assert!(matches!(
+        resp,
+        Err(ClientError::Replay(message))
+            if message.contains("/0/private/ClosedOrders") && message.contains("\"trades\": \"false\"")
+    ));
+}
+
+#[tokio::test]
+async fn test_replay_without_match_fails() {
+    let mut client = get_replay_client(Cassette::default());
+
+    let resp = client.get_server_time().await;
+
+    assert!(matches!(
+        resp,
+        Err(ClientError::Replay(message)) if message == "No recorded response for GET /0/public/Time with params {}"
+    ));
+}
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_account_balance_json() -> Value {
     json!({
         "error": [],
@@ -35,6 +36,7 @@ pub fn get_account_balance_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_extended_balance_json() -> Value {
     json!({
         "error":[],
@@ -155,6 +157,7 @@ pub fn get_extended_balance_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_trade_balance_json() -> Value {
     json!({
         "error":[],
@@ -172,6 +175,7 @@ pub fn get_trade_balance_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_open_orders_json() -> Value {
     json!({
         "error":[],
@@ -349,6 +353,7 @@ pub fn get_open_orders_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_closed_orders_json() -> Value {
     json!({
         "error": [ ],
@@ -402,6 +407,7 @@ pub fn get_closed_orders_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_query_order_info_json() -> Value {
     json!({
         "error": [],
@@ -439,6 +445,7 @@ pub fn get_query_order_info_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_trades_history_json() -> Value {
     json!({
         "error": [],
@@ -537,6 +544,7 @@ pub fn get_trades_history_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_query_trades_info_json() -> Value {
     json!({
         "error":[],
@@ -563,6 +571,7 @@ pub fn get_query_trades_info_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_open_positions_json() -> Value {
     json!({
         "error": [ ],
@@ -667,6 +676,7 @@ pub fn get_open_positions_json() -> Value {
         }
     })
 }
+#[allow(dead_code)]
 pub fn get_open_positions_json_do_calc_optional_fields() -> Value {
     json!({
         "error": [ ],
@@ -692,6 +702,7 @@ pub fn get_open_positions_json_do_calc_optional_fields() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_ledgers_info_json() -> Value {
     json!({
         "error": [],
@@ -824,6 +835,7 @@ pub fn get_ledgers_info_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_query_ledgers_json() -> Value {
     json!({
         "error": [],
@@ -843,6 +855,7 @@ pub fn get_query_ledgers_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_trade_volume_json() -> Value {
     json!({
         "error": [],
@@ -855,6 +868,7 @@ pub fn get_trade_volume_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_trade_volume_per_pair_json() -> Value {
     json!({
         "error":[],
@@ -885,6 +899,7 @@ pub fn get_trade_volume_per_pair_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_request_export_report_json() -> Value {
     json!({
         "error": [],
@@ -892,10 +907,12 @@ pub fn get_request_export_report_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_export_report_response() -> Vec<u8> {
     vec![0, 1, 0, 1]
 }
 
+#[allow(dead_code)]
 pub fn get_export_report_status_json() -> Value {
     json!({
         "error": [],
@@ -923,6 +940,7 @@ pub fn get_export_report_status_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_delete_export_report_json() -> Value {
     json!({"error": [], "result": {"delete": true}})
 }
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_allocate_earn_funds_json() -> Value {
     json!({
         "error": [],
@@ -7,6 +8,7 @@ pub fn get_allocate_earn_funds_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_deallocate_earn_funds_json() -> Value {
     json!({
         "error": [],
@@ -14,6 +16,7 @@ pub fn get_deallocate_earn_funds_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_allocation_status_json() -> Value {
     json!({
         "error": [],
@@ -21,6 +24,7 @@ pub fn get_allocation_status_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_deallocation_status_json() -> Value {
     json!({
         "error": [],
@@ -28,6 +32,7 @@ pub fn get_deallocation_status_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_list_earn_strategies_json() -> Value {
     json!({
         "error":
@@ -72,6 +77,7 @@ pub fn get_list_earn_strategies_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_list_earn_allocations_json() -> Value {
     json!({
         "error": [],
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_deposit_methods_json() -> Value {
     json!({
         "error":[],
@@ -50,6 +51,7 @@ pub fn get_deposit_methods_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_deposit_addresses_json() -> Value {
     json!({
         "error":[],
@@ -59,6 +61,7 @@ pub fn get_deposit_addresses_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_status_of_recent_deposits_json() -> Value {
     json!({
         "error":[],
@@ -79,6 +82,7 @@ pub fn get_status_of_recent_deposits_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_withdrawal_methods_json() -> Value {
     json!({
         "error":[],
@@ -129,6 +133,7 @@ pub fn get_withdrawal_methods_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_withdrawal_addresses_json() -> Value {
     json!({
         "error":[],
@@ -141,6 +146,7 @@ pub fn get_withdrawal_addresses_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_withdrawal_info_json() -> Value {
     json!({
         "error": [ ],
@@ -153,6 +159,7 @@ pub fn get_withdrawal_info_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_withdraw_funds_json() -> Value {
     json!({
         "error": [],
@@ -162,6 +169,7 @@ pub fn get_withdraw_funds_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_status_of_recent_withdrawals_json() -> Value {
     json!({
       "error": [],
@@ -197,6 +205,7 @@ pub fn get_status_of_recent_withdrawals_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_request_withdrawal_cancellation_json() -> Value {
     json!({
         "error": [],
@@ -204,6 +213,7 @@ pub fn get_request_withdrawal_cancellation_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_request_wallet_transfer_json() -> Value {
     json!({
         "error": [],
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_closed_orders_error_json() -> Value {
     json!({
         "result": null,
@@ -7,6 +8,7 @@ pub fn get_closed_orders_error_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_closed_orders_page_1_json() -> Value {
     serde_json::from_str(
         r#"{
@@ -1071,6 +1073,7 @@ pub fn get_closed_orders_page_1_json() -> Value {
     .unwrap()
 }
 
+#[allow(dead_code)]
 pub fn get_closed_orders_page_2_json() -> Value {
     serde_json::from_str(
         r#"{
@@ -2135,6 +2138,7 @@ pub fn get_closed_orders_page_2_json() -> Value {
     .unwrap()
 }
 
+#[allow(dead_code)]
 pub fn get_closed_orders_page_3_json() -> Value {
     serde_json::from_str(
         r#"{
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_server_time_json() -> Value {
     json!({
         "error": [],
@@ -11,6 +12,7 @@ pub fn get_server_time_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_system_status_json() -> Value {
     json!({
         "error":[],
@@ -18,6 +20,7 @@ pub fn get_system_status_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_asset_info_json() -> Value {
     json!({
       "error": [],
@@ -50,6 +53,7 @@ pub fn get_asset_info_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_tradable_asset_pairs_json() -> Value {
     json!({
         "error":[],
@@ -136,6 +140,7 @@ pub fn get_tradable_asset_pairs_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_ticker_information_json() -> Value {
     json!({
         "error":[],
@@ -177,6 +182,7 @@ pub fn get_ticker_information_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_ohlc_data_json() -> Value {
     json!({
         "error":[],
@@ -206,6 +212,7 @@ pub fn get_ohlc_data_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_orderbook_json() -> Value {
     json!({
         "error":[],
@@ -261,6 +268,7 @@ pub fn get_orderbook_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_recent_trades_json() -> Value {
     json!({
         "error":[],
@@ -303,6 +311,7 @@ pub fn get_recent_trades_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_recent_spreads_json() -> Value {
     json!({
         "error": [],
@@ -1,11 +1,13 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_create_sub_account_json() -> Value {
     json!({
         "error": [],
         "result": true
     })
 }
+#[allow(dead_code)]
 pub fn get_account_transfer_json() -> Value {
     json!({
         "error": [],
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_add_order_json() -> Value {
     json!({
         "error": [],
@@ -12,6 +13,7 @@ pub fn get_add_order_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_add_order_batch_json() -> Value {
     json!({
         "error": [],
@@ -31,6 +33,7 @@ pub fn get_add_order_batch_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_edit_order_json() -> Value {
     json!({
         "error":[],
@@ -47,6 +50,7 @@ pub fn get_edit_order_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_cancel_order_json() -> Value {
     json!({
         "error": [],
@@ -56,6 +60,7 @@ pub fn get_cancel_order_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_cancel_all_orders_json() -> Value {
     json!({
         "error": [],
@@ -65,6 +70,7 @@ pub fn get_cancel_all_orders_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_cancel_all_orders_after_json() -> Value {
     json!({
         "error": [],
@@ -75,6 +81,7 @@ pub fn get_cancel_all_orders_after_json() -> Value {
     })
 }
 
+#[allow(dead_code)]
 pub fn get_cancel_order_batch_json() -> Value {
     json!({
         "error": [],
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 
+#[allow(dead_code)]
 pub fn get_websockets_token_json() -> Value {
     json!({
         "result":{
@@ -1,6 +1,7 @@
 pub mod kraken_responses;
 pub mod test_auth;
 pub mod test_client;
+pub mod test_fixtures;
 pub mod test_logging;
 pub mod test_macros;
 pub mod test_serde;
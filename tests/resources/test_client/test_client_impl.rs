@@ -33,6 +33,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+#[allow(dead_code)]
 pub fn get_rate_limit_test_client(verification: VerificationTier) -> TestRateLimitedClient {
     let secrets_provider = get_null_secrets_provider();
     let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
@@ -43,13 +44,17 @@ pub fn get_rate_limit_test_client(verification: VerificationTier) -> TestRateLim
     RateLimitedKrakenClient::new_with_client(client, verification)
 }
 
+#[allow(dead_code)]
 pub type TestRateLimitedClient = RateLimitedKrakenClient<TestClient>;
 
+#[derive(Clone)]
+#[allow(dead_code)]
 pub struct TestClient {
     current_id: u64,
 }
 
 impl TestClient {
+    #[allow(dead_code)]
     fn get_unique_id(&mut self) -> String {
         self.current_id += 1;
         self.current_id.to_string()
@@ -311,7 +316,7 @@ impl KrakenClient for TestClient {
 
     async fn cancel_order(
         &mut self,
-        request: &CancelOrderRequest,
+        _request: &CancelOrderRequest,
     ) -> Result<ResultErrorResponse<CancelOrder>, ClientError> {
         Ok(ResultErrorResponse {
             result: Some(CancelOrder {
@@ -33,6 +33,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+#[allow(dead_code)]
 pub fn get_rate_limit_test_client_err(verification: VerificationTier) -> TestRateLimitedClientErr {
     let secrets_provider = get_null_secrets_provider();
     let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
@@ -46,8 +47,10 @@ pub fn get_rate_limit_test_client_err(verification: VerificationTier) -> TestRat
     )
 }
 
+#[allow(dead_code)]
 pub type TestRateLimitedClientErr = RateLimitedKrakenClient<TestClientErr>;
 
+#[allow(dead_code)]
 pub struct TestClientErr {}
 
 impl KrakenClient for TestClientErr {
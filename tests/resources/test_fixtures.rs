@@ -0,0 +1,31 @@
+use crate::resources::test_auth::get_null_secrets_provider;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::request_types::AddOrderRequest;
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use wiremock::MockServer;
+
+#[allow(dead_code)]
+pub fn get_increasing_nonce_provider() -> Box<Arc<Mutex<dyn NonceProvider>>> {
+    Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())))
+}
+
+/// Any [KrakenClient] pointed at the mock server, with null secrets and increasing nonces.
+#[allow(dead_code)]
+pub fn get_mock_client<C: KrakenClient>(mock_server: &MockServer) -> C {
+    C::new_with_url(
+        get_null_secrets_provider(),
+        get_increasing_nonce_provider(),
+        mock_server.uri(),
+    )
+}
+
+#[allow(dead_code)]
+pub fn limit_order(side: BuySell, volume: Decimal, price: Decimal) -> AddOrderRequest {
+    AddOrderRequest::builder(OrderType::Limit, side, volume, "XBTUSD".to_string())
+        .price(price)
+        .build()
+}
@@ -3,6 +3,7 @@ use std::fs::File;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{fmt, Registry};
 
+#[allow(dead_code)]
 pub fn set_up(filename: &str) {
     let subscriber = Registry::default()
         .with(
@@ -15,6 +16,7 @@ pub fn set_up(filename: &str) {
     tracing::subscriber::set_global_default(subscriber).unwrap();
 }
 
+#[allow(dead_code)]
 fn get_log_file(filename: &str) -> File {
     File::options()
         .append(true)
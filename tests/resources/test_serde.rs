@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use std::fmt::{Debug, Display};
 
+#[allow(dead_code)]
 pub fn test_display_output<T>(variant: T, expected: &str)
 where
     T: Display,
@@ -8,6 +9,7 @@ where
     assert_eq!(expected, variant.to_string());
 }
 
+#[allow(dead_code)]
 pub fn test_deserializing_expecting_error<'de, T>(data: &'de str, expected_error: &str)
 where
     T: Debug + Deserialize<'de>,
@@ -0,0 +1,278 @@
+mod resources;
+
+use crate::resources::kraken_responses::public_response_json::get_server_time_json;
+use crate::resources::kraken_responses::trading_response_json::{
+    get_add_order_batch_json, get_add_order_json,
+};
+use crate::resources::test_auth::get_null_secrets_provider;
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::{ClientError, KrakenError};
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::clients::retrying_kraken_client::{RetryPolicy, RetryingKrakenClient};
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::request_types::{
+    AddBatchedOrderRequest, AddOrderRequest, BatchedOrderRequest,
+};
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn get_retrying_client(
+    mock_server: &MockServer,
+    retry_policy: RetryPolicy,
+) -> RetryingKrakenClient<CoreKrakenClient> {
+    let secrets_provider = get_null_secrets_provider();
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    let client =
+        CoreKrakenClient::new_with_url(secrets_provider, nonce_provider, mock_server.uri());
+
+    RetryingKrakenClient::new_with_client(client, retry_policy)
+}
+
+fn get_fast_retry_policy() -> RetryPolicy {
+    RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(5))
+}
+
+fn get_add_order_request(user_ref: Option<i64>) -> AddOrderRequest {
+    let mut request =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5.0), "USDCUSD".into())
+            .price(dec!(0.90))
+            .build();
+    request.user_ref = user_ref;
+    request
+}
+
+fn get_batched_order_request(cl_ord_ids: &[Option<&str>]) -> AddBatchedOrderRequest {
+    let orders = cl_ord_ids
+        .iter()
+        .map(|cl_ord_id| {
+            let mut order = BatchedOrderRequest::builder(OrderType::Limit, BuySell::Buy, dec!(5.0))
+                .price(dec!(0.90))
+                .build();
+            order.cl_ord_id = cl_ord_id.map(str::to_string);
+            order
+        })
+        .collect();
+
+    AddBatchedOrderRequest::builder(orders, "USDCUSD".to_string()).build()
+}
+
+/// Mount a 502 for the first call to `endpoint`, and `success` for any after it.
+async fn mount_server_error_then(mock_server: &MockServer, endpoint: &str, success: Value) {
+    Mock::given(method("POST"))
+        .and(path(endpoint))
+        .respond_with(ResponseTemplate::new(502))
+        .up_to_n_times(1)
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path(endpoint))
+        .respond_with(ResponseTemplate::new(200).set_body_json(success))
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_retries_server_errors_until_success() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/0/public/Time"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_server_time_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.get_server_time().await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
+
+#[tokio::test]
+async fn test_retries_kraken_errors_until_exhausted() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EAPI:Rate limit exceeded"]})),
+        )
+        .expect(4)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.get_account_balance().await;
+
+    mock_server.verify().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::RateLimitExceeded(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_does_not_retry_permanent_errors() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/Balance"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EGeneral:Permission denied"]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.get_account_balance().await;
+
+    mock_server.verify().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::PermissionDenied(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_add_order_is_not_retried_after_server_error() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(502))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // a user ref doesn't make the order safe to resend, since the first attempt may have placed it
+    let resp = client.add_order(&get_add_order_request(Some(42))).await;
+
+    mock_server.verify().await;
+    assert!(matches!(resp, Err(ClientError::HttpStatus(502, _))));
+}
+
+#[tokio::test]
+async fn test_add_order_is_retried_when_rejected() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"error": ["EAPI:Rate limit exceeded"]})),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.add_order(&get_add_order_request(None)).await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
+
+#[tokio::test]
+async fn test_add_order_with_cl_ord_id_is_retried_after_server_error() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+    mount_server_error_then(&mock_server, "/0/private/AddOrder", get_add_order_json()).await;
+
+    let mut request = get_add_order_request(None);
+    request.cl_ord_id = Some("order-1".to_string());
+    let resp = client.add_order(&request).await;
+
+    assert!(resp.unwrap().result.is_some());
+    assert_eq!(2, mock_server.received_requests().await.unwrap().len());
+}
+
+#[tokio::test]
+async fn test_add_order_batch_is_retried_only_when_every_order_has_cl_ord_id() {
+    let mock_server = MockServer::start().await;
+    let mut client = get_retrying_client(&mock_server, get_fast_retry_policy());
+    mount_server_error_then(
+        &mock_server,
+        "/0/private/AddOrderBatch",
+        get_add_order_batch_json(),
+    )
+    .await;
+
+    let partial = get_batched_order_request(&[Some("order-1"), None]);
+    let resp = client.add_order_batch(&partial).await;
+
+    assert!(matches!(resp, Err(ClientError::HttpStatus(502, _))));
+    assert_eq!(1, mock_server.received_requests().await.unwrap().len());
+
+    mock_server.reset().await;
+    mount_server_error_then(
+        &mock_server,
+        "/0/private/AddOrderBatch",
+        get_add_order_batch_json(),
+    )
+    .await;
+
+    let complete = get_batched_order_request(&[Some("order-1"), Some("order-2")]);
+    let resp = client.add_order_batch(&complete).await;
+
+    assert!(resp.unwrap().result.is_some());
+    assert_eq!(2, mock_server.received_requests().await.unwrap().len());
+}
+
+#[tokio::test]
+async fn test_add_order_retried_when_opted_in() {
+    let mock_server = MockServer::start().await;
+    let retry_policy = RetryPolicy {
+        retry_non_idempotent: true,
+        ..get_fast_retry_policy()
+    };
+    let mut client = get_retrying_client(&mock_server, retry_policy);
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(502))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/0/private/AddOrder"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(get_add_order_json()))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.add_order(&get_add_order_request(None)).await;
+
+    mock_server.verify().await;
+    assert!(resp.unwrap().result.is_some());
+}
@@ -0,0 +1,275 @@
+mod resources;
+
+use crate::resources::test_fixtures::{get_increasing_nonce_provider, limit_order};
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::errors::{ClientError, KrakenError};
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::crypto::nonce_provider::NonceProvider;
+use kraken_async_rs::request_types::{
+    AddOrderRequest, CancelOrderRequest, ClosedOrdersRequest, EditOrderRequest, IntOrString,
+    OpenOrdersRequest, OrderFlags,
+};
+use kraken_async_rs::response_types::{BuySell, OrderFlag, OrderStatus, OrderType};
+use kraken_async_rs::secrets::secrets_provider::{SecretsProvider, StaticSecretsProvider};
+use kraken_async_rs::simulated_exchange::matching_engine::SimulatedPair;
+use kraken_async_rs::simulated_exchange::server::SimulatedExchange;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const MAKER_KEY: &str = "maker-key";
+const MAKER_SECRET: &str = "bWFrZXItc2VjcmV0";
+const TAKER_KEY: &str = "taker-key";
+const TAKER_SECRET: &str = "dGFrZXItc2VjcmV0";
+
+/// Always returns the same nonce, to test that reused nonces are rejected.
+#[derive(Debug)]
+struct FixedNonceProvider(u64);
+
+impl NonceProvider for FixedNonceProvider {
+    fn get_nonce(&mut self) -> u64 {
+        self.0
+    }
+}
+
+async fn get_exchange() -> SimulatedExchange {
+    let pair = SimulatedPair::new("XBTUSD", "XXBT", "ZUSD").fees(dec!(0.25), dec!(0.40));
+    let exchange = SimulatedExchange::start(vec![pair]).await.unwrap();
+
+    for (key, secret) in [(MAKER_KEY, MAKER_SECRET), (TAKER_KEY, TAKER_SECRET)] {
+        let balances = HashMap::from([
+            ("XXBT".to_string(), dec!(2)),
+            ("ZUSD".to_string(), dec!(100000)),
+        ]);
+        exchange.add_account(key, secret, balances).await;
+    }
+
+    exchange
+}
+
+fn get_client(
+    exchange: &SimulatedExchange,
+    key: &'static str,
+    secret: &'static str,
+    nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>>,
+) -> CoreKrakenClient {
+    let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> = Box::new(Arc::new(Mutex::new(
+        StaticSecretsProvider::new(key, secret),
+    )));
+    CoreKrakenClient::new_with_url(secrets_provider, nonce_provider, exchange.uri())
+}
+
+#[tokio::test]
+async fn test_trading_workflow() {
+    let exchange = get_exchange().await;
+    let nonces = get_increasing_nonce_provider();
+    let mut maker = get_client(&exchange, MAKER_KEY, MAKER_SECRET, nonces.clone());
+    let mut taker = get_client(&exchange, TAKER_KEY, TAKER_SECRET, nonces);
+
+    let resting = maker
+        .add_order(&limit_order(BuySell::Sell, dec!(1), dec!(50000)))
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(1, resting.tx_id.len());
+    assert_eq!("sell 1.00000000 XBTUSD @ limit 50000", resting.descr.order);
+
+    let market_buy =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(0.4), "XBTUSD".into())
+            .user_ref(42)
+            .build();
+    let taker_order = taker.add_order(&market_buy).await.unwrap().result.unwrap();
+
+    let open = maker
+        .get_open_orders(&OpenOrdersRequest::builder().trades(true).build())
+        .await
+        .unwrap()
+        .result
+        .unwrap()
+        .open;
+    let maker_order = &open[&resting.tx_id[0]];
+    assert_eq!(OrderStatus::Open, maker_order.status);
+    assert_eq!(dec!(0.4), maker_order.volume_executed);
+    assert_eq!(1, maker_order.trades.as_ref().unwrap().len());
+
+    let closed = taker
+        .get_closed_orders(&ClosedOrdersRequest::builder().trades(true).build())
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    let filled = &closed.closed[&taker_order.tx_id[0]];
+    assert_eq!(1, closed.count);
+    assert_eq!(OrderStatus::Closed, filled.status);
+    assert_eq!(Some(42), filled.userref);
+    assert_eq!(dec!(50000), filled.price);
+    assert_eq!(dec!(20000), filled.cost);
+    assert_eq!(dec!(80), filled.fee);
+    assert_eq!(
+        maker_order.trades.as_ref().unwrap(),
+        filled.trades.as_ref().unwrap()
+    );
+
+    let taker_balances = taker.get_account_balance().await.unwrap().result.unwrap();
+    assert_eq!(dec!(2.4), taker_balances["XXBT"]);
+    assert_eq!(dec!(79920), taker_balances["ZUSD"]);
+
+    // sells pay fees in the base currency by default
+    let maker_balances = exchange.balances(MAKER_KEY).await.unwrap();
+    assert_eq!(dec!(1.599), maker_balances["XXBT"]);
+    assert_eq!(dec!(120000), maker_balances["ZUSD"]);
+
+    let edit = EditOrderRequest::builder(resting.tx_id[0].clone(), dec!(0.8), "XBTUSD".into())
+        .price(dec!(51000))
+        .build();
+    let edited = maker.edit_order(&edit).await.unwrap().result.unwrap();
+    assert_eq!(resting.tx_id[0], edited.original_tx_id);
+    assert_eq!(dec!(0.4), edited.volume);
+    assert_eq!(dec!(51000), edited.price);
+
+    let cancel = CancelOrderRequest::builder(IntOrString::String(edited.tx_id.clone())).build();
+    let cancelled = maker.cancel_order(&cancel).await.unwrap().result.unwrap();
+    assert_eq!(1, cancelled.count);
+
+    let open = maker
+        .get_open_orders(&OpenOrdersRequest::builder().build())
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert!(open.open.is_empty());
+
+    let resp = maker.cancel_order(&cancel).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::UnknownOrder(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_fee_currency_order_flags() {
+    let exchange = get_exchange().await;
+    let nonces = get_increasing_nonce_provider();
+    let mut maker = get_client(&exchange, MAKER_KEY, MAKER_SECRET, nonces.clone());
+    let mut taker = get_client(&exchange, TAKER_KEY, TAKER_SECRET, nonces);
+
+    let sell_fees_in_quote =
+        AddOrderRequest::builder(OrderType::Limit, BuySell::Sell, dec!(1), "XBTUSD".into())
+            .price(dec!(50000))
+            .order_flags(OrderFlags::new(vec![OrderFlag::FeesInQuote]))
+            .build();
+    maker.add_order(&sell_fees_in_quote).await.unwrap();
+
+    let buy_fees_in_base =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(1), "XBTUSD".into())
+            .order_flags(OrderFlags::new(vec![OrderFlag::FeesInBase]))
+            .build();
+    taker.add_order(&buy_fees_in_base).await.unwrap();
+
+    let maker_balances = exchange.balances(MAKER_KEY).await.unwrap();
+    assert_eq!(dec!(1), maker_balances["XXBT"]);
+    assert_eq!(dec!(149875), maker_balances["ZUSD"]);
+
+    let taker_balances = exchange.balances(TAKER_KEY).await.unwrap();
+    assert_eq!(dec!(2.996), taker_balances["XXBT"]);
+    assert_eq!(dec!(50000), taker_balances["ZUSD"]);
+}
+
+#[tokio::test]
+async fn test_order_rejections() {
+    let exchange = get_exchange().await;
+    let nonces = get_increasing_nonce_provider();
+    let mut maker = get_client(&exchange, MAKER_KEY, MAKER_SECRET, nonces.clone());
+    let mut taker = get_client(&exchange, TAKER_KEY, TAKER_SECRET, nonces);
+
+    let resp = taker
+        .add_order(&limit_order(BuySell::Sell, dec!(3), dec!(50000)))
+        .await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::InsufficientFunds(_)))
+    ));
+
+    maker
+        .add_order(&limit_order(BuySell::Sell, dec!(1), dec!(50000)))
+        .await
+        .unwrap();
+
+    let post_only = AddOrderRequest::builder(
+        OrderType::Limit,
+        BuySell::Buy,
+        dec!(1),
+        "XBTUSD".to_string(),
+    )
+    .price(dec!(50000))
+    .order_flags(OrderFlag::Post.into())
+    .build();
+    let resp = taker.add_order(&post_only).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::PostOnlyRejected(_)))
+    ));
+
+    let validated = AddOrderRequest::builder(
+        OrderType::Limit,
+        BuySell::Buy,
+        dec!(1),
+        "XBTUSD".to_string(),
+    )
+    .price(dec!(50000))
+    .validate(true)
+    .build();
+    let resp = taker.add_order(&validated).await.unwrap().result.unwrap();
+    assert!(resp.tx_id.is_empty());
+    assert_eq!(dec!(2), exchange.balances(TAKER_KEY).await.unwrap()["XXBT"]);
+
+    let unknown_pair =
+        AddOrderRequest::builder(OrderType::Market, BuySell::Buy, dec!(1), "ETHUSD".into()).build();
+    let resp = taker.add_order(&unknown_pair).await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::UnknownAssetPair(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_authentication() {
+    let exchange = get_exchange().await;
+
+    let mut wrong_secret = get_client(
+        &exchange,
+        MAKER_KEY,
+        TAKER_SECRET,
+        get_increasing_nonce_provider(),
+    );
+    let resp = wrong_secret.get_account_balance().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::InvalidSignature(_)))
+    ));
+
+    let mut unknown_key = get_client(
+        &exchange,
+        "unknown",
+        MAKER_SECRET,
+        get_increasing_nonce_provider(),
+    );
+    let resp = unknown_key.get_account_balance().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::InvalidKey(_)))
+    ));
+
+    let fixed_nonce: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(FixedNonceProvider(1))));
+    let mut reused_nonce = get_client(&exchange, MAKER_KEY, MAKER_SECRET, fixed_nonce);
+
+    assert!(reused_nonce.get_account_balance().await.is_ok());
+    let resp = reused_nonce.get_account_balance().await;
+    assert!(matches!(
+        resp,
+        Err(ClientError::Kraken(KrakenError::InvalidNonce(_)))
+    ));
+}
@@ -9,6 +9,8 @@ use crate::resources::test_client::test_client_impl::{
 };
 use crate::resources::test_client::test_client_impl_err::get_rate_limit_test_client_err;
 use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::rate_limiting::token_bucket::{RateLimitBudget, RequestPriority};
+use kraken_async_rs::rate_limiting::trading_rate_limits::TradingCost;
 use kraken_async_rs::request_types::{
     AddBatchedOrderRequest, AddOrderRequest, BatchedOrderRequest, CancelBatchOrdersRequest,
     CancelOrderRequest, EditOrderRequest, IntOrString, OrderFlags,
@@ -16,7 +18,9 @@ use kraken_async_rs::request_types::{
 use kraken_async_rs::response_types::VerificationTier::{Intermediate, Pro};
 use kraken_async_rs::response_types::{AddOrder, BuySell, OrderFlag, OrderType, VerificationTier};
 use rust_decimal_macros::dec;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::task::yield_now;
 use tokio::time::{pause, Instant};
 
 #[tokio::test]
@@ -181,6 +185,143 @@ async fn test_cancel_order_batch_with_max_penalty() {
     assert!(edit_elapsed < Duration::from_secs(19));
 }
 
+#[tokio::test]
+async fn test_cost_preview_and_budget() {
+    pause();
+    let verification = Intermediate;
+    let mut client = get_rate_limit_test_client(verification);
+    let limiter = client.trading_rate_limiter();
+
+    let orders = max_out_rate_limits(&mut client, verification).await;
+    let order = orders.first().unwrap();
+
+    assert_eq!(
+        TradingCost {
+            pair: Some("USDCUSD".to_string()),
+            cost: 700,
+            penalty: 600,
+        },
+        limiter.edit_order_cost(&edit_from_order(order)).await
+    );
+    assert_eq!(
+        800,
+        limiter
+            .cancel_order_tx_id_cost(order.tx_id.first().unwrap())
+            .await
+            .cost
+    );
+    assert_eq!(
+        TradingCost {
+            pair: None,
+            cost: 0,
+            penalty: 0,
+        },
+        limiter.cancel_order_user_ref_cost(&42).await
+    );
+    assert_eq!(
+        900,
+        limiter
+            .add_order_batch_cost(&get_batched_order_request(16))
+            .cost
+    );
+
+    // all tokens were spent, so 800 tokens take 4 replenishments of 234
+    assert_eq!(
+        RateLimitBudget {
+            remaining_tokens: 0,
+            max_tokens: 12500,
+            time_until_affordable: Duration::from_secs(4),
+        },
+        client.trading_budget("USDCUSD", 800).await
+    );
+    assert_eq!(
+        12500,
+        client.trading_budget("XBTUSD", 800).await.remaining_tokens
+    );
+
+    // previews don't spend any tokens
+    tokio::time::advance(Duration::from_secs(2)).await;
+    assert_eq!(
+        468,
+        client.trading_budget("USDCUSD", 800).await.remaining_tokens
+    );
+    assert_eq!(
+        Duration::ZERO,
+        client.private_budget(100).await.time_until_affordable
+    );
+    assert_eq!(1, client.public_budget().await.remaining_tokens);
+}
+
+#[tokio::test]
+async fn test_cancels_served_before_waiting_orders() {
+    pause();
+    let verification = Intermediate;
+    let mut client = get_rate_limit_test_client(verification);
+    let served = Arc::new(Mutex::new(Vec::new()));
+    let start = Instant::now();
+
+    let orders = max_out_rate_limits(&mut client, verification).await;
+
+    let mut tasks = Vec::new();
+    for _ in 0..3 {
+        let mut client = client.clone();
+        let served = served.clone();
+        tasks.push(tokio::spawn(async move {
+            client.add_order(&get_add_order_request()).await.unwrap();
+            served.lock().unwrap().push("add");
+        }));
+        yield_now().await;
+    }
+
+    let mut cancel_client = client.clone();
+    let cancel_request = cancel_from_order(orders.first().unwrap());
+    let cancel_served = served.clone();
+    tasks.push(tokio::spawn(async move {
+        cancel_client.cancel_order(&cancel_request).await.unwrap();
+        cancel_served.lock().unwrap().push("cancel");
+    }));
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    // the cancel costs 800, taking 4s to replenish, but is still served before the orders that
+    // started waiting first
+    assert_eq!(vec!["cancel", "add", "add", "add"], *served.lock().unwrap());
+    assert!(start.elapsed() > Duration::from_secs(5));
+    assert!(start.elapsed() < Duration::from_secs(6));
+}
+
+#[tokio::test]
+async fn test_with_priority_overrides_endpoint_priority() {
+    pause();
+    let verification = Intermediate;
+    let mut client = get_rate_limit_test_client(verification);
+    let served = Arc::new(Mutex::new(Vec::new()));
+
+    max_out_rate_limits(&mut client, verification).await;
+
+    let mut tasks = Vec::new();
+    for (name, priority) in [
+        ("background", RequestPriority::Background),
+        ("critical", RequestPriority::Critical),
+    ] {
+        let mut client = client.with_priority(priority);
+        let served = served.clone();
+        tasks.push(tokio::spawn(async move {
+            client.add_order(&get_add_order_request()).await.unwrap();
+            served.lock().unwrap().push(name);
+        }));
+        yield_now().await;
+    }
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert_eq!(vec!["critical", "background"], *served.lock().unwrap());
+}
+
 /// Depending on the verification tier, submit enough orders to empty the rate limit bucket and
 /// return the created orders. Also checks that it has not exceeded the limits (executes in < 10ms).
 async fn max_out_rate_limits(
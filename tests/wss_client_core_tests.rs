@@ -1,3 +1,4 @@
+#![allow(deprecated)]
 mod resources;
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 mod admin_tests;
 mod error_tests;
 mod market_data_tests;
+mod rate_limited_tests;
 pub mod shared;
 mod subscription_tests;
 mod trading_tests;
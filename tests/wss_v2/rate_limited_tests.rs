@@ -0,0 +1,327 @@
+use crate::wss_v2::shared::WssTestState;
+use futures_util::StreamExt;
+use kraken_async_rs::crypto::secrets::Token;
+use kraken_async_rs::rate_limiting::trading_rate_limits::{KrakenTradingRateLimiter, TradingCost};
+use kraken_async_rs::response_types::VerificationTier::Intermediate;
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use kraken_async_rs::wss::v2::base_messages::{Message, WssMessage};
+use kraken_async_rs::wss::v2::rate_limited_message_stream::RateLimitedMessageStream;
+use kraken_async_rs::wss::v2::trading_messages::{
+    AddOrderParams, BatchOrder, BatchOrderParams, CancelOrderParams, EditOrderParams,
+};
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+use ws_mock::ws_mock_server::WsMock;
+
+struct RateLimitedTestState {
+    _test_state: WssTestState,
+    stream: RateLimitedMessageStream,
+    responses: mpsc::Sender<TungsteniteMessage>,
+}
+
+impl RateLimitedTestState {
+    async fn new(trading_rate_limiter: KrakenTradingRateLimiter) -> Self {
+        let mut test_state = WssTestState::new().await;
+        let (responses, mpsc_recv) = mpsc::channel::<TungsteniteMessage>(8);
+
+        WsMock::new()
+            .forward_from_channel(mpsc_recv)
+            .mount(&test_state.mock_server)
+            .await;
+
+        let stream = test_state.ws_client.connect::<WssMessage>().await.unwrap();
+
+        RateLimitedTestState {
+            _test_state: test_state,
+            stream: RateLimitedMessageStream::new(stream, trading_rate_limiter),
+            responses,
+        }
+    }
+
+    async fn respond(&mut self, response: &str) -> WssMessage {
+        self.responses
+            .send(TungsteniteMessage::Text(response.to_string()))
+            .await
+            .unwrap();
+
+        timeout(Duration::from_secs(1), self.stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap()
+    }
+}
+
+fn add_order_params(symbol: &str) -> AddOrderParams {
+    AddOrderParams {
+        order_type: OrderType::Limit,
+        side: BuySell::Buy,
+        symbol: symbol.to_string(),
+        limit_price: Some(dec!(0.95)),
+        limit_price_type: None,
+        triggers: None,
+        time_in_force: None,
+        order_quantity: dec!(5.0),
+        margin: None,
+        post_only: None,
+        reduce_only: None,
+        effective_time: None,
+        expire_time: None,
+        deadline: None,
+        order_user_ref: Some(7),
+        conditional: None,
+        display_quantity: None,
+        fee_preference: None,
+        no_market_price_protection: None,
+        stp_type: None,
+        cash_order_quantity: None,
+        validate: None,
+        token: Token::new("aToken".to_string()),
+        client_order_id: Some("my-order".to_string()),
+    }
+}
+
+fn batch_order() -> BatchOrder {
+    BatchOrder {
+        order_type: OrderType::Limit,
+        side: BuySell::Sell,
+        limit_price: Some(dec!(70000)),
+        limit_price_type: None,
+        triggers: None,
+        time_in_force: None,
+        order_quantity: dec!(0.001),
+        margin: None,
+        post_only: None,
+        reduce_only: None,
+        effective_time: None,
+        expire_time: None,
+        order_user_ref: None,
+        conditional: None,
+        display_quantity: None,
+        fee_preference: None,
+        no_market_price_protection: None,
+        stp_type: None,
+        cash_order_quantity: None,
+        client_order_id: None,
+    }
+}
+
+fn recent_cancel(pair: &str) -> TradingCost {
+    TradingCost {
+        pair: Some(pair.to_string()),
+        cost: 800,
+        penalty: 800,
+    }
+}
+
+#[tokio::test]
+async fn test_orders_recorded_from_add_order_responses() {
+    let trading_rate_limiter = KrakenTradingRateLimiter::new(Intermediate);
+    let mut test_state = RateLimitedTestState::new(trading_rate_limiter.clone()).await;
+
+    let message = Message {
+        method: "add_order".to_string(),
+        params: add_order_params("USDC/USD"),
+        req_id: 1,
+    };
+    test_state.stream.add_order(&message).await.unwrap();
+
+    let response = r#"{"method":"add_order","req_id":1,"result":{"order_id":"OPS23M-VS41G-DDE5Z2","order_userref":7,"cl_ord_id":"my-order"},"success":true,"time_in":"2024-05-18T12:05:50.293682Z","time_out":"2024-05-18T12:05:50.300542Z"}"#;
+    test_state.respond(response).await;
+
+    let budget = trading_rate_limiter.budget("USDC/USD", 0).await;
+    assert_eq!(12400, budget.remaining_tokens);
+
+    // the order can be cancelled by its order id, client order id or user ref
+    let order_id = "OPS23M-VS41G-DDE5Z2".to_string();
+    assert_eq!(
+        recent_cancel("USDC/USD"),
+        trading_rate_limiter
+            .cancel_order_tx_id_cost(&order_id)
+            .await
+    );
+    assert_eq!(
+        recent_cancel("USDC/USD"),
+        trading_rate_limiter
+            .cancel_order_tx_id_cost(&"my-order".to_string())
+            .await
+    );
+    assert_eq!(
+        recent_cancel("USDC/USD"),
+        trading_rate_limiter.cancel_order_user_ref_cost(&7).await
+    );
+
+    let cancel = Message {
+        method: "cancel_order".to_string(),
+        params: CancelOrderParams {
+            order_id: Some(vec![order_id]),
+            client_order_id: None,
+            order_user_ref: None,
+            token: Token::new("aToken".to_string()),
+        },
+        req_id: 2,
+    };
+    test_state.stream.cancel_order(&cancel).await.unwrap();
+
+    let budget = trading_rate_limiter.budget("USDC/USD", 0).await;
+    assert_eq!(11600, budget.remaining_tokens);
+}
+
+#[tokio::test]
+async fn test_batch_and_edit_responses_recorded() {
+    let trading_rate_limiter = KrakenTradingRateLimiter::new(Intermediate);
+    let mut test_state = RateLimitedTestState::new(trading_rate_limiter.clone()).await;
+
+    let batch = Message {
+        method: "batch_add".to_string(),
+        params: BatchOrderParams {
+            deadline: None,
+            symbol: "BTC/USD".to_string(),
+            validate: None,
+            token: Token::new("aToken".to_string()),
+            orders: vec![batch_order(), batch_order()],
+        },
+        req_id: 1,
+    };
+    test_state.stream.batch_add(&batch).await.unwrap();
+
+    let response = r#"{"method":"batch_add","req_id":1,"result":[{"order_id":"OIXKNI-DBZEL-KBW6RE"},{"order_id":"O4R2OT-JFMLE-2SX2PA"}],"success":true,"time_in":"2024-05-19T19:18:44.987402Z","time_out":"2024-05-19T19:18:44.989756Z"}"#;
+    test_state.respond(response).await;
+
+    // a batch of two costs 2, and an edit of a new order costs 1 plus a penalty of 6
+    let budget = trading_rate_limiter.budget("BTC/USD", 0).await;
+    assert_eq!(12300, budget.remaining_tokens);
+
+    let edit = Message {
+        method: "edit_order".to_string(),
+        params: EditOrderParams {
+            deadline: None,
+            display_quantity: None,
+            fee_preference: None,
+            limit_price: Some(dec!(71000)),
+            no_market_price_protection: None,
+            order_id: "OIXKNI-DBZEL-KBW6RE".to_string(),
+            order_quantity: None,
+            order_user_ref: None,
+            post_only: None,
+            reduce_only: None,
+            symbol: "BTC/USD".to_string(),
+            triggers: None,
+            validate: None,
+            token: Token::new("aToken".to_string()),
+        },
+        req_id: 2,
+    };
+    test_state.stream.edit_order(&edit).await.unwrap();
+
+    let response = r#"{"method":"edit_order","req_id":2,"result":{"order_id":"7FIK6B-S15X0-DPJJTH","original_order_id":"OIXKNI-DBZEL-KBW6RE"},"success":true,"time_in":"2024-05-19T12:12:30.171615Z","time_out":"2024-05-19T12:12:30.173877Z"}"#;
+    test_state.respond(response).await;
+
+    let budget = trading_rate_limiter.budget("BTC/USD", 0).await;
+    assert_eq!(11600, budget.remaining_tokens);
+
+    for order_id in ["O4R2OT-JFMLE-2SX2PA", "7FIK6B-S15X0-DPJJTH"] {
+        assert_eq!(
+            recent_cancel("BTC/USD"),
+            trading_rate_limiter
+                .cancel_order_tx_id_cost(&order_id.to_string())
+                .await
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_failed_orders_not_recorded() {
+    let trading_rate_limiter = KrakenTradingRateLimiter::new(Intermediate);
+    let mut test_state = RateLimitedTestState::new(trading_rate_limiter.clone()).await;
+
+    let message = Message {
+        method: "add_order".to_string(),
+        params: add_order_params("USDC/USD"),
+        req_id: 1,
+    };
+    test_state.stream.add_order(&message).await.unwrap();
+
+    let response = r#"{"error":"EOrder:Insufficient funds","method":"add_order","req_id":1,"success":false,"time_in":"2024-05-18T12:05:50.293682Z","time_out":"2024-05-18T12:05:50.300542Z"}"#;
+    test_state.respond(response).await;
+
+    assert_eq!(
+        TradingCost {
+            pair: None,
+            cost: 0,
+            penalty: 0,
+        },
+        trading_rate_limiter.cancel_order_user_ref_cost(&7).await
+    );
+}
+
+#[tokio::test]
+async fn test_reused_req_ids_matched_in_order() {
+    let trading_rate_limiter = KrakenTradingRateLimiter::new(Intermediate);
+    let mut test_state = RateLimitedTestState::new(trading_rate_limiter.clone()).await;
+
+    let first = Message {
+        method: "add_order".to_string(),
+        params: add_order_params("USDC/USD"),
+        req_id: 0,
+    };
+    let second = Message {
+        method: "add_order".to_string(),
+        params: AddOrderParams {
+            order_user_ref: Some(8),
+            client_order_id: None,
+            ..add_order_params("BTC/USD")
+        },
+        req_id: 0,
+    };
+    test_state.stream.add_order(&first).await.unwrap();
+    test_state.stream.add_order(&second).await.unwrap();
+
+    let response = r#"{"method":"add_order","req_id":0,"result":{"order_id":"OPS23M-VS41G-DDE5Z2","order_userref":7},"success":true,"time_in":"2024-05-18T12:05:50.293682Z","time_out":"2024-05-18T12:05:50.300542Z"}"#;
+    test_state.respond(response).await;
+    let response = r#"{"method":"add_order","req_id":0,"result":{"order_id":"OIXKNI-DBZEL-KBW6RE","order_userref":8},"success":true,"time_in":"2024-05-18T12:05:50.293682Z","time_out":"2024-05-18T12:05:50.300542Z"}"#;
+    test_state.respond(response).await;
+
+    assert_eq!(
+        recent_cancel("USDC/USD"),
+        trading_rate_limiter.cancel_order_user_ref_cost(&7).await
+    );
+    assert_eq!(
+        recent_cancel("BTC/USD"),
+        trading_rate_limiter.cancel_order_user_ref_cost(&8).await
+    );
+
+    // the edit's response doesn't include the user ref, so it's taken from the request
+    let edit = Message {
+        method: "edit_order".to_string(),
+        params: EditOrderParams {
+            deadline: None,
+            display_quantity: None,
+            fee_preference: None,
+            limit_price: Some(dec!(3500)),
+            no_market_price_protection: None,
+            order_id: "O3EX5H-NUHHO-IE7AOU".to_string(),
+            order_quantity: None,
+            order_user_ref: Some(9),
+            post_only: None,
+            reduce_only: None,
+            symbol: "ETH/USD".to_string(),
+            triggers: None,
+            validate: None,
+            token: Token::new("aToken".to_string()),
+        },
+        req_id: 0,
+    };
+    test_state.stream.edit_order(&edit).await.unwrap();
+
+    let response = r#"{"method":"edit_order","req_id":0,"result":{"order_id":"7FIK6B-S15X0-DPJJTH","original_order_id":"O3EX5H-NUHHO-IE7AOU"},"success":true,"time_in":"2024-05-19T12:12:30.171615Z","time_out":"2024-05-19T12:12:30.173877Z"}"#;
+    test_state.respond(response).await;
+
+    assert_eq!(
+        recent_cancel("ETH/USD"),
+        trading_rate_limiter.cancel_order_user_ref_cost(&9).await
+    );
+}
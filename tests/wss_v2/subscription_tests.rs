@@ -15,7 +15,7 @@ mod execution_subscription {
     };
 
     fn get_expected_execution_subscription() -> Value {
-        json!({"method":"subscribe","params":{"channel":"executions","token":"someToken","snapshot_trades":true,"snapshot":true},"req_id":0})
+        json!({"method":"subscribe","params":{"channel":"executions","token":"someToken","snapshot_trades":true,"ratecounter":true,"snapshot":true},"req_id":0})
     }
 
     fn get_execution_subscription_response() -> String {
@@ -49,6 +49,7 @@ mod execution_subscription {
         let mut execution_params = ExecutionSubscription::new(Token::new("someToken".to_string()));
         execution_params.snapshot = Some(true);
         execution_params.snapshot_trades = Some(true);
+        execution_params.rate_counter = Some(true);
 
         let subscription = Message::new_subscription(execution_params, 0);
 
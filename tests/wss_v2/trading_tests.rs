@@ -1,7 +1,9 @@
-use crate::wss_v2::shared::CallResponseTest;
+use crate::wss_v2::shared::{CallResponseTest, WssTestState};
+use futures_util::StreamExt;
 use kraken_async_rs::crypto::secrets::Token;
 use kraken_async_rs::request_types::TimeInForceV2;
 use kraken_async_rs::response_types::{BuySell, OrderType};
+use kraken_async_rs::wss::errors::WSSError;
 use kraken_async_rs::wss::v2::base_messages::MethodMessage::{AddOrder, CancelOrder, EditOrder};
 use kraken_async_rs::wss::v2::base_messages::{Message, MethodMessage, ResultResponse, WssMessage};
 use kraken_async_rs::wss::v2::trading_messages::{
@@ -12,6 +14,11 @@ use kraken_async_rs::wss::v2::trading_messages::{
 };
 use rust_decimal_macros::dec;
 use serde_json::json;
+use std::time::Duration;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+use ws_mock::matchers::{Any, JsonExact};
+use ws_mock::ws_mock_server::WsMock;
 
 #[tokio::test]
 async fn test_add_order() {
@@ -369,3 +376,85 @@ async fn test_batch_cancel() {
         .test()
         .await;
 }
+
+#[tokio::test]
+async fn test_validate_only_add_order() {
+    let expected_request = json!({"method":"add_order","params":{"order_type":"market","side":"sell","symbol":"USDC/USD","order_qty":5.0,"validate":true,"token":"aToken"},"req_id":0});
+    let response = r#"{"method":"add_order","req_id":0,"result":{"order_id":"OPS23M-VS41G-DDE5Z2"},"success":true,"time_in":"2024-05-18T12:05:50.293682Z","time_out":"2024-05-18T12:05:50.300542Z"}"#.to_string();
+
+    let mut test_state = WssTestState::new().await;
+    test_state.ws_client.set_validate_only(true);
+
+    WsMock::new()
+        .matcher(JsonExact::new(expected_request))
+        .expect(1)
+        .respond_with(TungsteniteMessage::Text(response))
+        .mount(&test_state.mock_server)
+        .await;
+
+    let mut stream = test_state.ws_client.connect::<WssMessage>().await.unwrap();
+
+    let add_order = AddOrderParams {
+        order_type: OrderType::Market,
+        side: BuySell::Sell,
+        symbol: "USDC/USD".to_string(),
+        limit_price: None,
+        limit_price_type: None,
+        triggers: None,
+        time_in_force: None,
+        order_quantity: dec!(5.0),
+        margin: None,
+        post_only: None,
+        reduce_only: None,
+        effective_time: None,
+        expire_time: None,
+        deadline: None,
+        order_user_ref: None,
+        conditional: None,
+        display_quantity: None,
+        fee_preference: None,
+        no_market_price_protection: None,
+        stp_type: None,
+        cash_order_quantity: None,
+        validate: None,
+        token: Token::new("aToken".to_string()),
+        client_order_id: None,
+    };
+
+    let message = Message {
+        method: "add_order".to_string(),
+        params: add_order,
+        req_id: 0,
+    };
+
+    stream.send(&message).await.unwrap();
+
+    let result = timeout(Duration::from_secs(3), stream.next()).await;
+    test_state.mock_server.verify().await;
+    assert!(result.unwrap().unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn test_validate_only_refuses_unvalidatable_params() {
+    let mut test_state = WssTestState::new().await;
+    test_state.ws_client.set_validate_only(true);
+
+    WsMock::new()
+        .matcher(Any::new())
+        .expect(0)
+        .mount(&test_state.mock_server)
+        .await;
+
+    let mut stream = test_state.ws_client.connect::<WssMessage>().await.unwrap();
+
+    let message = Message {
+        method: "add_order".to_string(),
+        params: vec!["not", "an", "object"],
+        req_id: 0,
+    };
+
+    let result = stream.send(&message).await;
+    assert!(matches!(result, Err(WSSError::Serde(_))));
+
+    test_state.mock_server.verify().await;
+}
@@ -61,6 +61,7 @@ async fn test_execution_trades_snapshot() {
                 order_user_ref: Some(0),
                 post_only: None,
                 position_status: None,
+                rate_count: None,
                 reduce_only: None,
                 side: Some(BuySell::Sell),
                 symbol: Some("BTC/USD".to_string()),
@@ -104,6 +105,7 @@ async fn test_execution_trades_snapshot() {
                 order_user_ref: Some(0),
                 post_only: None,
                 position_status: None,
+                rate_count: None,
                 reduce_only: None,
                 side: Some(BuySell::Buy),
                 symbol: Some("FET/USD".to_string()),
@@ -147,6 +149,7 @@ async fn test_execution_trades_snapshot() {
                 order_user_ref: Some(0),
                 post_only: None,
                 position_status: None,
+                rate_count: None,
                 reduce_only: None,
                 side: Some(BuySell::Sell),
                 symbol: Some("ETH/USD".to_string()),
@@ -190,6 +193,7 @@ async fn test_execution_trades_snapshot() {
                 order_user_ref: Some(0),
                 post_only: None,
                 position_status: None,
+                rate_count: None,
                 reduce_only: None,
                 side: Some(BuySell::Buy),
                 symbol: Some("BRICK/USD".to_string()),
@@ -249,6 +253,7 @@ async fn test_execution_order_update_cancelled() {
             order_user_ref: Some(0),
             post_only: None,
             position_status: None,
+            rate_count: None,
             reduce_only: None,
             side: None,
             symbol: None,
@@ -307,6 +312,7 @@ async fn test_execution_order_update_pending() {
             order_user_ref: Some(0),
             post_only: None,
             position_status: None,
+            rate_count: None,
             reduce_only: None,
             side: Some(BuySell::Buy),
             symbol: Some("ADX/USD".to_string()),
@@ -363,6 +369,7 @@ async fn test_execution_order_update_new() {
             order_user_ref: Some(0),
             post_only: None,
             position_status: None,
+            rate_count: None,
             reduce_only: None,
             side: None,
             symbol: None,